@@ -33,6 +33,14 @@ def get_block_rlp(block):
         bytearray(block['nonce']),
         block['baseFeePerGas']
     ]
+    # Shanghai
+    if 'withdrawalsRoot' in block:
+        block_list.append(bytearray(block['withdrawalsRoot']))
+    # Cancun
+    if 'blobGasUsed' in block:
+        block_list.append(block['blobGasUsed'])
+        block_list.append(block['excessBlobGas'])
+        block_list.append(bytearray(block['parentBeaconBlockRoot']))
     rlp_block = rlp.encode(block_list).hex()
     return rlp_block
 
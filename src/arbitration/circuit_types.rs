@@ -78,6 +78,8 @@ impl scheduler::CircuitType for EthTransactionCircuitType {
 pub struct EthReceiptCircuitType {
     pub network: Network,
     pub aggregated: bool,
+    /// The receipt circuit decodes an ERC20 `Transfer` log and exposes its fields.
+    pub transfer_log: bool,
 }
 
 impl EthReceiptCircuitType {
@@ -90,6 +92,8 @@ impl scheduler::CircuitType for EthReceiptCircuitType {
     fn name(&self) -> String {
         if self.is_aggregated() {
             format!("receipt_aggregate",)
+        } else if self.transfer_log {
+            format!("receipt_transfer_log",)
         } else {
             format!("receipt",)
         }
@@ -110,6 +114,8 @@ pub struct EthTransactionReceiptCircuitType {
     pub tasks_len: u64,
     pub tx_max_len: u64,
     pub aggregated: bool,
+    /// The ERC20 transfer of the transaction is bound to the `Transfer` log of its receipt.
+    pub transfer_log: bool,
 }
 
 impl EthTransactionReceiptCircuitType {
@@ -128,10 +134,11 @@ impl scheduler::CircuitType for EthTransactionReceiptCircuitType {
             )
         } else {
             format!(
-                "{}_transaction_receipt_{}_max_len_{}",
+                "{}_transaction_receipt_{}_max_len_{}{}",
                 self.network.to_string(),
                 self.tx_type.to_string(),
-                self.tx_max_len
+                self.tx_max_len,
+                if self.transfer_log { "_transfer_log" } else { "" }
             )
         }
     }
@@ -185,6 +192,34 @@ impl scheduler::CircuitType for EthStorageCircuitType {
     }
 }
 
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ZkSyncL1BatchCircuitType {
+    pub network: Network,
+}
+
+impl scheduler::CircuitType for ZkSyncL1BatchCircuitType {
+    fn name(&self) -> String {
+        format!("{}_zksync_era_l1_batch", self.network.to_string())
+    }
+    fn get_degree_from_pinning(&self, pinning_path: impl AsRef<Path>) -> u32 {
+        EthConfigPinning::from_path(pinning_path.as_ref()).degree()
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct EthAccountCircuitType {
+    pub network: Network,
+}
+
+impl scheduler::CircuitType for EthAccountCircuitType {
+    fn name(&self) -> String {
+        format!("{}_account", self.network.to_string())
+    }
+    fn get_degree_from_pinning(&self, pinning_path: impl AsRef<Path>) -> u32 {
+        EthConfigPinning::from_path(pinning_path.as_ref()).degree()
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum FinalAssemblyFinality {
     None,
@@ -199,15 +234,24 @@ pub struct FinalAssemblyCircuitType {
     pub aggregation_type: FinalAssemblyType,
     pub from_network: Network,
     pub to_network: Network,
+    /// The OP-Stack transaction is a deposit, whose snark is verified by another aggregation circuit.
+    pub deposit_transaction: bool,
+    /// The Ethereum transaction is an ERC20 transfer, bound to the `Transfer` log of its receipt.
+    pub transfer_log: bool,
+    /// An L1 account is proven along the transaction, its snark is aggregated last.
+    pub account: bool,
 }
 
 impl FinalAssemblyCircuitType {
     pub fn name(&self) -> String {
         format!(
-            "from_{}_to_{}_{}_final_{}",
+            "from_{}_to_{}_{}{}{}{}_final_{}",
             self.from_network.to_string(),
             self.to_network.to_string(),
             self.aggregation_type.to_string(),
+            if self.deposit_transaction { "_deposit" } else { "" },
+            if self.transfer_log { "_transfer_log" } else { "" },
+            if self.account { "_account" } else { "" },
             self.round
         )
     }
@@ -229,6 +273,8 @@ pub enum ArbitrationCircuitType {
     Receipt(EthReceiptCircuitType),
     TransactionReceipt(EthTransactionReceiptCircuitType),
     MdcStorage(EthStorageCircuitType),
+    ZkSyncL1Batch(ZkSyncL1BatchCircuitType),
+    Account(EthAccountCircuitType),
     FinalAssembly(FinalAssemblyCircuitType),
 }
 
@@ -240,6 +286,8 @@ impl scheduler::CircuitType for ArbitrationCircuitType {
             ArbitrationCircuitType::Receipt(circuit_type) => circuit_type.name(),
             ArbitrationCircuitType::TransactionReceipt(circuit_type) => circuit_type.name(),
             ArbitrationCircuitType::MdcStorage(circuit_type) => circuit_type.name(),
+            ArbitrationCircuitType::ZkSyncL1Batch(circuit_type) => circuit_type.name(),
+            ArbitrationCircuitType::Account(circuit_type) => circuit_type.name(),
             ArbitrationCircuitType::FinalAssembly(circuit_type) => circuit_type.name(),
         }
     }
@@ -265,6 +313,14 @@ impl scheduler::CircuitType for ArbitrationCircuitType {
                 circuit_type.get_degree_from_pinning(pinning_path)
             }
 
+            ArbitrationCircuitType::ZkSyncL1Batch(circuit_type) => {
+                circuit_type.get_degree_from_pinning(pinning_path)
+            }
+
+            ArbitrationCircuitType::Account(circuit_type) => {
+                circuit_type.get_degree_from_pinning(pinning_path)
+            }
+
             ArbitrationCircuitType::FinalAssembly(circuit_type) => {
                 circuit_type.get_degree_from_pinning(pinning_path)
             }
@@ -0,0 +1,241 @@
+use crate::arbitration::final_assembly::FinalAssemblyType;
+use crate::block_header::zksync_era::l1_batch::{
+    L1_BLOCK_HASH_INSTANCE_OFFSET, L2_BLOCK_HASH_INSTANCE_OFFSET,
+};
+use crate::storage::contract_storage::{
+    OB_CONTRACTS_STORAGE_BLOCKS_NUM, OB_CONTRACTS_STORAGE_BLOCK_INSTANCES_LEN,
+    OB_CONTRACTS_STORAGE_MDC_ADDRESS_INSTANCE_OFFSET,
+};
+use crate::track_block::{
+    BLOCK_MERKLE_INCLUSION_INSTANCES_PER_BATCH, BLOCK_MERKLE_INCLUSION_TARGET_LEAF_OFFSET,
+};
+use crate::transaction::{BLOCK_HASH_INSTANCE_OFFSET, TO_INSTANCE_OFFSET};
+use crate::util::circuit::{PinnableCircuit, PreCircuit, PublicAggregationCircuit};
+use crate::util::{
+    circuit_config::CircuitConfig, AggregationConfigPinning, Halo2ConfigPinning, H256_INSTANCES_LEN,
+};
+use crate::AggregationPreCircuit;
+use halo2_base::gates::builder::{CircuitBuilderStage, MultiPhaseThreadBreakPoints};
+use halo2_base::gates::{GateInstructions, RangeChip, RangeInstructions};
+use halo2_base::halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr},
+    poly::kzg::commitment::ParamsKZG,
+};
+use halo2_base::utils::ScalarField;
+use halo2_base::{AssignedValue, Context, QuantumCell::Constant};
+use snark_verifier_sdk::{halo2::aggregation::AggregationCircuit, Snark};
+
+#[cfg(test)]
+mod tests;
+
+/// The transaction (or receipt) block is proven by the first block batch.
+const TRANSACTION_BLOCK_BATCH_INDEX: usize = 0;
+/// The MDC current and next rule blocks are proven by the block batches following the transaction one.
+const MDC_STATE_BLOCK_BATCH_INDEXES: [usize; OB_CONTRACTS_STORAGE_BLOCKS_NUM] = [1, 2];
+/// The L1 block a zkSync Era batch is read at is proven by the block batch following the transaction one, only a destination
+/// final assembly proves both an L1 transaction and a batch. Without an L1 transaction it is the transaction block batch.
+const ZKSYNC_L1_BATCH_BLOCK_BATCH_INDEX: usize = 1;
+/// Block numbers are at most 8 bytes in any block header config.
+const BLOCK_NUMBER_BITS: usize = 64;
+
+/// Positions of the previous snarks of a final assembly, in the order of `FinalAssemblyTask` dependencies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FinalAssemblyInstanceLayout {
+    pub final_assembly_type: FinalAssemblyType,
+    /// Transaction and receipt snarks proven on L1, their block hash is the first instance.
+    pub l1_block_hash_snarks: Vec<usize>,
+    /// The transaction paying the maker, its `to` is the MDC the rule is read from.
+    pub source_transaction_snark: Option<usize>,
+    /// `(snark, instance)` of the L1 block number the source transaction happened at: the block of an L1 receipt, the L1 block
+    /// of an Arbitrum block, or the L1 block of the batch executing a zkSync Era block. An OP-Stack block does not prove its L1 origin.
+    pub transaction_l1_block_number: Option<(usize, usize)>,
+    pub block_merkle_inclusion_snark: usize,
+    /// Only the source final assembly proves the MDC state, its last instances are the blocks of the current and next rules.
+    pub mdc_state_snark: Option<usize>,
+    /// The zkSync Era transaction snark, its block hash is the first instance.
+    pub zksync_transaction_snark: Option<usize>,
+    /// The L1 batch of the zkSync Era transaction block, see `ZkSyncEraL1BatchCircuit`.
+    pub zksync_l1_batch_snark: Option<usize>,
+    /// The L1 account snark, see `EthBlockAccountCircuit`, its block hash is the first instance.
+    pub account_snark: Option<usize>,
+}
+
+/// Constrains the previous snarks of a final assembly to be about the same L1 blocks:
+/// - the block hash of every L1 transaction or receipt snark is the leaf of the transaction block batch.
+/// - for [`FinalAssemblyType::Source`], the MDC state block hashes are the leaves of the following block batches,
+///   the MDC is the `to` of the source transaction, and the current rule is the one enabled at the L1 block of the transaction:
+///   `current_block_number <= transaction_block_number < next_block_number`.
+/// - the block of a zkSync Era transaction is the L2 block proven in an L1 batch, if the batch is proven, and the L1 block
+///   the batch is read at is the leaf of a block batch.
+/// - the block hash of the account snark, if any, is the leaf of the transaction block batch.
+///
+/// The manager address of the MDC state is only exposed, the verifier compares it with the manager it trusts.
+///
+/// `prev_instances` should not include the accumulators.
+pub fn constrain_final_assembly_instances<F: ScalarField>(
+    ctx: &mut Context<F>,
+    range: &RangeChip<F>,
+    layout: &FinalAssemblyInstanceLayout,
+    prev_instances: &[Vec<AssignedValue<F>>],
+) {
+    let block_leaf = |batch_idx: usize| {
+        let start = batch_idx * BLOCK_MERKLE_INCLUSION_INSTANCES_PER_BATCH
+            + BLOCK_MERKLE_INCLUSION_TARGET_LEAF_OFFSET;
+        &prev_instances[layout.block_merkle_inclusion_snark][start..start + H256_INSTANCES_LEN]
+    };
+    let block_hash = |snark_idx: usize| {
+        &prev_instances[snark_idx]
+            [BLOCK_HASH_INSTANCE_OFFSET..BLOCK_HASH_INSTANCE_OFFSET + H256_INSTANCES_LEN]
+    };
+
+    for snark_idx in &layout.l1_block_hash_snarks {
+        let block_hash = block_hash(*snark_idx);
+        for (a, b) in block_hash.iter().zip(block_leaf(TRANSACTION_BLOCK_BATCH_INDEX)) {
+            ctx.constrain_equal(a, b);
+        }
+    }
+
+    if let Some(account_snark) = layout.account_snark {
+        let block_hash = block_hash(account_snark);
+        for (a, b) in block_hash.iter().zip(block_leaf(TRANSACTION_BLOCK_BATCH_INDEX)) {
+            ctx.constrain_equal(a, b);
+        }
+    }
+
+    if let (Some(transaction_snark), Some(l1_batch_snark)) =
+        (layout.zksync_transaction_snark, layout.zksync_l1_batch_snark)
+    {
+        let block_hash = block_hash(transaction_snark);
+        let l2_block_hash = &prev_instances[l1_batch_snark]
+            [L2_BLOCK_HASH_INSTANCE_OFFSET..L2_BLOCK_HASH_INSTANCE_OFFSET + H256_INSTANCES_LEN];
+        for (a, b) in block_hash.iter().zip(l2_block_hash) {
+            ctx.constrain_equal(a, b);
+        }
+
+        let l1_block_hash = &prev_instances[l1_batch_snark]
+            [L1_BLOCK_HASH_INSTANCE_OFFSET..L1_BLOCK_HASH_INSTANCE_OFFSET + H256_INSTANCES_LEN];
+        let batch_idx = if layout.l1_block_hash_snarks.is_empty() {
+            TRANSACTION_BLOCK_BATCH_INDEX
+        } else {
+            assert_eq!(
+                layout.final_assembly_type,
+                FinalAssemblyType::Destination,
+                "only a destination final assembly proves an L1 transaction and a batch"
+            );
+            ZKSYNC_L1_BATCH_BLOCK_BATCH_INDEX
+        };
+        for (a, b) in l1_block_hash.iter().zip(block_leaf(batch_idx)) {
+            ctx.constrain_equal(a, b);
+        }
+    }
+
+    match layout.final_assembly_type {
+        FinalAssemblyType::Source => {
+            let mdc_state_snark =
+                layout.mdc_state_snark.expect("source final assembly should prove the MDC state");
+            let mdc_state_instance = &prev_instances[mdc_state_snark];
+            let blocks = &mdc_state_instance[mdc_state_instance.len()
+                - OB_CONTRACTS_STORAGE_BLOCKS_NUM * OB_CONTRACTS_STORAGE_BLOCK_INSTANCES_LEN..];
+            for (block, batch_idx) in blocks
+                .chunks(OB_CONTRACTS_STORAGE_BLOCK_INSTANCES_LEN)
+                .zip(MDC_STATE_BLOCK_BATCH_INDEXES)
+            {
+                for (a, b) in block[..H256_INSTANCES_LEN].iter().zip(block_leaf(batch_idx)) {
+                    ctx.constrain_equal(a, b);
+                }
+            }
+
+            let source_transaction_snark = layout
+                .source_transaction_snark
+                .expect("source final assembly should prove the source transaction");
+            ctx.constrain_equal(
+                &mdc_state_instance[OB_CONTRACTS_STORAGE_MDC_ADDRESS_INSTANCE_OFFSET],
+                &prev_instances[source_transaction_snark][TO_INSTANCE_OFFSET],
+            );
+
+            // OP-Stack blocks don't prove their L1 origin, `FinalAssemblyTask::new` rejects them as source
+            let (snark_idx, instance_idx) = layout
+                .transaction_l1_block_number
+                .expect("source final assembly should prove the L1 block of the transaction");
+            let transaction_block_number = prev_instances[snark_idx][instance_idx];
+            let current_block_number = blocks[OB_CONTRACTS_STORAGE_BLOCK_INSTANCES_LEN - 1];
+            let next_block_number = *blocks.last().unwrap();
+            let transaction_block_number_plus_one =
+                range.gate().add(ctx, transaction_block_number, Constant(F::one()));
+            range.check_less_than(
+                ctx,
+                current_block_number,
+                transaction_block_number_plus_one,
+                BLOCK_NUMBER_BITS,
+            );
+            range.check_less_than(
+                ctx,
+                transaction_block_number,
+                next_block_number,
+                BLOCK_NUMBER_BITS,
+            );
+        }
+        FinalAssemblyType::Destination => {
+            assert!(
+                layout.mdc_state_snark.is_none(),
+                "destination final assembly should not prove the MDC state"
+            );
+        }
+    }
+}
+
+/// Aggregates the snarks of a final assembly, re-exposes their public inputs and checks they are consistent with each other.
+#[derive(Clone, Debug)]
+pub struct FinalAssemblyAggregationCircuit {
+    pub aggregation: PublicAggregationCircuit,
+    pub layout: FinalAssemblyInstanceLayout,
+}
+
+impl FinalAssemblyAggregationCircuit {
+    pub fn new(snarks: Vec<(Snark, bool)>, layout: FinalAssemblyInstanceLayout) -> Self {
+        Self { aggregation: PublicAggregationCircuit::new(snarks), layout }
+    }
+}
+
+impl AggregationPreCircuit for FinalAssemblyAggregationCircuit {
+    fn create(
+        self,
+        stage: CircuitBuilderStage,
+        break_points: Option<MultiPhaseThreadBreakPoints>,
+        lookup_bits: usize,
+        params: &ParamsKZG<Bn256>,
+    ) -> AggregationCircuit {
+        let mut private = self.aggregation.private(stage, break_points, lookup_bits, params);
+        {
+            // TODO: should reuse RangeChip from aggregation circuit, but can't refactor right now
+            let range = RangeChip::<Fr>::default(lookup_bits);
+            let mut builder = private.inner.circuit.0.builder.borrow_mut();
+            let ctx = builder.main(0);
+            constrain_final_assembly_instances(
+                ctx,
+                &range,
+                &self.layout,
+                &private.previous_instances,
+            );
+        }
+        for prev in &private.previous_instances {
+            private.inner.assigned_instances.extend_from_slice(prev);
+        }
+        private
+    }
+}
+
+impl PreCircuit for FinalAssemblyAggregationCircuit {
+    type Pinning = AggregationConfigPinning;
+
+    fn create_circuit(
+        self,
+        stage: CircuitBuilderStage,
+        pinning: Self::Pinning,
+        params: &ParamsKZG<Bn256>,
+    ) -> (impl PinnableCircuit<Fr>, CircuitConfig) {
+        let config = pinning.config();
+        let break_points = (stage == CircuitBuilderStage::Prover).then(|| pinning.break_points());
+        AggregationPreCircuit::create_circuit(self, stage, config, break_points, params)
+    }
+}
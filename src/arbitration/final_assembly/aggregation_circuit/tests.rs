@@ -0,0 +1,386 @@
+use ethers_core::types::H256;
+use ethers_core::utils::keccak256;
+use halo2_base::gates::builder::{GateThreadBuilder, RangeCircuitBuilder};
+use halo2_base::gates::RangeChip;
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use itertools::Itertools;
+
+use crate::arbitration::final_assembly::aggregation_circuit::{
+    constrain_final_assembly_instances, FinalAssemblyInstanceLayout,
+};
+use crate::arbitration::final_assembly::FinalAssemblyType;
+use crate::block_header::zksync_era::l1_batch::{
+    L1_BLOCK_HASH_INSTANCE_OFFSET, L1_BLOCK_NUMBER_INSTANCE_OFFSET, L2_BLOCK_HASH_INSTANCE_OFFSET,
+};
+use crate::halo2_proofs::dev::MockProver;
+use crate::rlp::builder::RlcThreadBuilder;
+use crate::rlp::rlc::FIRST_PHASE;
+use crate::storage::contract_storage::block_instances;
+use crate::track_block::BlockMerkleInclusionCircuit;
+use crate::transaction::{TO_INSTANCE_OFFSET, TRANSACTION_INSTANCES_LEN};
+use crate::transaction_receipt::tests::erc20_transfer_test_circuit;
+use crate::util::circuit_config::{isolate_env, CircuitConfig, CircuitConfigScope};
+use crate::util::{encode_h256_to_field, EthConfigParams};
+use crate::{EthPreCircuit, ETH_LOOKUP_BITS};
+
+const K: u32 = 10;
+
+const MDC_CURRENT_BLOCK_NUMBER: u64 = 16_999_950;
+const MDC_NEXT_BLOCK_NUMBER: u64 = 17_000_050;
+
+fn block_hash(seed: u64) -> H256 {
+    H256(keccak256(seed.to_be_bytes()))
+}
+
+/// The instances of the offline ERC20 transfer `TransactionReceiptCircuit`, with its block hash and number.
+fn transaction_receipt_instance() -> (Vec<Fr>, H256, u64) {
+    let params = EthConfigParams::from_path("configs/tests/transaction_receipt.json");
+    let input = erc20_transfer_test_circuit(1234567);
+    let block_hash = input.eth_receipt_input.block_hash;
+    let block_number = input.eth_receipt_input.block_number;
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    (circuit.instance(), block_hash, block_number)
+}
+
+/// The instances of a `BlockMerkleInclusionCircuit` whose batch `i` includes `target_blocks[i]`.
+fn block_merkle_inclusion_instance(target_blocks: &[H256]) -> Vec<Fr> {
+    let batches = target_blocks
+        .iter()
+        .enumerate()
+        .map(|(i, target_block)| {
+            let mut leaves = (0..4).map(|j| block_hash(100 * i as u64 + j)).collect_vec();
+            leaves[1] = *target_block;
+            (leaves, 1)
+        })
+        .collect_vec();
+    BlockMerkleInclusionCircuit::from_batches(batches).instance()
+}
+
+/// The contract values of an `ObContractsStorageCircuit` are not compared, only its MDC address and blocks are.
+fn mdc_state_instance(mdc_address: Fr, blocks: [(H256, u64); 2]) -> Vec<Fr> {
+    [vec![mdc_address, Fr::from(5), Fr::from(6)], block_instances(blocks)].concat()
+}
+
+fn final_assembly_circuit(
+    layout: &FinalAssemblyInstanceLayout,
+    prev_instances: Vec<Vec<Fr>>,
+) -> (RangeCircuitBuilder<Fr>, CircuitConfigScope) {
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(ETH_LOOKUP_BITS);
+    let ctx = builder.main(FIRST_PHASE);
+    let prev_instances = prev_instances
+        .into_iter()
+        .map(|instance| ctx.assign_witnesses(instance))
+        .collect::<Vec<_>>();
+    constrain_final_assembly_instances(ctx, &range, layout, &prev_instances);
+    let params = isolate_env(|| builder.config(K as usize, Some(20)));
+    let scope =
+        CircuitConfigScope::enter(CircuitConfig::Gate { params, lookup_bits: ETH_LOOKUP_BITS });
+    (RangeCircuitBuilder::mock(builder), scope)
+}
+
+fn source_layout() -> FinalAssemblyInstanceLayout {
+    // transaction receipt, block merkle inclusion, mdc state
+    FinalAssemblyInstanceLayout {
+        final_assembly_type: FinalAssemblyType::Source,
+        l1_block_hash_snarks: vec![0],
+        source_transaction_snark: Some(0),
+        transaction_l1_block_number: Some((0, TRANSACTION_INSTANCES_LEN)),
+        block_merkle_inclusion_snark: 1,
+        mdc_state_snark: Some(2),
+        zksync_transaction_snark: None,
+        zksync_l1_batch_snark: None,
+        account_snark: None,
+    }
+}
+
+/// The block merkle inclusion proves `[transaction_block, mdc current block, mdc next block]`,
+/// the MDC is the receiver of the transaction.
+fn source_instances(
+    transaction_receipt_instance: &[Fr],
+    merkle_blocks: [H256; 3],
+    mdc_blocks: [(H256, u64); 2],
+) -> Vec<Vec<Fr>> {
+    vec![
+        transaction_receipt_instance.to_vec(),
+        block_merkle_inclusion_instance(&merkle_blocks),
+        mdc_state_instance(transaction_receipt_instance[TO_INSTANCE_OFFSET], mdc_blocks),
+    ]
+}
+
+#[test]
+pub fn test_final_assembly_source_instances() {
+    let (instance, transaction_block, _) = transaction_receipt_instance();
+    let mdc_blocks =
+        [(block_hash(1), MDC_CURRENT_BLOCK_NUMBER), (block_hash(2), MDC_NEXT_BLOCK_NUMBER)];
+    let (circuit, _scope) = final_assembly_circuit(
+        &source_layout(),
+        source_instances(
+            &instance,
+            [transaction_block, mdc_blocks[0].0, mdc_blocks[1].0],
+            mdc_blocks,
+        ),
+    );
+    MockProver::run(K, &circuit, vec![]).unwrap().assert_satisfied();
+}
+
+#[test]
+pub fn test_final_assembly_source_mismatched_transaction_block() {
+    let (instance, _, _) = transaction_receipt_instance();
+    let mdc_blocks =
+        [(block_hash(1), MDC_CURRENT_BLOCK_NUMBER), (block_hash(2), MDC_NEXT_BLOCK_NUMBER)];
+    let (circuit, _scope) = final_assembly_circuit(
+        &source_layout(),
+        source_instances(&instance, [block_hash(3), mdc_blocks[0].0, mdc_blocks[1].0], mdc_blocks),
+    );
+    assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+}
+
+#[test]
+pub fn test_final_assembly_source_mismatched_mdc_state_blocks() {
+    let (instance, transaction_block, _) = transaction_receipt_instance();
+    let current = (block_hash(1), MDC_CURRENT_BLOCK_NUMBER);
+    let next = (block_hash(2), MDC_NEXT_BLOCK_NUMBER);
+    for merkle_blocks in
+        [[transaction_block, next.0, current.0], [transaction_block, current.0, block_hash(3)]]
+    {
+        let (circuit, _scope) = final_assembly_circuit(
+            &source_layout(),
+            source_instances(&instance, merkle_blocks, [current, next]),
+        );
+        assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+    }
+}
+
+#[test]
+pub fn test_final_assembly_source_mismatched_mdc_address() {
+    let (instance, transaction_block, _) = transaction_receipt_instance();
+    let mdc_blocks =
+        [(block_hash(1), MDC_CURRENT_BLOCK_NUMBER), (block_hash(2), MDC_NEXT_BLOCK_NUMBER)];
+    let mut instances = source_instances(
+        &instance,
+        [transaction_block, mdc_blocks[0].0, mdc_blocks[1].0],
+        mdc_blocks,
+    );
+    // the rule of another MDC than the one paid by the transaction
+    instances[2][0] += Fr::from(1);
+    let (circuit, _scope) = final_assembly_circuit(&source_layout(), instances);
+    assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+}
+
+#[test]
+pub fn test_final_assembly_source_transaction_block_number() {
+    let (instance, transaction_block, transaction_block_number) = transaction_receipt_instance();
+    let merkle_blocks = [transaction_block, block_hash(1), block_hash(2)];
+    let mdc_blocks = |current_block_number: u64, next_block_number: u64| {
+        [(block_hash(1), current_block_number), (block_hash(2), next_block_number)]
+    };
+
+    // the current rule may be enabled at the transaction block
+    for (current_block_number, next_block_number) in [
+        (transaction_block_number, MDC_NEXT_BLOCK_NUMBER),
+        (MDC_CURRENT_BLOCK_NUMBER, transaction_block_number + 1),
+    ] {
+        let (circuit, _scope) = final_assembly_circuit(
+            &source_layout(),
+            source_instances(
+                &instance,
+                merkle_blocks,
+                mdc_blocks(current_block_number, next_block_number),
+            ),
+        );
+        MockProver::run(K, &circuit, vec![]).unwrap().assert_satisfied();
+    }
+
+    // the transaction was sent before the current rule or after the next rule was enabled
+    for (current_block_number, next_block_number) in [
+        (transaction_block_number + 1, MDC_NEXT_BLOCK_NUMBER),
+        (MDC_CURRENT_BLOCK_NUMBER, transaction_block_number),
+    ] {
+        let (circuit, _scope) = final_assembly_circuit(
+            &source_layout(),
+            source_instances(
+                &instance,
+                merkle_blocks,
+                mdc_blocks(current_block_number, next_block_number),
+            ),
+        );
+        assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+    }
+}
+
+/// An L2 transaction instance, only its block hash and `to` are compared.
+fn l2_transaction_instance(block: H256) -> Vec<Fr> {
+    let fields = (0..TRANSACTION_INSTANCES_LEN as u64 - 2).map(Fr::from).collect_vec();
+    [encode_h256_to_field::<Fr>(&block).to_vec(), fields].concat()
+}
+
+#[test]
+pub fn test_final_assembly_destination_instances() {
+    // L2 transaction, L1 receipt, block merkle inclusion
+    let layout = FinalAssemblyInstanceLayout {
+        final_assembly_type: FinalAssemblyType::Destination,
+        l1_block_hash_snarks: vec![1],
+        source_transaction_snark: None,
+        transaction_l1_block_number: None,
+        block_merkle_inclusion_snark: 2,
+        mdc_state_snark: None,
+        zksync_transaction_snark: None,
+        zksync_l1_batch_snark: None,
+        account_snark: None,
+    };
+    let (receipt_instance, receipt_block, _) = transaction_receipt_instance();
+    let instances = |merkle_block: H256| {
+        vec![
+            l2_transaction_instance(block_hash(3)),
+            receipt_instance.clone(),
+            block_merkle_inclusion_instance(&[merkle_block]),
+        ]
+    };
+
+    // the L2 transaction block is not proven by the L1 block batches
+    let (circuit, _scope) = final_assembly_circuit(&layout, instances(receipt_block));
+    MockProver::run(K, &circuit, vec![]).unwrap().assert_satisfied();
+
+    let (circuit, _scope) = final_assembly_circuit(&layout, instances(block_hash(3)));
+    assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+}
+
+/// `[l1_block_hash, l1_block_number, diamond_proxy, batch_number, l2_block_hash, l2_block_number]`,
+/// see `ZkSyncEraL1BatchCircuit::instance`.
+fn l1_batch_instance(l1_block: H256, l1_block_number: u64, l2_block: H256) -> Vec<Fr> {
+    let instance = [
+        encode_h256_to_field::<Fr>(&l1_block).to_vec(),
+        vec![Fr::from(l1_block_number), Fr::from(0x32400084), Fr::from(301_000)],
+        encode_h256_to_field::<Fr>(&l2_block).to_vec(),
+        vec![Fr::from(22_000_000)],
+    ]
+    .concat();
+    assert_eq!(
+        instance[L1_BLOCK_HASH_INSTANCE_OFFSET..L1_BLOCK_HASH_INSTANCE_OFFSET + 2],
+        encode_h256_to_field::<Fr>(&l1_block)
+    );
+    assert_eq!(
+        instance[L2_BLOCK_HASH_INSTANCE_OFFSET..L2_BLOCK_HASH_INSTANCE_OFFSET + 2],
+        encode_h256_to_field::<Fr>(&l2_block)
+    );
+    instance
+}
+
+#[test]
+pub fn test_final_assembly_zksync_l1_batch_instances() {
+    // zkSync transaction, zkSync L1 batch, L1 receipt, block merkle inclusion
+    let layout = FinalAssemblyInstanceLayout {
+        final_assembly_type: FinalAssemblyType::Destination,
+        l1_block_hash_snarks: vec![2],
+        source_transaction_snark: None,
+        transaction_l1_block_number: None,
+        block_merkle_inclusion_snark: 3,
+        mdc_state_snark: None,
+        zksync_transaction_snark: Some(0),
+        zksync_l1_batch_snark: Some(1),
+        account_snark: None,
+    };
+    let (receipt_instance, receipt_block, _) = transaction_receipt_instance();
+    let l2_block = block_hash(3);
+    let instances = |batch_l1_block: H256, batch_l2_block: H256| {
+        vec![
+            l2_transaction_instance(l2_block),
+            l1_batch_instance(batch_l1_block, 17_000_100, batch_l2_block),
+            receipt_instance.clone(),
+            block_merkle_inclusion_instance(&[receipt_block, block_hash(4)]),
+        ]
+    };
+
+    let (circuit, _scope) = final_assembly_circuit(&layout, instances(block_hash(4), l2_block));
+    MockProver::run(K, &circuit, vec![]).unwrap().assert_satisfied();
+
+    // the batch proves another L2 block
+    let (circuit, _scope) =
+        final_assembly_circuit(&layout, instances(block_hash(4), block_hash(5)));
+    assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+
+    // the batch is read at an L1 block the block batches do not prove
+    for batch_l1_block in [receipt_block, block_hash(5)] {
+        let (circuit, _scope) =
+            final_assembly_circuit(&layout, instances(batch_l1_block, l2_block));
+        assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+    }
+}
+
+#[test]
+pub fn test_final_assembly_zksync_source_l1_block_number() {
+    // zkSync transaction, zkSync L1 batch, block merkle inclusion, mdc state
+    let layout = FinalAssemblyInstanceLayout {
+        final_assembly_type: FinalAssemblyType::Source,
+        l1_block_hash_snarks: vec![],
+        source_transaction_snark: Some(0),
+        transaction_l1_block_number: Some((1, L1_BLOCK_NUMBER_INSTANCE_OFFSET)),
+        block_merkle_inclusion_snark: 2,
+        mdc_state_snark: Some(3),
+        zksync_transaction_snark: Some(0),
+        zksync_l1_batch_snark: Some(1),
+        account_snark: None,
+    };
+    let l2_block = block_hash(3);
+    let transaction_instance = l2_transaction_instance(l2_block);
+    let mdc_blocks =
+        [(block_hash(1), MDC_CURRENT_BLOCK_NUMBER), (block_hash(2), MDC_NEXT_BLOCK_NUMBER)];
+    let instances = |l1_block_number: u64| {
+        vec![
+            transaction_instance.clone(),
+            l1_batch_instance(block_hash(4), l1_block_number, l2_block),
+            block_merkle_inclusion_instance(&[block_hash(4), mdc_blocks[0].0, mdc_blocks[1].0]),
+            mdc_state_instance(transaction_instance[TO_INSTANCE_OFFSET], mdc_blocks),
+        ]
+    };
+
+    let (circuit, _scope) = final_assembly_circuit(&layout, instances(17_000_000));
+    MockProver::run(K, &circuit, vec![]).unwrap().assert_satisfied();
+
+    // the batch was executed once the next rule was enabled
+    let (circuit, _scope) = final_assembly_circuit(&layout, instances(MDC_NEXT_BLOCK_NUMBER));
+    assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+}
+
+/// Only the block hash of an `EthBlockAccountCircuit` is compared, its account fields are not.
+fn account_instance(block_hash: H256) -> Vec<Fr> {
+    [encode_h256_to_field::<Fr>(&block_hash).to_vec(), (0..8).map(Fr::from).collect()].concat()
+}
+
+fn source_layout_with_account() -> FinalAssemblyInstanceLayout {
+    // transaction receipt, block merkle inclusion, mdc state, account
+    FinalAssemblyInstanceLayout { account_snark: Some(3), ..source_layout() }
+}
+
+#[test]
+pub fn test_final_assembly_account_at_transaction_block() {
+    let (instance, transaction_block, _) = transaction_receipt_instance();
+    let mdc_blocks =
+        [(block_hash(1), MDC_CURRENT_BLOCK_NUMBER), (block_hash(2), MDC_NEXT_BLOCK_NUMBER)];
+    let mut prev_instances = source_instances(
+        &instance,
+        [transaction_block, mdc_blocks[0].0, mdc_blocks[1].0],
+        mdc_blocks,
+    );
+    prev_instances.push(account_instance(transaction_block));
+    let (circuit, _scope) = final_assembly_circuit(&source_layout_with_account(), prev_instances);
+    MockProver::run(K, &circuit, vec![]).unwrap().assert_satisfied();
+}
+
+#[test]
+pub fn test_final_assembly_account_at_other_block() {
+    let (instance, transaction_block, _) = transaction_receipt_instance();
+    let mdc_blocks =
+        [(block_hash(1), MDC_CURRENT_BLOCK_NUMBER), (block_hash(2), MDC_NEXT_BLOCK_NUMBER)];
+    let mut prev_instances = source_instances(
+        &instance,
+        [transaction_block, mdc_blocks[0].0, mdc_blocks[1].0],
+        mdc_blocks,
+    );
+    // the account is proven at the current rule block instead
+    prev_instances.push(account_instance(mdc_blocks[0].0));
+    let (circuit, _scope) = final_assembly_circuit(&source_layout_with_account(), prev_instances);
+    assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+}
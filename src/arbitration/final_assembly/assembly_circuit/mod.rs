@@ -3,7 +3,7 @@ use crate::keccak::{FixedLenRLCs, FnSynthesize, KeccakChip, VarLenRLCs};
 use crate::rlp::builder::{RlcThreadBreakPoints, RlcThreadBuilder};
 use crate::rlp::RlpChip;
 use crate::util::circuit::{PinnableCircuit, PreCircuit};
-use crate::util::{EthConfigParams, EthConfigPinning, Halo2ConfigPinning};
+use crate::util::{circuit_config::CircuitConfig, EthConfigParams, EthConfigPinning};
 use crate::{EthChip, EthCircuitBuilder};
 use futures::stream::iter;
 use halo2_base::gates::builder::CircuitBuilderStage;
@@ -22,31 +22,26 @@ use snark_verifier::loader::halo2::Halo2Loader;
 use snark_verifier_sdk::halo2::aggregation::AggregationCircuit;
 use snark_verifier_sdk::{Snark, LIMBS, SHPLONK};
 use std::cell::RefCell;
-use std::env::var;
 
 #[derive(Clone, Debug)]
 pub struct FinalAssemblyCircuit {
     pub transaction_snark: Snark,
     pub block_snark: Snark,
-    // pub mdc_state_snark: Snark,
+    pub mdc_state_snark: Snark,
     pub transaction_has_accumulator: bool,
     pub block_has_accumulator: bool,
-    // pub mdc_state_has_accumulator: bool,
+    pub mdc_state_has_accumulator: bool,
 }
 
 impl FinalAssemblyCircuit {
-    pub fn new(
-        transaction: (Snark, bool),
-        block: (Snark, bool),
-        // mdc_state: (Snark, bool)
-    ) -> Self {
+    pub fn new(transaction: (Snark, bool), block: (Snark, bool), mdc_state: (Snark, bool)) -> Self {
         Self {
             transaction_snark: transaction.0,
             block_snark: block.0,
-            // mdc_state_snark: mdc_state.0,
+            mdc_state_snark: mdc_state.0,
             transaction_has_accumulator: transaction.1,
             block_has_accumulator: block.1,
-            // mdc_state_has_accumulator: mdc_state.1,
+            mdc_state_has_accumulator: mdc_state.1,
         }
     }
 }
@@ -66,17 +61,16 @@ impl FinalAssemblyCircuit {
             Some(Vec::new()), // break points aren't actually used, since we will just take the builder from this circuit
             lookup_bits,
             params,
-            // [self.transaction_snark, self.block_snark, self.mdc_state_snark],
-            [self.transaction_snark, self.block_snark],
+            [self.transaction_snark, self.block_snark, self.mdc_state_snark],
         );
 
-        let (transaction_instance, block_instance) = aggregation
+        let (transaction_instance, block_instance, mdc_state_instance) = aggregation
             .previous_instances
             .iter()
             .zip_eq([
                 self.transaction_has_accumulator,
                 self.block_has_accumulator,
-                // self.mdc_state_has_accumulator,
+                self.mdc_state_has_accumulator,
             ])
             .map(|(instance, has_accumulator)| {
                 let start = (has_accumulator as usize) * 4 * LIMBS;
@@ -98,9 +92,9 @@ impl FinalAssemblyCircuit {
 
         let builder = RlcThreadBuilder { threads_rlc: Vec::new(), gate_builder };
         let mut assigned_instances = aggregation.inner.assigned_instances;
-        // assigned_instances.extend(block_instance);
-
-        println!("assigned_instances:{:?}", &assigned_instances);
+        assigned_instances.extend_from_slice(transaction_instance);
+        assigned_instances.extend_from_slice(block_instance);
+        assigned_instances.extend_from_slice(mdc_state_instance);
 
         EthCircuitBuilder::new(
             assigned_instances,
@@ -114,22 +108,22 @@ impl FinalAssemblyCircuit {
         )
     }
 
+    /// Same as [`crate::EthPreCircuit::create_circuit`], the snarks are aggregated with the lookup bits of `config_params`.
     pub fn create_circuit(
         self,
         stage: CircuitBuilderStage,
+        config_params: EthConfigParams,
         break_points: Option<RlcThreadBreakPoints>,
-        lookup_bits: usize,
         params: &ParamsKZG<Bn256>,
     ) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
+        let lookup_bits = config_params.lookup_bits.expect("lookup_bits is not set");
         let circuit = self.create(stage, break_points, lookup_bits, params);
         #[cfg(not(feature = "production"))]
         if stage != CircuitBuilderStage::Prover {
-            let config_params: EthConfigParams = serde_json::from_str(
-                var("ETH_CONFIG_PARAMS").expect("ETH_CONFIG_PARAMS is not set").as_str(),
-            )
-            .unwrap();
             circuit.config(config_params.degree as usize, Some(config_params.unusable_rows));
+            return circuit;
         }
+        circuit.set_config_params(config_params);
         circuit
     }
 }
@@ -140,16 +134,14 @@ impl PreCircuit for FinalAssemblyCircuit {
     fn create_circuit(
         self,
         stage: CircuitBuilderStage,
-        pinning: Option<Self::Pinning>,
+        pinning: Self::Pinning,
         params: &ParamsKZG<Bn256>,
-    ) -> impl PinnableCircuit<Fr> {
-        // look for lookup_bits either from pinning, if available, or from env var
-        let lookup_bits = pinning
-            .as_ref()
-            .map(|p| p.params.lookup_bits.unwrap())
-            .or_else(|| var("LOOKUP_BITS").map(|v| v.parse().unwrap()).ok())
-            .expect("LOOKUP_BITS is not set");
-        let break_points = pinning.map(|p| p.break_points());
-        FinalAssemblyCircuit::create_circuit(self, stage, break_points, lookup_bits, params)
+    ) -> (impl PinnableCircuit<Fr>, CircuitConfig) {
+        let EthConfigPinning { params: config_params, break_points } = pinning;
+        let break_points = (stage == CircuitBuilderStage::Prover).then_some(break_points);
+        let circuit =
+            FinalAssemblyCircuit::create_circuit(self, stage, config_params, break_points, params);
+        let config = CircuitConfig::Eth(circuit.config_params());
+        (circuit, config)
     }
 }
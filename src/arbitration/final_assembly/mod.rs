@@ -1,3 +1,4 @@
+pub mod aggregation_circuit;
 pub mod assembly_circuit;
 
 use halo2_base::{
@@ -6,24 +6,33 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::arbitration::circuit_types::{
-    EthReceiptCircuitType, EthStorageCircuitType, EthTransactionCircuitType,
-    EthTransactionReceiptCircuitType, FinalAssemblyCircuitType,
+    EthAccountCircuitType, EthReceiptCircuitType, EthStorageCircuitType, EthTransactionCircuitType,
+    EthTransactionReceiptCircuitType, FinalAssemblyCircuitType, ZkSyncL1BatchCircuitType,
 };
+use crate::arbitration::final_assembly::aggregation_circuit::FinalAssemblyInstanceLayout;
 use crate::arbitration::final_assembly::FinalAssemblyType;
+use crate::block_header::zksync_era::l1_batch::{
+    ZkSyncEraL1BatchCircuit, L1_BLOCK_NUMBER_INSTANCE_OFFSET,
+};
 use crate::receipt::util::ReceiptConstructor;
 use crate::receipt::EthBlockReceiptCircuit;
+use crate::storage::account::EthBlockAccountCircuit;
 use crate::storage::contract_storage::util::{
     get_contracts_storage_circuit, MultiBlocksContractsStorageConstructor,
 };
 use crate::storage::contract_storage::ObContractsStorageCircuit;
 use crate::track_block::BlockMerkleInclusionCircuit;
+use crate::transaction::arbitrum::ArbitrumBlockTransactionCircuit;
+use crate::transaction::optimism::OptimismBlockTransactionCircuit;
 use crate::transaction::util::{
-    get_eth_transaction_circuit, get_zksync_transaction_circuit, TransactionConstructor,
+    get_arbitrum_transaction_circuit, get_eth_transaction_circuit,
+    get_optimism_transaction_circuit, get_zksync_transaction_circuit, TransactionConstructor,
 };
 use crate::transaction::zksync_era::ZkSyncEraBlockTransactionCircuit;
-use crate::transaction::EthTransactionType;
+use crate::transaction::{EthTransactionType, TRANSACTION_INSTANCES_LEN};
 use crate::transaction_receipt::util::TransactionReceiptConstructor;
 use crate::transaction_receipt::TransactionReceiptCircuit;
+use crate::util::errors::FinalAssemblyError;
 use crate::util::scheduler::CircuitType;
 use crate::{
     track_block::{util::get_eth_track_block_circuit, EthTrackBlockCircuit},
@@ -168,6 +177,35 @@ impl scheduler::Task for EthTransactionTask {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct ZkSyncL1BatchTask {
+    pub input: ZkSyncEraL1BatchCircuit,
+    /// The L1 network executing the zkSync Era batches.
+    pub network: Network,
+}
+
+impl ZkSyncL1BatchTask {
+    pub fn new(input: ZkSyncEraL1BatchCircuit, network: Network) -> Self {
+        Self { input, network }
+    }
+}
+
+impl scheduler::Task for ZkSyncL1BatchTask {
+    type CircuitType = ZkSyncL1BatchCircuitType;
+
+    fn circuit_type(&self) -> Self::CircuitType {
+        ZkSyncL1BatchCircuitType { network: self.network }
+    }
+
+    fn name(&self) -> String {
+        format!("zksync_era_l1_batch_l2_block_{}", self.input.inputs.l2_block_number)
+    }
+
+    fn dependencies(&self) -> Vec<Self> {
+        vec![]
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ZkSyncTransactionTask {
     pub input: ZkSyncEraBlockTransactionCircuit,
@@ -176,6 +214,8 @@ pub struct ZkSyncTransactionTask {
     pub constructor: Vec<TransactionConstructor>,
     pub aggregated: bool,
     pub network: Network,
+    /// Proves the transaction block belongs to a batch executed on L1.
+    pub l1_batch_task: Option<ZkSyncL1BatchTask>,
 }
 
 impl ZkSyncTransactionTask {
@@ -186,8 +226,9 @@ impl ZkSyncTransactionTask {
         constructor: Vec<TransactionConstructor>,
         aggregated: bool,
         network: Network,
+        l1_batch_task: Option<ZkSyncL1BatchTask>,
     ) -> Self {
-        Self { input, tx_type, tasks_len, constructor, aggregated, network }
+        Self { input, tx_type, tasks_len, constructor, aggregated, network, l1_batch_task }
     }
     fn hash(&self) -> H256 {
         self.constructor[0].transaction_hash
@@ -239,6 +280,169 @@ impl scheduler::Task for ZkSyncTransactionTask {
                     constructor: [constructor].to_vec(),
                     aggregated: false,
                     network: self.network,
+                    l1_batch_task: None,
+                })
+                .collect_vec();
+            result
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArbitrumTransactionTask {
+    pub input: ArbitrumBlockTransactionCircuit,
+    pub tx_type: EthTransactionType,
+    pub tasks_len: u64,
+    pub constructor: Vec<TransactionConstructor>,
+    pub aggregated: bool,
+    pub network: Network,
+}
+
+impl ArbitrumTransactionTask {
+    pub fn new(
+        input: ArbitrumBlockTransactionCircuit,
+        tx_type: EthTransactionType,
+        tasks_len: u64,
+        constructor: Vec<TransactionConstructor>,
+        aggregated: bool,
+        network: Network,
+    ) -> Self {
+        Self { input, tx_type, tasks_len, constructor, aggregated, network }
+    }
+    fn hash(&self) -> H256 {
+        self.constructor[0].transaction_hash
+    }
+    fn tx_max_len(&self) -> u64 {
+        self.constructor[0].tx_max_len() as u64
+    }
+}
+
+impl scheduler::Task for ArbitrumTransactionTask {
+    type CircuitType = EthTransactionCircuitType;
+
+    fn circuit_type(&self) -> Self::CircuitType {
+        EthTransactionCircuitType {
+            network: self.network,
+            tx_type: self.tx_type.clone(),
+            tasks_len: self.tasks_len,
+            tx_max_len: self.tx_max_len(),
+            aggregated: self.aggregated,
+        }
+    }
+
+    fn name(&self) -> String {
+        if self.circuit_type().is_aggregated() {
+            format!(
+                "arbitrum_transaction_aggregated_{}_task_len_{}",
+                self.tx_type.to_string(),
+                self.tasks_len
+            )
+        } else {
+            format!(
+                "arbitrum_transaction_{}_tx_{}_max_len_{}",
+                self.tx_type.to_string(),
+                self.hash(),
+                self.tx_max_len()
+            )
+        }
+    }
+
+    fn dependencies(&self) -> Vec<Self> {
+        if self.circuit_type().is_aggregated() {
+            let constructor = self.constructor.clone();
+            let result = constructor
+                .into_iter()
+                .map(|constructor| Self {
+                    input: get_arbitrum_transaction_circuit(constructor.clone()),
+                    tx_type: self.tx_type.clone(),
+                    tasks_len: 1u64,
+                    constructor: [constructor].to_vec(),
+                    aggregated: false,
+                    network: self.network,
+                })
+                .collect_vec();
+            result
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OptimismTransactionTask {
+    pub input: OptimismBlockTransactionCircuit,
+    pub tx_type: EthTransactionType,
+    pub tasks_len: u64,
+    pub constructor: Vec<TransactionConstructor>,
+    pub aggregated: bool,
+    pub network: Network,
+}
+
+impl OptimismTransactionTask {
+    /// The transaction type is the envelope of `input`, so the circuit type matches the circuit it selects.
+    pub fn new(
+        input: OptimismBlockTransactionCircuit,
+        tasks_len: u64,
+        constructor: Vec<TransactionConstructor>,
+        aggregated: bool,
+        network: Network,
+    ) -> Self {
+        let tx_type = input.tx_type();
+        Self { input, tx_type, tasks_len, constructor, aggregated, network }
+    }
+    fn hash(&self) -> H256 {
+        self.constructor[0].transaction_hash
+    }
+    fn tx_max_len(&self) -> u64 {
+        self.constructor[0].tx_max_len() as u64
+    }
+}
+
+impl scheduler::Task for OptimismTransactionTask {
+    type CircuitType = EthTransactionCircuitType;
+
+    fn circuit_type(&self) -> Self::CircuitType {
+        EthTransactionCircuitType {
+            network: self.network,
+            tx_type: self.tx_type.clone(),
+            tasks_len: self.tasks_len,
+            tx_max_len: self.tx_max_len(),
+            aggregated: self.aggregated,
+        }
+    }
+
+    fn name(&self) -> String {
+        if self.circuit_type().is_aggregated() {
+            format!(
+                "optimism_transaction_aggregated_{}_task_len_{}",
+                self.tx_type.to_string(),
+                self.tasks_len
+            )
+        } else {
+            format!(
+                "optimism_transaction_{}_tx_{}_max_len_{}",
+                self.tx_type.to_string(),
+                self.hash(),
+                self.tx_max_len()
+            )
+        }
+    }
+
+    fn dependencies(&self) -> Vec<Self> {
+        if self.circuit_type().is_aggregated() {
+            let constructor = self.constructor.clone();
+            let result = constructor
+                .into_iter()
+                .map(|constructor| {
+                    Self::new(
+                        get_optimism_transaction_circuit(constructor.clone()),
+                        1u64,
+                        [constructor].to_vec(),
+                        false,
+                        self.network,
+                    )
                 })
                 .collect_vec();
             result
@@ -272,12 +476,18 @@ impl scheduler::Task for EthReceiptTask {
     type CircuitType = EthReceiptCircuitType;
 
     fn circuit_type(&self) -> Self::CircuitType {
-        EthReceiptCircuitType { network: self.network, aggregated: self.aggregated }
+        EthReceiptCircuitType {
+            network: self.network,
+            aggregated: self.aggregated,
+            transfer_log: self.input.inputs.receipt.transfer_log_index.is_some(),
+        }
     }
 
     fn name(&self) -> String {
         if self.circuit_type().is_aggregated() {
             format!("receipt_aggregated",)
+        } else if self.circuit_type().transfer_log {
+            format!("receipt_transfer_log",)
         } else {
             format!("receipt",)
         }
@@ -329,6 +539,9 @@ impl EthTransactionReceiptTask {
     fn tx_max_len(&self) -> u64 {
         self.constructor[0].eth_transaction.tx_max_len() as u64
     }
+    pub fn transfer_log(&self) -> bool {
+        self.constructor[0].eth_receipt.transfer_log_index.is_some()
+    }
 }
 
 impl scheduler::Task for EthTransactionReceiptTask {
@@ -341,6 +554,7 @@ impl scheduler::Task for EthTransactionReceiptTask {
             tasks_len: self.tasks_len,
             tx_max_len: self.tx_max_len(),
             aggregated: self.aggregated,
+            transfer_log: self.transfer_log(),
         }
     }
 
@@ -353,10 +567,11 @@ impl scheduler::Task for EthTransactionReceiptTask {
             )
         } else {
             format!(
-                "transaction_receipt_{}_tx_{}_max_len_{}",
+                "transaction_receipt_{}_tx_{}_max_len_{}{}",
                 self.tx_type.to_string(),
                 self.hash(),
-                self.tx_max_len()
+                self.tx_max_len(),
+                if self.transfer_log() { "_transfer_log" } else { "" }
             )
         }
     }
@@ -382,6 +597,38 @@ impl scheduler::Task for EthTransactionReceiptTask {
     }
 }
 
+/// Account
+#[derive(Clone, Debug)]
+pub struct EthAccountTask {
+    pub input: EthBlockAccountCircuit,
+    pub network: Network,
+}
+
+impl EthAccountTask {
+    pub fn new(input: EthBlockAccountCircuit, network: Network) -> Self {
+        Self { input, network }
+    }
+}
+
+impl scheduler::Task for EthAccountTask {
+    type CircuitType = EthAccountCircuitType;
+
+    fn circuit_type(&self) -> Self::CircuitType {
+        EthAccountCircuitType { network: self.network }
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "account_{:?}_block_{}",
+            self.input.inputs.storage.addr, self.input.inputs.block_number
+        )
+    }
+
+    fn dependencies(&self) -> Vec<Self> {
+        vec![]
+    }
+}
+
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug)]
 pub enum TransactionInput {
@@ -449,10 +696,54 @@ impl scheduler::Task for MDCStateTask {
 pub struct FinalAssemblyConstructor {
     pub eth_transaction_task: Option<EthTransactionTask>,
     pub zksync_transaction_task: Option<ZkSyncTransactionTask>,
+    pub arbitrum_transaction_task: Option<ArbitrumTransactionTask>,
+    pub optimism_transaction_task: Option<OptimismTransactionTask>,
     pub eth_receipt_task: Option<EthReceiptTask>,
     pub eth_transaction_receipt_task: Option<EthTransactionReceiptTask>,
     pub mdc_state_task: Option<MDCStateTask>,
     pub block_merkle_inclusion_task: Option<BlockMerkleInclusionTask>,
+    /// An L1 account at the block of the transaction block batch, see [`crate::arbitration::types::AccountInput`].
+    pub eth_account_task: Option<EthAccountTask>,
+}
+
+impl FinalAssemblyConstructor {
+    /// The sub-tasks of a final assembly, their snarks are aggregated in this order.
+    pub fn into_tasks(self) -> Vec<ArbitrationTask> {
+        let mut task_array = vec![];
+
+        if let Some(task) = self.eth_transaction_task {
+            task_array.push(ArbitrationTask::EthTransaction(task));
+        }
+        if let Some(task) = self.zksync_transaction_task {
+            let l1_batch_task = task.l1_batch_task.clone();
+            task_array.push(ArbitrationTask::ZkSyncTransaction(task));
+            if let Some(task) = l1_batch_task {
+                task_array.push(ArbitrationTask::ZkSyncL1Batch(task));
+            }
+        }
+        if let Some(task) = self.arbitrum_transaction_task {
+            task_array.push(ArbitrationTask::ArbitrumTransaction(task));
+        }
+        if let Some(task) = self.optimism_transaction_task {
+            task_array.push(ArbitrationTask::OptimismTransaction(task));
+        }
+        if let Some(task) = self.eth_receipt_task {
+            task_array.push(ArbitrationTask::EthReceipt(task));
+        }
+        if let Some(task) = self.eth_transaction_receipt_task {
+            task_array.push(ArbitrationTask::EthTransactionReceipt(task));
+        }
+        if let Some(task) = self.block_merkle_inclusion_task {
+            task_array.push(ArbitrationTask::BlockMerkleInclusion(task));
+        }
+        if let Some(task) = self.mdc_state_task {
+            task_array.push(ArbitrationTask::MDCState(task));
+        }
+        if let Some(task) = self.eth_account_task {
+            task_array.push(ArbitrationTask::EthAccount(task));
+        }
+        task_array
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -471,8 +762,81 @@ impl FinalAssemblyTask {
         from_network: Network,
         to_network: Network,
         constructor: FinalAssemblyConstructor,
-    ) -> Self {
-        Self { round, final_assembly_type, from_network, to_network, constructor }
+    ) -> Result<Self, FinalAssemblyError> {
+        // the maker rule is picked at the L1 block of the transaction, which an OP-Stack block doesn't prove
+        if final_assembly_type == FinalAssemblyType::Source
+            && matches!(from_network, Network::Optimism(_) | Network::Base(_))
+        {
+            return Err(FinalAssemblyError::OpStackSourceIsNotSupported);
+        }
+        // the source final assembly proves the maker rule the transaction was sent under
+        if final_assembly_type == FinalAssemblyType::Source && constructor.mdc_state_task.is_none()
+        {
+            return Err(FinalAssemblyError::MdcStateTaskIsEmpty);
+        }
+        Ok(Self { round, final_assembly_type, from_network, to_network, constructor })
+    }
+
+    /// Locates the public inputs the final assembly compares among the snarks of its sub-tasks.
+    pub fn instance_layout(&self) -> FinalAssemblyInstanceLayout {
+        let tasks = self.constructor.clone().into_tasks();
+        let l1_block_hash_snarks = tasks
+            .iter()
+            .positions(|task| match task {
+                ArbitrationTask::EthTransaction(task) => task.network.is_l1(),
+                ArbitrationTask::EthReceipt(task) => task.network.is_l1(),
+                ArbitrationTask::EthTransactionReceipt(task) => task.network.is_l1(),
+                _ => false,
+            })
+            .collect_vec();
+        let source_transaction_snark = tasks
+            .iter()
+            .position(|task| {
+                matches!(
+                    task,
+                    ArbitrationTask::EthTransactionReceipt(_)
+                        | ArbitrationTask::ZkSyncTransaction(_)
+                        | ArbitrationTask::ArbitrumTransaction(_)
+                        | ArbitrationTask::OptimismTransaction(_)
+                )
+            })
+            .filter(|_| self.final_assembly_type == FinalAssemblyType::Source);
+        let block_merkle_inclusion_snark = tasks
+            .iter()
+            .position(|task| matches!(task, ArbitrationTask::BlockMerkleInclusion(_)))
+            .expect("final assembly should prove the block merkle inclusion");
+        let mdc_state_snark =
+            tasks.iter().position(|task| matches!(task, ArbitrationTask::MDCState(_)));
+        let zksync_transaction_snark =
+            tasks.iter().position(|task| matches!(task, ArbitrationTask::ZkSyncTransaction(_)));
+        let zksync_l1_batch_snark =
+            tasks.iter().position(|task| matches!(task, ArbitrationTask::ZkSyncL1Batch(_)));
+        let account_snark =
+            tasks.iter().position(|task| matches!(task, ArbitrationTask::EthAccount(_)));
+        let transaction_l1_block_number =
+            source_transaction_snark.and_then(|snark_idx| match &tasks[snark_idx] {
+                ArbitrationTask::EthTransactionReceipt(task) if task.network.is_l1() => {
+                    Some((snark_idx, TRANSACTION_INSTANCES_LEN))
+                }
+                ArbitrationTask::ArbitrumTransaction(_) => {
+                    Some((snark_idx, TRANSACTION_INSTANCES_LEN))
+                }
+                ArbitrationTask::ZkSyncTransaction(_) => zksync_l1_batch_snark
+                    .map(|l1_batch_snark| (l1_batch_snark, L1_BLOCK_NUMBER_INSTANCE_OFFSET)),
+                _ => None,
+            });
+
+        FinalAssemblyInstanceLayout {
+            final_assembly_type: self.final_assembly_type.clone(),
+            l1_block_hash_snarks,
+            source_transaction_snark,
+            transaction_l1_block_number,
+            block_merkle_inclusion_snark,
+            mdc_state_snark,
+            zksync_transaction_snark,
+            zksync_l1_batch_snark,
+            account_snark,
+        }
     }
 }
 
@@ -480,11 +844,25 @@ impl scheduler::Task for FinalAssemblyTask {
     type CircuitType = FinalAssemblyCircuitType;
 
     fn circuit_type(&self) -> Self::CircuitType {
+        let deposit_transaction = self
+            .constructor
+            .optimism_transaction_task
+            .as_ref()
+            .map_or(false, |task| task.tx_type == EthTransactionType::DepositTxType);
+        let transfer_log = self
+            .constructor
+            .eth_transaction_receipt_task
+            .as_ref()
+            .map_or(false, |task| task.transfer_log());
+        let account = self.constructor.eth_account_task.is_some();
         FinalAssemblyCircuitType {
             round: self.round,
             aggregation_type: self.final_assembly_type.clone(),
             from_network: self.from_network,
             to_network: self.to_network,
+            deposit_transaction,
+            transfer_log,
+            account,
         }
     }
 
@@ -502,10 +880,14 @@ impl scheduler::Task for FinalAssemblyTask {
 pub enum ArbitrationTask {
     EthTransaction(EthTransactionTask),
     ZkSyncTransaction(ZkSyncTransactionTask),
+    ArbitrumTransaction(ArbitrumTransactionTask),
+    OptimismTransaction(OptimismTransactionTask),
     EthReceipt(EthReceiptTask),
     EthTransactionReceipt(EthTransactionReceiptTask),
     BlockMerkleInclusion(BlockMerkleInclusionTask),
     MDCState(MDCStateTask),
+    ZkSyncL1Batch(ZkSyncL1BatchTask),
+    EthAccount(EthAccountTask),
     Final(FinalAssemblyTask),
 }
 
@@ -523,6 +905,12 @@ impl scheduler::Task for ArbitrationTask {
             ArbitrationTask::ZkSyncTransaction(task) => {
                 ArbitrationCircuitType::Transaction(task.circuit_type())
             }
+            ArbitrationTask::ArbitrumTransaction(task) => {
+                ArbitrationCircuitType::Transaction(task.circuit_type())
+            }
+            ArbitrationTask::OptimismTransaction(task) => {
+                ArbitrationCircuitType::Transaction(task.circuit_type())
+            }
             ArbitrationTask::EthReceipt(task) => {
                 ArbitrationCircuitType::Receipt(task.circuit_type())
             }
@@ -532,6 +920,12 @@ impl scheduler::Task for ArbitrationTask {
             ArbitrationTask::MDCState(task) => {
                 ArbitrationCircuitType::MdcStorage(task.circuit_type())
             }
+            ArbitrationTask::ZkSyncL1Batch(task) => {
+                ArbitrationCircuitType::ZkSyncL1Batch(task.circuit_type())
+            }
+            ArbitrationTask::EthAccount(task) => {
+                ArbitrationCircuitType::Account(task.circuit_type())
+            }
             ArbitrationTask::Final(task) => {
                 ArbitrationCircuitType::FinalAssembly(task.circuit_type())
             }
@@ -543,9 +937,13 @@ impl scheduler::Task for ArbitrationTask {
             ArbitrationTask::BlockMerkleInclusion(task) => task.name(),
             ArbitrationTask::EthTransaction(task) => task.name(),
             ArbitrationTask::ZkSyncTransaction(task) => task.name(),
+            ArbitrationTask::ArbitrumTransaction(task) => task.name(),
+            ArbitrationTask::OptimismTransaction(task) => task.name(),
             ArbitrationTask::EthReceipt(task) => task.name(),
             ArbitrationTask::EthTransactionReceipt(task) => task.name(),
             ArbitrationTask::MDCState(task) => task.name(),
+            ArbitrationTask::ZkSyncL1Batch(task) => task.name(),
+            ArbitrationTask::EthAccount(task) => task.name(),
             ArbitrationTask::Final(task) => task.name(),
         }
     }
@@ -558,6 +956,12 @@ impl scheduler::Task for ArbitrationTask {
             ArbitrationTask::ZkSyncTransaction(task) => {
                 task.dependencies().into_iter().map(ArbitrationTask::ZkSyncTransaction).collect()
             }
+            ArbitrationTask::ArbitrumTransaction(task) => {
+                task.dependencies().into_iter().map(ArbitrationTask::ArbitrumTransaction).collect()
+            }
+            ArbitrationTask::OptimismTransaction(task) => {
+                task.dependencies().into_iter().map(ArbitrationTask::OptimismTransaction).collect()
+            }
             ArbitrationTask::EthReceipt(task) => {
                 task.dependencies().into_iter().map(ArbitrationTask::EthReceipt).collect()
             }
@@ -572,6 +976,12 @@ impl scheduler::Task for ArbitrationTask {
             ArbitrationTask::MDCState(task) => {
                 task.dependencies().into_iter().map(ArbitrationTask::MDCState).collect()
             }
+            ArbitrationTask::ZkSyncL1Batch(task) => {
+                task.dependencies().into_iter().map(ArbitrationTask::ZkSyncL1Batch).collect()
+            }
+            ArbitrationTask::EthAccount(task) => {
+                task.dependencies().into_iter().map(ArbitrationTask::EthAccount).collect()
+            }
             ArbitrationTask::Final(task) => {
                 if task.circuit_type().round != 0 {
                     let mut circuit_type = task.circuit_type().clone();
@@ -581,39 +991,7 @@ impl scheduler::Task for ArbitrationTask {
                         ..task.clone()
                     })];
                 }
-                let task = task.clone();
-                let mut task_array = vec![];
-
-                if task.constructor.eth_transaction_task.is_some() {
-                    task_array.push(ArbitrationTask::EthTransaction(
-                        task.constructor.eth_transaction_task.unwrap(),
-                    ));
-                }
-                if task.constructor.zksync_transaction_task.is_some() {
-                    task_array.push(ArbitrationTask::ZkSyncTransaction(
-                        task.constructor.zksync_transaction_task.unwrap(),
-                    ));
-                }
-                if task.constructor.eth_receipt_task.is_some() {
-                    task_array.push(ArbitrationTask::EthReceipt(
-                        task.constructor.eth_receipt_task.unwrap(),
-                    ));
-                }
-                if task.constructor.eth_transaction_receipt_task.is_some() {
-                    task_array.push(ArbitrationTask::EthTransactionReceipt(
-                        task.constructor.eth_transaction_receipt_task.unwrap(),
-                    ));
-                }
-                if task.constructor.block_merkle_inclusion_task.is_some() {
-                    task_array.push(ArbitrationTask::BlockMerkleInclusion(
-                        task.constructor.block_merkle_inclusion_task.unwrap(),
-                    ));
-                }
-                if task.constructor.mdc_state_task.is_some() {
-                    task_array
-                        .push(ArbitrationTask::MDCState(task.constructor.mdc_state_task.unwrap()));
-                }
-                task_array
+                task.constructor.clone().into_tasks()
             }
         }
     }
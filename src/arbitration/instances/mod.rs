@@ -0,0 +1,222 @@
+//! Names of the public instances of the arbitration snarks, to read a snark or calldata without the circuit.
+//!
+//! An aggregation snark exposes its accumulator followed by the instances of the snarks it aggregates, e.g. the
+//! final assembly of an ethereum transaction is `accumulator,transaction,block-merkle-inclusion:3,mdc-state:N`,
+//! in the order of `FinalAssemblyConstructor::into_tasks`.
+
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use snark_verifier_sdk::LIMBS;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+
+/// `EthBlockTransactionCircuit` and the transaction circuits of the L2s, before any network specific instance.
+const TRANSACTION_INSTANCES: [&str; 14] = [
+    "block_hash_hi",
+    "block_hash_lo",
+    "transaction_hash_hi",
+    "transaction_hash_lo",
+    "chain_id",
+    "index",
+    "from",
+    "to",
+    "token",
+    "amount",
+    "nonce",
+    "time_stamp",
+    "dest_transfer_address",
+    "dest_transfer_token",
+];
+const TRANSFER_LOG_INSTANCES: [&str; 5] = ["log_index", "token", "from", "to", "amount"];
+const ZKSYNC_L1_BATCH_INSTANCES: [&str; 8] = [
+    "l1_block_hash_hi",
+    "l1_block_hash_lo",
+    "l1_block_number",
+    "diamond_proxy",
+    "batch_number",
+    "l2_block_hash_hi",
+    "l2_block_hash_lo",
+    "l2_block_number",
+];
+const ACCOUNT_INSTANCES: [&str; 10] = [
+    "block_hash_hi",
+    "block_hash_lo",
+    "block_number",
+    "address",
+    "nonce",
+    "balance",
+    "storage_root_hi",
+    "storage_root_lo",
+    "code_hash_hi",
+    "code_hash_lo",
+];
+/// `ObContractsStorageCircuit` ends with the blocks of the current and next rules.
+const MDC_STATE_BLOCK_INSTANCES: [&str; 6] = [
+    "current_rule_block_hash_hi",
+    "current_rule_block_hash_lo",
+    "current_rule_block_number",
+    "next_rule_block_hash_hi",
+    "next_rule_block_hash_lo",
+    "next_rule_block_number",
+];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum InstanceLayoutError {
+    #[error("unknown snark `{0}`")]
+    UnknownSnark(String),
+    #[error("`{0}` is not a valid count")]
+    InvalidCount(String),
+    #[error("mdc-state has at least {} instances, got {0}", MDC_STATE_BLOCK_INSTANCES.len())]
+    MdcStateTooShort(usize),
+    #[error("the layout has {expected} instances, got {actual}")]
+    LengthMismatch { expected: usize, actual: usize },
+}
+
+/// The public instances of one snark, as exposed by its circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnarkInstances {
+    /// The KZG accumulator of an aggregation snark, `4 * LIMBS` limbs.
+    Accumulator,
+    /// `EthBlockTransactionCircuit`, and the zkSync Era and Optimism transaction circuits.
+    Transaction,
+    /// `ArbitrumBlockTransactionCircuit`, which also exposes the L1 block number.
+    ArbitrumTransaction,
+    /// `EthBlockReceiptCircuit`, `transfer_log` if the receipt has a transfer log.
+    Receipt { transfer_log: bool },
+    /// `TransactionReceiptCircuit`, which also exposes the receipt block number.
+    TransactionReceipt,
+    /// `BlockMerkleInclusionCircuit`, `[root, target_leaf]` for every block batch.
+    BlockMerkleInclusion { block_batch_num: usize },
+    /// `ZkSyncEraL1BatchCircuit`.
+    ZkSyncL1Batch,
+    /// `EthBlockAccountCircuit`.
+    Account,
+    /// `ObContractsStorageCircuit`, whose number of instances depends on the contracts and slots it proves.
+    MdcState { len: usize },
+}
+
+impl SnarkInstances {
+    fn prefix(&self) -> &'static str {
+        match self {
+            SnarkInstances::Accumulator => "accumulator",
+            SnarkInstances::Transaction => "transaction",
+            SnarkInstances::ArbitrumTransaction => "arbitrum_transaction",
+            SnarkInstances::Receipt { .. } => "receipt",
+            SnarkInstances::TransactionReceipt => "transaction_receipt",
+            SnarkInstances::BlockMerkleInclusion { .. } => "block_merkle_inclusion",
+            SnarkInstances::ZkSyncL1Batch => "zksync_l1_batch",
+            SnarkInstances::Account => "account",
+            SnarkInstances::MdcState { .. } => "mdc_state",
+        }
+    }
+
+    /// The names of the instances, without the name of the snark.
+    pub fn names(&self) -> Vec<String> {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        match *self {
+            SnarkInstances::Accumulator => (0..4 * LIMBS).map(|i| format!("limb_{i}")).collect(),
+            SnarkInstances::Transaction => names(&TRANSACTION_INSTANCES),
+            SnarkInstances::ArbitrumTransaction => {
+                [names(&TRANSACTION_INSTANCES), names(&["l1_block_number"])].concat()
+            }
+            SnarkInstances::Receipt { transfer_log } => {
+                let mut receipt = names(&["block_hash_hi", "block_hash_lo", "index"]);
+                if transfer_log {
+                    receipt.extend(TRANSFER_LOG_INSTANCES.iter().map(|name| format!("log_{name}")));
+                }
+                receipt
+            }
+            SnarkInstances::TransactionReceipt => {
+                [names(&TRANSACTION_INSTANCES), names(&["block_number"])].concat()
+            }
+            SnarkInstances::BlockMerkleInclusion { block_batch_num } => (0..block_batch_num)
+                .flat_map(|i| {
+                    ["root_hi", "root_lo", "target_leaf_hi", "target_leaf_lo"]
+                        .map(|name| format!("batch_{i}_{name}"))
+                })
+                .collect(),
+            SnarkInstances::ZkSyncL1Batch => names(&ZKSYNC_L1_BATCH_INSTANCES),
+            SnarkInstances::Account => names(&ACCOUNT_INSTANCES),
+            SnarkInstances::MdcState { len } => {
+                let contracts_len = len.saturating_sub(MDC_STATE_BLOCK_INSTANCES.len());
+                (0..contracts_len)
+                    .map(|i| format!("contracts_{i}"))
+                    .chain(names(&MDC_STATE_BLOCK_INSTANCES))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl FromStr for SnarkInstances {
+    type Err = InstanceLayoutError;
+
+    /// `accumulator`, `transaction`, `arbitrum-transaction`, `receipt`, `receipt-with-transfer-log`,
+    /// `transaction-receipt`, `block-merkle-inclusion:<block batches>`, `zksync-l1-batch`, `account` or
+    /// `mdc-state:<instances>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, count) = match s.split_once(':') {
+            Some((name, count)) => {
+                let count = count
+                    .parse()
+                    .map_err(|_| InstanceLayoutError::InvalidCount(count.to_string()))?;
+                (name, Some(count))
+            }
+            None => (s, None),
+        };
+        let snark = match (name, count) {
+            ("accumulator", None) => SnarkInstances::Accumulator,
+            ("transaction", None) => SnarkInstances::Transaction,
+            ("arbitrum-transaction", None) => SnarkInstances::ArbitrumTransaction,
+            ("receipt", None) => SnarkInstances::Receipt { transfer_log: false },
+            ("receipt-with-transfer-log", None) => SnarkInstances::Receipt { transfer_log: true },
+            ("transaction-receipt", None) => SnarkInstances::TransactionReceipt,
+            ("block-merkle-inclusion", Some(block_batch_num)) => {
+                SnarkInstances::BlockMerkleInclusion { block_batch_num }
+            }
+            ("zksync-l1-batch", None) => SnarkInstances::ZkSyncL1Batch,
+            ("account", None) => SnarkInstances::Account,
+            ("mdc-state", Some(len)) => {
+                if len < MDC_STATE_BLOCK_INSTANCES.len() {
+                    return Err(InstanceLayoutError::MdcStateTooShort(len));
+                }
+                SnarkInstances::MdcState { len }
+            }
+            _ => return Err(InstanceLayoutError::UnknownSnark(s.to_string())),
+        };
+        Ok(snark)
+    }
+}
+
+/// The names of the instances of a snark that exposes the instances of `layout` one after the other,
+/// each prefixed with the name of its snark and its position among the snarks of that kind.
+pub fn instance_names(layout: &[SnarkInstances]) -> Vec<String> {
+    layout
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, snark)| {
+            let position = layout[..idx].iter().filter(|s| s.prefix() == snark.prefix()).count();
+            snark
+                .names()
+                .into_iter()
+                .map(move |name| format!("{}_{position}.{name}", snark.prefix()))
+        })
+        .collect()
+}
+
+/// Pairs `instances` with their names in `layout`.
+pub fn name_instances(
+    layout: &[SnarkInstances],
+    instances: &[Fr],
+) -> Result<Vec<(String, Fr)>, InstanceLayoutError> {
+    let names = instance_names(layout);
+    if names.len() != instances.len() {
+        return Err(InstanceLayoutError::LengthMismatch {
+            expected: names.len(),
+            actual: instances.len(),
+        });
+    }
+    Ok(names.into_iter().zip(instances.iter().copied()).collect())
+}
@@ -0,0 +1,60 @@
+use super::{instance_names, name_instances, InstanceLayoutError, SnarkInstances};
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+
+#[test]
+fn test_parse_snark_instances() {
+    assert_eq!("transaction".parse(), Ok(SnarkInstances::Transaction));
+    assert_eq!("account".parse(), Ok(SnarkInstances::Account));
+    assert_eq!(
+        "receipt-with-transfer-log".parse(),
+        Ok(SnarkInstances::Receipt { transfer_log: true })
+    );
+    assert_eq!(
+        "block-merkle-inclusion:3".parse(),
+        Ok(SnarkInstances::BlockMerkleInclusion { block_batch_num: 3 })
+    );
+    assert_eq!(
+        "block-merkle-inclusion".parse::<SnarkInstances>(),
+        Err(InstanceLayoutError::UnknownSnark("block-merkle-inclusion".to_string()))
+    );
+    assert_eq!(
+        "mdc-state:x".parse::<SnarkInstances>(),
+        Err(InstanceLayoutError::InvalidCount("x".to_string()))
+    );
+    assert_eq!(
+        "mdc-state:5".parse::<SnarkInstances>(),
+        Err(InstanceLayoutError::MdcStateTooShort(5))
+    );
+}
+
+#[test]
+fn test_final_assembly_instance_names() {
+    let layout = [
+        SnarkInstances::Accumulator,
+        SnarkInstances::Transaction,
+        SnarkInstances::BlockMerkleInclusion { block_batch_num: 3 },
+        SnarkInstances::MdcState { len: 8 },
+    ];
+    let names = instance_names(&layout);
+    assert_eq!(names.len(), 12 + 14 + 12 + 8);
+    assert_eq!(names[0], "accumulator_0.limb_0");
+    assert_eq!(names[12], "transaction_0.block_hash_hi");
+    assert_eq!(names[26 + 4], "block_merkle_inclusion_0.batch_1_root_hi");
+    assert_eq!(names[38], "mdc_state_0.contracts_0");
+    assert_eq!(names.last().unwrap(), "mdc_state_0.next_rule_block_number");
+}
+
+#[test]
+fn test_aggregated_instance_names() {
+    // an aggregated transaction snark re-exposes the instances of every transaction
+    let names = instance_names(&[SnarkInstances::Transaction, SnarkInstances::Transaction]);
+    assert_eq!(names[14], "transaction_1.block_hash_hi");
+
+    let instances = (0..names.len() as u64).map(Fr::from).collect::<Vec<_>>();
+    let named = name_instances(&[SnarkInstances::Transaction; 2], &instances).unwrap();
+    assert_eq!(named[15], ("transaction_1.block_hash_lo".to_string(), Fr::from(15)));
+    assert_eq!(
+        name_instances(&[SnarkInstances::Transaction], &instances),
+        Err(InstanceLayoutError::LengthMismatch { expected: 14, actual: 28 })
+    );
+}
@@ -18,8 +18,10 @@ use crate::util::scheduler::{SchedulerCommon, Task};
 pub mod circuit_types;
 pub mod final_assembly;
 pub mod helper;
+pub mod instances;
 mod network_pairs;
 pub mod router;
+#[cfg(test)]
 pub mod test;
 pub mod types;
 
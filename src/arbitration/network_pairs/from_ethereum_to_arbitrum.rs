@@ -0,0 +1,124 @@
+use crate::arbitration::helper::{
+    ArbitrumTransactionTask, BlockMerkleInclusionTask, EthReceiptTask, EthTransactionReceiptTask,
+    FinalAssemblyConstructor,
+};
+use crate::arbitration::network_pairs::utils::get_mdc_state_task;
+use crate::arbitration::network_pairs::NetworkPairs;
+use crate::arbitration::types::{BatchBlocksInput, ObContractStorageInput, TransactionInput};
+use crate::receipt::util::{
+    get_erc20_transfer_log_index, ReceiptConstructor, RECEIPT_PF_MAX_DEPTH,
+};
+use crate::track_block::BlockMerkleInclusionCircuit;
+use crate::transaction::util::{
+    get_arbitrum_transaction_circuit, TransactionConstructor, TRANSACTION_PF_MAX_DEPTH,
+};
+use crate::transaction::EthTransactionType;
+use crate::transaction_receipt::util::TransactionReceiptConstructor;
+use crate::util::errors::COMMIT_TRANSACTION_IS_EMPTY;
+
+pub fn parse_from_ethereum_to_arbitrum(
+    pairs: &NetworkPairs,
+    ob_contract_storage_input: Option<ObContractStorageInput>,
+    batch_blocks_input: BatchBlocksInput,
+    original_transaction: TransactionInput,
+    commit_transaction: Option<TransactionInput>,
+) -> FinalAssemblyConstructor {
+    let (_, _, is_source) = pairs.get_details();
+    let (l1_network, l2_network) = pairs.get_layer_network();
+
+    let mut arbitrum_transaction_task = None;
+    let mut eth_receipt_task = None;
+    let mut eth_transaction_receipt_task = None;
+    let mut mdc_state_task = None;
+
+    let batch_blocks_task_input = BlockMerkleInclusionCircuit::from_json_object(batch_blocks_input);
+    let block_merkle_inclusion_task = Some(BlockMerkleInclusionTask::new(
+        batch_blocks_task_input.clone(),
+        l1_network,
+        batch_blocks_task_input.block_batch_num,
+        8,
+        batch_blocks_task_input.block_range_length,
+    ));
+
+    let mut original_transaction_constructor = TransactionConstructor::new(
+        original_transaction.transaction_hash,
+        Some(original_transaction.transaction_proof.key.clone()),
+        Some(original_transaction.transaction_proof.value.clone()),
+        Some(original_transaction.transaction_proof.proof.clone()),
+        Some(TRANSACTION_PF_MAX_DEPTH),
+        l1_network,
+    );
+
+    if is_source {
+        // ob storage
+        let storage_input = ob_contract_storage_input.as_ref().unwrap();
+        mdc_state_task = Some(get_mdc_state_task(storage_input, l1_network));
+
+        let original_receipt_constructor = ReceiptConstructor::new(
+            original_transaction.transaction_hash,
+            Some(original_transaction.receipt_proof.key.clone()),
+            original_transaction.receipt_proof.value.clone(),
+            original_transaction.receipt_proof.proof.clone(),
+            RECEIPT_PF_MAX_DEPTH,
+            l1_network,
+            get_erc20_transfer_log_index(
+                &original_transaction.transaction_proof.value,
+                &original_transaction.receipt_proof.value,
+            ),
+        );
+
+        let original_transaction_receipt_constructor = TransactionReceiptConstructor::new(
+            original_transaction_constructor,
+            original_receipt_constructor,
+        );
+
+        eth_transaction_receipt_task = Some(EthTransactionReceiptTask::new(
+            original_transaction_receipt_constructor.clone().get_circuit(),
+            original_transaction_receipt_constructor.eth_transaction.tx_type(),
+            1,
+            vec![original_transaction_receipt_constructor],
+            false,
+            l1_network,
+        ));
+    } else {
+        // The arbitrum block is committed to L1 through the rollup assertion, it is proven by the receipt of the commit tx.
+        let commit_transaction = commit_transaction.expect(COMMIT_TRANSACTION_IS_EMPTY);
+        let commit_receipt_constructor = ReceiptConstructor::new(
+            commit_transaction.transaction_hash,
+            Some(commit_transaction.receipt_proof.key.clone()),
+            commit_transaction.receipt_proof.value.clone(),
+            commit_transaction.receipt_proof.proof.clone(),
+            RECEIPT_PF_MAX_DEPTH,
+            l1_network,
+            None,
+        );
+        original_transaction_constructor.network = l2_network;
+
+        arbitrum_transaction_task = Some(ArbitrumTransactionTask::new(
+            get_arbitrum_transaction_circuit(original_transaction_constructor.clone()),
+            EthTransactionType::DynamicFeeTxType,
+            1,
+            vec![original_transaction_constructor],
+            false,
+            l2_network,
+        ));
+        eth_receipt_task = Some(EthReceiptTask::new(
+            commit_receipt_constructor.clone().get_circuit(),
+            vec![commit_receipt_constructor],
+            false,
+            l1_network,
+        ));
+    }
+
+    FinalAssemblyConstructor {
+        eth_transaction_task: None,
+        zksync_transaction_task: None,
+        arbitrum_transaction_task,
+        optimism_transaction_task: None,
+        eth_receipt_task,
+        eth_transaction_receipt_task,
+        mdc_state_task,
+        block_merkle_inclusion_task,
+        eth_account_task: None,
+    }
+}
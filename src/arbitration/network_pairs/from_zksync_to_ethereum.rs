@@ -1,15 +1,13 @@
 use crate::arbitration::helper::{
     BlockMerkleInclusionTask, EthReceiptTask, EthTransactionReceiptTask, EthTransactionTask,
-    FinalAssemblyConstructor, MDCStateTask, ZkSyncTransactionTask,
+    FinalAssemblyConstructor, ZkSyncTransactionTask,
 };
+use crate::arbitration::network_pairs::utils::{get_mdc_state_task, get_zksync_l1_batch_task};
 use crate::arbitration::network_pairs::NetworkPairs;
 use crate::arbitration::types::{BatchBlocksInput, ObContractStorageInput, TransactionInput};
-use crate::receipt::util::{ReceiptConstructor, RECEIPT_PF_MAX_DEPTH};
-use crate::storage::contract_storage::util::{
-    EbcRuleParams, MultiBlocksContractsStorageConstructor, ObContractStorageConstructor,
-    SingleBlockContractsStorageConstructor, EBC_RULE_PF_MAX_DEPTH,
+use crate::receipt::util::{
+    get_erc20_transfer_log_index, ReceiptConstructor, RECEIPT_PF_MAX_DEPTH,
 };
-use crate::storage::util::{ACCOUNT_PF_MAX_DEPTH, STORAGE_PF_MAX_DEPTH};
 use crate::track_block::BlockMerkleInclusionCircuit;
 use crate::transaction::util::{
     get_eth_transaction_circuit, get_zksync_transaction_circuit, TransactionConstructor,
@@ -18,7 +16,6 @@ use crate::transaction::util::{
 use crate::transaction::EthTransactionType;
 use crate::transaction_receipt::util::TransactionReceiptConstructor;
 use crate::util::errors::COMMIT_TRANSACTION_IS_EMPTY;
-use ethers_core::types::H256;
 
 pub fn parse_from_zksync_to_ethereum(
     pairs: &NetworkPairs,
@@ -58,61 +55,7 @@ pub fn parse_from_zksync_to_ethereum(
     if is_source {
         let storage_input = ob_contract_storage_input.as_ref().unwrap();
 
-        let mdc_contract_storage_current_constructor = ObContractStorageConstructor::new(
-            storage_input.mdc_address,
-            storage_input.contracts_slots_hash[..5].to_vec(),
-            ACCOUNT_PF_MAX_DEPTH,
-            STORAGE_PF_MAX_DEPTH,
-        );
-
-        let manage_contract_storage_current_constructor = ObContractStorageConstructor::new(
-            storage_input.manage_address,
-            storage_input.contracts_slots_hash[5..].to_vec(),
-            ACCOUNT_PF_MAX_DEPTH,
-            STORAGE_PF_MAX_DEPTH,
-        );
-
-        let mdc_contract_storage_next_constructor = ObContractStorageConstructor::new(
-            storage_input.mdc_address,
-            storage_input.contracts_slots_hash[1..3].to_vec(),
-            ACCOUNT_PF_MAX_DEPTH,
-            STORAGE_PF_MAX_DEPTH,
-        );
-
-        let single_block_contracts_storage_constructor_current =
-            SingleBlockContractsStorageConstructor::new(
-                storage_input.mdc_current_enable_time_block_number as u32,
-                vec![
-                    mdc_contract_storage_current_constructor,
-                    manage_contract_storage_current_constructor,
-                ],
-            );
-        let single_block_contracts_storage_constructor_next =
-            SingleBlockContractsStorageConstructor::new(
-                storage_input.mdc_next_enable_time_block_number as u32,
-                vec![mdc_contract_storage_next_constructor],
-            );
-        let ob_contracts_constructor = MultiBlocksContractsStorageConstructor::new(
-            vec![
-                single_block_contracts_storage_constructor_current,
-                single_block_contracts_storage_constructor_next,
-            ],
-            EbcRuleParams::new(
-                H256::from_slice(&*storage_input.mdc_current_rule.key.clone()),
-                storage_input.mdc_current_rule.root.unwrap(),
-                storage_input.mdc_current_rule.value.clone(),
-                storage_input.mdc_current_rule.proof.clone(),
-                EBC_RULE_PF_MAX_DEPTH,
-            ),
-            l1_network,
-        );
-        mdc_state_task = Some(MDCStateTask::new(
-            ob_contracts_constructor.clone().get_circuit(),
-            2,
-            2,
-            vec![ob_contracts_constructor],
-            false,
-        ));
+        mdc_state_task = Some(get_mdc_state_task(storage_input, l1_network));
 
         // Todo: Currently the maximum encoding is not supported.
         // let commit_transaction = commit_transaction.expect(COMMIT_TRANSACTION_IS_EMPTY);
@@ -126,13 +69,18 @@ pub fn parse_from_zksync_to_ethereum(
         //     l1_network,
         // );
 
+        let zksync_transaction_circuit =
+            get_zksync_transaction_circuit(original_transaction_constructor.clone());
+        let l1_batch_task =
+            get_zksync_l1_batch_task(&zksync_transaction_circuit, l1_network, l2_network);
         zksync_transaction_task = Some(ZkSyncTransactionTask::new(
-            get_zksync_transaction_circuit(original_transaction_constructor.clone()),
+            zksync_transaction_circuit,
             EthTransactionType::DynamicFeeTxType,
             1,
             vec![original_transaction_constructor],
             false,
             l2_network,
+            Some(l1_batch_task),
         ));
 
         // eth_transaction_task = Some(EthTransactionTask::new(
@@ -152,6 +100,10 @@ pub fn parse_from_zksync_to_ethereum(
             original_transaction.receipt_proof.proof.clone(),
             RECEIPT_PF_MAX_DEPTH,
             l1_network,
+            get_erc20_transfer_log_index(
+                &original_transaction.transaction_proof.value,
+                &original_transaction.receipt_proof.value,
+            ),
         );
         let original_transaction_receipt_constructor = TransactionReceiptConstructor::new(
             original_transaction_constructor,
@@ -159,7 +111,7 @@ pub fn parse_from_zksync_to_ethereum(
         );
         eth_transaction_receipt_task = Some(EthTransactionReceiptTask::new(
             original_transaction_receipt_constructor.clone().get_circuit(),
-            EthTransactionType::DynamicFeeTxType,
+            original_transaction_receipt_constructor.eth_transaction.tx_type(),
             1,
             vec![original_transaction_receipt_constructor],
             false,
@@ -170,9 +122,12 @@ pub fn parse_from_zksync_to_ethereum(
     FinalAssemblyConstructor {
         eth_transaction_task,
         zksync_transaction_task,
+        arbitrum_transaction_task: None,
+        optimism_transaction_task: None,
         eth_receipt_task,
         eth_transaction_receipt_task,
         mdc_state_task,
         block_merkle_inclusion_task,
+        eth_account_task: None,
     }
 }
@@ -1,12 +1,20 @@
 use crate::arbitration::helper::FinalAssemblyConstructor;
+use crate::arbitration::network_pairs::from_arbitrum_to_ethereum::parse_from_arbitrum_to_ethereum;
+use crate::arbitration::network_pairs::from_ethereum_to_arbitrum::parse_from_ethereum_to_arbitrum;
+use crate::arbitration::network_pairs::from_ethereum_to_optimism::parse_from_ethereum_to_optimism;
 use crate::arbitration::network_pairs::from_ethereum_to_zksync::parse_from_ethereum_to_zksync;
+use crate::arbitration::network_pairs::from_optimism_to_ethereum::parse_from_optimism_to_ethereum;
 use crate::arbitration::network_pairs::from_zksync_to_ethereum::parse_from_zksync_to_ethereum;
 use crate::arbitration::types::{BatchBlocksInput, ObContractStorageInput, TransactionInput};
 use crate::util::errors::{ErrorType, COMMIT_TRANSACTION_IS_EMPTY};
 use crate::Network;
 use serde::{Deserialize, Serialize};
 
+mod from_arbitrum_to_ethereum;
+mod from_ethereum_to_arbitrum;
+mod from_ethereum_to_optimism;
 mod from_ethereum_to_zksync;
+mod from_optimism_to_ethereum;
 mod from_zksync_to_ethereum;
 mod utils;
 
@@ -14,6 +22,11 @@ mod utils;
 pub enum NetworkPairs {
     FromEthereumToZkSync(Network, Network, bool),
     FromZkSyncToEthereum(Network, Network, bool),
+    FromEthereumToArbitrum(Network, Network, bool),
+    FromArbitrumToEthereum(Network, Network, bool),
+    // Optimism and Base, all OP-Stack chains share the same circuits
+    FromEthereumToOptimism(Network, Network, bool),
+    FromOptimismToEthereum(Network, Network, bool),
 }
 
 impl NetworkPairs {
@@ -25,6 +38,22 @@ impl NetworkPairs {
             && matches!(to_network, Network::Ethereum(_))
         {
             return Some(NetworkPairs::FromZkSyncToEthereum(from_network, to_network, is_source));
+        } else if matches!(from_network, Network::Ethereum(_))
+            && matches!(to_network, Network::Arbitrum(_))
+        {
+            return Some(NetworkPairs::FromEthereumToArbitrum(from_network, to_network, is_source));
+        } else if matches!(from_network, Network::Arbitrum(_))
+            && matches!(to_network, Network::Ethereum(_))
+        {
+            return Some(NetworkPairs::FromArbitrumToEthereum(from_network, to_network, is_source));
+        } else if matches!(from_network, Network::Ethereum(_))
+            && matches!(to_network, Network::Optimism(_) | Network::Base(_))
+        {
+            return Some(NetworkPairs::FromEthereumToOptimism(from_network, to_network, is_source));
+        } else if matches!(from_network, Network::Optimism(_) | Network::Base(_))
+            && matches!(to_network, Network::Ethereum(_))
+        {
+            return Some(NetworkPairs::FromOptimismToEthereum(from_network, to_network, is_source));
         }
         None
     }
@@ -51,6 +80,34 @@ impl NetworkPairs {
                 original_transaction,
                 commit_transaction,
             ),
+            NetworkPairs::FromEthereumToArbitrum(_, ..) => parse_from_ethereum_to_arbitrum(
+                self,
+                ob_contract_storage_input,
+                batch_blocks_input,
+                original_transaction,
+                commit_transaction,
+            ),
+            NetworkPairs::FromArbitrumToEthereum(_, ..) => parse_from_arbitrum_to_ethereum(
+                self,
+                ob_contract_storage_input,
+                batch_blocks_input,
+                original_transaction,
+                commit_transaction,
+            ),
+            NetworkPairs::FromEthereumToOptimism(_, ..) => parse_from_ethereum_to_optimism(
+                self,
+                ob_contract_storage_input,
+                batch_blocks_input,
+                original_transaction,
+                commit_transaction,
+            ),
+            NetworkPairs::FromOptimismToEthereum(_, ..) => parse_from_optimism_to_ethereum(
+                self,
+                ob_contract_storage_input,
+                batch_blocks_input,
+                original_transaction,
+                commit_transaction,
+            ),
         }
     }
 
@@ -62,6 +119,18 @@ impl NetworkPairs {
             NetworkPairs::FromZkSyncToEthereum(from_network, to_network, is_source) => {
                 (from_network.clone(), to_network.clone(), *is_source)
             }
+            NetworkPairs::FromEthereumToArbitrum(from_network, to_network, is_source) => {
+                (from_network.clone(), to_network.clone(), *is_source)
+            }
+            NetworkPairs::FromArbitrumToEthereum(from_network, to_network, is_source) => {
+                (from_network.clone(), to_network.clone(), *is_source)
+            }
+            NetworkPairs::FromEthereumToOptimism(from_network, to_network, is_source) => {
+                (from_network.clone(), to_network.clone(), *is_source)
+            }
+            NetworkPairs::FromOptimismToEthereum(from_network, to_network, is_source) => {
+                (from_network.clone(), to_network.clone(), *is_source)
+            }
         }
     }
 
@@ -1 +1,99 @@
+use crate::arbitration::helper::{MDCStateTask, ZkSyncL1BatchTask};
+use crate::arbitration::types::ObContractStorageInput;
+use crate::block_header::zksync_era::l1_batch::{
+    ZkSyncEraL1BatchCircuit, ZkSyncEraL1BatchConstructor,
+};
+use crate::storage::contract_storage::util::{
+    EbcRuleParams, MultiBlocksContractsStorageConstructor, ObContractStorageConstructor,
+    SingleBlockContractsStorageConstructor, EBC_RULE_PF_MAX_DEPTH,
+};
+use crate::storage::util::{ACCOUNT_PF_MAX_DEPTH, STORAGE_PF_MAX_DEPTH};
+use crate::transaction::zksync_era::ZkSyncEraBlockTransactionCircuit;
+use crate::util::helpers::get_provider;
+use crate::Network;
+use ethers_core::types::H256;
 
+/// The MDC state is always proven on L1, whatever the network pairs is.
+pub(crate) fn get_mdc_state_task(
+    storage_input: &ObContractStorageInput,
+    l1_network: Network,
+) -> MDCStateTask {
+    let mdc_contract_storage_current_constructor = ObContractStorageConstructor::new(
+        storage_input.mdc_address,
+        storage_input.contracts_slots_hash[..5].to_vec(),
+        ACCOUNT_PF_MAX_DEPTH,
+        STORAGE_PF_MAX_DEPTH,
+    );
+
+    let manage_contract_storage_current_constructor = ObContractStorageConstructor::new(
+        storage_input.manage_address,
+        storage_input.contracts_slots_hash[5..].to_vec(),
+        ACCOUNT_PF_MAX_DEPTH,
+        STORAGE_PF_MAX_DEPTH,
+    );
+
+    let mdc_contract_storage_next_constructor = ObContractStorageConstructor::new(
+        storage_input.mdc_address,
+        storage_input.contracts_slots_hash[1..3].to_vec(),
+        ACCOUNT_PF_MAX_DEPTH,
+        STORAGE_PF_MAX_DEPTH,
+    );
+
+    let single_block_contracts_storage_constructor_current =
+        SingleBlockContractsStorageConstructor::new(
+            storage_input.mdc_current_enable_time_block_number as u32,
+            vec![
+                mdc_contract_storage_current_constructor,
+                manage_contract_storage_current_constructor,
+            ],
+        );
+    let single_block_contracts_storage_constructor_next =
+        SingleBlockContractsStorageConstructor::new(
+            storage_input.mdc_next_enable_time_block_number as u32,
+            vec![mdc_contract_storage_next_constructor],
+        );
+    let ob_contracts_constructor = MultiBlocksContractsStorageConstructor::new(
+        vec![
+            single_block_contracts_storage_constructor_current,
+            single_block_contracts_storage_constructor_next,
+        ],
+        EbcRuleParams::new(
+            H256::from_slice(&*storage_input.mdc_current_rule.key.clone()),
+            storage_input.mdc_current_rule.root.unwrap(),
+            storage_input.mdc_current_rule.value.clone(),
+            storage_input.mdc_current_rule.proof.clone(),
+            EBC_RULE_PF_MAX_DEPTH,
+        ),
+        l1_network,
+    );
+    MDCStateTask::new(
+        ob_contracts_constructor.clone().get_circuit(),
+        2,
+        2,
+        vec![ob_contracts_constructor],
+        false,
+    )
+}
+
+/// Links the block of a zkSync Era transaction to the batch executing it on L1.
+pub(crate) fn get_zksync_l1_batch_task(
+    transaction_circuit: &ZkSyncEraBlockTransactionCircuit,
+    l1_network: Network,
+    l2_network: Network,
+) -> ZkSyncL1BatchTask {
+    let zksync_network = match l2_network {
+        Network::ZkSync(zksync_network) => zksync_network,
+        _ => panic!("the L2 network should be zkSync Era"),
+    };
+    let constructor = ZkSyncEraL1BatchConstructor::new(
+        transaction_circuit.inputs.block_header.block_number(),
+        l1_network,
+        zksync_network,
+    );
+    let l1_batch_circuit = ZkSyncEraL1BatchCircuit::from_provider(
+        &get_provider(&l1_network),
+        &get_provider(&l2_network),
+        constructor,
+    );
+    ZkSyncL1BatchTask::new(l1_batch_circuit, l1_network)
+}
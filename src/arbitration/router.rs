@@ -1,13 +1,15 @@
 use crate::arbitration::helper::ArbitrationTask::Final;
 use crate::arbitration::helper::FinalAssemblyTask;
 use crate::arbitration::types::SchedulerRouterConstructor;
+use crate::gen::solidity::SolidityVerifier;
+use crate::util::errors::ProofRequestError;
 use crate::util::helpers::get_provider;
 use crate::util::scheduler::arbitration_scheduler::ArbitrationScheduler;
-use crate::util::scheduler::Scheduler;
+use crate::util::scheduler::{Scheduler, Task};
 use crate::Network;
 use ark_std::{end_timer, start_timer};
 use itertools::Itertools;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 pub struct SchedulerRouter {
@@ -16,18 +18,25 @@ pub struct SchedulerRouter {
 }
 
 impl SchedulerRouter {
-    pub fn new(constructor: SchedulerRouterConstructor, round: usize) -> Self {
-        let task = constructor.proof.get_final_task(round);
+    pub fn new(
+        constructor: SchedulerRouterConstructor,
+        round: usize,
+    ) -> Result<Self, ProofRequestError> {
+        let task = constructor.proof.get_final_task(round)?;
         let arbitration_scheduler = ArbitrationScheduler::default(task.from_network);
-        SchedulerRouter { arbitration_scheduler: Some(arbitration_scheduler), task: Some(task) }
+        Ok(SchedulerRouter { arbitration_scheduler: Some(arbitration_scheduler), task: Some(task) })
     }
 
     pub fn default() -> Self {
         Self { arbitration_scheduler: None, task: None }
     }
 
-    pub fn update(&mut self, constructor: SchedulerRouterConstructor, round: usize) {
-        let task = constructor.proof.get_final_task(round);
+    pub fn update(
+        &mut self,
+        constructor: SchedulerRouterConstructor,
+        round: usize,
+    ) -> Result<(), ProofRequestError> {
+        let task = constructor.proof.get_final_task(round)?;
         if let Some(arbitration_scheduler) = self.arbitration_scheduler.as_mut() {
             arbitration_scheduler.network = task.from_network;
             arbitration_scheduler.provider = Arc::from(get_provider(&task.from_network));
@@ -35,6 +44,12 @@ impl SchedulerRouter {
             self.arbitration_scheduler = Some(ArbitrationScheduler::default(task.from_network));
         }
         self.task = Some(task);
+        Ok(())
+    }
+
+    /// The names of the tasks proven by `get_calldata`, dependencies first.
+    pub fn stages(&self) -> Vec<String> {
+        Final(self.task.clone().unwrap()).dag_names()
     }
 
     pub fn cache_srs_pk_files(&self) {
@@ -56,4 +71,34 @@ impl SchedulerRouter {
         end_timer!(real_proof_time);
         calldata
     }
+
+    /// Writes the Solidity verifier and verifying key contracts of the final circuit to `dir`,
+    /// [`crate::util::scheduler::arbitration_scheduler::ARBITRATION_SOLIDITY_DIR`] by default.
+    ///
+    /// The final circuit is an aggregation circuit, its `num_instance` public inputs start with the accumulator.
+    /// Returns `None` until its proving key is loaded, e.g. by `cache_srs_pk_files` or `get_calldata`.
+    pub fn gen_solidity_verifier(
+        &self,
+        num_instance: usize,
+        dir: impl AsRef<Path>,
+    ) -> Option<SolidityVerifier> {
+        self.arbitration_scheduler.as_ref().unwrap().gen_solidity_verifier(
+            &Final(self.task.clone().unwrap()).circuit_type(),
+            num_instance,
+            true,
+            dir.as_ref(),
+        )
+    }
+
+    /// The calldata for the verifier of `gen_solidity_verifier`, whose verifying key contract is deployed at `vk_address`.
+    pub fn get_solidity_calldata(&self, vk_address: [u8; 20]) -> String {
+        let real_proof_time = start_timer!(|| "Real Proof time");
+        let calldata = self
+            .arbitration_scheduler
+            .as_ref()
+            .unwrap()
+            .get_solidity_calldata(Final(self.task.clone().unwrap()), vk_address);
+        end_timer!(real_proof_time);
+        calldata
+    }
 }
@@ -1,11 +1,11 @@
 use std::str::FromStr;
 use std::{
-    env::set_var,
     fs,
     ops::Range,
     path::{Path, PathBuf},
 };
 
+use crate::arbitration::final_assembly::FinalAssemblyType;
 use crate::arbitration::helper::{
     EthReceiptTask, EthTransactionTask, FinalAssemblyConstructor, FinalAssemblyTask, MDCStateTask,
     ZkSyncTransactionTask,
@@ -20,18 +20,17 @@ use crate::transaction::util::{
     get_eth_transaction_circuit, get_zksync_transaction_circuit, TransactionConstructor,
 };
 use crate::transaction::EthTransactionType;
+use crate::util::errors::FinalAssemblyError;
 use crate::{
     rlp::builder::{RlcThreadBreakPoints, RlcThreadBuilder},
-    storage::{
-        tests::get_test_circuit as get_test_storage_circuit, EthBlockStorageCircuit,
-        StorageConfigParams,
-    },
+    storage::{tests::get_test_circuit as get_test_storage_circuit, EthBlockStorageCircuit},
     util::{
         circuit::custom_gen_evm_verifier_shplonk,
+        circuit_config::{isolate_env, CircuitConfig, CircuitConfigScope},
         scheduler::{arbitration_scheduler::ArbitrationScheduler, Scheduler},
         EthConfigParams,
     },
-    EthPreCircuit, EthereumNetwork, Network, ZkSyncEraNetwork,
+    EthPreCircuit, EthereumNetwork, Network, OptimismNetwork, ZkSyncEraNetwork,
 };
 use ark_std::{end_timer, start_timer};
 use ethers_core::types::{Address, Bytes, H256};
@@ -50,6 +49,41 @@ use snark_verifier_sdk::{
 
 use super::helper::{ArbitrationTask, BlockMerkleInclusionTask};
 
+#[test]
+pub fn test_op_stack_source_final_assembly_is_rejected() {
+    let constructor = FinalAssemblyConstructor {
+        eth_transaction_task: None,
+        zksync_transaction_task: None,
+        arbitrum_transaction_task: None,
+        optimism_transaction_task: None,
+        eth_receipt_task: None,
+        eth_transaction_receipt_task: None,
+        mdc_state_task: None,
+        block_merkle_inclusion_task: None,
+        eth_account_task: None,
+    };
+    let from_network = Network::Optimism(OptimismNetwork::Mainnet);
+    let to_network = Network::Ethereum(EthereumNetwork::Mainnet);
+    let err = FinalAssemblyTask::new(
+        0,
+        FinalAssemblyType::Source,
+        from_network,
+        to_network,
+        constructor.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, FinalAssemblyError::OpStackSourceIsNotSupported));
+    // the destination doesn't bind the transaction to the MDC state
+    FinalAssemblyTask::new(
+        0,
+        FinalAssemblyType::Destination,
+        from_network,
+        to_network,
+        constructor,
+    )
+    .unwrap();
+}
+
 fn test_get_storage_circuit(network: Network, block_number: u32) -> EthBlockStorageCircuit {
     get_test_storage_circuit(network, block_number)
 }
@@ -190,6 +224,7 @@ fn test_zksync_era_transaction_task(network: Network) -> ZkSyncTransactionTask {
         constructor: vec![constructor],
         aggregated: false,
         network,
+        l1_batch_task: None,
     }
 }
 
@@ -311,6 +346,7 @@ fn test_transaction_receipt(network: Network) -> EthReceiptTask {
         merkle_proof,
         RECEIPT_PF_MAX_DEPTH,
         network,
+        None,
     );
 
     EthReceiptTask {
@@ -357,11 +393,10 @@ pub fn test_arbitration_scheduler_transaction_receipt_task() {
 #[test]
 pub fn test_arbitration_circuit() {
     let transaction_param = EthConfigParams::from_path("configs/arbitration/ethereum_tx.json");
-    let storage_param = StorageConfigParams::from_path("configs/arbitration/storage.json");
+    let storage_param = EthConfigParams::from_path("configs/arbitration/storage.json");
     let evm_param = AggregationConfigParams::from_path("configs/arbitration/arbitration_evm.json");
 
     let (eth_tx_snark, eth_tx_proof_time) = {
-        set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&transaction_param).unwrap());
         let transaction_hash =
             H256::from_str("0x9fe482ff766f354529914bba6eef4bb6abcd288aa42de0ea00db2ec12f343fc4")
                 .unwrap();
@@ -389,7 +424,9 @@ pub fn test_arbitration_circuit() {
 
         let k = transaction_param.degree;
         let input = get_eth_transaction_circuit(constructor);
-        let circuit = input.clone().create_circuit(RlcThreadBuilder::keygen(), None);
+        let circuit =
+            input.clone().create_circuit(RlcThreadBuilder::keygen(), transaction_param, None);
+        let _scope = circuit.config_scope();
         let manual_break_points = RlcThreadBreakPoints {
             gate: [
                 [
@@ -415,21 +452,24 @@ pub fn test_arbitration_circuit() {
         let break_points_t = circuit.circuit.break_points.take();
         let params = gen_srs(k);
         let pk = gen_pk(&params, &circuit, None);
+        let config_params = circuit.config_params();
         let break_points = circuit.circuit.break_points.take();
         let storage_proof_time = start_timer!(|| "Ethereum Tx Proof SHPLONK");
-        let circuit = input.create_circuit(RlcThreadBuilder::prover(), Some(break_points));
+        let circuit =
+            input.create_circuit(RlcThreadBuilder::prover(), config_params, Some(break_points));
         let snark = gen_snark_shplonk(&params, &pk, circuit, None::<&str>);
         end_timer!(storage_proof_time);
         (snark, storage_proof_time)
     };
 
     let (storage_snark, storage_proof_time) = {
-        set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&storage_param).unwrap());
         let k = storage_param.degree;
         let input = test_get_storage_circuit(Network::Ethereum(EthereumNetwork::Goerli), 9731724);
-        let circuit = input.clone().create_circuit(RlcThreadBuilder::keygen(), None);
+        let circuit = input.clone().create_circuit(RlcThreadBuilder::keygen(), storage_param, None);
+        let _scope = circuit.config_scope();
         let params = gen_srs(k);
         let pk = gen_pk(&params, &circuit, None);
+        let config_params = circuit.config_params();
         let break_points = circuit.circuit.break_points.take();
         println!("break_points {:?}", break_points);
         let manual_break_points = RlcThreadBreakPoints {
@@ -442,7 +482,8 @@ pub fn test_arbitration_circuit() {
             rlc: [].into(),
         };
         let storage_proof_time = start_timer!(|| "Storage Proof SHPLONK");
-        let circuit = input.create_circuit(RlcThreadBuilder::prover(), Some(break_points));
+        let circuit =
+            input.create_circuit(RlcThreadBuilder::prover(), config_params, Some(break_points));
         let snark = gen_snark_shplonk(&params, &pk, circuit, None::<&str>);
         end_timer!(storage_proof_time);
         (snark, storage_proof_time)
@@ -450,7 +491,6 @@ pub fn test_arbitration_circuit() {
 
     let k = evm_param.degree;
     let params = gen_srs(k);
-    set_var("LOOKUP_BITS", evm_param.lookup_bits.to_string());
     let evm_circuit = AggregationCircuit::public::<SHPLONK>(
         CircuitBuilderStage::Keygen,
         None,
@@ -460,7 +500,10 @@ pub fn test_arbitration_circuit() {
         // vec![eth_tx_snark.clone()],
         false,
     );
-    evm_circuit.config(k, Some(10));
+    let _scope = CircuitConfigScope::enter(CircuitConfig::Gate {
+        params: isolate_env(|| evm_circuit.config(k, Some(10))),
+        lookup_bits: evm_param.lookup_bits,
+    });
     let pk = gen_pk(&params, &evm_circuit, None);
     let break_points = evm_circuit.break_points();
     println!("arbitration evm break_points {:?}", break_points);
@@ -1,34 +1,37 @@
 use crate::arbitration::final_assembly::FinalAssemblyType;
-use crate::arbitration::helper::FinalAssemblyTask;
+use crate::arbitration::helper::{EthAccountTask, FinalAssemblyTask};
 use crate::server::OriginalProof;
+use crate::storage::account::{get_account_circuit, AccountConstructor};
 use crate::storage::contract_storage::util::ObContractStorageConstructor;
+use crate::storage::util::ACCOUNT_PF_MAX_DEPTH;
 
 use crate::arbitration::network_pairs::NetworkPairs;
+use crate::util::errors::ProofRequestError;
 use crate::{get_network_from_chain_id, Network};
 use ethers_core::types::{Address, Bytes, H256};
 use hex::FromHex;
+use serde::de::{DeserializeOwned, Error as _};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt::Debug;
-use std::str::FromStr;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+// The `proof` of a `generate_proof` request is parsed by hand into these types, so that a bad value
+// is reported with its json path. Every hash, address and proof node is hex with or without the
+// `0x` prefix.
+
+#[derive(Clone, Debug)]
 pub struct BatchBlocksMerkleInput {
-    #[serde(rename(deserialize = "blockHashBatch"))]
     pub block_hash_batch: Vec<H256>,
-    #[serde(rename(deserialize = "blockBatchMerkleRoot"))]
     pub block_batch_merkle_root: H256,
-    #[serde(rename(deserialize = "targetBlockIndex"))]
     pub target_block_index: u32,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug)]
 pub struct BatchBlocksInput {
-    #[serde(rename(deserialize = "batchData"))]
     pub batch_blocks_merkle: Vec<BatchBlocksMerkleInput>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct MerkleProof {
     pub key: Vec<u8>,
     pub value: Vec<u8>,
@@ -36,287 +39,644 @@ pub struct MerkleProof {
     pub root: Option<H256>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug)]
 pub struct ObContractStorageInput {
-    #[serde(rename(deserialize = "mdcAddress"))]
     pub mdc_address: Address,
-    #[serde(rename(deserialize = "manageAddress"))]
     pub manage_address: Address,
-    #[serde(rename(deserialize = "mdcCurrentEnableTimeBlockNumber"))]
     pub mdc_current_enable_time_block_number: u64,
-    #[serde(rename(deserialize = "mdcNextEnableTimeBlockNumber"))]
     pub mdc_next_enable_time_block_number: u64,
-    #[serde(rename(deserialize = "mdcCurrentRuleProof"), skip)]
     pub mdc_current_rule: MerkleProof,
     /// see [`ObContractStorageConstructor`]
-    #[serde(rename(deserialize = "contractsSlotsHash"))]
     pub contracts_slots_hash: [H256; 9],
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// An L1 account proven at the block of the transaction, e.g. to check the ETH balance of a maker.
+#[derive(Clone, Debug)]
+pub struct AccountInput {
+    pub address: Address,
+    pub block_number: u32,
+}
+
+#[derive(Clone, Debug)]
 pub struct TransactionInput {
-    #[serde(rename(deserialize = "transactionHash"))]
     pub transaction_hash: H256,
-    #[serde(rename(deserialize = "transactionProof"), skip)]
     pub transaction_proof: MerkleProof,
-    #[serde(rename(deserialize = "transactionProofEnable"))]
     pub transaction_proof_enable: bool,
-    #[serde(rename(deserialize = "receiptProof"), skip)]
     pub receipt_proof: MerkleProof,
-    #[serde(rename(deserialize = "receiptProofEnable"))]
     pub receipt_proof_enable: bool,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug)]
 pub struct TransactionsInput {
-    #[serde(rename(deserialize = "originalTransaction"))]
     pub original_transaction: TransactionInput,
-    #[serde(rename(deserialize = "commitTransaction"))]
     pub commit_transaction: Option<TransactionInput>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ProofConfig {
-    #[serde(rename(deserialize = "isSource"))]
     pub is_source: bool,
-    #[serde(rename(deserialize = "isL2"))]
     pub is_l2: bool,
-    #[serde(rename(deserialize = "sourceNetwork"))]
+    #[serde(alias = "fromNetwork")]
     pub source_network: u64,
-    #[serde(rename(deserialize = "destNetwork"))]
+    #[serde(alias = "toNetwork")]
     pub dest_network: u64,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug)]
 pub struct ProofInput {
-    #[serde(rename(deserialize = "transactionsInput"))]
     pub transactions_input: TransactionsInput,
-    #[serde(rename(deserialize = "obContractStorageInput"))]
     pub ob_contract_storage_input: Option<ObContractStorageInput>,
-    #[serde(rename(deserialize = "blockBatchData"))]
     pub batch_blocks_input: BatchBlocksInput,
-    #[serde(rename(deserialize = "config"))]
+    pub account_input: Option<AccountInput>,
     pub config: ProofConfig,
 }
 
 impl ProofInput {
-    pub fn get_final_task(self, round: usize) -> FinalAssemblyTask {
+    pub fn get_final_task(self, round: usize) -> Result<FinalAssemblyTask, ProofRequestError> {
         let is_source = self.config.is_source;
-        let source_network = get_network_from_chain_id(self.config.source_network).unwrap();
-        let dest_network = get_network_from_chain_id(self.config.dest_network).unwrap();
-        let pairs = NetworkPairs::new_pairs(source_network, dest_network, is_source).unwrap();
+        let (source_network, dest_network) = self.config.networks()?;
+        let pairs = NetworkPairs::new_pairs(source_network, dest_network, is_source).ok_or(
+            ProofRequestError::UnsupportedNetworkPair {
+                source_network: self.config.source_network,
+                dest_network: self.config.dest_network,
+            },
+        )?;
         let ob_contract_storage_input = self.ob_contract_storage_input;
         let batch_blocks_input = self.batch_blocks_input;
         let original_transaction = self.transactions_input.original_transaction;
         let commit_transaction = self.transactions_input.commit_transaction;
-        let constructor = pairs.parse_pairs_task(
+        let mut constructor = pairs.parse_pairs_task(
             ob_contract_storage_input,
             batch_blocks_input,
             original_transaction,
             commit_transaction,
         );
+        let (l1_network, _) = pairs.get_layer_network();
+        constructor.eth_account_task = self.account_input.map(|input| {
+            let constructor = AccountConstructor {
+                block_number: input.block_number,
+                address: input.address,
+                acct_pf_max_depth: ACCOUNT_PF_MAX_DEPTH,
+                network: l1_network,
+            };
+            EthAccountTask::new(get_account_circuit(constructor), l1_network)
+        });
 
         let final_assembly_type =
             if is_source { FinalAssemblyType::Source } else { FinalAssemblyType::Destination };
 
-        FinalAssemblyTask::new(
+        Ok(FinalAssemblyTask::new(
             round,
             final_assembly_type,
             source_network,
             dest_network,
             constructor,
-        )
+        )?)
     }
 }
+
 #[derive(Clone, Debug)]
 pub struct SchedulerRouterConstructor {
     pub proof: ProofInput,
 }
 
-impl OriginalProof {
-    pub fn get_constructor_by_parse_proof(self) -> SchedulerRouterConstructor {
-        let value: Value = serde_json::from_str(self.proof.as_str()).unwrap();
-        let mut proof_params = serde_json::from_str::<ProofInput>(self.proof.as_str()).unwrap();
+/// The value of `key` in the json object `value`, a `null` value is the same as a missing key.
+fn optional_field<'a>(
+    value: &'a Value,
+    field: &str,
+    key: &str,
+) -> Result<Option<&'a Value>, ProofRequestError> {
+    let object = value.as_object().ok_or_else(|| {
+        ProofRequestError::MalformedJson(serde_json::Error::custom(format!(
+            "{field} should be an object"
+        )))
+    })?;
+    Ok(object.get(key).filter(|value| !value.is_null()))
+}
 
-        // If the challenge is from L2, commit tx needs to be loaded
-        if proof_params.config.is_l2 {
-            let commit_transaction =
-                proof_params.transactions_input.commit_transaction.clone().unwrap();
-            if commit_transaction.transaction_proof_enable {
-                let mut transaction_merkle_proof_proof: Vec<Bytes> = vec![];
-                let proofs = value["transactionsInput"]["commitTransaction"]["transactionProof"]
-                    ["proof"]
-                    .as_array()
-                    .unwrap();
-                for proof in proofs {
-                    let proof_bytes = Vec::from_hex(proof.as_str().unwrap()).unwrap();
-                    transaction_merkle_proof_proof.push(Bytes::from(proof_bytes));
-                }
+/// A missing field which is not optional makes the json malformed, like a value of the wrong type.
+fn required_field<'a>(
+    value: &'a Value,
+    field: &str,
+    key: &str,
+) -> Result<&'a Value, ProofRequestError> {
+    optional_field(value, field, key)?.ok_or_else(|| {
+        ProofRequestError::MalformedJson(serde_json::Error::custom(format!(
+            "missing field `{}`",
+            join_field(field, key)
+        )))
+    })
+}
 
-                let transaction_merkle_proof = MerkleProof {
-                    key: Vec::from_hex(
-                        &value["transactionsInput"]["commitTransaction"]["transactionProof"]["key"]
-                            .as_str()
-                            .unwrap(),
-                    )
-                    .unwrap(),
-                    value: Vec::from_hex(
-                        &value["transactionsInput"]["commitTransaction"]["transactionProof"]
-                            ["value"]
-                            .as_str()
-                            .unwrap(),
-                    )
-                    .unwrap(),
-                    proof: transaction_merkle_proof_proof,
-                    root: None,
-                };
-
-                proof_params
-                    .transactions_input
-                    .commit_transaction
-                    .as_mut()
-                    .unwrap()
-                    .transaction_proof = transaction_merkle_proof;
-            }
+fn join_field(field: &str, key: &str) -> String {
+    if field.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{field}.{key}")
+    }
+}
 
-            if commit_transaction.receipt_proof_enable {
-                let mut receipt_merkle_proof_proof: Vec<Bytes> = vec![];
-                let proofs = value["transactionsInput"]["commitTransaction"]["receiptProof"]
-                    ["proof"]
-                    .as_array()
-                    .unwrap();
-                for proof in proofs {
-                    let proof_bytes = Vec::from_hex(proof.as_str().unwrap()).unwrap();
-                    receipt_merkle_proof_proof.push(Bytes::from(proof_bytes));
-                }
+fn parse_value<T: DeserializeOwned>(value: &Value) -> Result<T, ProofRequestError> {
+    Ok(T::deserialize(value)?)
+}
 
-                let receipt_merkle_proof = MerkleProof {
-                    key: Vec::from_hex(
-                        &value["transactionsInput"]["commitTransaction"]["receiptProof"]["key"]
-                            .as_str()
-                            .unwrap(),
-                    )
-                    .unwrap(),
-                    value: Vec::from_hex(
-                        &value["transactionsInput"]["commitTransaction"]["receiptProof"]["value"]
-                            .as_str()
-                            .unwrap(),
-                    )
-                    .unwrap(),
-                    proof: receipt_merkle_proof_proof,
-                    root: None,
-                };
-
-                proof_params
-                    .transactions_input
-                    .commit_transaction
-                    .as_mut()
-                    .unwrap()
-                    .receipt_proof = receipt_merkle_proof;
-            }
+fn decode_hex(field: &str, hex: &str) -> Result<Vec<u8>, ProofRequestError> {
+    Vec::from_hex(hex.strip_prefix("0x").unwrap_or(hex))
+        .map_err(|_| ProofRequestError::InvalidHex { field: field.to_owned() })
+}
+
+fn decode_non_empty_hex(field: &str, hex: &str) -> Result<Vec<u8>, ProofRequestError> {
+    let bytes = decode_hex(field, hex)?;
+    if bytes.is_empty() {
+        return Err(ProofRequestError::Empty { field: field.to_owned() });
+    }
+    Ok(bytes)
+}
+
+fn decode_fixed_hex<const N: usize>(field: &str, hex: &str) -> Result<[u8; N], ProofRequestError> {
+    decode_hex(field, hex)?.try_into().map_err(|bytes: Vec<u8>| ProofRequestError::InvalidLength {
+        field: field.to_owned(),
+        expected: N,
+        actual: bytes.len(),
+    })
+}
+
+fn decode_h256(field: &str, hex: &str) -> Result<H256, ProofRequestError> {
+    decode_fixed_hex(field, hex).map(H256)
+}
+
+fn decode_address(field: &str, hex: &str) -> Result<Address, ProofRequestError> {
+    decode_fixed_hex(field, hex).map(Address::from)
+}
+
+/// Decodes the hex string `key` of `value` with `decode`.
+fn hex_field<T>(
+    value: &Value,
+    field: &str,
+    key: &str,
+    decode: fn(&str, &str) -> Result<T, ProofRequestError>,
+) -> Result<T, ProofRequestError> {
+    let hex: String = parse_value(required_field(value, field, key)?)?;
+    decode(&join_field(field, key), &hex)
+}
+
+/// Decodes the array of hex strings `key` of `value` with `decode`.
+fn hex_array_field<T>(
+    value: &Value,
+    field: &str,
+    key: &str,
+    decode: fn(&str, &str) -> Result<T, ProofRequestError>,
+) -> Result<Vec<T>, ProofRequestError> {
+    let items: Vec<String> = parse_value(required_field(value, field, key)?)?;
+    let field = join_field(field, key);
+    items.iter().enumerate().map(|(i, hex)| decode(&format!("{field}[{i}]"), hex)).collect()
+}
+
+impl ProofConfig {
+    fn networks(&self) -> Result<(Network, Network), ProofRequestError> {
+        let source_network = get_network_from_chain_id(self.source_network)
+            .map_err(|_| ProofRequestError::UnsupportedNetwork(self.source_network))?;
+        let dest_network = get_network_from_chain_id(self.dest_network)
+            .map_err(|_| ProofRequestError::UnsupportedNetwork(self.dest_network))?;
+        Ok((source_network, dest_network))
+    }
+}
+
+impl MerkleProof {
+    fn from_json(value: &Value, field: &str, with_root: bool) -> Result<Self, ProofRequestError> {
+        let proof = hex_array_field(value, field, "proof", decode_non_empty_hex)?;
+        if proof.is_empty() {
+            return Err(ProofRequestError::Empty { field: join_field(field, "proof") });
         }
+        let root = if with_root {
+            let root = optional_field(value, field, "root")?
+                .ok_or(ProofRequestError::Missing { field: join_field(field, "root") })?;
+            Some(decode_h256(&join_field(field, "root"), &parse_value::<String>(root)?)?)
+        } else {
+            None
+        };
+        Ok(MerkleProof {
+            key: hex_field(value, field, "key", decode_non_empty_hex)?,
+            value: hex_field(value, field, "value", decode_non_empty_hex)?,
+            proof: proof.into_iter().map(Bytes::from).collect(),
+            root,
+        })
+    }
+}
 
-        // Load original tx
-        if proof_params.transactions_input.original_transaction.transaction_proof_enable {
-            let mut transaction_merkle_proof_proof: Vec<Bytes> = vec![];
-            let proofs = value["transactionsInput"]["originalTransaction"]["transactionProof"]
-                ["proof"]
-                .as_array()
-                .unwrap();
-            for proof in proofs {
-                let proof_bytes = Vec::from_hex(proof.as_str().unwrap()).unwrap();
-                transaction_merkle_proof_proof.push(Bytes::from(proof_bytes));
-            }
+/// An enabled proof is required, a disabled one is left empty.
+fn enabled_proof_field(
+    value: &Value,
+    field: &str,
+    key: &str,
+    enable: bool,
+) -> Result<MerkleProof, ProofRequestError> {
+    if !enable {
+        return Ok(MerkleProof::default());
+    }
+    let proof_field = join_field(field, key);
+    let proof = optional_field(value, field, key)?
+        .ok_or(ProofRequestError::Missing { field: proof_field.clone() })?;
+    MerkleProof::from_json(proof, &proof_field, false)
+}
 
-            let transaction_merkle_proof = MerkleProof {
-                key: Vec::from_hex(
-                    &value["transactionsInput"]["originalTransaction"]["transactionProof"]["key"]
-                        .as_str()
-                        .unwrap(),
-                )
-                .unwrap(),
-                value: Vec::from_hex(
-                    &value["transactionsInput"]["originalTransaction"]["transactionProof"]["value"]
-                        .as_str()
-                        .unwrap(),
-                )
-                .unwrap(),
-                proof: transaction_merkle_proof_proof,
-                root: None,
-            };
+impl TransactionInput {
+    fn from_json(value: &Value, field: &str) -> Result<Self, ProofRequestError> {
+        let transaction_proof_enable =
+            parse_value(required_field(value, field, "transactionProofEnable")?)?;
+        let receipt_proof_enable =
+            parse_value(required_field(value, field, "receiptProofEnable")?)?;
+        Ok(TransactionInput {
+            transaction_hash: hex_field(value, field, "transactionHash", decode_h256)?,
+            transaction_proof: enabled_proof_field(
+                value,
+                field,
+                "transactionProof",
+                transaction_proof_enable,
+            )?,
+            transaction_proof_enable,
+            receipt_proof: enabled_proof_field(value, field, "receiptProof", receipt_proof_enable)?,
+            receipt_proof_enable,
+        })
+    }
+}
+
+impl ObContractStorageInput {
+    /// The mdc current rule merkle proof is only proven by the source final assembly.
+    fn from_json(
+        value: &Value,
+        field: &str,
+        with_current_rule: bool,
+    ) -> Result<Self, ProofRequestError> {
+        let mdc_current_rule = if with_current_rule {
+            let rule_field = join_field(field, "mdcCurrentRuleProof");
+            let rule = optional_field(value, field, "mdcCurrentRuleProof")?
+                .ok_or(ProofRequestError::Missing { field: rule_field.clone() })?;
+            MerkleProof::from_json(rule, &rule_field, true)?
+        } else {
+            MerkleProof::default()
+        };
+        let contracts_slots_hash =
+            hex_array_field(value, field, "contractsSlotsHash", decode_h256)?;
+        let actual = contracts_slots_hash.len();
+        Ok(ObContractStorageInput {
+            mdc_address: hex_field(value, field, "mdcAddress", decode_address)?,
+            manage_address: hex_field(value, field, "manageAddress", decode_address)?,
+            mdc_current_enable_time_block_number: parse_value(required_field(
+                value,
+                field,
+                "mdcCurrentEnableTimeBlockNumber",
+            )?)?,
+            mdc_next_enable_time_block_number: parse_value(required_field(
+                value,
+                field,
+                "mdcNextEnableTimeBlockNumber",
+            )?)?,
+            mdc_current_rule,
+            contracts_slots_hash: contracts_slots_hash.try_into().map_err(|_| {
+                ProofRequestError::InvalidCount {
+                    field: join_field(field, "contractsSlotsHash"),
+                    expected: 9,
+                    actual,
+                }
+            })?,
+        })
+    }
+}
+
+impl AccountInput {
+    fn from_json(value: &Value, field: &str) -> Result<Self, ProofRequestError> {
+        Ok(AccountInput {
+            address: hex_field(value, field, "address", decode_address)?,
+            block_number: parse_value(required_field(value, field, "blockNumber")?)?,
+        })
+    }
+}
 
-            proof_params.transactions_input.original_transaction.transaction_proof =
-                transaction_merkle_proof;
+impl BatchBlocksMerkleInput {
+    fn from_json(value: &Value, field: &str) -> Result<Self, ProofRequestError> {
+        let block_hash_batch = hex_array_field(value, field, "blockHashBatch", decode_h256)?;
+        if block_hash_batch.is_empty() {
+            return Err(ProofRequestError::Empty { field: join_field(field, "blockHashBatch") });
         }
-        if proof_params.transactions_input.original_transaction.receipt_proof_enable {
-            let mut receipt_merkle_proof_proof: Vec<Bytes> = vec![];
-            let proofs = value["transactionsInput"]["originalTransaction"]["receiptProof"]["proof"]
-                .as_array()
-                .unwrap();
-            for proof in proofs {
-                let proof_bytes = Vec::from_hex(proof.as_str().unwrap()).unwrap();
-                receipt_merkle_proof_proof.push(Bytes::from(proof_bytes));
-            }
+        let target_block_index: u32 =
+            parse_value(required_field(value, field, "targetBlockIndex")?)?;
+        if target_block_index as usize >= block_hash_batch.len() {
+            return Err(ProofRequestError::TargetBlockIndexOutOfRange {
+                field: join_field(field, "targetBlockIndex"),
+                index: target_block_index,
+                len: block_hash_batch.len(),
+            });
+        }
+        Ok(BatchBlocksMerkleInput {
+            block_batch_merkle_root: hex_field(value, field, "blockBatchMerkleRoot", decode_h256)?,
+            block_hash_batch,
+            target_block_index,
+        })
+    }
+}
 
-            let receipt_merkle_proof = MerkleProof {
-                key: Vec::from_hex(
-                    &value["transactionsInput"]["originalTransaction"]["receiptProof"]["key"]
-                        .as_str()
-                        .unwrap(),
-                )
-                .unwrap(),
-                value: Vec::from_hex(
-                    &value["transactionsInput"]["originalTransaction"]["receiptProof"]["value"]
-                        .as_str()
-                        .unwrap(),
-                )
-                .unwrap(),
-                proof: receipt_merkle_proof_proof,
-                root: None,
-            };
+impl BatchBlocksInput {
+    pub(crate) fn from_json(value: &Value, field: &str) -> Result<Self, ProofRequestError> {
+        let batch_field = join_field(field, "batchData");
+        let batch_data: Vec<Value> = parse_value(required_field(value, field, "batchData")?)?;
+        if batch_data.is_empty() {
+            return Err(ProofRequestError::Empty { field: batch_field });
+        }
+        let batch_blocks_merkle = batch_data
+            .iter()
+            .enumerate()
+            .map(|(i, batch)| {
+                BatchBlocksMerkleInput::from_json(batch, &format!("{batch_field}[{i}]"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(BatchBlocksInput { batch_blocks_merkle })
+    }
+}
 
-            proof_params.transactions_input.original_transaction.receipt_proof =
-                receipt_merkle_proof;
+impl ProofInput {
+    /// Parses and validates the proof json of a `generate_proof` request.
+    pub fn from_request_json(proof: &str) -> Result<Self, ProofRequestError> {
+        let request: Value = serde_json::from_str(proof)?;
+        let config: ProofConfig = parse_value(required_field(&request, "", "config")?)?;
+        let (source_network, dest_network) = config.networks()?;
+        if NetworkPairs::new_pairs(source_network, dest_network, config.is_source).is_none() {
+            return Err(ProofRequestError::UnsupportedNetworkPair {
+                source_network: config.source_network,
+                dest_network: config.dest_network,
+            });
         }
 
-        // Load mdc current rule merkle proof
-        if proof_params.config.is_source {
-            let mut mdc_current_rule_merkle_proof_proof: Vec<Bytes> = vec![];
-            let proofs =
-                value["obContractStorageInput"]["mdcCurrentRuleProof"]["proof"].as_array().unwrap();
-            for proof in proofs {
-                let proof_bytes = Vec::from_hex(proof.as_str().unwrap()).unwrap();
-                mdc_current_rule_merkle_proof_proof.push(Bytes::from(proof_bytes));
-            }
+        let transactions = required_field(&request, "", "transactionsInput")?;
+        let original_transaction = TransactionInput::from_json(
+            required_field(transactions, "transactionsInput", "originalTransaction")?,
+            "transactionsInput.originalTransaction",
+        )?;
+        // If the challenge is from L2, commit tx needs to be loaded
+        let commit_transaction = if config.is_l2 {
+            let commit_field = "transactionsInput.commitTransaction";
+            let commit = optional_field(transactions, "transactionsInput", "commitTransaction")?
+                .ok_or(ProofRequestError::Missing { field: commit_field.to_owned() })?;
+            Some(TransactionInput::from_json(commit, commit_field)?)
+        } else {
+            None
+        };
 
-            let mdc_pre_rule_merkle_proof = MerkleProof {
-                key: Vec::from_hex(
-                    &value["obContractStorageInput"]["mdcCurrentRuleProof"]["key"]
-                        .as_str()
-                        .unwrap(),
-                )
-                .unwrap(),
-                value: Vec::from_hex(
-                    &value["obContractStorageInput"]["mdcCurrentRuleProof"]["value"]
-                        .as_str()
-                        .unwrap(),
-                )
-                .unwrap(),
-                proof: mdc_current_rule_merkle_proof_proof,
-                root: Option::from(
-                    H256::from_str(
-                        &value["obContractStorageInput"]["mdcCurrentRuleProof"]["root"]
-                            .as_str()
-                            .unwrap(),
-                    )
-                    .unwrap(),
-                ),
+        let ob_contract_storage_input =
+            match optional_field(&request, "", "obContractStorageInput")? {
+                Some(input) => Some(ObContractStorageInput::from_json(
+                    input,
+                    "obContractStorageInput",
+                    config.is_source,
+                )?),
+                None if config.is_source => {
+                    return Err(ProofRequestError::Missing {
+                        field: "obContractStorageInput".to_owned(),
+                    })
+                }
+                None => None,
             };
 
-            proof_params.ob_contract_storage_input.as_mut().unwrap().mdc_current_rule =
-                mdc_pre_rule_merkle_proof;
+        let batch_blocks_input = BatchBlocksInput::from_json(
+            required_field(&request, "", "blockBatchData")?,
+            "blockBatchData",
+        )?;
+
+        let account_input = optional_field(&request, "", "accountInput")?
+            .map(|input| AccountInput::from_json(input, "accountInput"))
+            .transpose()?;
+
+        Ok(ProofInput {
+            transactions_input: TransactionsInput { original_transaction, commit_transaction },
+            ob_contract_storage_input,
+            batch_blocks_input,
+            account_input,
+            config,
+        })
+    }
+}
+
+impl OriginalProof {
+    pub fn get_constructor_by_parse_proof(
+        self,
+    ) -> Result<SchedulerRouterConstructor, ProofRequestError> {
+        Ok(SchedulerRouterConstructor { proof: ProofInput::from_request_json(&self.proof)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::fs;
+
+    const SOURCE_REQUEST: &str = "test_data/from_ethereum_to_zksync_era_source.json";
+    const DEST_REQUEST: &str = "test_data/from_ethereum_to_zksync_era_dest.json";
+
+    fn read_request(path: &str) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    /// Replaces (or removes, if `value` is `None`) the value at the json `pointer` of a valid request.
+    fn parse_modified_request(
+        path: &str,
+        pointer: &str,
+        value: Option<Value>,
+    ) -> Result<ProofInput, ProofRequestError> {
+        let mut request: Value = serde_json::from_str(&read_request(path)).unwrap();
+        let (parent, key) = pointer.rsplit_once('/').unwrap();
+        match (request.pointer_mut(parent).unwrap(), value) {
+            (Value::Object(map), Some(value)) => {
+                map.insert(key.to_owned(), value);
+            }
+            (Value::Object(map), None) => {
+                map.remove(key);
+            }
+            (Value::Array(items), Some(value)) => items[key.parse::<usize>().unwrap()] = value,
+            (Value::Array(items), None) => {
+                items.remove(key.parse::<usize>().unwrap());
+            }
+            _ => panic!("{parent} is not an object or an array"),
         }
+        ProofInput::from_request_json(&request.to_string())
+    }
 
-        SchedulerRouterConstructor { proof: proof_params }
+    #[test]
+    fn test_parse_test_data_requests() {
+        for path in [
+            SOURCE_REQUEST,
+            DEST_REQUEST,
+            "test_data/from_zksync_era_to_ethereum_source.json",
+            "test_data/from_zksync_era_to_ethereum_dest.json",
+        ] {
+            ProofInput::from_request_json(&read_request(path))
+                .unwrap_or_else(|err| panic!("{path}: {err}"));
+        }
+
+        let source = ProofInput::from_request_json(&read_request(SOURCE_REQUEST)).unwrap();
+        let mdc_current_rule = source.ob_contract_storage_input.unwrap().mdc_current_rule;
+        assert_eq!(
+            mdc_current_rule.root,
+            Some(H256::from_slice(
+                &Vec::from_hex("19ca13a2aa593e50bf9341f672d763bbac94081d92a8e8de74efda7806d2450f")
+                    .unwrap()
+            ))
+        );
+        assert_eq!(
+            source
+                .batch_blocks_input
+                .batch_blocks_merkle
+                .iter()
+                .map(|batch| batch.target_block_index)
+                .collect::<Vec<_>>(),
+            [122, 80, 123]
+        );
+
+        let dest = ProofInput::from_request_json(&read_request(DEST_REQUEST)).unwrap();
+        assert!(dest.transactions_input.commit_transaction.is_some());
+        assert!(dest.ob_contract_storage_input.is_none());
+        // disabled proofs are not sent
+        assert!(dest.transactions_input.original_transaction.transaction_proof.proof.is_empty());
+        // the account is optional
+        assert!(source.account_input.is_none());
+    }
+
+    #[test]
+    fn test_parse_account_input() {
+        let address = "0xafcfbb382b28dae47b76224f24ee29be2c823648";
+        let account = json!({ "address": address, "blockNumber": 10160720 });
+        let input = parse_modified_request(SOURCE_REQUEST, "/accountInput", Some(account)).unwrap();
+        let account_input = input.account_input.unwrap();
+        assert_eq!(account_input.address, address.parse::<Address>().unwrap());
+        assert_eq!(account_input.block_number, 10160720);
+    }
+
+    #[test]
+    fn test_malformed_requests() {
+        let corpus = [
+            (
+                SOURCE_REQUEST,
+                "/transactionsInput/originalTransaction/transactionHash",
+                Some(json!("0x1234")),
+                "transactionsInput.originalTransaction.transactionHash should be 32 bytes, got 2",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/transactionsInput/originalTransaction/transactionHash",
+                Some(json!("0xzz")),
+                "transactionsInput.originalTransaction.transactionHash is not a hex string",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/transactionsInput/originalTransaction/transactionProof",
+                None,
+                "transactionsInput.originalTransaction.transactionProof is required",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/transactionsInput/originalTransaction/transactionProof/key",
+                Some(json!("")),
+                "transactionsInput.originalTransaction.transactionProof.key should not be empty",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/transactionsInput/originalTransaction/receiptProof/proof",
+                Some(json!([])),
+                "transactionsInput.originalTransaction.receiptProof.proof should not be empty",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/transactionsInput/originalTransaction/receiptProof/proof/1",
+                Some(json!("0xabc")),
+                "transactionsInput.originalTransaction.receiptProof.proof[1] is not a hex string",
+            ),
+            (SOURCE_REQUEST, "/obContractStorageInput", None, "obContractStorageInput is required"),
+            (
+                SOURCE_REQUEST,
+                "/obContractStorageInput/mdcAddress",
+                Some(json!("0x3661c469")),
+                "obContractStorageInput.mdcAddress should be 20 bytes, got 4",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/obContractStorageInput/mdcCurrentRuleProof/root",
+                None,
+                "obContractStorageInput.mdcCurrentRuleProof.root is required",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/obContractStorageInput/contractsSlotsHash/8",
+                None,
+                "obContractStorageInput.contractsSlotsHash should have 9 items, got 8",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/blockBatchData/batchData",
+                Some(json!([])),
+                "blockBatchData.batchData should not be empty",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/blockBatchData/batchData/1/targetBlockIndex",
+                Some(json!(192)),
+                "blockBatchData.batchData[1].targetBlockIndex is 192 but the batch has 192 blocks",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/blockBatchData/batchData/2/blockHashBatch/5",
+                Some(json!("0x00")),
+                "blockBatchData.batchData[2].blockHashBatch[5] should be 32 bytes, got 1",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/config/sourceNetwork",
+                Some(json!(56)),
+                "chain id 56 is not supported",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/config/destNetwork",
+                Some(json!(1)),
+                "arbitration from chain id 5 to chain id 1 is not supported",
+            ),
+            (
+                DEST_REQUEST,
+                "/transactionsInput/commitTransaction",
+                Some(Value::Null),
+                "transactionsInput.commitTransaction is required",
+            ),
+            (
+                SOURCE_REQUEST,
+                "/accountInput",
+                Some(json!({ "address": "0x1234", "blockNumber": 10160720 })),
+                "accountInput.address should be 20 bytes, got 2",
+            ),
+        ];
+        for (path, pointer, value, expected) in corpus {
+            let err = parse_modified_request(path, pointer, value).unwrap_err();
+            assert_eq!(err.to_string(), expected, "{pointer}");
+        }
+    }
+
+    #[test]
+    fn test_malformed_json_requests() {
+        let corpus = [
+            ("/config/isSource", None),
+            ("/obContractStorageInput/mdcCurrentEnableTimeBlockNumber", Some(json!("10160720"))),
+            ("/blockBatchData/batchData/0/blockHashBatch", Some(json!("0x00"))),
+            (
+                "/accountInput",
+                Some(json!({ "address": "0xafcfbb382b28dae47b76224f24ee29be2c823648" })),
+            ),
+        ];
+        for (pointer, value) in corpus {
+            let err = parse_modified_request(SOURCE_REQUEST, pointer, value).unwrap_err();
+            assert!(matches!(err, ProofRequestError::MalformedJson(_)), "{pointer}: {err}");
+        }
+        assert!(matches!(
+            ProofInput::from_request_json("{"),
+            Err(ProofRequestError::MalformedJson(_))
+        ));
     }
 }
@@ -24,8 +24,8 @@ fn main() {
     let proof_str: Value = serde_json::from_reader(data_reader).unwrap();
 
     let op = OriginalProof { task_id: H256([0u8; 32]), proof: proof_str.to_string() };
-    let constructor = op.clone().get_constructor_by_parse_proof();
+    let constructor = op.clone().get_constructor_by_parse_proof().expect("invalid proof request");
 
-    let task = SchedulerRouter::new(constructor, 1);
+    let task = SchedulerRouter::new(constructor, 1).expect("invalid proof request");
     let _proof = task.get_calldata(true);
 }
@@ -1,11 +1,16 @@
 #[cfg(feature = "display")]
 use ark_std::{end_timer, start_timer};
-use clap::{Parser, ValueEnum};
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
 use clap_num::maybe_hex;
+use itertools::Itertools;
 use std::{cmp::min, fmt::Display, path::PathBuf};
 use zkspv_circuits::{
     block_header::helper::{BlockHeaderScheduler, CircuitType, Finality, Task},
-    util::scheduler::Scheduler,
+    get_network_from_chain_id,
+    util::{
+        helpers::{get_offline_provider, get_provider},
+        scheduler::Scheduler,
+    },
     EthereumNetwork, Network,
 };
 
@@ -34,6 +39,16 @@ struct Cli {
     readonly: bool,
     #[arg(long = "srs-readonly")]
     srs_readonly: bool,
+    /// The chain of the block headers. Its name prefixes the pinnings in `configs/headers` and the files in `data/headers`.
+    #[arg(long = "network", value_enum, default_value_t = EthereumNetwork::Mainnet)]
+    network: EthereumNetwork,
+    /// Selects the chain by chain id instead, e.g. `11155111` for Sepolia.
+    /// Only the Ethereum chains have header chain pinnings in `configs/headers`.
+    #[arg(long = "chain-id", conflicts_with = "network")]
+    chain_id: Option<u64>,
+    /// Reads the blocks recorded in `data/chain` by previous runs instead of requesting them, without any RPC.
+    #[arg(long = "offline")]
+    offline: bool,
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -67,10 +82,30 @@ fn main() {
     #[cfg(not(feature = "production"))]
     let srs_readonly = args.srs_readonly;
 
-    let network = Network::Ethereum(EthereumNetwork::Mainnet);
+    let network = match args.chain_id {
+        Some(chain_id) => match get_network_from_chain_id(chain_id) {
+            Ok(network @ Network::Ethereum(_)) => network,
+            _ => Cli::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    format!(
+                        "chain id {chain_id} is not supported, expected one of {}",
+                        EthereumNetwork::value_variants()
+                            .iter()
+                            .map(|network| format!("{} ({})", *network as u64, network.name()))
+                            .join(", ")
+                    ),
+                )
+                .exit(),
+        },
+        None => Network::Ethereum(args.network),
+    };
+    let provider =
+        if args.offline { get_offline_provider(&network) } else { get_provider(&network) };
 
-    let scheduler = BlockHeaderScheduler::new(
+    let scheduler = BlockHeaderScheduler::with_provider(
         network,
+        provider,
         srs_readonly,
         args.readonly,
         PathBuf::from("configs/headers"),
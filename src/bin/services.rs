@@ -4,12 +4,17 @@ use log::{info, warn};
 use serde_json::Value;
 use std::fs::File;
 use std::io::BufReader;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::{Arc, Mutex};
 use tokio::task;
+use tokio::task::JoinError;
 use zkspv_circuits::config::log::init_log;
+use zkspv_circuits::db::{JobFailure, JobStatus};
 use zkspv_circuits::integration::Integration;
-use zkspv_circuits::server::{init_server, OriginalProof};
+use zkspv_circuits::server::{get_stage_progress, init_server, OriginalProof, ServerContext};
+use zkspv_circuits::util::errors::ProofRequestError;
+use zkspv_circuits::util::scheduler::arbitration_scheduler::ARBITRATION_DATA_DIR;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -45,8 +50,13 @@ async fn main() {
             let proof_str: Value = serde_json::from_reader(data_reader).unwrap();
 
             let op = OriginalProof { task_id: H256::zero(), proof: proof_str.to_string() };
-            let constructor = op.clone().get_constructor_by_parse_proof();
-            scheduler_cache_srs_pk.lock().unwrap().update(constructor, 1);
+            let constructor =
+                op.clone().get_constructor_by_parse_proof().expect("invalid proof request");
+            scheduler_cache_srs_pk
+                .lock()
+                .unwrap()
+                .update(constructor, 1)
+                .expect("invalid proof request");
             scheduler_cache_srs_pk.lock().unwrap().cache_srs_pk_files();
         })
         .await
@@ -57,20 +67,61 @@ async fn main() {
 
     let (tx, mut rx) = integration.mpsc;
 
-    let challenge_storage_clone = integration.storage.clone();
+    // resume the jobs received before the last shutdown
+    let recovered_jobs = integration.jobs.lock().unwrap().recover().expect("recover jobs error");
+    for job in recovered_jobs {
+        info!(target: "app","Resume generating proof for Challenge: {:?}",job.task_id);
+        tx.send(job.original_proof()).unwrap();
+    }
+
+    let retry_tx = tx.clone();
+    let server_context = ServerContext {
+        tx,
+        challenges_storage: integration.storage.clone(),
+        job_queue: integration.jobs.clone(),
+        data_dir: PathBuf::from(ARBITRATION_DATA_DIR),
+    };
     let receive_tasks = task::spawn(async move {
-        init_server(tx, challenge_storage_clone).await.expect("init server error");
+        init_server(server_context).await.expect("init server error");
     });
 
     let execute_tasks = task::spawn(async move {
-        while let original_proof = rx.recv().await {
+        while let Some(original_proof) = rx.recv().await {
             let scheduler_running = scheduler_running.clone();
             let challenge_storage_clone = integration.storage.clone();
+            let job_queue = integration.jobs.clone();
             let challenge = Arc::new(Mutex::new(Challenge::default()));
             let challenge_scheduler = challenge.clone();
-            let scheduler_result = task::spawn_blocking(move || {
-                challenge_scheduler.lock().unwrap().update_challenge_id(original_proof.clone().unwrap().task_id);
-                info!(target: "app","Start generating proof for Challenge: {:?}",original_proof.clone().unwrap().task_id);
+            let task_id = original_proof.task_id;
+            let job = job_queue.lock().unwrap().get_job(task_id).expect("read job error");
+            if job.map_or(true, |job| job.status == JobStatus::Cancelled) {
+                info!(target: "app","Skip cancelled Challenge: {:?}",task_id);
+                continue;
+            }
+            // jobs are validated by the server, only a job queued by an older version may be rejected
+            let constructor = match original_proof.clone().get_constructor_by_parse_proof() {
+                Ok(constructor) => constructor,
+                Err(err) => {
+                    warn!(target: "app","Reject Challenge: {:?},err: {}",task_id,err);
+                    let failure = JobFailure { stage: None, message: err.to_string() };
+                    job_queue
+                        .lock()
+                        .unwrap()
+                        .mark_rejected(task_id, failure)
+                        .expect("update job error");
+                    challenge_storage_clone
+                        .lock()
+                        .unwrap()
+                        .storage_challenge_proof(task_id, "".to_string())
+                        .expect("save success");
+                    continue;
+                }
+            };
+            job_queue.lock().unwrap().mark_running(task_id).expect("update job error");
+            let scheduler_job_queue = job_queue.clone();
+            let scheduler_result = task::spawn_blocking(move || -> Result<_, ProofRequestError> {
+                challenge_scheduler.lock().unwrap().update_challenge_id(task_id);
+                info!(target: "app","Start generating proof for Challenge: {:?}",task_id);
 
                 // clear
                 let mut clear = Command::new("sh")
@@ -78,25 +129,66 @@ async fn main() {
                     .spawn()
                     .expect("Failed to execute command");
                 let _ = clear.wait();
-                let constructor = original_proof.clone().unwrap().get_constructor_by_parse_proof();
-                scheduler_running.lock().unwrap().update(constructor, 1);
-                scheduler_running.lock().unwrap().get_calldata(args.generate_smart_contract)
+                // a failed job poisons the scheduler, it is reset by `update` anyway
+                let mut scheduler = scheduler_running.lock().unwrap_or_else(|e| e.into_inner());
+                scheduler.update(constructor, 1)?;
+                scheduler_job_queue
+                    .lock()
+                    .unwrap()
+                    .set_stages(task_id, scheduler.stages())
+                    .expect("update job error");
+                Ok(scheduler.get_calldata(args.generate_smart_contract))
             })
             .await;
 
             match scheduler_result {
-                Ok(proof) => {
+                Ok(Err(err)) => {
+                    warn!(target: "app","Reject Challenge: {:?},err: {}",task_id,err);
+                    let failure = JobFailure { stage: None, message: err.to_string() };
+                    job_queue
+                        .lock()
+                        .unwrap()
+                        .mark_rejected(task_id, failure)
+                        .expect("update job error");
+                }
+                Ok(Ok(proof)) => {
                     challenge.lock().unwrap().update_proof(proof);
-                    info!(target: "app","Successfully generated proof for Challenge: {:?}",challenge.lock().unwrap().challenge_id);
+                    info!(target: "app","Successfully generated proof for Challenge: {:?}",task_id);
 
                     println!("prove success")
                 }
                 Err(err) => {
-                    warn!(target: "app","Failed to generate proof for Challenge: {:?},err: {}",challenge.lock().unwrap().challenge_id,err);
-                    eprintln!("prove error: {}", err)
+                    let message = get_panic_message(err);
+                    warn!(target: "app","Failed to generate proof for Challenge: {:?},err: {}",task_id,message);
+                    eprintln!("prove error: {}", message);
+
+                    let job_queue = job_queue.lock().unwrap();
+                    let stages = job_queue
+                        .get_job(task_id)
+                        .expect("read job error")
+                        .map(|job| job.stages)
+                        .unwrap_or_default();
+                    let stage = get_stage_progress(Path::new(ARBITRATION_DATA_DIR), &stages)
+                        .into_iter()
+                        .find(|stage| !stage.done)
+                        .map(|stage| stage.name);
+                    let job = job_queue
+                        .mark_failed(task_id, JobFailure { stage, message })
+                        .expect("update job error");
+                    if let Some(job) = job.filter(|job| job.status == JobStatus::Pending) {
+                        info!(target: "app","Retry generating proof for Challenge: {:?}, retries: {}",task_id,job.retries);
+                        retry_tx.send(original_proof).unwrap();
+                        continue;
+                    }
                 }
             }
 
+            let job = job_queue.lock().unwrap().get_job(task_id).expect("read job error");
+            if job.map_or(true, |job| job.status == JobStatus::Cancelled) {
+                info!(target: "app","Discard the proof of cancelled Challenge: {:?}",task_id);
+                continue;
+            }
+
             let storage = challenge_storage_clone.lock().unwrap();
             let challenge = challenge.lock().unwrap();
 
@@ -104,12 +196,30 @@ async fn main() {
                 .storage_challenge_proof(challenge.challenge_id, challenge.proof.clone())
                 .expect("save success");
             info!(target: "app","Storage Challenge Prove Success: {:?}",challenge.challenge_id);
+            // only done once the proof is stored, a failed job has an empty proof
+            if !challenge.proof.is_empty() {
+                job_queue.lock().unwrap().mark_done(task_id).expect("update job error");
+            }
         }
     });
 
     tokio::join!(receive_tasks, execute_tasks);
 }
 
+fn get_panic_message(err: JoinError) -> String {
+    if !err.is_panic() {
+        return err.to_string();
+    }
+    let payload = err.into_panic();
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "proving task panicked".to_string()
+    }
+}
+
 struct Challenge {
     challenge_id: H256,
     proof: String,
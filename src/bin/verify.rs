@@ -0,0 +1,138 @@
+use clap::{Parser, Subcommand};
+use halo2_base::{halo2_proofs::halo2curves::bn256::Fr, utils::fs::read_params};
+use snark_verifier_sdk::halo2::read_snark;
+use std::{
+    path::{Path, PathBuf},
+    process::exit,
+};
+#[cfg(feature = "evm")]
+use zkspv_circuits::util::verify::{read_evm_verifier, verify_calldata};
+use zkspv_circuits::{
+    arbitration::instances::{instance_names, name_instances, SnarkInstances},
+    util::verify::{decode_calldata, read_calldata, read_vk, verify_snark_with_vk},
+};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)] // Read from `Cargo.toml`
+/// Checks the snarks and calldata written by the scheduler and prints their public instances.
+///
+/// The SRS is read from `PARAMS_DIR`, like the scheduler does in readonly mode.
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Verifies a `.snark` natively, against the verifying key of its circuit.
+    ///
+    /// The protocol in the snark file is not trusted, it is written by whoever proved the snark.
+    Snark {
+        snark: PathBuf,
+        /// The proving key of the circuit, `data/arbitration/<circuit>.pk`.
+        #[arg(long = "pk")]
+        pk: PathBuf,
+        /// The pinning the proving key was generated with, `configs/arbitration/<circuit>.json`.
+        #[arg(long = "pinning")]
+        pinning: PathBuf,
+        #[command(flatten)]
+        layout: Layout,
+    },
+    /// Verifies a `.calldata` with the EVM verifier of its circuit, in a local revm.
+    #[cfg(feature = "evm")]
+    Calldata {
+        calldata: PathBuf,
+        /// The verifier written with `--generate_smart_contract`, `data/arbitration/<circuit>.yul`.
+        #[arg(long = "yul")]
+        yul: PathBuf,
+        #[command(flatten)]
+        layout: Layout,
+    },
+    /// Prints the public instances of a `.calldata`.
+    Decode {
+        calldata: PathBuf,
+        #[command(flatten)]
+        layout: Layout,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct Layout {
+    /// The snarks whose instances the circuit exposes, in order, e.g.
+    /// `accumulator,transaction,block-merkle-inclusion:3,mdc-state:38`.
+    #[arg(long = "layout", value_delimiter = ',')]
+    snarks: Vec<SnarkInstances>,
+    /// The number of instances of the calldata, if there is no layout.
+    #[arg(long = "num-instance", conflicts_with = "snarks")]
+    num_instance: Option<usize>,
+}
+
+impl Layout {
+    fn num_instance(&self) -> usize {
+        if self.snarks.is_empty() {
+            self.num_instance.expect("either --layout or --num-instance is required")
+        } else {
+            instance_names(&self.snarks).len()
+        }
+    }
+
+    fn print(&self, instances: &[Fr]) {
+        if self.snarks.is_empty() {
+            for (i, instance) in instances.iter().enumerate() {
+                println!("instance_{i}: {instance:?}");
+            }
+            return;
+        }
+        match name_instances(&self.snarks, instances) {
+            Ok(named) => {
+                for (name, instance) in named {
+                    println!("{name}: {instance:?}");
+                }
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                exit(1);
+            }
+        }
+    }
+}
+
+fn read_instances(calldata: &Path, layout: &Layout) -> (Vec<Fr>, Vec<u8>) {
+    let calldata = read_calldata(calldata);
+    decode_calldata(&calldata, layout.num_instance()).unwrap_or_else(|| {
+        eprintln!("calldata doesn't start with {} instances", layout.num_instance());
+        exit(1);
+    })
+}
+
+fn report(valid: bool) {
+    if valid {
+        println!("valid");
+    } else {
+        println!("invalid");
+        exit(1);
+    }
+}
+
+fn main() {
+    let args = Cli::parse();
+    match args.command {
+        Command::Snark { snark, pk, pinning, layout } => {
+            let snark = read_snark(&snark).expect("snark should exist");
+            let vk = read_vk(pk, pinning);
+            let params = read_params(vk.get_domain().k());
+            layout.print(&snark.instances.concat());
+            report(verify_snark_with_vk(&params, &vk, &snark));
+        }
+        #[cfg(feature = "evm")]
+        Command::Calldata { calldata, yul, layout } => {
+            let (instances, _) = read_instances(&calldata, &layout);
+            layout.print(&instances);
+            report(verify_calldata(read_evm_verifier(yul), read_calldata(calldata)));
+        }
+        Command::Decode { calldata, layout } => {
+            let (instances, _) = read_instances(&calldata, &layout);
+            layout.print(&instances);
+        }
+    }
+}
@@ -1,5 +1,3 @@
-#[cfg(not(feature = "production"))]
-use crate::util::EthConfigParams;
 use crate::{
     block_header::aggregation::join_previous_instances,
     keccak::{FixedLenRLCs, FnSynthesize, KeccakChip, VarLenRLCs},
@@ -7,7 +5,7 @@ use crate::{
         builder::{RlcThreadBreakPoints, RlcThreadBuilder},
         RlpChip,
     },
-    util::{bytes_be_to_u128, num_to_bytes_be, NUM_BYTES_IN_U128},
+    util::{bytes_be_to_u128, num_to_bytes_be, EthConfigParams, NUM_BYTES_IN_U128},
     EthCircuitBuilder,
 };
 #[cfg(feature = "display")]
@@ -22,8 +20,6 @@ use halo2_base::{
 };
 use snark_verifier_sdk::{halo2::aggregation::AggregationCircuit, Snark, SHPLONK};
 use std::cell::RefCell;
-#[cfg(not(feature = "production"))]
-use std::env::var;
 
 use super::EthBlockHeaderChainAggregationCircuit;
 
@@ -59,13 +55,17 @@ impl EthBlockHeaderChainFinalAggregationCircuit {
     /// `snarks` should be exactly two snarks of either
     /// - `EthBlockHeaderChainCircuit` if `max_depth == initial_depth + 1` or
     /// - `EthBlockHeaderChainAggregationCircuit` otherwise
+    ///
+    /// The snarks are aggregated with the lookup bits of `config_params`, which are used as in
+    /// [`crate::EthPreCircuit::create_circuit`].
     pub fn create_circuit(
         self,
         stage: CircuitBuilderStage,
+        config_params: EthConfigParams,
         break_points: Option<RlcThreadBreakPoints>,
-        lookup_bits: usize,
         params: &ParamsKZG<Bn256>,
     ) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
+        let lookup_bits = config_params.lookup_bits.expect("lookup_bits is not set");
         let num_blocks = self.0.num_blocks;
         let max_depth = self.0.max_depth;
         let initial_depth = self.0.initial_depth;
@@ -149,12 +149,10 @@ impl EthBlockHeaderChainFinalAggregationCircuit {
         end_timer!(timer);
         #[cfg(not(feature = "production"))]
         if !_prover {
-            let config_params: EthConfigParams = serde_json::from_str(
-                var("ETH_CONFIG_PARAMS").expect("ETH_CONFIG_PARAMS is not set").as_str(),
-            )
-            .unwrap();
             circuit.config(config_params.degree as usize, Some(config_params.unusable_rows));
+            return circuit;
         }
+        circuit.set_config_params(config_params);
         circuit
     }
 }
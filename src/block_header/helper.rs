@@ -8,6 +8,7 @@ use crate::{
     util::{
         circuit::{AnyCircuit, PinnableCircuit},
         circuit::{PreCircuit, PublicAggregationCircuit},
+        circuit_config::CircuitConfig,
         scheduler::{self, EthScheduler, Scheduler},
         AggregationConfigPinning, EthConfigPinning, Halo2ConfigPinning,
     },
@@ -24,7 +25,7 @@ use halo2_base::{
     },
 };
 use snark_verifier_sdk::Snark;
-use std::{env::var, path::Path};
+use std::path::Path;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum Finality {
@@ -73,11 +74,17 @@ impl CircuitType {
         }
     }
 
+    /// Starts with the name of the chain, e.g. `sepolia_3` or `arbitrum_42161_3`, so that the pinnings and snarks
+    /// of every chain can share the same directories.
     pub fn fname_prefix(&self) -> String {
+        let network = match self.network {
+            Network::Ethereum(network) => network.name().to_string(),
+            network => format!("{}_{}", network.to_string(), network.chain_id()),
+        };
         if self.depth == self.initial_depth {
-            format!("{}_{}", self.network.to_string(), self.depth)
+            format!("{}_{}", network, self.depth)
         } else {
-            format!("{}_{}_{}", self.network.to_string(), self.depth, self.initial_depth)
+            format!("{}_{}_{}", network, self.depth, self.initial_depth)
         }
     }
 
@@ -232,17 +239,12 @@ impl PreCircuit for EthBlockHeaderChainAggregationCircuit {
     fn create_circuit(
         self,
         stage: CircuitBuilderStage,
-        pinning: Option<Self::Pinning>,
+        pinning: Self::Pinning,
         params: &ParamsKZG<Bn256>,
-    ) -> impl PinnableCircuit<Fr> {
-        // look for lookup_bits either from pinning, if available, or from env var
-        let lookup_bits = pinning
-            .as_ref()
-            .map(|p| p.params.lookup_bits)
-            .or_else(|| var("LOOKUP_BITS").map(|v| v.parse().unwrap()).ok())
-            .expect("LOOKUP_BITS is not set");
-        let break_points = pinning.map(|p| p.break_points());
-        AggregationPreCircuit::create_circuit(self, stage, break_points, lookup_bits, params)
+    ) -> (impl PinnableCircuit<Fr>, CircuitConfig) {
+        let config = pinning.config();
+        let break_points = (stage == CircuitBuilderStage::Prover).then(|| pinning.break_points());
+        AggregationPreCircuit::create_circuit(self, stage, config, break_points, params)
     }
 }
 
@@ -252,22 +254,19 @@ impl PreCircuit for EthBlockHeaderChainFinalAggregationCircuit {
     fn create_circuit(
         self,
         stage: CircuitBuilderStage,
-        pinning: Option<Self::Pinning>,
+        pinning: Self::Pinning,
         params: &ParamsKZG<Bn256>,
-    ) -> impl PinnableCircuit<Fr> {
-        // look for lookup_bits either from pinning, if available, or from env var
-        let lookup_bits = pinning
-            .as_ref()
-            .map(|p| p.params.lookup_bits.unwrap())
-            .or_else(|| var("LOOKUP_BITS").map(|v| v.parse().unwrap()).ok())
-            .expect("LOOKUP_BITS is not set");
-        let break_points = pinning.map(|p| p.break_points());
-        EthBlockHeaderChainFinalAggregationCircuit::create_circuit(
+    ) -> (impl PinnableCircuit<Fr>, CircuitConfig) {
+        let EthConfigPinning { params: config_params, break_points } = pinning;
+        let break_points = (stage == CircuitBuilderStage::Prover).then_some(break_points);
+        let circuit = EthBlockHeaderChainFinalAggregationCircuit::create_circuit(
             self,
             stage,
+            config_params,
             break_points,
-            lookup_bits,
             params,
-        )
+        );
+        let config = CircuitConfig::Eth(circuit.config_params());
+        (circuit, config)
     }
 }
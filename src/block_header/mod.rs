@@ -4,7 +4,7 @@ use core::{
 };
 use std::cell::RefCell;
 
-use ethers_providers::{Http, Provider, RetryClient};
+use ethers_providers::Provider;
 use halo2_base::gates::builder::GateThreadBuilder;
 use halo2_base::gates::{GateInstructions, RangeChip, RangeInstructions};
 use halo2_base::halo2_proofs::{halo2curves::bn256::Fr, plonk::*};
@@ -20,22 +20,24 @@ use crate::keccak::{
 };
 use crate::rlp::builder::{parallelize_phase1, RlcThreadBreakPoints, RlcThreadBuilder};
 use crate::rlp::rlc::{RlcContextPair, RlcFixedTrace, RlcTrace, FIRST_PHASE, RLC_PHASE};
-use crate::rlp::{RlpArrayTraceWitness, RlpChip, RlpFieldTrace, RlpFieldWitness};
+use crate::rlp::{max_rlp_len_len, RlpArrayTraceWitness, RlpChip, RlpFieldTrace, RlpFieldWitness};
 use crate::util::errors::ErrorType;
+use crate::util::rpc_client::RpcClient;
 use crate::util::{bytes_be_to_u128, bytes_be_var_to_fixed};
 use crate::{
     ArbitrumNetwork, EthChip, EthCircuitBuilder, EthPreCircuit, EthereumNetwork, Network,
-    OptimismNetwork, ETH_LOOKUP_BITS,
+    ETH_LOOKUP_BITS,
 };
 
 #[cfg(feature = "aggregation")]
 pub mod aggregation;
 #[cfg(all(feature = "aggregation", feature = "providers"))]
 pub mod helper;
+#[cfg(test)]
 mod tests;
 pub mod zksync_era;
 
-const NUM_BLOCK_HEADER_FIELDS: usize = 17;
+const NUM_BLOCK_HEADER_FIELDS: usize = 21;
 const BLOCK_NUMBER_MAX_BYTES: usize = 4;
 
 /// Config
@@ -51,6 +53,8 @@ pub struct BlockHeaderConfig {
     /// Provided that the total length is < 256^2, this will be 1 + 2 + sum(max RLP byte length of each field)
     pub block_header_rlp_max_bytes: usize,
 
+    /// The header is decoded as a variable length list, so the London (16 fields), Shanghai (17 fields),
+    /// Cancun (20 fields) and Prague (21 fields) layouts are all accepted by the same circuit. Fields missing from the header have length 0.
     /**
     | Field                        | Type            | Size (bytes)    | RLP size (bytes) | RLP size (bits) |
     |------------------------------|-----------------|-----------------|------------------|-----------------|
@@ -71,6 +75,10 @@ pub struct BlockHeaderConfig {
     | nonce                        | 64 bits         | 8               | 9                | 72              |
     | basefee (post-1559)          | big int scalar  | variable        | <= 6             | <= 48           |
     | withdrawalsRoot (post-4895) | 256 bits        | 32              | 33               | 264             |
+    | blobGasUsed (post-4844)      | big int scalar  | variable        | <= 9             | <= 72           |
+    | excessBlobGas (post-4844)    | big int scalar  | variable        | <= 9             | <= 72           |
+    | parentBeaconBlockRoot (4788) | 256 bits        | 32              | 33               | 264             |
+    | requestsHash (post-7685)     | 256 bits        | 32              | 33               | 264             |
      */
     header_fields_max_bytes: Vec<usize>,
 
@@ -84,8 +92,14 @@ impl BlockHeaderConfig {
         block_header_rlp_min_bytes: usize,
         header_fields_max_bytes: Vec<usize>,
     ) -> BlockHeaderConfig {
+        assert_eq!(header_fields_max_bytes.len(), NUM_BLOCK_HEADER_FIELDS);
         let extra_data_rlp_max_bytes = extra_data_max_bytes + 1;
-        let block_header_rlp_max_bytes = 1 + 2 + (521 + extra_data_rlp_max_bytes + 33);
+        let block_header_rlp_max_bytes = 1
+            + 2
+            + header_fields_max_bytes
+                .iter()
+                .map(|max_bytes| 1 + max_rlp_len_len(*max_bytes) + max_bytes)
+                .sum::<usize>();
         let block_number_max_bytes = header_fields_max_bytes[8];
         BlockHeaderConfig {
             extra_data_max_bytes,
@@ -123,6 +137,10 @@ pub fn get_block_header_config(network: &Network) -> BlockHeaderConfig {
                     8,
                     6,
                     32,
+                    8,
+                    8,
+                    32,
+                    32,
                 ];
                 Ok(BlockHeaderConfig::new(
                     extra_data_max_bytes,
@@ -130,7 +148,8 @@ pub fn get_block_header_config(network: &Network) -> BlockHeaderConfig {
                     header_fields_max_bytes,
                 ))
             }
-            EthereumNetwork::Goerli => {
+            // post-merge extra data is at most 32 bytes, and so was the clique and ethash extra data of the testnets
+            EthereumNetwork::Goerli | EthereumNetwork::Sepolia | EthereumNetwork::Holesky => {
                 let extra_data_max_bytes = 32;
                 let header_fields_max_bytes = vec![
                     32,
@@ -150,6 +169,10 @@ pub fn get_block_header_config(network: &Network) -> BlockHeaderConfig {
                     8,
                     6,
                     32,
+                    8,
+                    8,
+                    32,
+                    32,
                 ];
                 Ok(BlockHeaderConfig::new(
                     extra_data_max_bytes,
@@ -179,6 +202,10 @@ pub fn get_block_header_config(network: &Network) -> BlockHeaderConfig {
                     8,
                     6,
                     32,
+                    8,
+                    8,
+                    32,
+                    32,
                 ];
                 Ok(BlockHeaderConfig::new(
                     extra_data_max_bytes,
@@ -206,61 +233,9 @@ pub fn get_block_header_config(network: &Network) -> BlockHeaderConfig {
                     8,
                     6,
                     32,
-                ];
-                Ok(BlockHeaderConfig::new(
-                    extra_data_max_bytes,
-                    block_header_rlp_min_bytes,
-                    header_fields_max_bytes,
-                ))
-            }
-        },
-        Network::Optimism(network) => match network {
-            OptimismNetwork::Mainnet => {
-                let extra_data_max_bytes = 97;
-                let header_fields_max_bytes = vec![
-                    32,
-                    32,
-                    20,
-                    32,
-                    32,
-                    32,
-                    256,
-                    7,
-                    4,
-                    4,
-                    4,
-                    4,
-                    extra_data_max_bytes,
-                    32,
                     8,
-                    6,
-                    32,
-                ];
-                Ok(BlockHeaderConfig::new(
-                    extra_data_max_bytes,
-                    block_header_rlp_min_bytes,
-                    header_fields_max_bytes,
-                ))
-            }
-            OptimismNetwork::Goerli => {
-                let extra_data_max_bytes = 32;
-                let header_fields_max_bytes = vec![
-                    32,
-                    32,
-                    20,
-                    32,
-                    32,
-                    32,
-                    256,
-                    7,
-                    4,
-                    4,
-                    4,
-                    4,
-                    extra_data_max_bytes,
-                    32,
                     8,
-                    6,
+                    32,
                     32,
                 ];
                 Ok(BlockHeaderConfig::new(
@@ -270,11 +245,59 @@ pub fn get_block_header_config(network: &Network) -> BlockHeaderConfig {
                 ))
             }
         },
+        // All OP-Stack chains share the Bedrock header layout.
+        Network::Optimism(_) | Network::Base(_) => {
+            Ok(get_op_stack_block_header_config(block_header_rlp_min_bytes))
+        }
         _ => Err(ErrorType::NetworkNotSupported),
     };
     block_header_config.unwrap()
 }
 
+/**
+OP-Stack (Bedrock) blocks keep the Ethereum London header layout, with some fields fixed by the derivation pipeline,
+see https://github.com/ethereum-optimism/specs/blob/main/specs/protocol/derivation.md
+
+| Field                        | OP-Stack meaning                                                   |
+|------------------------------|--------------------------------------------------------------------|
+| difficulty                   | always 0                                                           |
+| extraData                    | empty                                                              |
+| mixHash                      | prevRandao of the L1 origin block                                  |
+| nonce                        | always 0                                                           |
+| withdrawalsRoot (Canyon)     | always the empty trie root                                         |
+ */
+fn get_op_stack_block_header_config(block_header_rlp_min_bytes: usize) -> BlockHeaderConfig {
+    let extra_data_max_bytes = 32;
+    let header_fields_max_bytes = vec![
+        32,
+        32,
+        20,
+        32,
+        32,
+        32,
+        256,
+        7,
+        4,
+        4,
+        4,
+        4,
+        extra_data_max_bytes,
+        32,
+        8,
+        6,
+        32,
+        8,
+        8,
+        32,
+        32,
+    ];
+    BlockHeaderConfig::new(
+        extra_data_max_bytes,
+        block_header_rlp_min_bytes,
+        header_fields_max_bytes,
+    )
+}
+
 /// Block Body
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
@@ -300,6 +323,12 @@ pub struct EthBlockHeaderTrace<F: Field> {
     pub withdrawals_root: RlpFieldTrace<F>,
     // this is 0 (or undefined) for pre-EIP4895 (Shapella) blocks (before block number 1681338455)
     // the user will have to separately determine whether the block is EIP1559 or not
+    pub blob_gas_used: RlpFieldTrace<F>,
+    pub excess_blob_gas: RlpFieldTrace<F>,
+    pub parent_beacon_block_root: RlpFieldTrace<F>,
+    // these are 0 (or undefined) for pre-EIP4844 (Dencun) blocks (before block number 19426587)
+    pub requests_hash: RlpFieldTrace<F>,
+    // this is 0 (or undefined) for pre-EIP7685 (Pectra) blocks (before block number 22431084)
     pub block_hash: RlcFixedTrace<F>,
 
     // pub prefix: AssignedValue<F>,
@@ -365,6 +394,18 @@ impl<F: Field> EthBlockHeaderTraceWitness<F> {
     pub fn get_withdrawals_root(&self) -> &RlpFieldWitness<F> {
         &self.rlp_witness.field_witness[16]
     }
+    pub fn get_blob_gas_used(&self) -> &RlpFieldWitness<F> {
+        &self.rlp_witness.field_witness[17]
+    }
+    pub fn get_excess_blob_gas(&self) -> &RlpFieldWitness<F> {
+        &self.rlp_witness.field_witness[18]
+    }
+    pub fn get_parent_beacon_block_root(&self) -> &RlpFieldWitness<F> {
+        &self.rlp_witness.field_witness[19]
+    }
+    pub fn get_requests_hash(&self) -> &RlpFieldWitness<F> {
+        &self.rlp_witness.field_witness[20]
+    }
     pub fn get_index(&self, idx: usize) -> Option<&RlpFieldWitness<F>> {
         self.rlp_witness.field_witness.get(idx)
     }
@@ -498,7 +539,7 @@ impl<'chip, F: Field> EthBlockHeaderChip<F> for EthChip<'chip, F> {
             block_header_assigned,
             &block_header_config.header_fields_max_bytes,
             true,
-        ); // `is_variable_len = true` because RLP can have between 15 to 17 fields, depending on which EIPs are active at that block
+        ); // `is_variable_len = true` because RLP can have between 15 to 21 fields, depending on which EIPs are active at that block
 
         let block_hash_query_idx = keccak.keccak_var_len(
             ctx,
@@ -523,7 +564,7 @@ impl<'chip, F: Field> EthBlockHeaderChip<F> for EthChip<'chip, F> {
         // The block field of each network may be different.
         // let number_field = block_header_config.header_fields_max_bytes.len();
         // Base fee per unit gas only after London
-        let [parent_hash, ommers_hash, beneficiary, state_root, transactions_root, receipts_root, logs_bloom, difficulty, number, gas_limit, gas_used, timestamp, extra_data, mix_hash, nonce, basefee, withdrawals_root]: [RlpFieldTrace<F>; NUM_BLOCK_HEADER_FIELDS]
+        let [parent_hash, ommers_hash, beneficiary, state_root, transactions_root, receipts_root, logs_bloom, difficulty, number, gas_limit, gas_used, timestamp, extra_data, mix_hash, nonce, basefee, withdrawals_root, blob_gas_used, excess_blob_gas, parent_beacon_block_root, requests_hash]: [RlpFieldTrace<F>; NUM_BLOCK_HEADER_FIELDS]
             = trace.field_trace.try_into().unwrap();
 
         EthBlockHeaderTrace {
@@ -544,6 +585,10 @@ impl<'chip, F: Field> EthBlockHeaderChip<F> for EthChip<'chip, F> {
             nonce,
             basefee,
             withdrawals_root,
+            blob_gas_used,
+            excess_blob_gas,
+            parent_beacon_block_root,
+            requests_hash,
             block_hash,
             len_trace: trace.len_trace,
         }
@@ -724,7 +769,7 @@ pub struct EthBlockHeaderChainCircuit<F> {
 impl<F: Field> EthBlockHeaderChainCircuit<F> {
     #[cfg(feature = "providers")]
     pub fn from_provider(
-        provider: &Provider<RetryClient<Http>>,
+        provider: &Provider<RpcClient>,
         network: Network,
         start_block_number: u32,
         num_blocks: u32,
@@ -732,8 +777,13 @@ impl<F: Field> EthBlockHeaderChainCircuit<F> {
     ) -> Self {
         let block_header_config = get_block_header_config(&network);
         let header_rlp_max_bytes = block_header_config.block_header_rlp_max_bytes;
-        let mut block_rlps =
-            crate::providers::get_blocks_input(provider, start_block_number, num_blocks, max_depth);
+        let mut block_rlps = crate::providers::get_blocks_input(
+            provider,
+            network.chain_id(),
+            start_block_number,
+            num_blocks,
+            max_depth,
+        );
         for block_rlp in block_rlps.iter_mut() {
             block_rlp.resize(header_rlp_max_bytes, 0u8);
         }
@@ -1,12 +1,15 @@
 use crate::{
     keccak::FnSynthesize,
     keccak::SharedKeccakChip,
+    providers::encode_block_header,
     util::{EthConfigParams, EthConfigPinning, Halo2ConfigPinning},
 };
 
 use super::*;
 use ark_std::{end_timer, start_timer};
+use ethers_core::types::{Block, H256};
 use ethers_core::utils::hex::FromHex;
+use ethers_core::utils::keccak256;
 use halo2_base::{
     halo2_proofs::{
         dev::MockProver,
@@ -26,14 +29,13 @@ use halo2_base::{
 };
 use rand_core::OsRng;
 use rayon::ThreadPoolBuilder;
-use std::{
-    env::{set_var, var},
-    fs::File,
-};
+use std::fs::File;
+use std::str::FromStr;
 use test_log::test;
 
 fn block_header_test_circuit<F: Field>(
     mut builder: RlcThreadBuilder<F>,
+    config_params: &EthConfigParams,
     inputs: Vec<Vec<u8>>,
     network: Network,
     break_points: Option<RlcThreadBreakPoints>,
@@ -64,11 +66,9 @@ fn block_header_test_circuit<F: Field>(
                 chip.decompose_block_header_chain_phase1(builder, chain_witness, None);
         },
     );
-    if !prover {
-        let config_params: EthConfigParams = serde_json::from_str(
-            var("ETH_CONFIG_PARAMS").expect("ETH_CONFIG_PARAMS is not set").as_str(),
-        )
-        .unwrap();
+    if prover {
+        circuit.set_config_params(config_params.clone());
+    } else {
         circuit.config(config_params.degree as usize, Some(config_params.unusable_rows));
     }
     circuit
@@ -77,7 +77,6 @@ fn block_header_test_circuit<F: Field>(
 #[test]
 pub fn test_one_mainnet_header_mock() {
     let params = EthConfigPinning::from_path("configs/tests/one_block.json").params;
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let network = Network::Ethereum(EthereumNetwork::Mainnet);
     let config = get_block_header_config(&network);
     let k = params.degree;
@@ -85,15 +84,20 @@ pub fn test_one_mainnet_header_mock() {
     let mut input_bytes: Vec<u8> = Vec::from_hex(input_hex).unwrap();
     input_bytes.resize(config.block_header_rlp_max_bytes, 0);
 
-    let circuit =
-        block_header_test_circuit::<Fr>(RlcThreadBuilder::mock(), vec![input_bytes], network, None);
+    let circuit = block_header_test_circuit::<Fr>(
+        RlcThreadBuilder::mock(),
+        &params,
+        vec![input_bytes],
+        network,
+        None,
+    );
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![vec![]]).unwrap().assert_satisfied();
 }
 
 #[test]
 pub fn test_one_mainnet_header_before_london_mock() {
     let params = EthConfigPinning::from_path("configs/tests/one_block.json").params;
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let network = Network::Ethereum(EthereumNetwork::Mainnet);
     let config = get_block_header_config(&network);
     let k = params.degree;
@@ -101,15 +105,20 @@ pub fn test_one_mainnet_header_before_london_mock() {
     let mut input_bytes: Vec<u8> = Vec::from_hex(input_hex).unwrap();
     input_bytes.resize(config.block_header_rlp_max_bytes, 0);
 
-    let circuit =
-        block_header_test_circuit::<Fr>(RlcThreadBuilder::mock(), vec![input_bytes], network, None);
+    let circuit = block_header_test_circuit::<Fr>(
+        RlcThreadBuilder::mock(),
+        &params,
+        vec![input_bytes],
+        network,
+        None,
+    );
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![vec![]]).unwrap().assert_satisfied();
 }
 
 #[test]
 pub fn test_one_mainnet_header_withdrawals_mock() {
     let params = EthConfigPinning::from_path("configs/tests/one_block.json").params;
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let network = Network::Ethereum(EthereumNetwork::Mainnet);
     let config = get_block_header_config(&network);
     let k = params.degree;
@@ -117,19 +126,174 @@ pub fn test_one_mainnet_header_withdrawals_mock() {
     let mut input_bytes: Vec<u8> = Vec::from_hex(input_hex).unwrap();
     input_bytes.resize(config.block_header_rlp_max_bytes, 0);
 
-    let circuit =
-        block_header_test_circuit::<Fr>(RlcThreadBuilder::mock(), vec![input_bytes], network, None);
+    let circuit = block_header_test_circuit::<Fr>(
+        RlcThreadBuilder::mock(),
+        &params,
+        vec![input_bytes],
+        network,
+        None,
+    );
+    let _scope = circuit.config_scope();
+    MockProver::run(k, &circuit, vec![vec![]]).unwrap().assert_satisfied();
+}
+
+/// Mainnet block 16329190, a London header after the merge and before Shanghai.
+fn mainnet_london_block() -> Block<H256> {
+    let mut block: serde_json::Value =
+        serde_json::from_reader(File::open("scripts/input_gen/block.json").unwrap()).unwrap();
+    block["transactions"] = serde_json::Value::Array(vec![]);
+    serde_json::from_value(block).unwrap()
+}
+
+/// Mainnet block 19449567, a Cancun header.
+const MAINNET_CANCUN_HEADER_HEX: &str = "f90255a090926e0298d418181bd20c23b332451e35fd7d696b5dcdc5a3a0a6b715f4c717a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d493479495222290dd7278aa3ddd389cc1e1d165cc4bafe5a0707875120a7103621fb4131df59904cda39de948dfda9084a1e3da44594d5404a0889a1c26dc42ba829dab552b779620feac231cde8a6c79af022bdc605c23a780a0d43aa19ecb03571d1b86d89d9bb980139d32f2f2ba59646cd5c1de9e80c68c90b90100c36919406572730518285284f2293101104140c0d42c4a786c892467868a8806f40159d29988002870403902413a1d04321320308da2e845438429e0012a00b419d8ccc8584a1c28f82a415d04eab8a5ae75c00d07761acf233414c08b6d9b571c06156086c70ea5186e9b989b0c2d55c0213c936805cd2ab331589c90194d070c00867549b1e1be14cb24500b0386cd901197c1ef5a00da453234fa48f3003dcaa894e3111c22b80e17f7d4388385a10720cda1140c0400f9e084ca34fc4870fb16b472340a2a6a63115a82522f506c06c2675080508834828c63defd06bc2331b4aa708906a06a560457b114248041e40179ebc05c6846c1e922125982f42780840128c6df8401c9c38083b0033c8465f5f4c38f6265617665726275696c642e6f7267a04c068e902990f21f92a2456fc75c59bec8be03b7f13682b6ebd27da56269beb5880000000000000000850886b221ada0360c33f20eeed5efbc7d08be46e58f8440af5db503e40908ef3d1eb314856ef78080a02843cb9f7d001bd58816a915e685ed96a555c9aeec1217736bd83a96ebd409cc";
+
+/// Block 789 of a Prague devnet, a header with the EIP-7685 requests hash.
+const PRAGUE_HEADER_HEX: &str = "f9027ea060f1563d2c572116091a4b91421d8d972118e39604d23455d841f9431cea4b6aa01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d4934794f97e180c050e5ab072211ad2c213eb5aee4df134a08101d88f2761eb9849634740f92fe09735551ad5a4d5e9da9bcae1ef4726a475a0f543eb3d405d2d6320344d348b06703ff1abeef71288181a24061e53f89bb5efa0eaa8c40899a61ae59615cf9985f5e2194f8fd2b57d273be63bde6733e89b12abb9010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000808203158401c9c380825208846712ba6e99d883010e0c846765746888676f312e32332e32856c696e7578a0e6d9c084dd36560520d5776a5387a82fb44793c9cd1b69afb61d53af29ee64b088000000000000000007a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b4218302000083040000a0d0bdb48ab45028568e66c8ddd600ac4c2a52522714bbfbf00ea6d20ba40f3ae2a06036c41849da9c076ed79654d434017387a88fb833c2856b32e18218b3341c5f";
+
+/// Sepolia block 7265502, a Cancun header with blobs.
+const SEPOLIA_CANCUN_HEADER_HEX: &str = "f90269a026294675693da22db5a47e4d403c755b9c553217344ffabcd3eb85d3cd69abe5a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d493479413cb6ae34a13a0977f4d7101ebc24b87bb23f0d5a0fbe8dfa60641eeba2193d352e828103552e2ce50c84f99b86b2893e788f2afbca0e30f5d072dc8e9af2dae21fbe038af4559d9ec5af3a1006a1c0413b66bf86f2da06bce99b4f4958223532baf9a1189ac1cc6d79538ca521ce262b4aad4f58d423db901006568092188404090e899a04202092859802ab0b31c032903ca302e200cb1405a1089924222609235682a0046219e40c44514520022260e45120a5c944420c80b108104a0081c024750182c4f0840a2110406c94282c28108d06202200042301aca1ca203468c7c8921a15e1410d1cc01253a24c329106014084d3295048d75c12608a56001a934412414685144900074b0210c8a814057041041254f4cc59833120940415005741c2c851a14820a066d62c15c214009800080c8262a008059345180b0931a490c09054140858d128328564509628101e86011225b2e0c902021c290480c7908050442a21140aef0701400a214132a608382446008002008184180836edcde840224c769838ab9bf84675b2c389f496c6c756d696e61746520446d6f63726174697a6520447374726962757465a0de62e24541140e4c7ac178ab21d15b69fef1eeb1cc6311754a5b38a2542dc07688000000000000000084db634f3da0295461777823420ca60f755d61d43eadc048ebbc60f4d0083d1e4ce5486aa22c8302000083860000a08e6ba325d64a46f9ea071e2d1fc1a748cafc22b84c649b9d1c53148e1877380e";
+
+#[test]
+pub fn test_one_mainnet_header_london_mock() {
+    let block = mainnet_london_block();
+    let header = encode_block_header(&block);
+    assert_eq!(header.len(), 548);
+    assert_eq!(
+        H256(keccak256(&header)),
+        H256::from_str("0xeaa53f3fbfe912c45af96f4a1a34e3cb1de8e9ac1b6fe8d8b1c9eadad976eda9")
+            .unwrap()
+    );
+    assert_eq!(block.hash, Some(H256(keccak256(&header))));
+    one_header_mock(
+        &header,
+        Network::Ethereum(EthereumNetwork::Mainnet),
+        "configs/tests/one_block.json",
+    );
+}
+
+#[test]
+pub fn test_one_mainnet_header_cancun_mock() {
+    let header = Vec::from_hex(MAINNET_CANCUN_HEADER_HEX).unwrap();
+    assert_eq!(
+        H256(keccak256(&header)),
+        H256::from_str("0x85cdcbe36217fd57bf2c33731d8460657a7ce512401f49c9f6392c82a7ccf7ac")
+            .unwrap()
+    );
+    one_header_mock(
+        &header,
+        Network::Ethereum(EthereumNetwork::Mainnet),
+        "configs/tests/one_block.json",
+    );
+}
+
+#[test]
+pub fn test_one_prague_header_mock() {
+    let header = Vec::from_hex(PRAGUE_HEADER_HEX).unwrap();
+    assert_eq!(
+        H256(keccak256(&header)),
+        H256::from_str("0x661da523f3e44725f3a1cee38183d35424155a05674609a9f6ed81243adf9e26")
+            .unwrap()
+    );
+    one_header_mock(
+        &header,
+        Network::Ethereum(EthereumNetwork::Mainnet),
+        "configs/tests/one_block.json",
+    );
+}
+
+/// Returns the byte length of [withdrawalsRoot, blobGasUsed, excessBlobGas, parentBeaconBlockRoot, requestsHash].
+fn post_london_fields_len(header: &[u8], network: Network) -> Vec<Fr> {
+    let config = get_block_header_config(&network);
+    let mut input_bytes = header.to_vec();
+    input_bytes.resize(config.block_header_rlp_max_bytes, 0);
+
+    let mut builder = RlcThreadBuilder::<Fr>::mock();
+    let range = RangeChip::default(ETH_LOOKUP_BITS);
+    let chip = EthChip::new(RlpChip::new(&range, None), None);
+    let mut keccak = KeccakChip::default();
+    let ctx = builder.gate_builder.main(FIRST_PHASE);
+    let witness = chip.decompose_block_header_phase0(ctx, &mut keccak, &input_bytes, &config);
+    [
+        witness.get_withdrawals_root(),
+        witness.get_blob_gas_used(),
+        witness.get_excess_blob_gas(),
+        witness.get_parent_beacon_block_root(),
+        witness.get_requests_hash(),
+    ]
+    .iter()
+    .map(|field| *field.field_len.value())
+    .collect()
+}
+
+#[test]
+pub fn test_mainnet_header_fork_fields() {
+    let network = Network::Ethereum(EthereumNetwork::Mainnet);
+    // London: none of the later fields are in the list
+    assert_eq!(
+        post_london_fields_len(&encode_block_header(&mainnet_london_block()), network),
+        vec![Fr::zero(); 5]
+    );
+    // Shanghai: the Cancun fields are not in the list
+    let shanghai_hex = "f90222a0d7519abd494a823b2c9c28908eaf250fe4a6287d747f1cc53a5a193b6533a549a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347944675c7e5baafbffbca748158becba61ef3b0a263a025000d51f040ee5c473fed74eda9ace87d55a35187b11bcde6f5176025c395bfa0a5800a6de6d28d7425ff72714af2af769b9f8f9e1baf56fb42f793fbb40fde07a056e1062a3dc63791e8a8496837606b14062da70ee69178cea97d6eeb5047550cb9010000236420014dc00423903000840002280080282100004704018340c0241c20011211400426000f900001d8088000011006020002ce98bc00c0000020c9a02040000688040200348c3a0082b81402002814922008085d008008200802802c4000130000101703124801400400018008a6108002020420144011200070020bc0202681810804221304004800088600300000040463614a000e200201c00611c0008e800b014081608010a0218a0b410010082000428209080200f50260a00840006700100f40a000000400000448301008c4a00341040e343500800d06250020010215200c008018002c88350404000bc5000a8000210c00724a0d0a4010210a448083eee2468401c9c3808343107884633899e780a07980d8d1f15474c9185e4d1cef5f207167735009daad2eb6af6da37ffba213c28800000000000000008501e08469e6a0f7519abd494a823b2c9c28908eaf250fe4a6287d747f1cc53a5a193b6533a549";
+    assert_eq!(
+        post_london_fields_len(&Vec::from_hex(shanghai_hex).unwrap(), network),
+        vec![Fr::from(32), Fr::zero(), Fr::zero(), Fr::zero(), Fr::zero()]
+    );
+    // Cancun: no blob gas was used and there was no excess blob gas
+    assert_eq!(
+        post_london_fields_len(&Vec::from_hex(MAINNET_CANCUN_HEADER_HEX).unwrap(), network),
+        vec![Fr::from(32), Fr::zero(), Fr::zero(), Fr::from(32), Fr::zero()]
+    );
+    // Prague
+    assert_eq!(
+        post_london_fields_len(&Vec::from_hex(PRAGUE_HEADER_HEX).unwrap(), network),
+        vec![Fr::from(32), Fr::from(3), Fr::from(3), Fr::from(32), Fr::from(32)]
+    );
+}
+
+fn one_header_mock(header: &[u8], network: Network, pinning_path: &str) {
+    let params = EthConfigPinning::from_path(pinning_path).params;
+    let config = get_block_header_config(&network);
+    let k = params.degree;
+    let mut input_bytes = header.to_vec();
+    input_bytes.resize(config.block_header_rlp_max_bytes, 0);
+
+    let circuit = block_header_test_circuit::<Fr>(
+        RlcThreadBuilder::mock(),
+        &params,
+        vec![input_bytes],
+        network,
+        None,
+    );
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![vec![]]).unwrap().assert_satisfied();
 }
 
+/// Holesky shares the header config of Sepolia.
+#[test]
+pub fn test_one_sepolia_header_mock() {
+    let network = Network::Ethereum(EthereumNetwork::Sepolia);
+    let header = Vec::from_hex(SEPOLIA_CANCUN_HEADER_HEX).unwrap();
+    assert_eq!(
+        H256(keccak256(&header)),
+        H256::from_str("0xc0c3190292a82c2ee148774e37e5665f6a205f5ef0cd0885e84701d90ebd442e")
+            .unwrap()
+    );
+    one_header_mock(&header, network, "configs/headers/sepolia_3.json");
+    assert_eq!(
+        post_london_fields_len(&header, network),
+        vec![Fr::from(32), Fr::from(3), Fr::from(3), Fr::from(32), Fr::zero()]
+    );
+}
+
 #[test]
 pub fn test_one_mainnet_header_prover() -> Result<(), Box<dyn std::error::Error>> {
     ThreadPoolBuilder::new().num_threads(256).build_global().unwrap();
-    let params = EthConfigPinning::from_path("configs/tests/one_block.json").params;
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
+    let config_params = EthConfigPinning::from_path("configs/tests/one_block.json").params;
     let network = Network::Ethereum(EthereumNetwork::Mainnet);
     let config = get_block_header_config(&network);
-    let k = params.degree;
+    let k = config_params.degree;
     let input_hex = "f90222a0d7519abd494a823b2c9c28908eaf250fe4a6287d747f1cc53a5a193b6533a549a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347944675c7e5baafbffbca748158becba61ef3b0a263a025000d51f040ee5c473fed74eda9ace87d55a35187b11bcde6f5176025c395bfa0a5800a6de6d28d7425ff72714af2af769b9f8f9e1baf56fb42f793fbb40fde07a056e1062a3dc63791e8a8496837606b14062da70ee69178cea97d6eeb5047550cb9010000236420014dc00423903000840002280080282100004704018340c0241c20011211400426000f900001d8088000011006020002ce98bc00c0000020c9a02040000688040200348c3a0082b81402002814922008085d008008200802802c4000130000101703124801400400018008a6108002020420144011200070020bc0202681810804221304004800088600300000040463614a000e200201c00611c0008e800b014081608010a0218a0b410010082000428209080200f50260a00840006700100f40a000000400000448301008c4a00341040e343500800d06250020010215200c008018002c88350404000bc5000a8000210c00724a0d0a4010210a448083eee2468401c9c3808343107884633899e780a07980d8d1f15474c9185e4d1cef5f207167735009daad2eb6af6da37ffba213c28800000000000000008501e08469e6a0f7519abd494a823b2c9c28908eaf250fe4a6287d747f1cc53a5a193b6533a549";
     let mut input_bytes: Vec<u8> = Vec::from_hex(input_hex).unwrap();
     input_bytes.resize(config.block_header_rlp_max_bytes, 0);
@@ -138,10 +302,12 @@ pub fn test_one_mainnet_header_prover() -> Result<(), Box<dyn std::error::Error>
     let params = ParamsKZG::<Bn256>::setup(k, &mut rng);
     let circuit = block_header_test_circuit::<Fr>(
         RlcThreadBuilder::keygen(),
+        &config_params,
         vec![input_bytes.clone()],
         network,
         None,
     );
+    let _scope = circuit.config_scope();
     let vk_time = start_timer!(|| "vk gen");
     let vk = keygen_vk(&params, &circuit).unwrap();
     end_timer!(vk_time);
@@ -149,20 +315,17 @@ pub fn test_one_mainnet_header_prover() -> Result<(), Box<dyn std::error::Error>
     let pk = keygen_pk(&params, vk, &circuit).unwrap();
     end_timer!(pk_time);
     let break_points = circuit.circuit.break_points.take();
-    let pinning = EthConfigPinning {
-        params: serde_json::from_str(var("ETH_CONFIG_PARAMS").unwrap().as_str()).unwrap(),
-        break_points,
-    };
+    let pinning = EthConfigPinning { params: circuit.config_params(), break_points };
     serde_json::to_writer(File::create("configs/tests/one_block.json").unwrap(), &pinning)?;
 
     let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
     let pf_time = start_timer!(|| "proof gen");
-    let break_points = pinning.break_points();
     let circuit = block_header_test_circuit::<Fr>(
         RlcThreadBuilder::prover(),
+        &pinning.params,
         vec![input_bytes],
         network,
-        Some(break_points),
+        Some(pinning.break_points),
     );
     create_proof::<
         KZGCommitmentScheme<Bn256>,
@@ -220,11 +383,11 @@ fn get_default_goerli_header_chain_circuit() -> EthBlockHeaderChainCircuit<Fr> {
 #[test]
 pub fn test_multi_goerli_header_mock() {
     let config = EthConfigPinning::from_path("configs/tests/multi_block.json").params;
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&config).unwrap());
     let k = config.degree;
 
     let input = get_default_goerli_header_chain_circuit();
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), config, None);
+    let _scope = circuit.config_scope();
     let instance = circuit.instance();
 
     MockProver::run(k, &circuit, vec![instance]).unwrap().assert_satisfied();
@@ -233,10 +396,10 @@ pub fn test_multi_goerli_header_mock() {
 #[test]
 pub fn test_multi_goerli_header_prover() {
     let config = EthConfigPinning::from_path("configs/tests/multi_block.json").params;
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&config).unwrap());
     let k = config.degree;
     let input = get_default_goerli_header_chain_circuit();
-    let circuit = input.clone().create_circuit(RlcThreadBuilder::keygen(), None);
+    let circuit = input.clone().create_circuit(RlcThreadBuilder::keygen(), config, None);
+    let _scope = circuit.config_scope();
 
     let params = gen_srs(k);
 
@@ -247,17 +410,17 @@ pub fn test_multi_goerli_header_prover() {
     let pk = keygen_pk(&params, vk, &circuit).unwrap();
     end_timer!(pk_time);
     let break_points = circuit.circuit.break_points.take();
-    let pinning = EthConfigPinning {
-        params: serde_json::from_str(var("ETH_CONFIG_PARAMS").unwrap().as_str()).unwrap(),
-        break_points,
-    };
+    let pinning = EthConfigPinning { params: circuit.config_params(), break_points };
     serde_json::to_writer(File::create("configs/tests/multi_block.json").unwrap(), &pinning)
         .unwrap();
 
     let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
     let pf_time = start_timer!(|| "proof gen");
-    let break_points = pinning.break_points();
-    let circuit = input.create_circuit(RlcThreadBuilder::prover(), Some(break_points));
+    let circuit = input.create_circuit(
+        RlcThreadBuilder::prover(),
+        pinning.params,
+        Some(pinning.break_points),
+    );
     let instance = circuit.instance();
     create_proof::<
         KZGCommitmentScheme<Bn256>,
@@ -361,7 +524,6 @@ mod aggregation {
 #[test]
 pub fn test_one_arbitrum_goerli_header_mock() {
     let params = EthConfigPinning::from_path("configs/tests/one_block.json").params;
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let network = Network::Arbitrum(ArbitrumNetwork::Goerli);
     let config = get_block_header_config(&network);
     let k = params.degree;
@@ -369,15 +531,20 @@ pub fn test_one_arbitrum_goerli_header_mock() {
     let mut input_bytes: Vec<u8> = Vec::from_hex(input_hex).unwrap();
     input_bytes.resize(config.block_header_rlp_max_bytes, 0);
 
-    let circuit =
-        block_header_test_circuit::<Fr>(RlcThreadBuilder::mock(), vec![input_bytes], network, None);
+    let circuit = block_header_test_circuit::<Fr>(
+        RlcThreadBuilder::mock(),
+        &params,
+        vec![input_bytes],
+        network,
+        None,
+    );
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![vec![]]).unwrap().assert_satisfied();
 }
 
 #[test]
 pub fn test_one_arbitrum_mainnet_header_mock() {
     let params = EthConfigPinning::from_path("configs/tests/one_block.json").params;
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let network = Network::Arbitrum(ArbitrumNetwork::Mainnet);
     let config = get_block_header_config(&network);
     let k = params.degree;
@@ -387,9 +554,11 @@ pub fn test_one_arbitrum_mainnet_header_mock() {
 
     let circuit = block_header_test_circuit::<Fr>(
         RlcThreadBuilder::mock(),
+        &params,
         vec![input_bytes],
         Network::Arbitrum(ArbitrumNetwork::Mainnet),
         None,
     );
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![vec![]]).unwrap().assert_satisfied();
 }
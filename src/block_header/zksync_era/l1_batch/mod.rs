@@ -0,0 +1,444 @@
+#[cfg(test)]
+mod tests;
+
+use crate::block_header::zksync_era::{
+    ZkSyncEraBlockHeaderChip, ZkSyncEraBlockHeaderInput, ZkSyncEraBlockHeaderInputAssigned,
+    ZkSyncEraBlockHeaderTrace, ZkSyncEraBlockHeaderTraceWitness, BLOCK_HEADER_MAX_FIELD_LENS,
+};
+use crate::block_header::{get_block_header_config, BlockHeaderConfig, EthBlockHeaderChip};
+use crate::keccak::{FixedLenRLCs, FnSynthesize, KeccakChip, VarLenRLCs};
+use crate::mpt::AssignedBytes;
+use crate::providers::get_zksync_era_l1_batch_input;
+use crate::rlp::builder::{RlcThreadBreakPoints, RlcThreadBuilder};
+use crate::rlp::rlc::FIRST_PHASE;
+use crate::rlp::RlpChip;
+use crate::storage::{
+    EIP1186ResponseDigest, EthBlockAccountStorageTrace, EthBlockAccountStorageTraceWitness,
+    EthBlockStorageInput, EthBlockStorageInputAssigned, EthStorageChip,
+};
+use crate::util::contract_abi::zksync_era::StoredBatchInfo;
+use crate::util::helpers::load_bytes;
+use crate::util::rpc_client::RpcClient;
+use crate::util::{
+    bytes_be_to_u128, bytes_be_to_uint, encode_addr_to_field, encode_h256_to_field, AssignedH256,
+};
+use crate::{
+    EthChip, EthCircuitBuilder, EthPreCircuit, Network, ZkSyncEraNetwork, ETH_LOOKUP_BITS,
+};
+use ethers_core::types::{Address, H256};
+use ethers_core::utils::keccak256;
+use ethers_providers::Provider;
+use halo2_base::gates::builder::GateThreadBuilder;
+use halo2_base::gates::{GateInstructions, RangeChip, RangeInstructions};
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::QuantumCell::Constant;
+use halo2_base::{AssignedValue, Context};
+use itertools::Itertools;
+use std::cell::RefCell;
+use zkevm_keccak::util::eth_types::Field;
+
+/// Slots of the diamond proxy `AppStorage`, see contracts/ethereum/contracts/zksync/Storage.sol
+pub const TOTAL_BATCHES_EXECUTED_SLOT: u64 = 11;
+pub const STORED_BATCH_HASHES_SLOT: u64 = 14;
+
+const WORD_BYTES_LEN: usize = 32;
+/// `abi.encode(StoredBatchInfo)` takes one word per field.
+const STORED_BATCH_INFO_BYTES_LEN: usize = 8 * WORD_BYTES_LEN;
+const STORED_BATCH_INFO_BATCH_NUMBER_WORD: usize = 0;
+const STORED_BATCH_INFO_TIMESTAMP_WORD: usize = 6;
+/// Batch numbers and timestamps are `uint64` in the zkSync system contracts.
+const UINT64_BYTES_LEN: usize = 8;
+const UINT64_BITS: usize = 64;
+
+/// `[l1_block_hash, l1_block_number, diamond_proxy, batch_number, l2_block_hash, l2_block_number]`, the L1 block hash of the
+/// block the batch is read at starts at this instance.
+pub const L1_BLOCK_HASH_INSTANCE_OFFSET: usize = 0;
+/// The L1 block the batch is read at.
+pub const L1_BLOCK_NUMBER_INSTANCE_OFFSET: usize = 2;
+/// The L2 block hash starts at this instance.
+pub const L2_BLOCK_HASH_INSTANCE_OFFSET: usize = 5;
+
+pub fn get_diamond_proxy_address(network: &ZkSyncEraNetwork) -> Address {
+    match network {
+        ZkSyncEraNetwork::Mainnet => "0x32400084C286CF3E17e7B677ea9583e60a000324",
+        ZkSyncEraNetwork::Goerli => "0x1908e2BF4a88F91E4eF0DC72f02b8Ea36BEa2319",
+        ZkSyncEraNetwork::Sepolia => "0x9A6DE0f62Aa270A8bCB1e2610078650D539B1Ef9",
+    }
+    .parse()
+    .unwrap()
+}
+
+/// `storedBatchHashes[batch_number]`
+pub fn get_stored_batch_hash_slot(batch_number: u64) -> H256 {
+    let internal_bytes = [
+        H256::from_low_u64_be(batch_number).to_fixed_bytes(),
+        H256::from_low_u64_be(STORED_BATCH_HASHES_SLOT).to_fixed_bytes(),
+    ]
+    .concat();
+    H256::from(keccak256(internal_bytes))
+}
+
+#[derive(Clone, Debug)]
+pub struct ZkSyncEraL1BatchConstructor {
+    pub l2_block_number: u64,
+    pub l1_network: Network,
+    pub l2_network: ZkSyncEraNetwork,
+}
+
+impl ZkSyncEraL1BatchConstructor {
+    pub fn new(l2_block_number: u64, l1_network: Network, l2_network: ZkSyncEraNetwork) -> Self {
+        Self { l2_block_number, l1_network, l2_network }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ZkSyncEraL1BatchInput {
+    /// `[totalBatchesExecuted, storedBatchHashes[N], storedBatchHashes[N + 1]]` of the diamond proxy at an L1 block.
+    pub l1_storage: EthBlockStorageInput,
+    /// The batch N including the L2 block, followed by the batch N + 1.
+    pub stored_batches: [StoredBatchInfo; 2],
+    // provided for convenience, actual block_hash is computed from l2_block_header
+    pub l2_block_number: u64,
+    pub l2_block_hash: H256,
+    pub l2_block_header: ZkSyncEraBlockHeaderInput,
+}
+
+#[derive(Clone, Debug)]
+pub struct ZkSyncEraL1BatchInputAssigned<F: Field> {
+    pub l1_storage: EthBlockStorageInputAssigned<F>,
+    pub stored_batches: [AssignedBytes<F>; 2],
+    pub l2_block_header: ZkSyncEraBlockHeaderInputAssigned<F>,
+}
+
+impl ZkSyncEraL1BatchInput {
+    pub fn assign<F: Field>(self, ctx: &mut Context<F>) -> ZkSyncEraL1BatchInputAssigned<F> {
+        let l1_storage = self.l1_storage.assign(ctx);
+        let stored_batches = self.stored_batches.map(|stored_batch| {
+            let stored_batch = stored_batch.abi_encode();
+            assert_eq!(stored_batch.len(), STORED_BATCH_INFO_BYTES_LEN);
+            load_bytes(ctx, &stored_batch)
+        });
+        let l2_block_header = self.l2_block_header.assign(ctx);
+        ZkSyncEraL1BatchInputAssigned { l1_storage, stored_batches, l2_block_header }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ZkSyncEraL1BatchTrace<F: Field> {
+    pub storage_trace: EthBlockAccountStorageTrace<F>,
+    pub l2_block_trace: ZkSyncEraBlockHeaderTrace<F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ZkSyncEraL1BatchTraceWitness<F: Field> {
+    pub storage_witness: EthBlockAccountStorageTraceWitness<F>,
+    pub l2_block_witness: ZkSyncEraBlockHeaderTraceWitness<F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ZkSyncEraL1BatchDigest<F: Field> {
+    pub l1_block_hash: AssignedH256<F>,
+    pub l1_block_number: AssignedValue<F>,
+    pub diamond_proxy: AssignedValue<F>,
+    pub batch_number: AssignedValue<F>,
+    pub l2_block_hash: AssignedH256<F>,
+    pub l2_block_number: AssignedValue<F>,
+}
+
+/// The batch number and timestamp of a stored batch, after checking it is the one the diamond proxy stores.
+fn constrain_stored_batch<F: Field>(
+    ctx: &mut Context<F>,
+    gate: &impl GateInstructions<F>,
+    keccak: &mut KeccakChip<F>,
+    stored_batch: &AssignedBytes<F>,
+    (slot, value): &(AssignedH256<F>, AssignedH256<F>),
+) -> (AssignedValue<F>, AssignedValue<F>) {
+    let word = |idx: usize| &stored_batch[idx * WORD_BYTES_LEN..(idx + 1) * WORD_BYTES_LEN];
+
+    // storedBatchHashes[batchNumber] = keccak(abi.encode(storedBatchInfo))
+    let hash_query_idx = keccak.keccak_fixed_len(ctx, gate, stored_batch.clone(), None);
+    let hash_bytes = &keccak.fixed_len_queries[hash_query_idx].output_assigned;
+    let hash = bytes_be_to_u128(ctx, gate, hash_bytes);
+    for (hash, value) in hash.iter().zip(value.iter()) {
+        ctx.constrain_equal(hash, value);
+    }
+
+    // the mapping slot is keccak(batchNumber . STORED_BATCH_HASHES_SLOT)
+    let mapping_slot = H256::from_low_u64_be(STORED_BATCH_HASHES_SLOT)
+        .as_bytes()
+        .iter()
+        .map(|byte| ctx.load_constant(F::from(*byte as u64)))
+        .collect_vec();
+    let slot_query_idx = keccak.keccak_fixed_len(
+        ctx,
+        gate,
+        [word(STORED_BATCH_INFO_BATCH_NUMBER_WORD), mapping_slot.as_slice()].concat(),
+        None,
+    );
+    let slot_bytes = &keccak.fixed_len_queries[slot_query_idx].output_assigned;
+    let expected_slot = bytes_be_to_u128(ctx, gate, slot_bytes);
+    for (expected_slot, slot) in expected_slot.iter().zip(slot.iter()) {
+        ctx.constrain_equal(expected_slot, slot);
+    }
+
+    let [batch_number, timestamp] =
+        [STORED_BATCH_INFO_BATCH_NUMBER_WORD, STORED_BATCH_INFO_TIMESTAMP_WORD].map(|idx| {
+            bytes_be_to_uint(
+                ctx,
+                gate,
+                &word(idx)[WORD_BYTES_LEN - UINT64_BYTES_LEN..],
+                UINT64_BYTES_LEN,
+            )
+        });
+    (batch_number, timestamp)
+}
+
+pub trait ZkSyncEraL1BatchChip<F: Field> {
+    /// Proves the L2 block belongs to a batch executed on L1:
+    /// - the account is the diamond proxy of `l2_network`.
+    /// - the diamond proxy stores the hashes of the batches N and N + 1 at the L1 block, and N + 1 <= totalBatchesExecuted.
+    /// - the L2 block was sealed in the batch N: `timestamp(N) <= l2_block_timestamp < timestamp(N + 1)`.
+    ///
+    /// `StoredBatchInfo` commits to the batch state root and L2 to L1 logs but not to the L2 block hashes, so the block
+    /// is only bound to the batch through its timestamp, a batch being started strictly after the last block of the previous one.
+    /// The L2 header is not otherwise checked against the batch.
+    fn parse_zksync_era_l1_batch_phase0(
+        &self,
+        thread_pool: &mut GateThreadBuilder<F>,
+        keccak: &mut KeccakChip<F>,
+        input: ZkSyncEraL1BatchInputAssigned<F>,
+        block_header_config: &BlockHeaderConfig,
+        l2_network: &ZkSyncEraNetwork,
+    ) -> (ZkSyncEraL1BatchTraceWitness<F>, ZkSyncEraL1BatchDigest<F>)
+    where
+        Self: EthBlockHeaderChip<F>;
+
+    fn parse_zksync_era_l1_batch_phase1(
+        &self,
+        thread_pool: &mut RlcThreadBuilder<F>,
+        witness: ZkSyncEraL1BatchTraceWitness<F>,
+    ) -> ZkSyncEraL1BatchTrace<F>
+    where
+        Self: EthBlockHeaderChip<F>;
+}
+
+impl<'chip, F: Field> ZkSyncEraL1BatchChip<F> for EthChip<'chip, F> {
+    fn parse_zksync_era_l1_batch_phase0(
+        &self,
+        thread_pool: &mut GateThreadBuilder<F>,
+        keccak: &mut KeccakChip<F>,
+        input: ZkSyncEraL1BatchInputAssigned<F>,
+        block_header_config: &BlockHeaderConfig,
+        l2_network: &ZkSyncEraNetwork,
+    ) -> (ZkSyncEraL1BatchTraceWitness<F>, ZkSyncEraL1BatchDigest<F>)
+    where
+        Self: EthBlockHeaderChip<F>,
+    {
+        let (storage_witness, storage_digest) = self.parse_eip1186_proofs_from_block_phase0(
+            thread_pool,
+            keccak,
+            input.l1_storage,
+            block_header_config,
+        );
+        let EIP1186ResponseDigest {
+            block_hash: l1_block_hash,
+            block_number: l1_block_number,
+            address: diamond_proxy,
+            slots_values,
+            address_is_empty,
+            slot_is_empty,
+        } = storage_digest;
+
+        let ctx = thread_pool.main(FIRST_PHASE);
+        let gate = self.gate();
+        gate.assert_is_const(ctx, &address_is_empty, &F::zero());
+        gate.assert_is_const(
+            ctx,
+            &diamond_proxy,
+            &encode_addr_to_field(&get_diamond_proxy_address(l2_network)),
+        );
+        for slot_is_empty in slot_is_empty {
+            gate.assert_is_const(ctx, &slot_is_empty, &F::zero());
+        }
+
+        let [total_batches_executed, current_batch, next_batch]: [_; 3] = slots_values
+            .try_into()
+            .expect("should prove the executed batches and two batch hashes");
+        let (total_batches_executed_slot, total_batches_executed) = total_batches_executed;
+        gate.assert_is_const(ctx, &total_batches_executed_slot[0], &F::zero());
+        gate.assert_is_const(
+            ctx,
+            &total_batches_executed_slot[1],
+            &F::from(TOTAL_BATCHES_EXECUTED_SLOT),
+        );
+        gate.assert_is_const(ctx, &total_batches_executed[0], &F::zero());
+
+        let [current_stored_batch, next_stored_batch] = input.stored_batches;
+        let (batch_number, current_timestamp) =
+            constrain_stored_batch(ctx, gate, keccak, &current_stored_batch, &current_batch);
+        let (next_batch_number, next_timestamp) =
+            constrain_stored_batch(ctx, gate, keccak, &next_stored_batch, &next_batch);
+        let batch_number_plus_one = gate.add(ctx, batch_number, Constant(F::one()));
+        ctx.constrain_equal(&next_batch_number, &batch_number_plus_one);
+        // N + 1 <= totalBatchesExecuted
+        self.range().check_less_than(ctx, batch_number, total_batches_executed[1], UINT64_BITS);
+
+        let l2_block_witness = ZkSyncEraBlockHeaderChip::decompose_block_header_phase0(
+            self,
+            ctx,
+            keccak,
+            input.l2_block_header,
+        );
+        let l2_block_hash = bytes_be_to_u128(ctx, gate, &l2_block_witness.block_hash);
+        let [l2_block_number, l2_block_timestamp] = [
+            l2_block_witness.rlp_witness.get_number(),
+            l2_block_witness.rlp_witness.get_timestamp(),
+        ]
+        .into_iter()
+        .zip(BLOCK_HEADER_MAX_FIELD_LENS)
+        .map(|(field, max_bytes)| {
+            self.assigned_value_to_uint(ctx, field.field_cells.clone(), field.field_len, max_bytes)
+        })
+        .collect_vec()
+        .try_into()
+        .unwrap();
+
+        // timestamp(N) <= l2_block_timestamp < timestamp(N + 1)
+        let l2_block_timestamp_plus_one = gate.add(ctx, l2_block_timestamp, Constant(F::one()));
+        self.range().check_less_than(
+            ctx,
+            current_timestamp,
+            l2_block_timestamp_plus_one,
+            UINT64_BITS,
+        );
+        self.range().check_less_than(ctx, l2_block_timestamp, next_timestamp, UINT64_BITS);
+
+        let digest = ZkSyncEraL1BatchDigest {
+            l1_block_hash,
+            l1_block_number,
+            diamond_proxy,
+            batch_number,
+            l2_block_hash: l2_block_hash.try_into().unwrap(),
+            l2_block_number,
+        };
+        (ZkSyncEraL1BatchTraceWitness { storage_witness, l2_block_witness }, digest)
+    }
+
+    fn parse_zksync_era_l1_batch_phase1(
+        &self,
+        thread_pool: &mut RlcThreadBuilder<F>,
+        witness: ZkSyncEraL1BatchTraceWitness<F>,
+    ) -> ZkSyncEraL1BatchTrace<F>
+    where
+        Self: EthBlockHeaderChip<F>,
+    {
+        let storage_trace =
+            self.parse_eip1186_proofs_from_block_phase1(thread_pool, witness.storage_witness);
+        let l2_block_trace = ZkSyncEraBlockHeaderChip::decompose_block_headers_phase1(
+            self,
+            thread_pool,
+            vec![witness.l2_block_witness],
+        )
+        .pop()
+        .unwrap();
+        ZkSyncEraL1BatchTrace { storage_trace, l2_block_trace }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ZkSyncEraL1BatchCircuit {
+    pub inputs: ZkSyncEraL1BatchInput,
+    pub block_header_config: BlockHeaderConfig,
+    pub l2_network: ZkSyncEraNetwork,
+}
+
+impl ZkSyncEraL1BatchCircuit {
+    #[cfg(feature = "providers")]
+    pub fn from_provider(
+        l1_provider: &Provider<RpcClient>,
+        l2_provider: &Provider<RpcClient>,
+        constructor: ZkSyncEraL1BatchConstructor,
+    ) -> Self {
+        let inputs = get_zksync_era_l1_batch_input(
+            l1_provider,
+            l2_provider,
+            constructor.l2_block_number,
+            get_diamond_proxy_address(&constructor.l2_network),
+        );
+        let block_header_config = get_block_header_config(&constructor.l1_network);
+        Self { inputs, block_header_config, l2_network: constructor.l2_network }
+    }
+
+    // l1BlockHash, l1BlockNumber, diamondProxy, batchNumber, l2BlockHash, l2BlockNumber
+    // with H256 encoded as hi-lo (u128, u128)
+    pub fn instance<F: Field>(&self) -> Vec<F> {
+        let ZkSyncEraL1BatchInput {
+            l1_storage,
+            stored_batches,
+            l2_block_number,
+            l2_block_hash,
+            ..
+        } = &self.inputs;
+        let mut instance = Vec::with_capacity(8);
+        instance.extend(encode_h256_to_field::<F>(&l1_storage.block_hash));
+        instance.push(F::from(l1_storage.block_number as u64));
+        instance.push(encode_addr_to_field(&l1_storage.storage.addr));
+        instance.push(F::from(stored_batches[0].batch_number));
+        instance.extend(encode_h256_to_field::<F>(l2_block_hash));
+        instance.push(F::from(*l2_block_number));
+        instance
+    }
+}
+
+impl EthPreCircuit for ZkSyncEraL1BatchCircuit {
+    fn create(
+        self,
+        mut builder: RlcThreadBuilder<Fr>,
+        break_points: Option<RlcThreadBreakPoints>,
+    ) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
+        let range = RangeChip::default(ETH_LOOKUP_BITS);
+        let chip = EthChip::new(RlpChip::new(&range, None), None);
+        let mut keccak = KeccakChip::default();
+
+        // ================= FIRST PHASE ================
+        let ctx = builder.gate_builder.main(FIRST_PHASE);
+        let input = self.inputs.assign(ctx);
+        let (witness, digest) = chip.parse_zksync_era_l1_batch_phase0(
+            &mut builder.gate_builder,
+            &mut keccak,
+            input,
+            &self.block_header_config,
+            &self.l2_network,
+        );
+        let ZkSyncEraL1BatchDigest {
+            l1_block_hash,
+            l1_block_number,
+            diamond_proxy,
+            batch_number,
+            l2_block_hash,
+            l2_block_number,
+        } = digest;
+
+        let assigned_instances = l1_block_hash
+            .into_iter()
+            .chain([l1_block_number, diamond_proxy, batch_number])
+            .chain(l2_block_hash)
+            .chain([l2_block_number])
+            .collect_vec();
+
+        EthCircuitBuilder::new(
+            assigned_instances,
+            builder,
+            RefCell::new(keccak),
+            range,
+            break_points,
+            move |builder: &mut RlcThreadBuilder<Fr>,
+                  rlp: RlpChip<Fr>,
+                  keccak_rlcs: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {
+                // ======== SECOND PHASE ===========
+                let chip = EthChip::new(rlp, Some(keccak_rlcs));
+                let _trace = chip.parse_zksync_era_l1_batch_phase1(builder, witness);
+            },
+        )
+    }
+}
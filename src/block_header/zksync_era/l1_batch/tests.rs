@@ -0,0 +1,75 @@
+use crate::block_header::zksync_era::l1_batch::{
+    get_stored_batch_hash_slot, ZkSyncEraL1BatchCircuit, ZkSyncEraL1BatchConstructor,
+};
+use crate::halo2_proofs::dev::MockProver;
+use crate::providers::get_zksync_era_block_with_txs_input;
+use crate::rlp::builder::RlcThreadBuilder;
+use crate::util::helpers::get_provider;
+use crate::util::EthConfigParams;
+use crate::{EthPreCircuit, EthereumNetwork, Network, ZkSyncEraNetwork};
+use ethers_core::types::H256;
+use std::str::FromStr;
+
+fn config_params() -> EthConfigParams {
+    EthConfigParams::from_path("configs/tests/zksync_era_l1_batch.json")
+}
+
+fn get_test_circuit(l2_block_number: u64) -> ZkSyncEraL1BatchCircuit {
+    let l1_network = Network::Ethereum(EthereumNetwork::Mainnet);
+    let l2_network = ZkSyncEraNetwork::Mainnet;
+    let l1_provider = get_provider(&l1_network);
+    let l2_provider = get_provider(&Network::ZkSync(l2_network));
+    let constructor = ZkSyncEraL1BatchConstructor::new(l2_block_number, l1_network, l2_network);
+    ZkSyncEraL1BatchCircuit::from_provider(&l1_provider, &l2_provider, constructor)
+}
+
+#[test]
+fn test_stored_batch_hash_slot() {
+    // keccak256(abi.encode(1, 14))
+    let slot = H256::from_str("0xa7c5ba7114a813b50159add3a36832908dc83db71d0b9a24c2ad0f83be958207")
+        .unwrap();
+    assert_eq!(get_stored_batch_hash_slot(1), slot);
+}
+
+#[test]
+#[ignore = "requires an RPC endpoint or recorded fixtures, see docs/rpc_fixtures.md"]
+fn test_mainnet_l1_batch() {
+    let params = config_params();
+    let k = params.degree;
+    let input = get_test_circuit(0x11be9ff);
+    let instance = input.instance();
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    assert_eq!(circuit.instance(), instance);
+    MockProver::run(k, &circuit, vec![instance]).unwrap().assert_satisfied();
+}
+
+#[test]
+#[ignore = "requires an RPC endpoint or recorded fixtures, see docs/rpc_fixtures.md"]
+fn test_mainnet_l1_batch_block_outside_batch() {
+    let params = config_params();
+    let k = params.degree;
+    let mut input = get_test_circuit(0x11be9ff);
+    // a block sealed long after the proven batches
+    let l2_provider = get_provider(&Network::ZkSync(ZkSyncEraNetwork::Mainnet));
+    input.inputs.l2_block_header =
+        get_zksync_era_block_with_txs_input(&l2_provider, vec![0x123b8cc]).headers.remove(0);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    let instance = circuit.instance();
+    assert!(MockProver::run(k, &circuit, vec![instance]).unwrap().verify().is_err());
+}
+
+#[test]
+#[ignore = "requires an RPC endpoint or recorded fixtures, see docs/rpc_fixtures.md"]
+fn test_mainnet_l1_batch_other_diamond_proxy() {
+    let params = config_params();
+    let k = params.degree;
+    let mut input = get_test_circuit(0x11be9ff);
+    // the mainnet batches are not stored by the Sepolia diamond proxy
+    input.l2_network = ZkSyncEraNetwork::Sepolia;
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    let instance = circuit.instance();
+    assert!(MockProver::run(k, &circuit, vec![instance]).unwrap().verify().is_err());
+}
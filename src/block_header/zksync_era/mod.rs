@@ -1,3 +1,5 @@
+pub mod l1_batch;
+#[cfg(test)]
 mod tests;
 
 use crate::keccak::{
@@ -9,12 +11,13 @@ use crate::rlp::builder::{parallelize_phase1, RlcThreadBreakPoints, RlcThreadBui
 use crate::rlp::rlc::{RlcContextPair, RlcFixedTrace, RlcTrace, FIRST_PHASE};
 use crate::rlp::{RlpArrayTraceWitness, RlpChip, RlpFieldTrace, RlpFieldWitness};
 use crate::util::helpers::load_bytes;
+use crate::util::rpc_client::RpcClient;
 use crate::util::{
     bytes_be_to_u128, bytes_be_var_to_fixed, get_hash_bytes_inner_product, is_zero_vec,
 };
 use crate::{EthChip, EthCircuitBuilder, EthPreCircuit, Network, ETH_LOOKUP_BITS};
 use ethers_core::types::H256;
-use ethers_providers::{Http, Provider, RetryClient};
+use ethers_providers::Provider;
 use halo2_base::gates::builder::GateThreadBuilder;
 use halo2_base::gates::{GateInstructions, RangeChip};
 use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
@@ -25,7 +28,7 @@ use std::cell::RefCell;
 use std::iter::repeat;
 use zkevm_keccak::util::eth_types::Field;
 const NUM_BLOCK_HEADER_FIELDS: usize = 3;
-const BLOCK_HEADER_MAX_FIELD_LENS: [usize; NUM_BLOCK_HEADER_FIELDS] = [4, 8, 32];
+pub(crate) const BLOCK_HEADER_MAX_FIELD_LENS: [usize; NUM_BLOCK_HEADER_FIELDS] = [4, 8, 32];
 pub(crate) const BLOCK_HEADER_RLP_MAX_BYTES: usize = 5 + 9 + 33;
 pub(crate) const BLOCK_INCLUDE_TXS_MAX_NUMBER: u64 = 50;
 
@@ -44,6 +47,10 @@ pub struct ZkSyncEraBlockHeaderInputAssigned<F: Field> {
 }
 
 impl ZkSyncEraBlockHeaderInput {
+    pub fn block_number(&self) -> u64 {
+        rlp::Rlp::new(&self.block_header).val_at(0).unwrap()
+    }
+
     pub fn assign<F: Field>(self, ctx: &mut Context<F>) -> ZkSyncEraBlockHeaderInputAssigned<F> {
         let mut block_header = self.block_header;
         block_header.resize(BLOCK_HEADER_RLP_MAX_BYTES, 0u8);
@@ -86,7 +93,7 @@ pub struct ZkSyncEraBlockHeaderChainCircuit {
 impl ZkSyncEraBlockHeaderChainCircuit {
     #[cfg(feature = "providers")]
     pub fn from_provider(
-        provider: &Provider<RetryClient<Http>>,
+        provider: &Provider<RpcClient>,
         _network: Network,
         blocks_number: Vec<u64>,
     ) -> Self {
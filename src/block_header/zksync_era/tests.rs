@@ -6,7 +6,6 @@ use crate::util::{EthConfigPinning, Halo2ConfigPinning};
 use crate::{EthPreCircuit, Network, ZkSyncEraNetwork};
 use ethers_core::types::{H256, U256};
 use ethers_core::utils::keccak256;
-use std::env::set_var;
 use std::str::FromStr;
 
 pub fn get_test_circuit(
@@ -20,10 +19,10 @@ pub fn get_test_circuit(
 #[test]
 pub fn test_mainnet_block_header() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigPinning::from_path("configs/tests/zksync_era_block.json").params;
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree; //0x123b8cc 0x11be9fe
     let input = get_test_circuit(vec![0x11be9ff], Network::ZkSync(ZkSyncEraNetwork::Mainnet));
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
     Ok(())
 }
@@ -31,10 +30,10 @@ pub fn test_mainnet_block_header() -> Result<(), Box<dyn std::error::Error>> {
 #[test]
 pub fn test_zksync_goerli_block_header() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigPinning::from_path("configs/tests/zksync_era_block.json").params;
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree; //0xd20633 0xd2064f
     let input = get_test_circuit(vec![0xd2064f], Network::ZkSync(ZkSyncEraNetwork::Goerli));
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
     Ok(())
 }
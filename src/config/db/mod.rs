@@ -4,3 +4,8 @@ pub fn get_rocks_db_path() -> String {
     let setting = Settings::get();
     setting.db.path.clone()
 }
+
+pub fn get_jobs_db_path() -> String {
+    let setting = Settings::get();
+    setting.db.jobs_path.clone()
+}
@@ -4,6 +4,8 @@ use crate::config::setting::Settings;
 pub struct Ethereum {
     pub mainnet: String,
     pub goerli: String,
+    pub sepolia: String,
+    pub holesky: String,
 }
 
 #[derive(Clone, Debug)]
@@ -18,10 +20,17 @@ pub struct Optimism {
     pub goerli: String,
 }
 
+#[derive(Clone, Debug)]
+pub struct Base {
+    pub mainnet: String,
+    pub goerli: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct ZksyncEra {
     pub mainnet: String,
     pub goerli: String,
+    pub sepolia: String,
 }
 
 #[derive(Clone, Debug)]
@@ -29,6 +38,7 @@ pub struct RPCS {
     pub ethereum: Ethereum,
     pub arbitrum: Arbitrum,
     pub optimism: Optimism,
+    pub base: Base,
     pub zksync_era: ZksyncEra,
 }
 
@@ -38,6 +48,8 @@ pub fn get_rpcs_config() -> RPCS {
         ethereum: Ethereum {
             mainnet: setting.rpcs.mainnet.clone(),
             goerli: setting.rpcs.goerli.clone(),
+            sepolia: setting.rpcs.sepolia.clone(),
+            holesky: setting.rpcs.holesky.clone(),
         },
         arbitrum: Arbitrum {
             mainnet: setting.rpcs.arbitrum_mainnet.clone(),
@@ -47,9 +59,14 @@ pub fn get_rpcs_config() -> RPCS {
             mainnet: setting.rpcs.optimism_mainnet.clone(),
             goerli: setting.rpcs.optimism_goerli.clone(),
         },
+        base: Base {
+            mainnet: setting.rpcs.base_mainnet.clone(),
+            goerli: setting.rpcs.base_goerli.clone(),
+        },
         zksync_era: ZksyncEra {
             mainnet: setting.rpcs.zksync_mainnet.clone(),
             goerli: setting.rpcs.zksync_goerli.clone(),
+            sepolia: setting.rpcs.zksync_sepolia.clone(),
         },
     }
 }
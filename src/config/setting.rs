@@ -5,6 +5,13 @@ use std::{fs::File, io::Read};
 #[derive(Clone, Debug, Deserialize)]
 pub struct DB {
     pub path: String,
+    /// Added after `path`, a config without it keeps the jobs next to the challenges.
+    #[serde(default = "default_jobs_path")]
+    pub jobs_path: String,
+}
+
+fn default_jobs_path() -> String {
+    "./jobs_db".to_owned()
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -17,6 +24,10 @@ pub struct Api {
 pub struct Rpcs {
     pub mainnet: String,
     pub goerli: String,
+    #[serde(default)]
+    pub sepolia: String,
+    #[serde(default)]
+    pub holesky: String,
 
     pub arbitrum_mainnet: String,
     pub arbitrum_goerli: String,
@@ -24,8 +35,13 @@ pub struct Rpcs {
     pub optimism_mainnet: String,
     pub optimism_goerli: String,
 
+    pub base_mainnet: String,
+    pub base_goerli: String,
+
     pub zksync_mainnet: String,
     pub zksync_goerli: String,
+    #[serde(default)]
+    pub zksync_sepolia: String,
 }
 #[derive(Clone, Debug, Deserialize)]
 pub struct Settings {
@@ -1,8 +1,17 @@
-use crate::config::db::get_rocks_db_path;
+#[cfg(test)]
+mod tests;
+
+use crate::config::db::{get_jobs_db_path, get_rocks_db_path};
+use crate::server::OriginalProof;
 use ethers_core::types::H256;
 use jsonrpsee::core::Serialize;
-use rocksdb::{DBWithThreadMode, Error, Options, SingleThreaded, DB};
+use rocksdb::{DBWithThreadMode, Error, IteratorMode, Options, SingleThreaded, DB};
 use serde::Deserialize;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A failed job is put back in the queue until it has been retried this many times.
+pub const MAX_JOB_RETRIES: u32 = 3;
 
 pub struct ChallengesStorage {
     pub storage: DBWithThreadMode<SingleThreaded>,
@@ -10,7 +19,10 @@ pub struct ChallengesStorage {
 
 impl ChallengesStorage {
     pub fn new() -> Self {
-        let path = get_rocks_db_path();
+        Self::open(get_rocks_db_path())
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Self {
         let mut opts = Options::default();
         opts.create_if_missing(true);
         ChallengesStorage {
@@ -26,3 +38,202 @@ impl ChallengesStorage {
         self.storage.get(challenge_id)
     }
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Failed,
+    Done,
+    Cancelled,
+}
+
+impl JobStatus {
+    /// The job won't be proven anymore.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Failed | JobStatus::Done | JobStatus::Cancelled)
+    }
+}
+
+/// Why an attempt to prove a job failed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobFailure {
+    /// The first stage of the DAG whose snark was not generated, if the stages are known.
+    pub stage: Option<String>,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProofJob {
+    pub task_id: H256,
+    pub proof: String,
+    pub status: JobStatus,
+    pub retries: u32,
+    /// Unix time in milliseconds, jobs are resumed in the order they were received.
+    pub enqueued_at: u128,
+    /// The tasks of the proving DAG, dependencies first, known once the job has started.
+    #[serde(default)]
+    pub stages: Vec<String>,
+    /// One failure per failed attempt.
+    #[serde(default)]
+    pub failures: Vec<JobFailure>,
+}
+
+impl ProofJob {
+    pub fn original_proof(&self) -> OriginalProof {
+        OriginalProof { task_id: self.task_id, proof: self.proof.clone() }
+    }
+}
+
+/// Proving jobs received by the server, keyed by task id, so that they survive a restart.
+pub struct ProofJobQueue {
+    pub storage: DBWithThreadMode<SingleThreaded>,
+}
+
+impl ProofJobQueue {
+    pub fn new() -> Self {
+        Self::open(get_jobs_db_path())
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        ProofJobQueue {
+            storage: DB::open(&opts, path).expect("Initialize job queue should succeed"),
+        }
+    }
+
+    fn put_job(&self, job: &ProofJob) -> Result<(), Error> {
+        let value = serde_json::to_vec(job).expect("serialize job should succeed");
+        self.storage.put(job.task_id.as_bytes(), value)
+    }
+
+    pub fn get_job(&self, task_id: H256) -> Result<Option<ProofJob>, Error> {
+        Ok(self
+            .storage
+            .get(task_id)?
+            .map(|value| serde_json::from_slice(&value).expect("deserialize job should succeed")))
+    }
+
+    /// Adds a pending job, a task submitted again starts over with no retries.
+    pub fn enqueue(&self, original_proof: &OriginalProof) -> Result<ProofJob, Error> {
+        let enqueued_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
+        let job = ProofJob {
+            task_id: original_proof.task_id,
+            proof: original_proof.proof.clone(),
+            status: JobStatus::Pending,
+            retries: 0,
+            enqueued_at,
+            stages: vec![],
+            failures: vec![],
+        };
+        self.put_job(&job)?;
+        Ok(job)
+    }
+
+    fn update_job(
+        &self,
+        task_id: H256,
+        update: impl FnOnce(&mut ProofJob),
+    ) -> Result<Option<ProofJob>, Error> {
+        let mut job = match self.get_job(task_id)? {
+            Some(job) => job,
+            None => return Ok(None),
+        };
+        update(&mut job);
+        self.put_job(&job)?;
+        Ok(Some(job))
+    }
+
+    pub fn mark_running(&self, task_id: H256) -> Result<Option<ProofJob>, Error> {
+        self.update_job(task_id, |job| job.status = JobStatus::Running)
+    }
+
+    pub fn set_stages(
+        &self,
+        task_id: H256,
+        stages: Vec<String>,
+    ) -> Result<Option<ProofJob>, Error> {
+        self.update_job(task_id, |job| job.stages = stages)
+    }
+
+    /// A job cancelled while it was running stays cancelled.
+    pub fn mark_done(&self, task_id: H256) -> Result<Option<ProofJob>, Error> {
+        self.update_job(task_id, |job| {
+            if job.status != JobStatus::Cancelled {
+                job.status = JobStatus::Done;
+            }
+        })
+    }
+
+    /// Counts a failed attempt, the job is pending again until it reaches [`MAX_JOB_RETRIES`].
+    pub fn mark_failed(
+        &self,
+        task_id: H256,
+        failure: JobFailure,
+    ) -> Result<Option<ProofJob>, Error> {
+        self.update_job(task_id, |job| {
+            job.retries += 1;
+            job.failures.push(failure);
+            if job.status != JobStatus::Cancelled {
+                job.status = if job.retries < MAX_JOB_RETRIES {
+                    JobStatus::Pending
+                } else {
+                    JobStatus::Failed
+                };
+            }
+        })
+    }
+
+    /// Fails the job without retrying it, another attempt would fail the same way.
+    pub fn mark_rejected(
+        &self,
+        task_id: H256,
+        failure: JobFailure,
+    ) -> Result<Option<ProofJob>, Error> {
+        self.update_job(task_id, |job| {
+            job.failures.push(failure);
+            if job.status != JobStatus::Cancelled {
+                job.status = JobStatus::Failed;
+            }
+        })
+    }
+
+    /// Cancels a job which is not finished yet. A running attempt is not interrupted, its proof is discarded
+    /// once it ends.
+    pub fn cancel(&self, task_id: H256) -> Result<Option<ProofJob>, Error> {
+        self.update_job(task_id, |job| {
+            if !job.status.is_finished() {
+                job.status = JobStatus::Cancelled;
+            }
+        })
+    }
+
+    /// Every job, oldest first.
+    pub fn jobs(&self) -> Result<Vec<ProofJob>, Error> {
+        let mut jobs = vec![];
+        for item in self.storage.iterator(IteratorMode::Start) {
+            let (_, value) = item?;
+            jobs.push(serde_json::from_slice(&value).expect("deserialize job should succeed"));
+        }
+        jobs.sort_by_key(|job: &ProofJob| job.enqueued_at);
+        Ok(jobs)
+    }
+
+    /// Puts the jobs interrupted by a restart back to pending and returns every pending job, oldest first.
+    pub fn recover(&self) -> Result<Vec<ProofJob>, Error> {
+        let mut jobs = vec![];
+        for mut job in self.jobs()? {
+            match job.status {
+                JobStatus::Running => {
+                    job.status = JobStatus::Pending;
+                    self.put_job(&job)?;
+                    jobs.push(job);
+                }
+                JobStatus::Pending => jobs.push(job),
+                JobStatus::Failed | JobStatus::Done | JobStatus::Cancelled => {}
+            }
+        }
+        Ok(jobs)
+    }
+}
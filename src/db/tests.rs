@@ -0,0 +1,173 @@
+use crate::config::setting::DB;
+use crate::db::{JobFailure, JobStatus, ProofJobQueue, MAX_JOB_RETRIES};
+use crate::server::OriginalProof;
+use ethers_core::types::H256;
+use std::env::temp_dir;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+/// A job queue in a temporary directory, removed once dropped.
+struct TestQueue {
+    dir: PathBuf,
+}
+
+impl TestQueue {
+    fn new(name: &str) -> Self {
+        let dir = temp_dir().join(format!("zkspv_jobs_{name}_{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Self { dir }
+    }
+
+    /// Opening the queue again is what the server does after a crash.
+    fn open(&self) -> ProofJobQueue {
+        ProofJobQueue::open(&self.dir)
+    }
+}
+
+impl Drop for TestQueue {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn task_id(id: u64) -> H256 {
+    H256::from_low_u64_be(id)
+}
+
+fn enqueue(job_queue: &ProofJobQueue, id: u64) {
+    job_queue
+        .enqueue(&OriginalProof { task_id: task_id(id), proof: format!("{{\"id\":{id}}}") })
+        .unwrap();
+}
+
+fn failure(message: &str) -> JobFailure {
+    JobFailure { stage: Some("ethereum_transaction".to_owned()), message: message.to_owned() }
+}
+
+#[test]
+fn test_recover_running_job_after_crash() {
+    let queue = TestQueue::new("crash");
+    {
+        let job_queue = queue.open();
+        enqueue(&job_queue, 1);
+        job_queue.mark_running(task_id(1)).unwrap();
+        job_queue.set_stages(task_id(1), vec!["ethereum_transaction".to_owned()]).unwrap();
+        job_queue.mark_failed(task_id(1), failure("out of memory")).unwrap();
+        job_queue.mark_running(task_id(1)).unwrap();
+        // the process dies while proving, the queue is never updated again
+    }
+
+    let job_queue = queue.open();
+    let jobs = job_queue.recover().unwrap();
+    assert_eq!(jobs.len(), 1);
+    let job = &jobs[0];
+    assert_eq!(job.status, JobStatus::Pending);
+    assert_eq!(job.proof, "{\"id\":1}");
+    // the interrupted attempt is not counted as a failure
+    assert_eq!(job.retries, 1);
+    assert_eq!(job.failures, vec![failure("out of memory")]);
+    assert_eq!(job.stages, vec!["ethereum_transaction".to_owned()]);
+    assert_eq!(job_queue.get_job(task_id(1)).unwrap().unwrap().status, JobStatus::Pending);
+
+    // recovering twice resumes the same jobs
+    let jobs = job_queue.recover().unwrap();
+    assert_eq!(jobs.iter().map(|job| job.task_id).collect::<Vec<_>>(), [task_id(1)]);
+}
+
+#[test]
+fn test_recover_jobs_in_order() {
+    let queue = TestQueue::new("order");
+    {
+        let job_queue = queue.open();
+        for id in [3, 1, 2] {
+            enqueue(&job_queue, id);
+            // jobs are ordered by their enqueue time in milliseconds
+            thread::sleep(Duration::from_millis(2));
+        }
+        job_queue.mark_running(task_id(1)).unwrap();
+    }
+
+    let jobs = queue.open().recover().unwrap();
+    assert_eq!(
+        jobs.iter().map(|job| job.task_id).collect::<Vec<_>>(),
+        [task_id(3), task_id(1), task_id(2)]
+    );
+}
+
+#[test]
+fn test_retry_failed_job() {
+    let queue = TestQueue::new("retry");
+    let job_queue = queue.open();
+    enqueue(&job_queue, 1);
+
+    for retries in 1..MAX_JOB_RETRIES {
+        job_queue.mark_running(task_id(1)).unwrap();
+        let job = job_queue.mark_failed(task_id(1), failure("prove error")).unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Pending);
+        assert_eq!(job.retries, retries);
+    }
+    job_queue.mark_running(task_id(1)).unwrap();
+    let job = job_queue.mark_failed(task_id(1), failure("prove error")).unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Failed);
+    assert_eq!(job.retries, MAX_JOB_RETRIES);
+    assert_eq!(job.failures.len(), MAX_JOB_RETRIES as usize);
+
+    // a job out of retries is not resumed after a restart
+    drop(job_queue);
+    assert!(queue.open().recover().unwrap().is_empty());
+}
+
+#[test]
+fn test_rejected_job_is_not_retried() {
+    let queue = TestQueue::new("rejected");
+    let job_queue = queue.open();
+    enqueue(&job_queue, 1);
+    job_queue.mark_running(task_id(1)).unwrap();
+    let job = job_queue
+        .mark_rejected(task_id(1), JobFailure { stage: None, message: "invalid".to_owned() })
+        .unwrap()
+        .unwrap();
+    assert_eq!(job.status, JobStatus::Failed);
+    assert_eq!(job.retries, 0);
+    assert!(job_queue.recover().unwrap().is_empty());
+}
+
+#[test]
+fn test_cancelled_running_job_stays_cancelled() {
+    let queue = TestQueue::new("cancelled");
+    let job_queue = queue.open();
+    for id in [1, 2] {
+        enqueue(&job_queue, id);
+        job_queue.mark_running(task_id(id)).unwrap();
+        job_queue.cancel(task_id(id)).unwrap();
+    }
+
+    let job = job_queue.mark_failed(task_id(1), failure("prove error")).unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Cancelled);
+    let job = job_queue.mark_done(task_id(2)).unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Cancelled);
+    assert!(job_queue.recover().unwrap().is_empty());
+}
+
+#[test]
+fn test_enqueue_again_resets_retries() {
+    let queue = TestQueue::new("enqueue_again");
+    let job_queue = queue.open();
+    enqueue(&job_queue, 1);
+    job_queue.mark_failed(task_id(1), failure("prove error")).unwrap();
+    enqueue(&job_queue, 1);
+
+    let job = job_queue.get_job(task_id(1)).unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Pending);
+    assert_eq!(job.retries, 0);
+    assert!(job.failures.is_empty());
+}
+
+#[test]
+fn test_db_config_without_jobs_path() {
+    let db: DB = toml::from_str("path = \"./challenges_db\"").unwrap();
+    assert_eq!(db.jobs_path, "./jobs_db");
+}
@@ -1,5 +1,8 @@
 pub mod util;
 
+#[cfg(test)]
+mod tests;
+
 use crate::ecdsa::util::recover_tx_info;
 use crate::halo2_proofs::{
     arithmetic::CurveAffine,
@@ -18,10 +21,17 @@ use crate::halo2_proofs::{
     },
     transcript::{TranscriptReadBuffer, TranscriptWriterBuffer},
 };
+use crate::keccak::{FixedLenRLCs, FnSynthesize, KeccakChip, VarLenRLCs};
 use crate::mpt::AssignedBytes;
+use crate::rlp::builder::{RlcThreadBreakPoints, RlcThreadBuilder};
 use crate::rlp::rlc::FIRST_PHASE;
+use crate::rlp::RlpChip;
+use crate::util::circuit_config::{isolate_env, CircuitConfig, CircuitConfigScope};
 use crate::util::helpers::load_bytes;
-use crate::{EthChip, EthCircuitBuilder, EthPreCircuit, ETH_LOOKUP_BITS};
+use crate::util::{bytes_be_to_uint, encode_addr_to_field};
+use crate::{
+    EthChip, EthCircuitBuilder, EthPreCircuit, ETH_LIMB_BITS, ETH_LOOKUP_BITS, ETH_NUM_LIMBS,
+};
 use ark_std::{end_timer, start_timer};
 use ethers_core::k256::elliptic_curve::consts::U32;
 use ethers_core::k256::elliptic_curve::generic_array::GenericArray;
@@ -33,12 +43,14 @@ use ethers_core::k256::{
     EncodedPoint, PublicKey, Secp256k1,
 };
 use ethers_core::types::transaction::eip2718::TypedTransaction;
-use ethers_core::types::{Bytes, RecoveryMessage, Signature, Transaction, H256};
+use ethers_core::types::{Address, Bytes, RecoveryMessage, Signature, Transaction, H256};
 use ethers_core::utils::{hash_message, keccak256};
 use halo2_base::gates::builder::{
-    CircuitBuilderStage, GateThreadBuilder, MultiPhaseThreadBreakPoints, RangeCircuitBuilder,
+    CircuitBuilderStage, FlexGateConfigParams, GateThreadBuilder, MultiPhaseThreadBreakPoints,
+    RangeCircuitBuilder,
 };
-use halo2_base::gates::{GateInstructions, RangeChip};
+use halo2_base::gates::flex_gate::GateStrategy;
+use halo2_base::gates::{GateInstructions, RangeChip, RangeInstructions};
 use halo2_base::halo2_proofs::halo2curves::group::{Curve, GroupEncoding};
 use halo2_base::halo2_proofs::halo2curves::secp256k1::Secp256k1Compressed;
 use halo2_base::halo2_proofs::halo2curves::serde::SerdeObject;
@@ -53,6 +65,7 @@ use snark_verifier::loader::halo2::halo2_ecc::ecc::ecdsa::ecdsa_verify_no_pubkey
 use snark_verifier::loader::halo2::halo2_ecc::ecc::EccChip;
 use snark_verifier::loader::halo2::halo2_ecc::fields::{FieldChip, FpStrategy, PrimeField};
 use snark_verifier::loader::halo2::halo2_ecc::secp256k1::{FpChip, FqChip};
+use std::cell::RefCell;
 use std::fs::File;
 use std::hash::Hasher;
 use zkevm_keccak::util::eth_types::Field;
@@ -79,24 +92,95 @@ impl<'chip, F: PrimeField> EcdsaChip<'chip, F> {
         Self { fp_chip, fq_chip }
     }
 
+    /// Verifies the signature over the witness message hash. The public key is constrained to be
+    /// on secp256k1 and `public_key_bytes` to be its uncompressed encoding.
     pub fn ecdsa_pubkey_verify(
         &self,
         ctx: &mut Context<F>,
         ecdsa_input_assigned: EthEcdsaInputAssigned<F>,
+    ) -> AssignedValue<F> {
+        self.ecdsa_verify(ctx, ecdsa_input_assigned, None)
+    }
+
+    fn ecdsa_verify(
+        &self,
+        ctx: &mut Context<F>,
+        ecdsa_input_assigned: EthEcdsaInputAssigned<F>,
+        message_hash_bytes: Option<&[AssignedValue<F>]>,
     ) -> AssignedValue<F> {
         let [m, r, s] =
             [ecdsa_input_assigned.message_hash, ecdsa_input_assigned.r, ecdsa_input_assigned.s]
                 .map(|x| self.fq_chip.load_private(ctx, x));
+        if let Some(message_hash_bytes) = message_hash_bytes {
+            self.constrain_limbs_to_bytes(ctx, m.limbs(), message_hash_bytes);
+        }
 
+        // `load_private` checks that the point is on the curve
         let ecc_chip = EccChip::<F, FpChip<F>>::new(&self.fp_chip);
-        let pk = ecc_chip.load_private_unchecked(
+        let pk = ecc_chip.load_private::<Secp256k1Affine>(
             ctx,
             (ecdsa_input_assigned.public_key.x, ecdsa_input_assigned.public_key.y),
         );
+        // the coordinates must be reduced, otherwise `x + p` would encode the same point
+        for coordinate in [pk.x(), pk.y()] {
+            self.fp_chip.enforce_less_than(ctx, coordinate.clone());
+        }
+        let public_key_bytes = &ecdsa_input_assigned.public_key_bytes;
+        assert_eq!(public_key_bytes.len(), 64);
+        for byte in public_key_bytes.iter() {
+            self.fp_chip.range().range_check(ctx, *byte, 8);
+        }
+        self.constrain_limbs_to_bytes(ctx, pk.x().limbs(), &public_key_bytes[..32]);
+        self.constrain_limbs_to_bytes(ctx, pk.y().limbs(), &public_key_bytes[32..]);
+
         ecdsa_verify_no_pubkey_check::<F, Fp, Fq, Secp256k1Affine>(
             &ecc_chip, ctx, pk, r, s, m, 4, 4,
         )
     }
+
+    /// Constrains the little endian CRT `limbs` to be the big endian `bytes`, every limb packs
+    /// `limb_bits / 8` bytes.
+    fn constrain_limbs_to_bytes(
+        &self,
+        ctx: &mut Context<F>,
+        limbs: &[AssignedValue<F>],
+        bytes: &[AssignedValue<F>],
+    ) {
+        let limb_bits = self.fq_chip.limb_bits;
+        assert_eq!(limb_bits % 8, 0);
+        let bytes_le = bytes.iter().rev().copied().collect_vec();
+        let limbs_bytes_le = bytes_le.chunks(limb_bits / 8).collect_vec();
+        assert_eq!(limbs_bytes_le.len(), limbs.len());
+        for (limb, limb_bytes_le) in limbs.iter().zip(limbs_bytes_le) {
+            let limb_bytes_be = limb_bytes_le.iter().rev().copied().collect_vec();
+            let expected = bytes_be_to_uint(
+                ctx,
+                self.fq_chip.range().gate(),
+                &limb_bytes_be,
+                limb_bytes_be.len(),
+            );
+            ctx.constrain_equal(limb, &expected);
+        }
+    }
+}
+
+impl<'chip, F: PrimeField + Field> EcdsaChip<'chip, F> {
+    /// Same as `ecdsa_pubkey_verify`, and also returns the 20 bytes address `keccak(public_key)[12..]`.
+    /// If `message_hash_bytes` is set, the message hash is constrained to equal these big endian bytes.
+    pub fn ecdsa_address_verify(
+        &self,
+        ctx: &mut Context<F>,
+        keccak: &mut KeccakChip<F>,
+        ecdsa_input_assigned: EthEcdsaInputAssigned<F>,
+        message_hash_bytes: Option<&[AssignedValue<F>]>,
+    ) -> (AssignedValue<F>, AssignedBytes<F>) {
+        let public_key_bytes = ecdsa_input_assigned.public_key_bytes.clone();
+        let verify_result = self.ecdsa_verify(ctx, ecdsa_input_assigned, message_hash_bytes);
+        let public_key_hash_idx =
+            keccak.keccak_fixed_len(ctx, self.fp_chip.range().gate(), public_key_bytes, None);
+        let public_key_hash = &keccak.fixed_len_queries[public_key_hash_idx].output_assigned;
+        (verify_result, public_key_hash[12..].to_vec())
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -114,10 +198,18 @@ pub struct EthEcdsaInputAssigned<F: PrimeField> {
     pub message_hash: Fq,
     pub public_key: Secp256k1Affine,
     pub public_key_bytes: AssignedBytes<F>,
+    // the zero padded signing payload, bytes are not range checked here
+    pub message: AssignedBytes<F>,
 }
 
 impl EthEcdsaInput {
-    pub fn assign<F: Field>(self, ctx: &mut Context<F>) -> EthEcdsaInputAssigned<F> {
+    /// The signing payload is zero padded to `message_max_len` bytes, so the cells assigned for it only
+    /// depend on the config. Circuits which only verify the message hash pass `None` and don't load it.
+    pub fn assign<F: Field>(
+        self,
+        ctx: &mut Context<F>,
+        message_max_len: Option<usize>,
+    ) -> EthEcdsaInputAssigned<F> {
         let public_key_point = self.public_key.to_encoded_point(false);
         let public_key_point_x = hex::encode(public_key_point.x().unwrap());
         let public_key_point_y = hex::encode(public_key_point.y().unwrap());
@@ -140,18 +232,34 @@ impl EthEcdsaInput {
         let s_q = self.signature.s.0.clone();
         let s = Fq::from_raw(s_q);
 
-        let p_x_bytes: [u8; 32] = public_key.x.to_bytes();
-        let mut p_x_bytes = p_x_bytes.to_vec();
-        p_x_bytes.reverse();
-        let p_y_bytes: [u8; 32] = public_key.y.to_bytes();
-        let mut p_y_bytes = p_y_bytes.to_vec();
-        p_y_bytes.reverse();
-        let public_key_bytes = load_bytes(ctx, [p_x_bytes, p_y_bytes].concat().as_slice());
+        let public_key_bytes = load_bytes(ctx, &encode_public_key(&public_key));
+
+        let message = match message_max_len {
+            Some(message_max_len) => {
+                assert!(self.message.len() <= message_max_len);
+                let mut message = self.message.to_vec();
+                message.resize(message_max_len, 0);
+                load_bytes(ctx, &message)
+            }
+            None => vec![],
+        };
 
-        EthEcdsaInputAssigned { r, s, message_hash, public_key, public_key_bytes }
+        EthEcdsaInputAssigned { r, s, message_hash, public_key, public_key_bytes, message }
     }
 }
 
+/// Uncompressed public key without the 0x04 prefix, `x || y` in big endian.
+pub fn encode_public_key(public_key: &Secp256k1Affine) -> Vec<u8> {
+    [public_key.x, public_key.y]
+        .iter()
+        .flat_map(|coordinate| {
+            let mut bytes: [u8; 32] = coordinate.to_bytes();
+            bytes.reverse();
+            bytes
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug)]
 pub struct EthEcdsaCircuit {
     pub inputs: EthEcdsaInput,
@@ -163,13 +271,24 @@ impl EthEcdsaCircuit {
         let (signature, message, message_hash, public_key) = recover_tx_info(&transaction);
         Self { inputs: EthEcdsaInput { signature, message, message_hash, public_key } }
     }
-    fn create_circuit(
+
+    /// The signer address.
+    pub fn instance<F: Field>(&self) -> Vec<F> {
+        let public_key_point = self.inputs.public_key.to_encoded_point(false);
+        let address = Address::from_slice(&keccak256(&public_key_point.as_bytes()[1..])[12..]);
+        vec![encode_addr_to_field(&address)]
+    }
+
+    /// Only verifies the signature, without instances or keccak. `create_circuit` is taken by
+    /// `EthPreCircuit`, which also exposes the signer address.
+    ///
+    /// Returns the configuration the circuit must be keygened and proven in.
+    fn create_range_circuit(
         self,
         params: CircuitParams,
         stage: CircuitBuilderStage,
         break_points: Option<MultiPhaseThreadBreakPoints>,
-    ) -> RangeCircuitBuilder<Fr> {
-        std::env::set_var("LOOKUP_BITS", params.lookup_bits.to_string());
+    ) -> (RangeCircuitBuilder<Fr>, CircuitConfig) {
         let mut builder = match stage {
             CircuitBuilderStage::Mock => GateThreadBuilder::mock(),
             CircuitBuilderStage::Prover => GateThreadBuilder::prover(),
@@ -181,7 +300,7 @@ impl EthEcdsaCircuit {
         let fq_chip = FqChip::new(&range, params.limb_bits, params.num_limbs);
 
         let ctx = builder.main(FIRST_PHASE);
-        let input = self.inputs.assign(ctx);
+        let input = self.inputs.assign(ctx, None);
         let ecdsa_chip = EcdsaChip::new(&fp_chip, &fq_chip);
 
         let res = ecdsa_chip.ecdsa_pubkey_verify(ctx, input);
@@ -190,20 +309,57 @@ impl EthEcdsaCircuit {
             range.gate.assert_is_const(ctx, &res, &Fr::one());
         }
 
+        let gate_params = match stage {
+            CircuitBuilderStage::Prover => FlexGateConfigParams {
+                k: params.degree as usize,
+                num_advice_per_phase: vec![params.num_advice],
+                num_lookup_advice_per_phase: vec![params.num_lookup_advice],
+                strategy: GateStrategy::Vertical,
+                num_fixed: params.num_fixed,
+            },
+            _ => isolate_env(|| builder.config(params.degree as usize, Some(20))),
+        };
         let circuit = match stage {
-            CircuitBuilderStage::Mock => {
-                builder.config(params.degree as usize, Some(20));
-                RangeCircuitBuilder::mock(builder)
-            }
-            CircuitBuilderStage::Keygen => {
-                builder.config(params.degree as usize, Some(20));
-                RangeCircuitBuilder::keygen(builder)
-            }
+            CircuitBuilderStage::Mock => RangeCircuitBuilder::mock(builder),
+            CircuitBuilderStage::Keygen => RangeCircuitBuilder::keygen(builder),
             CircuitBuilderStage::Prover => {
                 RangeCircuitBuilder::prover(builder, break_points.unwrap())
             }
         };
-        circuit
+        (circuit, CircuitConfig::Gate { params: gate_params, lookup_bits: params.lookup_bits })
+    }
+}
+
+impl EthPreCircuit for EthEcdsaCircuit {
+    fn create(
+        self,
+        mut builder: RlcThreadBuilder<Fr>,
+        break_points: Option<RlcThreadBreakPoints>,
+    ) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
+        let range = RangeChip::default(ETH_LOOKUP_BITS);
+        let mut keccak = KeccakChip::default();
+        let fp_chip = FpChip::new(&range, ETH_LIMB_BITS, ETH_NUM_LIMBS);
+        let fq_chip = FqChip::new(&range, ETH_LIMB_BITS, ETH_NUM_LIMBS);
+        let ecdsa_chip = EcdsaChip::new(&fp_chip, &fq_chip);
+
+        // ================= FIRST PHASE ================
+        let ctx = builder.gate_builder.main(FIRST_PHASE);
+        let input = self.inputs.assign(ctx, None);
+        let (verify_result, address) =
+            ecdsa_chip.ecdsa_address_verify(ctx, &mut keccak, input, None);
+        range.gate.assert_is_const(ctx, &verify_result, &Fr::one());
+        let address = bytes_be_to_uint(ctx, range.gate(), &address, 20);
+
+        EthCircuitBuilder::new(
+            vec![address],
+            builder,
+            RefCell::new(keccak),
+            range,
+            break_points,
+            |_: &mut RlcThreadBuilder<Fr>,
+             _: RlpChip<Fr>,
+             _: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {},
+        )
     }
 }
 
@@ -217,7 +373,7 @@ fn test_ecdsa_circuit() {
     let transaction_rlp = Vec::from_hex("02f873010285020a08fb2885020a08fb2882520894a79ed52d6774259535428f2533a8420703a4078f87054e13428c955280c080a02a3222ebb694535ee03ced3a0bc75a7c37b5053be9dcccc15894e014b1fd3a81a079250a246c8846c86cc24a84d2966752d9999ab4f05b5cca98762400e0a0f813").unwrap();
 
     let input = EthEcdsaCircuit::new(transaction_rlp);
-    let circuit = input.create_circuit(params, CircuitBuilderStage::Mock, None);
-    // let circuit = real_ecdsa_circuit(params, CircuitBuilderStage::Mock, None);
+    let (circuit, config) = input.create_range_circuit(params, CircuitBuilderStage::Mock, None);
+    let _scope = CircuitConfigScope::enter(config);
     MockProver::run(params.degree, &circuit, vec![]).unwrap().assert_satisfied();
 }
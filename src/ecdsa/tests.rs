@@ -0,0 +1,134 @@
+use std::cell::RefCell;
+
+use ethers_core::types::Address;
+use halo2_base::gates::{GateInstructions, RangeChip};
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::Context;
+use hex::FromHex;
+use snark_verifier::loader::halo2::halo2_ecc::secp256k1::{FpChip, FqChip};
+
+use crate::ecdsa::{
+    encode_public_key, EcdsaChip, EthEcdsaCircuit, EthEcdsaInput, EthEcdsaInputAssigned,
+};
+use crate::halo2_proofs::arithmetic::CurveAffine;
+use crate::halo2_proofs::dev::MockProver;
+use crate::halo2_proofs::halo2curves::secp256k1::{Fp, Secp256k1Affine};
+use crate::keccak::{FixedLenRLCs, FnSynthesize, KeccakChip, VarLenRLCs};
+use crate::rlp::builder::RlcThreadBuilder;
+use crate::rlp::rlc::FIRST_PHASE;
+use crate::rlp::RlpChip;
+use crate::util::helpers::load_bytes;
+use crate::util::{bytes_be_to_uint, encode_addr_to_field, EthConfigParams};
+use crate::{EthCircuitBuilder, EthPreCircuit, ETH_LIMB_BITS, ETH_LOOKUP_BITS, ETH_NUM_LIMBS};
+
+const TRANSACTION_HEX: &str = "02f873010285020a08fb2885020a08fb2882520894a79ed52d6774259535428f2533a8420703a4078f87054e13428c955280c080a02a3222ebb694535ee03ced3a0bc75a7c37b5053be9dcccc15894e014b1fd3a81a079250a246c8846c86cc24a84d2966752d9999ab4f05b5cca98762400e0a0f813";
+
+fn config_params() -> EthConfigParams {
+    EthConfigParams::from_path("configs/tests/transaction.json")
+}
+
+/// Same as `EthEcdsaCircuit`, `tamper` may change the assigned input before it is verified.
+fn tampered_ecdsa_circuit(
+    mut builder: RlcThreadBuilder<Fr>,
+    config_params: &EthConfigParams,
+    input: EthEcdsaInput,
+    tamper: impl FnOnce(&mut Context<Fr>, &mut EthEcdsaInputAssigned<Fr>),
+) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
+    let range = RangeChip::default(ETH_LOOKUP_BITS);
+    let mut keccak = KeccakChip::default();
+    let fp_chip = FpChip::new(&range, ETH_LIMB_BITS, ETH_NUM_LIMBS);
+    let fq_chip = FqChip::new(&range, ETH_LIMB_BITS, ETH_NUM_LIMBS);
+    let ecdsa_chip = EcdsaChip::new(&fp_chip, &fq_chip);
+
+    let ctx = builder.gate_builder.main(FIRST_PHASE);
+    let mut input = input.assign(ctx, None);
+    tamper(ctx, &mut input);
+    let (verify_result, address) = ecdsa_chip.ecdsa_address_verify(ctx, &mut keccak, input, None);
+    range.gate.assert_is_const(ctx, &verify_result, &Fr::one());
+    let address = bytes_be_to_uint(ctx, range.gate(), &address, 20);
+
+    let circuit = EthCircuitBuilder::new(
+        vec![address],
+        builder,
+        RefCell::new(keccak),
+        range,
+        None,
+        |_: &mut RlcThreadBuilder<Fr>, _: RlpChip<Fr>, _: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {},
+    );
+    circuit.config(config_params.degree as usize, Some(config_params.unusable_rows));
+    circuit
+}
+
+#[test]
+fn test_ecdsa_address_circuit() {
+    let params = config_params();
+    let k = params.degree;
+    let transaction_rlp = Vec::from_hex(TRANSACTION_HEX).unwrap();
+
+    let input = EthEcdsaCircuit::new(transaction_rlp);
+    let instance = input.instance();
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    assert_eq!(circuit.instance(), instance);
+    MockProver::run(k, &circuit, vec![instance]).unwrap().assert_satisfied();
+}
+
+#[test]
+fn test_ecdsa_wrong_address() {
+    let params = config_params();
+    let k = params.degree;
+    let transaction_rlp = Vec::from_hex(TRANSACTION_HEX).unwrap();
+
+    let circuit = EthEcdsaCircuit::new(transaction_rlp).create_circuit(
+        RlcThreadBuilder::mock(),
+        params,
+        None,
+    );
+    let _scope = circuit.config_scope();
+    let instance = vec![encode_addr_to_field(&Address::repeat_byte(0x11))];
+    assert!(MockProver::run(k, &circuit, vec![instance]).unwrap().verify().is_err());
+}
+
+#[test]
+fn test_ecdsa_off_curve_public_key() {
+    let params = config_params();
+    let k = params.degree;
+    let transaction_rlp = Vec::from_hex(TRANSACTION_HEX).unwrap();
+    let input = EthEcdsaCircuit::new(transaction_rlp).inputs;
+
+    // the encoding is consistent with the point, only the curve equation fails
+    let circuit = tampered_ecdsa_circuit(RlcThreadBuilder::mock(), &params, input, |ctx, input| {
+        input.public_key.y += Fp::from(1);
+        input.public_key_bytes = load_bytes(ctx, &encode_public_key(&input.public_key));
+    });
+    let instance = circuit.instance();
+    let _scope = circuit.config_scope();
+    assert!(MockProver::run(k, &circuit, vec![instance]).unwrap().verify().is_err());
+}
+
+#[test]
+fn test_ecdsa_mismatched_public_key() {
+    let params = config_params();
+    let k = params.degree;
+    let transaction_rlp = Vec::from_hex(TRANSACTION_HEX).unwrap();
+    let input = EthEcdsaCircuit::new(transaction_rlp).inputs;
+    let other_public_key = Secp256k1Affine::generator();
+
+    // a valid key which did not sign the message
+    let circuit =
+        tampered_ecdsa_circuit(RlcThreadBuilder::mock(), &params, input.clone(), |ctx, input| {
+            input.public_key = other_public_key;
+            input.public_key_bytes = load_bytes(ctx, &encode_public_key(&other_public_key));
+        });
+    let instance = circuit.instance();
+    let _scope = circuit.config_scope();
+    assert!(MockProver::run(k, &circuit, vec![instance]).unwrap().verify().is_err());
+
+    // the signer key is verified, but the address is derived from another key
+    let circuit = tampered_ecdsa_circuit(RlcThreadBuilder::mock(), &params, input, |ctx, input| {
+        input.public_key_bytes = load_bytes(ctx, &encode_public_key(&other_public_key));
+    });
+    let instance = circuit.instance();
+    let _scope = circuit.config_scope();
+    assert!(MockProver::run(k, &circuit, vec![instance]).unwrap().verify().is_err());
+}
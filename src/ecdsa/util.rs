@@ -15,6 +15,7 @@ use crate::halo2_proofs::{
     },
     transcript::{TranscriptReadBuffer, TranscriptWriterBuffer},
 };
+use crate::transaction::EIP_4844_TX_TYPE_FIELDS_NUM;
 use ark_std::{end_timer, start_timer};
 use ethers_core::k256::elliptic_curve::consts::U32;
 use ethers_core::k256::elliptic_curve::generic_array::GenericArray;
@@ -25,8 +26,8 @@ use ethers_core::k256::{
     EncodedPoint, PublicKey, Secp256k1,
 };
 use ethers_core::types::transaction::eip2718::TypedTransaction;
-use ethers_core::types::{Bytes, RecoveryMessage, Signature, Transaction, H256};
-use ethers_core::utils::hash_message;
+use ethers_core::types::{Bytes, RecoveryMessage, Signature, Transaction, H256, U256};
+use ethers_core::utils::{hash_message, keccak256};
 use ff::Field;
 use halo2_base::gates::builder::{
     CircuitBuilderStage, GateThreadBuilder, MultiPhaseThreadBreakPoints, RangeCircuitBuilder,
@@ -41,7 +42,7 @@ use halo2_base::utils::{biguint_to_fe, fe_to_biguint, modulus};
 use halo2_base::Context;
 use hex::{FromHex, ToHex};
 use rand_core::OsRng;
-use rlp::{Decodable, Rlp};
+use rlp::{Decodable, Rlp, RlpStream};
 use serde::{Deserialize, Serialize};
 use snark_verifier::loader::halo2::halo2_ecc::ecc::ecdsa::ecdsa_verify_no_pubkey_check;
 use snark_verifier::loader::halo2::halo2_ecc::ecc::EccChip;
@@ -57,6 +58,27 @@ pub fn recover_tx_info(t: &Transaction) -> (Signature, Bytes, H256, PublicKey) {
     (signature, typed_tx.rlp(), msg_hash, public_key)
 }
 
+/// `TypedTransaction` has no EIP-4844 envelope, so the signing payload is rebuilt from the raw transaction:
+/// 0x03 || rlp([chainId, ..., maxFeePerBlobGas, blobVersionedHashes]).
+pub fn recover_blob_tx_info(transaction_rlp: &[u8]) -> (Signature, Bytes, H256, PublicKey) {
+    let rlp = Rlp::new(&transaction_rlp[1..]);
+    let unsigned_fields_num = EIP_4844_TX_TYPE_FIELDS_NUM - 3;
+    let mut stream = RlpStream::new_list(unsigned_fields_num);
+    for i in 0..unsigned_fields_num {
+        stream.append_raw(rlp.at(i).unwrap().as_raw(), 1);
+    }
+    let mut message = vec![transaction_rlp[0]];
+    message.extend_from_slice(&stream.out());
+    let msg_hash = H256(keccak256(&message));
+    let signature = Signature {
+        r: U256::from_big_endian(rlp.at(unsigned_fields_num + 1).unwrap().data().unwrap()),
+        s: U256::from_big_endian(rlp.at(unsigned_fields_num + 2).unwrap().data().unwrap()),
+        v: rlp.val_at(unsigned_fields_num).unwrap(),
+    };
+    let public_key = recover_public_key(&signature, msg_hash);
+    (signature, message.into(), msg_hash, public_key)
+}
+
 fn recover_public_key<M>(s: &Signature, message: M) -> PublicKey
 where
     M: Into<RecoveryMessage>,
@@ -7,34 +7,44 @@ use itertools::Itertools;
 use regex_simple::Regex;
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, BufWriter, Read, Write};
-use std::path::PathBuf;
+use std::path::Path;
 use std::rc::Rc;
 
+#[cfg(feature = "evm")]
+pub mod solidity;
+#[cfg(all(test, feature = "evm"))]
+mod tests;
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct DeployParamsJson {
     max_transcript_addr: u32,
     num_func_contracts: usize,
 }
+pub const YUL_PATH_ENV: &str = "YUL_PATH";
+pub const SOL_VERIFIERS_DIR_ENV: &str = "SOL_VERIFIERS_DIR";
+
+/// Cmdline: YUL_PATH=./data/arbitration/ethereum_source_final_1.yul SOL_VERIFIERS_DIR=./data/arbitration/sol_verifiers cargo test test_sol -- --ignored
 #[test]
+#[ignore = "splits the yul verifier at YUL_PATH into SOL_VERIFIERS_DIR, which is cleared first"]
 fn test_sol() {
-    let yul: String = fs::read_to_string("./data/arbitration/ethereum_source_final_1.yul")
-        .unwrap()
-        .parse()
-        .unwrap();
-    gen_sol_verifiers(yul);
+    let yul_path = std::env::var(YUL_PATH_ENV)
+        .unwrap_or_else(|_| "./data/arbitration/ethereum_source_final_1.yul".to_string());
+    let sols_dir = std::env::var(SOL_VERIFIERS_DIR_ENV)
+        .unwrap_or_else(|_| "./data/arbitration/sol_verifiers".to_string());
+    gen_sol_verifiers(fs::read_to_string(yul_path).unwrap(), sols_dir);
 }
 
-pub fn gen_sol_verifiers(yul: String) {
-    let sols_dir = "../axiom-v1-contracts/contracts/Verify";
-    if PathBuf::new().join(sols_dir).exists() {
+/// Splits the Yul verifier into Solidity contracts, written to `sols_dir` (which is cleared first).
+pub fn gen_sol_verifiers(yul: String, sols_dir: impl AsRef<Path>) {
+    let sols_dir = sols_dir.as_ref();
+    if sols_dir.exists() {
         fs::remove_dir_all(sols_dir).unwrap();
     }
-    let sols_dir = &PathBuf::new().join(sols_dir);
     let max_line_size_per_file = 100 * 1000;
     let (sols, max_transcript_addr) =
         gen_evm_verifier_sols_from_yul(&yul, max_line_size_per_file).unwrap();
     {
-        fs::create_dir_all(&sols_dir).unwrap();
+        fs::create_dir_all(sols_dir).unwrap();
         for (idx, sol) in sols.iter().enumerate() {
             let mut file =
                 File::create(sols_dir.join(format!("VerifierLogicPart{}.sol", idx))).unwrap();
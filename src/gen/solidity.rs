@@ -0,0 +1,168 @@
+//! Standalone Solidity verifiers generated by halo2_solidity_verifier.
+//!
+//! Unlike the Yul verifier of `gen_evm_verifier_shplonk`, the verifying key is rendered as its own
+//! contract, whose address is the first argument of the calldata.
+//!
+//! The proofs for these verifiers use the keccak transcript of halo2_solidity_verifier, the calldata of
+//! [`crate::util::scheduler::Scheduler::get_calldata`] can't be verified by them.
+
+use ethers_core::utils::hex::encode;
+use halo2_base::halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{create_proof, ProvingKey, VerifyingKey},
+    poly::kzg::{
+        commitment::{KZGCommitmentScheme, ParamsKZG},
+        multiopen::ProverSHPLONK,
+    },
+    transcript::TranscriptWriterBuffer,
+};
+use halo2_solidity_verifier::{
+    compile_solidity, encode_calldata, AccumulatorEncoding, BatchOpenScheme::Bdfg21,
+    Keccak256Transcript, SolidityGenerator,
+};
+use itertools::Itertools;
+use rand_core::OsRng;
+use revm::primitives::{Bytes, CreateScheme, ExecutionResult, Output, TransactTo, TxEnv, B160};
+use revm::{InMemoryDB, EVM};
+use snark_verifier_sdk::{CircuitExt, BITS, LIMBS};
+use std::fs;
+use std::path::Path;
+
+pub const VERIFIER_SOL: &str = "Halo2Verifier.sol";
+pub const VERIFYING_KEY_SOL: &str = "Halo2VerifyingKey.sol";
+
+#[derive(Clone, Debug)]
+pub struct SolidityVerifier {
+    pub verifier: String,
+    pub verifying_key: String,
+}
+
+impl SolidityVerifier {
+    /// The verifier of a circuit with a single instance column of `num_instance` instances. If `accumulator`
+    /// is true, the circuit is a snark-verifier aggregation circuit whose first `4 * LIMBS` instances are
+    /// the accumulator, which the verifier checks too.
+    pub fn new(
+        params: &ParamsKZG<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+        num_instance: usize,
+        accumulator: bool,
+    ) -> Self {
+        let accumulator = accumulator.then(|| AccumulatorEncoding::new(0, LIMBS, BITS));
+        let generator =
+            SolidityGenerator::new(params, vk, Bdfg21, num_instance).set_acc_encoding(accumulator);
+        let (verifier, verifying_key) =
+            generator.render_separately().expect("render solidity verifier should not fail");
+        Self { verifier, verifying_key }
+    }
+
+    /// The verifier of `circuit`, which must have a single instance column. The accumulator of an
+    /// aggregation circuit is checked by the verifier too.
+    pub fn from_circuit<C: CircuitExt<Fr>>(
+        params: &ParamsKZG<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+        circuit: &C,
+    ) -> Self {
+        let num_instance = circuit.num_instance();
+        assert_eq!(num_instance.len(), 1, "solidity verifier supports one instance column");
+        Self::new(params, vk, num_instance[0], C::accumulator_indices().is_some())
+    }
+
+    /// Writes `Halo2Verifier.sol` and `Halo2VerifyingKey.sol` to `dir`.
+    pub fn write(&self, dir: impl AsRef<Path>) {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).expect("create solidity verifier directory should not fail");
+        fs::write(dir.join(VERIFIER_SOL), &self.verifier).expect("write verifier should not fail");
+        fs::write(dir.join(VERIFYING_KEY_SOL), &self.verifying_key)
+            .expect("write verifying key should not fail");
+    }
+
+    /// The creation code of the verifier and of the verifying key contracts, compiled by `solc`.
+    pub fn compile(&self) -> (Vec<u8>, Vec<u8>) {
+        (compile_solidity(&self.verifier), compile_solidity(&self.verifying_key))
+    }
+}
+
+/// A SHPLONK proof with the keccak transcript of the solidity verifiers.
+pub fn gen_solidity_proof<C: CircuitExt<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    instances: &[Vec<Fr>],
+) -> Vec<u8> {
+    let instances = instances.iter().map(Vec::as_slice).collect_vec();
+    let mut transcript = Keccak256Transcript::new(Vec::new());
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&instances],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("proof generation should not fail");
+    transcript.finalize()
+}
+
+/// The calldata of `verifyProof(vk, proof, instances)`, `vk_address` is the address of the verifying key contract.
+pub fn encode_solidity_calldata(vk_address: [u8; 20], proof: &[u8], instances: &[Fr]) -> Vec<u8> {
+    encode_calldata(Some(vk_address), proof, instances)
+}
+
+/// Proves `circuit` and writes the calldata for its solidity verifier to `path`, as a hex string.
+pub fn write_solidity_calldata<C: CircuitExt<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    vk_address: [u8; 20],
+    path: impl AsRef<Path>,
+) -> String {
+    let instances = circuit.instances();
+    let proof = gen_solidity_proof(params, pk, circuit, &instances);
+    let calldata = encode(encode_solidity_calldata(vk_address, &proof, &instances[0]));
+    fs::write(path, &calldata).expect("write calldata should not fail");
+    calldata
+}
+
+/// Deploys `creation_code` in `evm` and returns the address of the contract.
+pub fn deploy_contract(evm: &mut EVM<InMemoryDB>, creation_code: Vec<u8>) -> B160 {
+    evm.env.tx = TxEnv {
+        gas_limit: u64::MAX,
+        transact_to: TransactTo::Create(CreateScheme::Create),
+        data: Bytes::from(creation_code),
+        ..Default::default()
+    };
+    match evm.transact_commit().expect("deploy contract should not fail") {
+        ExecutionResult::Success { output: Output::Create(_, Some(address)), .. } => address,
+        result => panic!("deploy contract failed: {result:?}"),
+    }
+}
+
+/// Calls the contract at `address` with `calldata`, the state changes are committed.
+pub fn call_contract(
+    evm: &mut EVM<InMemoryDB>,
+    address: B160,
+    calldata: Vec<u8>,
+) -> ExecutionResult {
+    evm.env.tx = TxEnv {
+        gas_limit: u64::MAX,
+        transact_to: TransactTo::Call(address),
+        data: Bytes::from(calldata),
+        ..Default::default()
+    };
+    evm.transact_commit().expect("call contract should not fail")
+}
+
+/// Calls the verifier at `verifier` with `calldata`, true if the proof is valid.
+pub fn verify_solidity_calldata(
+    evm: &mut EVM<InMemoryDB>,
+    verifier: B160,
+    calldata: Vec<u8>,
+) -> bool {
+    match call_contract(evm, verifier, calldata) {
+        // the verifier returns `true` and reverts on an invalid proof
+        ExecutionResult::Success { output: Output::Call(output), .. } => {
+            output.to_vec() == [vec![0; 31], vec![1]].concat()
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,104 @@
+use crate::gen::solidity::{
+    deploy_contract, encode_solidity_calldata, gen_solidity_proof, verify_solidity_calldata,
+    SolidityVerifier, VERIFIER_SOL, VERIFYING_KEY_SOL,
+};
+use crate::util::circuit_config::{isolate_env, CircuitConfig, CircuitConfigScope};
+use crate::util::verify::tests::{mul_circuit, K};
+use halo2_base::gates::builder::CircuitBuilderStage;
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::halo2_proofs::poly::commitment::Params;
+use halo2_base::utils::fs::gen_srs;
+use revm::primitives::B160;
+use revm::{InMemoryDB, EVM};
+use snark_verifier_sdk::halo2::{aggregation::AggregationCircuit, gen_snark_shplonk};
+use snark_verifier_sdk::{gen_pk, CircuitExt, LIMBS, SHPLONK};
+use std::env::temp_dir;
+use std::fs;
+use std::process;
+
+const AGGREGATION_K: u32 = 20;
+
+/// Deploys the verifier and verifying key contracts, and returns their addresses.
+fn deploy_verifier(verifier: &SolidityVerifier) -> (EVM<InMemoryDB>, B160, B160) {
+    let mut evm = EVM::new();
+    evm.database(InMemoryDB::default());
+    let (verifier_code, vk_code) = verifier.compile();
+    let verifier_address = deploy_contract(&mut evm, verifier_code);
+    let vk_address = deploy_contract(&mut evm, vk_code);
+    (evm, verifier_address, vk_address)
+}
+
+#[test]
+fn test_solidity_verifier() {
+    let params = gen_srs(K);
+    let (circuit, config) = mul_circuit(CircuitBuilderStage::Keygen, None);
+    let _scope = CircuitConfigScope::enter(config);
+    let pk = gen_pk(&params, &circuit, None);
+    let verifier = SolidityVerifier::from_circuit(&params, pk.get_vk(), &circuit);
+
+    let dir = temp_dir().join(format!("zkspv_solidity_verifier_{}", process::id()));
+    verifier.write(&dir);
+    assert!(dir.join(VERIFIER_SOL).exists() && dir.join(VERIFYING_KEY_SOL).exists());
+    fs::remove_dir_all(&dir).unwrap();
+
+    let (mut evm, verifier_address, vk_address) = deploy_verifier(&verifier);
+    let (circuit, _) = mul_circuit(CircuitBuilderStage::Prover, Some(circuit.break_points()));
+    let instances = circuit.instances();
+    assert_eq!(instances, [vec![Fr::from(3), Fr::from(12)]]);
+    let proof = gen_solidity_proof(&params, &pk, circuit, &instances);
+
+    let calldata = encode_solidity_calldata(vk_address.0, &proof, &instances[0]);
+    assert!(verify_solidity_calldata(&mut evm, verifier_address, calldata));
+    // the proof doesn't hold for other instances
+    let calldata = encode_solidity_calldata(vk_address.0, &proof, &[Fr::from(3), Fr::from(13)]);
+    assert!(!verify_solidity_calldata(&mut evm, verifier_address, calldata));
+}
+
+#[test]
+#[ignore = "keygens and proves an aggregation circuit of degree 20"]
+fn test_solidity_verifier_with_accumulator() {
+    let agg_params = gen_srs(AGGREGATION_K);
+    let mut params = agg_params.clone();
+    params.downsize(K);
+    let snark = {
+        let (circuit, config) = mul_circuit(CircuitBuilderStage::Keygen, None);
+        let _scope = CircuitConfigScope::enter(config);
+        let pk = gen_pk(&params, &circuit, None);
+        let (circuit, _) = mul_circuit(CircuitBuilderStage::Prover, Some(circuit.break_points()));
+        gen_snark_shplonk(&params, &pk, circuit, None::<&str>)
+    };
+
+    let lookup_bits = AGGREGATION_K as usize - 1;
+    let circuit = AggregationCircuit::new::<SHPLONK>(
+        CircuitBuilderStage::Keygen,
+        None,
+        lookup_bits,
+        &agg_params,
+        vec![snark.clone()],
+    );
+    let gate_params = isolate_env(|| circuit.config(AGGREGATION_K, Some(20)));
+    let _scope =
+        CircuitConfigScope::enter(CircuitConfig::Gate { params: gate_params, lookup_bits });
+    let pk = gen_pk(&agg_params, &circuit, None);
+    // the verifier checks the accumulator of the aggregated snark
+    let verifier = SolidityVerifier::from_circuit(&agg_params, pk.get_vk(), &circuit);
+    let (mut evm, verifier_address, vk_address) = deploy_verifier(&verifier);
+
+    let circuit = AggregationCircuit::new::<SHPLONK>(
+        CircuitBuilderStage::Prover,
+        Some(circuit.break_points()),
+        lookup_bits,
+        &agg_params,
+        vec![snark],
+    );
+    let instances = circuit.instances();
+    assert_eq!(instances[0].len(), 4 * LIMBS);
+    let proof = gen_solidity_proof(&agg_params, &pk, circuit, &instances);
+
+    let calldata = encode_solidity_calldata(vk_address.0, &proof, &instances[0]);
+    assert!(verify_solidity_calldata(&mut evm, verifier_address, calldata));
+    let mut accumulator = instances[0].clone();
+    accumulator[0] += Fr::from(1);
+    let calldata = encode_solidity_calldata(vk_address.0, &proof, &accumulator);
+    assert!(!verify_solidity_calldata(&mut evm, verifier_address, calldata));
+}
@@ -1,5 +1,5 @@
 use crate::arbitration::router::SchedulerRouter;
-use crate::db::ChallengesStorage;
+use crate::db::{ChallengesStorage, ProofJobQueue};
 use crate::server::OriginalProof;
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
@@ -8,6 +8,7 @@ use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 pub struct Integration {
     pub scheduler: Arc<Mutex<SchedulerRouter>>,
     pub storage: Arc<Mutex<ChallengesStorage>>,
+    pub jobs: Arc<Mutex<ProofJobQueue>>,
     pub mpsc: (UnboundedSender<OriginalProof>, UnboundedReceiver<OriginalProof>),
 }
 
@@ -15,7 +16,8 @@ impl Integration {
     pub fn new() -> Self {
         let scheduler = Arc::new(Mutex::new(SchedulerRouter::default()));
         let storage = Arc::new(Mutex::new(ChallengesStorage::new()));
+        let jobs = Arc::new(Mutex::new(ProofJobQueue::new()));
         let mpsc = mpsc::unbounded_channel::<OriginalProof>();
-        Self { scheduler, storage, mpsc }
+        Self { scheduler, storage, jobs, mpsc }
     }
 }
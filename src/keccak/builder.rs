@@ -4,7 +4,7 @@ use crate::rlp::rlc::FIRST_PHASE;
 
 use super::*;
 
-use crate::util::concur_var::{set_var_thread_safe, var_thread_safe};
+use crate::util::circuit_config::{eth_config_params, CircuitConfig, CircuitConfigScope};
 
 /// We need a more custom synthesize function to work with the outputs of keccak RLCs.
 pub trait FnSynthesize<F> =
@@ -19,6 +19,8 @@ where
     pub synthesize_phase1: RefCell<Option<FnPhase1>>,
     pub keccak: SharedKeccakChip<F>,
     pub range: RangeChip<F>,
+    /// Set by `config` or `set_config_params`, `configure` reads them from the [`CircuitConfigScope`].
+    pub config_params: RefCell<EthConfigParams>,
 }
 
 impl<F: Field, FnPhase1> KeccakCircuitBuilder<F, FnPhase1>
@@ -38,6 +40,7 @@ where
             synthesize_phase1: RefCell::new(Some(synthesize_phase1)),
             keccak,
             range,
+            config_params: RefCell::new(EthConfigParams::default()),
         }
     }
 
@@ -75,19 +78,23 @@ where
         f(&mut builder, rlp_chip, keccak_rlcs);
         let mut params = builder.config(k, minimum_rows);
         params.keccak_rows_per_round = std::cmp::min(optimal_rows_per_round, 50); // empirically more than 50 rows per round makes the rotation offsets too large
-        self.keccak.borrow_mut().num_rows_per_round = params.keccak_rows_per_round;
+        params.lookup_bits = Some(self.range.lookup_bits());
         #[cfg(feature = "display")]
         log::info!("KeccakCircuitBuilder auto-calculated config params: {:#?}", params);
-        // set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
-        // set_var("KECCAK_DEGREE", k.to_string());
-        // set_var("KECCAK_ROWS", params.keccak_rows_per_round.to_string());
-        set_var_thread_safe("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
-        set_var_thread_safe("KECCAK_DEGREE", k.to_string());
-        set_var_thread_safe("KECCAK_ROWS", params.keccak_rows_per_round.to_string());
-
+        self.set_config_params(params.clone());
         params
     }
 
+    pub fn set_config_params(&self, params: EthConfigParams) {
+        self.keccak.borrow_mut().num_rows_per_round = params.keccak_rows_per_round;
+        *self.config_params.borrow_mut() = params;
+    }
+
+    /// Keygen, mock proving and proving of the circuit must happen inside this scope.
+    pub fn config_scope(&self) -> CircuitConfigScope {
+        CircuitConfigScope::enter(CircuitConfig::Eth(self.config_params.borrow().clone()))
+    }
+
     // re-usable function for synthesize
     pub fn two_phase_synthesize(
         &self,
@@ -238,11 +245,7 @@ impl<F: Field, FnPhase1: FnSynthesize<F>> Circuit<F> for KeccakCircuitBuilder<F,
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> MPTConfig<F> {
-        // let params: EthConfigParams =
-        //     serde_json::from_str(&std::env::var("ETH_CONFIG_PARAMS").unwrap()).unwrap();
-        let params: EthConfigParams =
-            serde_json::from_str(&var_thread_safe("ETH_CONFIG_PARAMS").unwrap()).unwrap();
-        MPTConfig::configure(meta, params)
+        MPTConfig::configure(meta, eth_config_params())
     }
 
     fn synthesize(
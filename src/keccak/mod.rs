@@ -57,11 +57,9 @@ type KeccakAssignedValue<'v, F> = AssignedCell<&'v Assigned<F>, F>;
 type KeccakAssignedValue<'v, F> = AssignedCell<F, F>;
 
 mod builder;
-// #[cfg(test)]
+#[cfg(test)]
 pub(crate) mod tests;
 
-pub use tests::get_block_data_hashes_from_json;
-
 pub use builder::*;
 pub(crate) type FixedLenRLCs<F> = Vec<(RlcFixedTrace<F>, RlcFixedTrace<F>)>;
 pub(crate) type VarLenRLCs<F> = Vec<(RlcTrace<F>, RlcFixedTrace<F>)>;
@@ -20,9 +20,10 @@ use crate::{
     providers::get_batch_block_merkle_root,
     rlp::rlc::RlcConfig,
     util::{
+        circuit_config::{read_env_var, with_env_var},
         encode_h256_to_bytes_field, encode_merkle_path_to_field,
         h256_non_standard_tree_root_and_proof, h256_tree_verify,
-        helpers::{get_block_batch_hashes, get_provider},
+        helpers::{get_block_batch_hashes, get_block_data_hashes_from_json, get_provider},
     },
     EthereumNetwork, Network,
 };
@@ -46,10 +47,14 @@ use std::{
     fs::{self, File},
     io::{BufRead, BufReader, Write},
     str::FromStr,
+    sync::{Arc, Barrier},
+    thread,
 };
 use tokio::runtime::Runtime;
 use zkevm_keccak::keccak_packed_multi::get_keccak_capacity;
 
+const UNUSABLE_ROWS: usize = 109;
+
 #[test]
 pub fn test_non_standard_merkle_inclusion_verify_from_json() {
     let batch_data = get_block_data_hashes_from_json();
@@ -114,19 +119,11 @@ fn test_keccak_non_standard_merkle_verify_circuit<F: Field>(
         |_: &mut RlcThreadBuilder<F>, _: RlpChip<F>, _: (FixedLenRLCs<F>, VarLenRLCs<F>)| {},
     );
     if !prover {
-        let unusable_rows =
-            var("UNUSABLE_ROWS").unwrap_or_else(|_| "109".to_string()).parse().unwrap();
-        circuit.config(k as usize, Some(unusable_rows));
+        circuit.config(k as usize, Some(UNUSABLE_ROWS));
     }
     circuit
 }
 
-pub fn get_block_data_hashes_from_json() -> BatchBlocksInput {
-    let data = fs::read_to_string("test_data/block_batch_data.json").unwrap();
-    let batch_data: BatchBlocksInput = serde_json::from_str(&data).unwrap();
-    return batch_data;
-}
-
 fn test_keccak_circuit<F: Field>(
     k: u32,
     mut builder: RlcThreadBuilder<F>,
@@ -164,9 +161,7 @@ fn test_keccak_circuit<F: Field>(
         |_: &mut RlcThreadBuilder<F>, _: RlpChip<F>, _: (FixedLenRLCs<F>, VarLenRLCs<F>)| {},
     );
     if !prover {
-        let unusable_rows =
-            var("UNUSABLE_ROWS").unwrap_or_else(|_| "109".to_string()).parse().unwrap();
-        circuit.config(k as usize, Some(unusable_rows));
+        circuit.config(k as usize, Some(UNUSABLE_ROWS));
     }
     circuit
 }
@@ -176,7 +171,7 @@ fn test_keccak_circuit<F: Field>(
 pub fn test_keccak() {
     let _ = env_logger::builder().is_test(true).try_init();
 
-    let k: u32 = var("KECCAK_DEGREE").unwrap_or_else(|_| "14".to_string()).parse().unwrap();
+    let k: u32 = read_env_var("KECCAK_DEGREE").unwrap_or_else(|| "14".to_string()).parse().unwrap();
     let inputs = vec![
         vec![],
         (0u8..1).collect::<Vec<_>>(),
@@ -185,17 +180,52 @@ pub fn test_keccak() {
         (0u8..200).collect::<Vec<_>>(),
     ];
     let circuit = test_keccak_circuit(k, RlcThreadBuilder::mock(), inputs.clone(), false);
+    let scope = circuit.config_scope();
     MockProver::<Fr>::run(k, &circuit, vec![]).unwrap().assert_satisfied();
+    drop(scope);
     println!("Fixed len keccak passed");
 
     let circuit = test_keccak_circuit(k, RlcThreadBuilder::mock(), inputs, true);
+    let _scope = circuit.config_scope();
     MockProver::<Fr>::run(k, &circuit, vec![]).unwrap().assert_satisfied();
     println!("Var len keccak passed");
 }
 
+fn keygen_keccak_circuit(k: u32, start: &Barrier) -> (EthConfigParams, Fr) {
+    let inputs = vec![(0u8..200).collect::<Vec<_>>(); 2];
+    let circuit = test_keccak_circuit(k, RlcThreadBuilder::keygen(), inputs, false);
+    let config_params = circuit.config_params.borrow().clone();
+    let params = gen_srs(k);
+    start.wait();
+    let _scope = circuit.config_scope();
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    (config_params, vk.transcript_repr())
+}
+
+#[test]
+pub fn test_keccak_parallel_keygen() {
+    let sequential = [12, 14].map(|k| keygen_keccak_circuit(k, &Barrier::new(1)));
+
+    // both threads enter the scope of their own params at the same time
+    let start = Arc::new(Barrier::new(2));
+    let parallel = [12, 14]
+        .map(|k| {
+            let start = Arc::clone(&start);
+            thread::spawn(move || keygen_keccak_circuit(k, &start))
+        })
+        .map(|handle| handle.join().unwrap());
+    for ((params, vk), (parallel_params, parallel_vk)) in sequential.iter().zip(parallel.iter()) {
+        assert_eq!(
+            serde_json::to_string(params).unwrap(),
+            serde_json::to_string(parallel_params).unwrap()
+        );
+        assert_eq!(vk, parallel_vk);
+    }
+}
+
 #[test]
 pub fn test_keccak_non_standard_merkle_verify() {
-    let k: u32 = var("KECCAK_DEGREE").unwrap_or_else(|_| "14".to_string()).parse().unwrap();
+    let k: u32 = read_env_var("KECCAK_DEGREE").unwrap_or_else(|| "14".to_string()).parse().unwrap();
     let batch = get_block_data_hashes_from_json();
     let (leaves, root) = (
         batch.batch_blocks_merkle[0].block_hash_batch.clone(),
@@ -217,6 +247,8 @@ pub fn test_keccak_non_standard_merkle_verify() {
     );
     // MockProver::<Fr>::run(k, &circuit, vec![]).unwrap().assert_satisfied();
 
+    let config_params = circuit.config_params.borrow().clone();
+    let _scope = circuit.config_scope();
     let params = gen_srs(k);
     let vk = keygen_vk(&params, &circuit).unwrap();
     let pk = keygen_pk(&params, vk, &circuit).unwrap();
@@ -239,6 +271,7 @@ pub fn test_keccak_non_standard_merkle_verify() {
             path,
         );
         *circuit.break_points.borrow_mut() = break_points.clone();
+        circuit.set_config_params(config_params.clone());
         let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
         create_proof::<
             KZGCommitmentScheme<Bn256>,
@@ -301,14 +334,17 @@ fn bench_keccak() {
         );
         let k = bench_params.degree as u32;
         let num_rows = (1 << k) - bench_params.unusable_rows;
-        set_var("KECCAK_ROWS", bench_params.rows_per_round.to_string());
-        let capacity = get_keccak_capacity(num_rows);
+        let capacity = with_env_var("KECCAK_ROWS", bench_params.rows_per_round.to_string(), || {
+            get_keccak_capacity(num_rows)
+        });
         println!("Performing {capacity} keccak_f permutations");
         let inputs = vec![vec![0; 135]; capacity];
         let circuit = test_keccak_circuit(k, RlcThreadBuilder::keygen(), inputs.clone(), var_len);
 
         // MockProver::<Fr>::run(k, &circuit, vec![]).unwrap().assert_satisfied();
 
+        let auto_params = circuit.config_params.borrow().clone();
+        let _scope = circuit.config_scope();
         let params = gen_srs(k);
         let vk = keygen_vk(&params, &circuit).unwrap();
         let pk = keygen_pk(&params, vk, &circuit).unwrap();
@@ -321,6 +357,7 @@ fn bench_keccak() {
         let proof_time = start_timer!(|| "Create proof SHPLONK");
         let circuit = test_keccak_circuit(k, RlcThreadBuilder::prover(), inputs.clone(), var_len);
         *circuit.break_points.borrow_mut() = break_points;
+        circuit.set_config_params(auto_params.clone());
         let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
         create_proof::<
             KZGCommitmentScheme<Bn256>,
@@ -348,8 +385,6 @@ fn bench_keccak() {
         .unwrap();
         end_timer!(verify_time);
 
-        let auto_params: EthConfigParams =
-            serde_json::from_str(var("ETH_CONFIG_PARAMS").unwrap().as_str()).unwrap();
         let keccak_advice = std::env::var("KECCAK_ADVICE_COLUMNS")
             .unwrap_or_else(|_| "0".to_string())
             .parse::<usize>()
@@ -359,7 +394,7 @@ fn bench_keccak() {
             "{},{},{},{},{:.2},{},{:.2}s,{:?}",
             auto_params.degree,
             auto_params.num_range_advice.iter().sum::<usize>() + keccak_advice + 2,
-            var("UNUSABLE_ROWS").unwrap(),
+            auto_params.unusable_rows,
             auto_params.keccak_rows_per_round,
             f64::from(capacity as u32) / proof_time.time.elapsed().as_secs_f64(),
             capacity,
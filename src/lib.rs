@@ -11,8 +11,6 @@ extern crate log4rs;
 extern crate core;
 
 use ark_std::{end_timer, start_timer};
-use std::env::{set_var, var};
-
 use halo2_base::gates::builder::{CircuitBuilderStage, MultiPhaseThreadBreakPoints};
 use halo2_base::{
     gates::{flex_gate::FlexGateConfig, range::RangeConfig, RangeChip},
@@ -30,10 +28,10 @@ use snark_verifier_sdk::halo2::aggregation::AggregationCircuit;
 pub use zkevm_keccak::util::eth_types::Field;
 use zkevm_keccak::KeccakConfig;
 
-use crate::Network::{Arbitrum, Ethereum, Optimism, ZkSync};
+use crate::Network::{Arbitrum, Base, Ethereum, Optimism, ZkSync};
 use keccak::{FnSynthesize, KeccakCircuitBuilder, SharedKeccakChip};
 pub use mpt::EthChip;
-use util::concur_var::{set_var_thread_safe, var_thread_safe};
+use util::circuit_config::{eth_config_params, isolate_env, CircuitConfig, CircuitConfigScope};
 use util::EthConfigParams;
 
 use crate::rlp::{
@@ -57,7 +55,7 @@ pub mod transaction;
 pub mod config;
 pub mod db;
 pub mod ecdsa;
-mod gen;
+pub mod gen;
 
 #[cfg(feature = "providers")]
 pub mod providers;
@@ -71,12 +69,29 @@ pub mod integration;
 pub(crate) const ETH_LOOKUP_BITS: usize = 8; // always want 8 to range check bytes
 pub(crate) const ETH_LIMB_BITS: usize = 88;
 pub(crate) const ETH_NUM_LIMBS: usize = 3;
+// the aggregation circuits have no keccak chip, so few rows are unusable
+pub(crate) const AGGREGATION_MINIMUM_ROWS: usize = 10;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 pub enum EthereumNetwork {
     Mainnet = 1,
+    /// Deprecated, superseded by Sepolia and Holesky.
     Goerli = 5,
+    Sepolia = 11155111,
+    Holesky = 17000,
+}
+
+impl EthereumNetwork {
+    /// The prefix of the file names of the block header circuits, e.g. `configs/headers/sepolia_10_7.json`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EthereumNetwork::Mainnet => "mainnet",
+            EthereumNetwork::Goerli => "goerli",
+            EthereumNetwork::Sepolia => "sepolia",
+            EthereumNetwork::Holesky => "holesky",
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -93,11 +108,20 @@ pub enum OptimismNetwork {
     Goerli = 420,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum BaseNetwork {
+    Mainnet = 8453,
+    Goerli = 84531,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 pub enum ZkSyncEraNetwork {
     Mainnet = 324,
+    /// Deprecated, superseded by Sepolia.
     Goerli = 280,
+    Sepolia = 300,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -106,6 +130,7 @@ pub enum Network {
     Ethereum(EthereumNetwork),
     Arbitrum(ArbitrumNetwork),
     Optimism(OptimismNetwork),
+    Base(BaseNetwork),
     ZkSync(ZkSyncEraNetwork),
 }
 
@@ -113,6 +138,17 @@ impl Network {
     pub fn is_l1(&self) -> bool {
         matches!(self, Network::Ethereum(_))
     }
+
+    /// The inverse of [`get_network_from_chain_id`].
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            Ethereum(network) => *network as u64,
+            Arbitrum(network) => *network as u64,
+            Optimism(network) => *network as u64,
+            Base(network) => *network as u64,
+            ZkSync(network) => *network as u64,
+        }
+    }
 }
 
 impl ToString for Network {
@@ -121,6 +157,7 @@ impl ToString for Network {
             Ethereum(_) => String::from("ethereum"),
             Arbitrum(_) => String::from("arbitrum"),
             Optimism(_) => String::from("optimism"),
+            Base(_) => String::from("base"),
             ZkSync(_) => String::from("zksync_era"),
         }
     }
@@ -130,12 +167,17 @@ pub fn get_network_from_chain_id(chain_id: u64) -> Result<Network, ErrorType> {
     match chain_id {
         1 => Ok(Ethereum(EthereumNetwork::Mainnet)),
         5 => Ok(Ethereum(EthereumNetwork::Goerli)),
+        11155111 => Ok(Ethereum(EthereumNetwork::Sepolia)),
+        17000 => Ok(Ethereum(EthereumNetwork::Holesky)),
         42161 => Ok(Arbitrum(ArbitrumNetwork::Mainnet)),
         421613 => Ok(Arbitrum(ArbitrumNetwork::Goerli)),
         10 => Ok(Optimism(OptimismNetwork::Mainnet)),
         420 => Ok(Optimism(OptimismNetwork::Goerli)),
+        8453 => Ok(Base(BaseNetwork::Mainnet)),
+        84531 => Ok(Base(BaseNetwork::Goerli)),
         324 => Ok(ZkSync(ZkSyncEraNetwork::Mainnet)),
         280 => Ok(ZkSync(ZkSyncEraNetwork::Goerli)),
+        300 => Ok(ZkSync(ZkSyncEraNetwork::Sepolia)),
         _ => Err(ErrorType::NetworkNotSupported),
     }
 }
@@ -145,6 +187,19 @@ fn test_n() {
     println!("N:{:?}", Ethereum(EthereumNetwork::Goerli).to_string());
 }
 
+#[test]
+fn test_testnet_chain_ids() {
+    for network in [
+        Ethereum(EthereumNetwork::Sepolia),
+        Ethereum(EthereumNetwork::Holesky),
+        ZkSync(ZkSyncEraNetwork::Sepolia),
+    ] {
+        assert_eq!(get_network_from_chain_id(network.chain_id()).unwrap(), network);
+    }
+    assert_eq!(Ethereum(EthereumNetwork::Sepolia).chain_id(), 11155111);
+    assert_eq!(ZkSync(ZkSyncEraNetwork::Sepolia).chain_id(), 300);
+}
+
 #[derive(Clone, Debug)]
 pub struct MPTConfig<F: Field> {
     pub rlp: RlpConfig<F>,
@@ -163,18 +218,8 @@ impl<F: Field> MPTConfig<F> {
             params.lookup_bits.unwrap_or(ETH_LOOKUP_BITS),
             degree as usize,
         );
-        // set_var("KECCAK_DEGREE", degree.to_string());
-        // set_var("KECCAK_ROWS", params.keccak_rows_per_round.to_string());
-        // let keccak = KeccakConfig::new(meta, rlp.rlc.gamma);
-        // set_var("UNUSABLE_ROWS", meta.minimum_rows().to_string());
-        // #[cfg(feature = "display")]
-        // println!("Unusable rows: {}", meta.minimum_rows());
-        // rlp.range.gate.max_rows = (1 << degree) - meta.minimum_rows();
-        // Self { rlp, keccak }
-        set_var_thread_safe("KECCAK_DEGREE", degree.to_string());
-        set_var_thread_safe("KECCAK_ROWS", params.keccak_rows_per_round.to_string());
+        // `KECCAK_DEGREE` and `KECCAK_ROWS` are set by the `CircuitConfigScope` of `params`
         let keccak = KeccakConfig::new(meta, rlp.rlc.gamma);
-        set_var_thread_safe("UNUSABLE_ROWS", meta.minimum_rows().to_string());
         #[cfg(feature = "display")]
         println!("Unusable rows: {}", meta.minimum_rows());
         rlp.range.gate.max_rows = (1 << degree) - meta.minimum_rows();
@@ -251,6 +296,20 @@ impl<F: Field, FnPhase1: FnSynthesize<F>> EthCircuitBuilder<F, FnPhase1> {
         self.circuit.config(k, minimum_rows)
     }
 
+    pub fn config_params(&self) -> EthConfigParams {
+        self.circuit.config_params.borrow().clone()
+    }
+
+    /// Uses `params` instead of auto-configuring the circuit, e.g. the params of a pinning.
+    pub fn set_config_params(&self, params: EthConfigParams) {
+        self.circuit.set_config_params(params)
+    }
+
+    /// Keygen, mock proving and proving of the circuit must happen inside this scope.
+    pub fn config_scope(&self) -> CircuitConfigScope {
+        self.circuit.config_scope()
+    }
+
     pub fn break_points(&self) -> RlcThreadBreakPoints {
         self.circuit.break_points.borrow().clone()
     }
@@ -273,12 +332,7 @@ impl<F: Field, FnPhase1: FnSynthesize<F>> Circuit<F> for EthCircuitBuilder<F, Fn
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        // let params: EthConfigParams =
-        //     serde_json::from_str(&var("ETH_CONFIG_PARAMS").unwrap()).unwrap();
-        // EthConfig::configure(meta, params)
-        let params: EthConfigParams =
-            serde_json::from_str(&var_thread_safe("ETH_CONFIG_PARAMS").unwrap()).unwrap();
-        EthConfig::configure(meta, params)
+        EthConfig::configure(meta, eth_config_params())
     }
 
     fn synthesize(
@@ -326,12 +380,15 @@ pub trait EthPreCircuit: Sized {
         break_points: Option<RlcThreadBreakPoints>,
     ) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>>;
 
-    /// If feature 'production' is on, this is the same as `create`. Otherwise, it will read `ETH_CONFIG_PARAMS`
-    /// from the environment to determine the desired circuit degree and number of unusable rows and then auto-configure
-    /// the circuit and set environmental variables.
+    /// If feature 'production' is on or the builder is a prover, the circuit uses `config_params`, which should be
+    /// the params it was keygen'd with. Otherwise, it takes the desired circuit degree and number of unusable rows
+    /// from `config_params` and auto-configures the circuit.
+    ///
+    /// Either way the params are stored in the circuit, see [`EthCircuitBuilder::config_scope`].
     fn create_circuit(
         self,
         builder: RlcThreadBuilder<Fr>,
+        config_params: EthConfigParams,
         break_points: Option<RlcThreadBreakPoints>,
     ) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
         let prover = builder.witness_gen_only();
@@ -342,19 +399,10 @@ pub trait EthPreCircuit: Sized {
         end_timer!(start);
         #[cfg(not(feature = "production"))]
         if !prover {
-            // let config_params: EthConfigParams = serde_json::from_str(
-            //     var("ETH_CONFIG_PARAMS").expect("ETH_CONFIG_PARAMS is not set").as_str(),
-            // )
-            // .unwrap();
-            // circuit.config(config_params.degree as usize, Some(config_params.unusable_rows));
-            let config_params: EthConfigParams = serde_json::from_str(
-                var_thread_safe("ETH_CONFIG_PARAMS")
-                    .expect("ETH_CONFIG_PARAMS is not set")
-                    .as_str(),
-            )
-            .unwrap();
             circuit.config(config_params.degree as usize, Some(config_params.unusable_rows));
+            return circuit;
         }
+        circuit.set_config_params(config_params);
         circuit
     }
 }
@@ -374,15 +422,22 @@ pub trait AggregationPreCircuit: Sized {
         params: &ParamsKZG<Bn256>,
     ) -> AggregationCircuit;
 
-    /// If feature 'production' is on, this is the same as `create`. Otherwise, it will determine the desired
-    /// circuit degree from `params.k()` and auto-configure the circuit and set environmental variables.
+    /// If feature 'production' is on or in the prover stage, the circuit uses `config`, which should be the
+    /// config it was keygen'd with. Otherwise, it takes the lookup bits from `config`, determines the desired
+    /// circuit degree from `params.k()` and auto-configures the circuit.
+    ///
+    /// Returns the circuit with its config, keygen and proving must happen inside its [`CircuitConfigScope`].
     fn create_circuit(
         self,
         stage: CircuitBuilderStage,
+        config: CircuitConfig,
         break_points: Option<MultiPhaseThreadBreakPoints>,
-        lookup_bits: usize,
         params: &ParamsKZG<Bn256>,
-    ) -> AggregationCircuit {
+    ) -> (AggregationCircuit, CircuitConfig) {
+        let lookup_bits = match &config {
+            CircuitConfig::Gate { lookup_bits, .. } => *lookup_bits,
+            config => panic!("expected a gate circuit config, found {config:?}"),
+        };
         #[cfg(feature = "display")]
         let start = start_timer!(|| "AggregationPreCircuit: create_circuit");
         let circuit = self.create(stage, break_points, lookup_bits, params);
@@ -390,12 +445,11 @@ pub trait AggregationPreCircuit: Sized {
         end_timer!(start);
         #[cfg(not(feature = "production"))]
         if stage != CircuitBuilderStage::Prover {
-            // let minimum_rows = var("UNUSABLE_ROWS").map(|s| s.parse().unwrap_or(10)).unwrap_or(10);
-            // circuit.config(params.k(), Some(minimum_rows));
-            let minimum_rows =
-                var_thread_safe("UNUSABLE_ROWS").map(|s| s.parse().unwrap_or(10)).unwrap_or(10);
-            circuit.config(params.k(), Some(minimum_rows));
+            // the auto-configuration writes `FLEX_GATE_CONFIG_PARAMS`, which other threads may be using
+            let gate_params =
+                isolate_env(|| circuit.config(params.k(), Some(AGGREGATION_MINIMUM_ROWS)));
+            return (circuit, CircuitConfig::Gate { params: gate_params, lookup_bits });
         }
-        circuit
+        (circuit, config)
     }
 }
@@ -16,7 +16,7 @@ use ethers_core::types::{
 };
 use ethers_core::utils::hex::FromHex;
 use ethers_core::utils::keccak256;
-use ethers_providers::{Http, Middleware, Provider, ProviderError, RetryClient, StreamExt};
+use ethers_providers::{Http, Middleware, Provider, ProviderError, StreamExt};
 use futures::future::{join, join_all};
 use itertools::Itertools;
 use lazy_static::__Deref;
@@ -24,11 +24,15 @@ use rlp::{decode, decode_list, Decodable, Encodable, Rlp, RlpIterator, RlpStream
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use tokio::runtime::Runtime;
+use zksync_web3_rs::zks_provider::ZKSProvider;
 
+use crate::block_header::zksync_era::l1_batch::{
+    get_stored_batch_hash_slot, ZkSyncEraL1BatchInput, TOTAL_BATCHES_EXECUTED_SLOT,
+};
 use crate::block_header::zksync_era::{
     ZkSyncEraBlockHeaderInput, ZkSyncEraBlockHeadersInput, BLOCK_INCLUDE_TXS_MAX_NUMBER,
 };
-use crate::ecdsa::util::recover_tx_info;
+use crate::ecdsa::util::{recover_blob_tx_info, recover_tx_info};
 use crate::ecdsa::EthEcdsaInput;
 use crate::mpt::MPTInput;
 use crate::receipt::{EthBlockReceiptInput, EthReceiptInput, RECEIPT_MAX_LEN};
@@ -37,16 +41,24 @@ use crate::storage::contract_storage::{
     BlockInput, ObContractsStorageBlockInput, ObContractsStorageInput,
     EBC_RULE_PROOF_VALUE_MAX_BYTE_LEN,
 };
+use crate::storage::util::{ACCOUNT_PF_MAX_DEPTH, STORAGE_PF_MAX_DEPTH};
 use crate::storage::{
     EbcRuleVersion, ACCOUNT_PROOF_VALUE_MAX_BYTE_LEN, STORAGE_PROOF_VALUE_MAX_BYTE_LEN,
 };
 use crate::track_block::util::TrackBlockConstructor;
 use crate::track_block::EthTrackBlockInput;
 use crate::transaction::ethereum::{EthBlockTransactionInput, EthTransactionInput};
+use crate::transaction::optimism::{
+    OptimismBlockDepositTransactionInput, OptimismDepositTransactionInput,
+};
 use crate::transaction::zksync_era::{ZkSyncEraBlockTransactionInput, ZkSyncEraTransactionInput};
-use crate::transaction::{calculate_tx_max_len, TX_MAX_LEN};
+use crate::transaction::{
+    calculate_tx_max_len, calculate_tx_max_len_from_rlp, EIP_4844_TX_TYPE, TX_MAX_LEN,
+};
 use crate::util::contract_abi::erc20::{decode_input, is_erc20_transaction};
+use crate::util::contract_abi::zksync_era::{decode_execute_batches_input, StoredBatchInfo};
 use crate::util::helpers::calculate_storage_mapping_key;
+use crate::util::rpc_client::RpcClient;
 use crate::util::{
     h256_non_standard_tree_root_and_proof, h256_tree_root, h256_tree_root_and_proof,
     h256_tree_verify,
@@ -61,7 +73,7 @@ const TRANSACTION_INDEX_MAX_KEY_BYTES_LEN: usize = 3;
 const K256_MAX_KEY_BYTES_LEN: usize = 32;
 
 pub fn get_batch_block_merkle_root(
-    provider: &Provider<RetryClient<Http>>,
+    provider: &Provider<RpcClient>,
     start_block_num: u32,
     end_block_num: u32,
     block_verify_index: u32,
@@ -88,7 +100,7 @@ fn get_buffer_rlp(value: u32) -> Vec<u8> {
 }
 
 pub fn get_block_track_input(
-    provider: &Provider<RetryClient<Http>>,
+    provider: &Provider<RpcClient>,
     constructor: &TrackBlockConstructor,
 ) -> EthTrackBlockInput {
     let rt = Runtime::new().unwrap();
@@ -112,18 +124,42 @@ pub fn get_block_track_input(
 
 //
 pub fn get_receipt_input(
-    provider: &Provider<RetryClient<Http>>,
+    provider: &Provider<RpcClient>,
     transaction_hash: H256,
     receipt_index_bytes: Option<Vec<u8>>,
     receipt_rlp: Vec<u8>,
     merkle_proof: Vec<Bytes>,
     receipt_pf_max_depth: usize,
+    transfer_log_index: Option<u64>,
 ) -> EthBlockReceiptInput {
     let rt = Runtime::new().unwrap();
     let tx = rt.block_on(provider.get_transaction(transaction_hash)).unwrap().unwrap();
     let receipt_index = tx.transaction_index.unwrap().as_u64();
     let block_number = tx.block_number.unwrap().as_u64();
     let block = rt.block_on(provider.get_block(block_number)).unwrap().unwrap();
+    get_receipt_proof_input(
+        block,
+        receipt_index,
+        receipt_index_bytes,
+        receipt_rlp,
+        merkle_proof,
+        receipt_pf_max_depth,
+        transfer_log_index,
+    )
+}
+
+/// The input of the receipt at `receipt_index` of `block`, `merkle_proof` being its proof in the
+/// receipts trie.
+pub fn get_receipt_proof_input(
+    block: Block<H256>,
+    receipt_index: u64,
+    receipt_index_bytes: Option<Vec<u8>>,
+    receipt_rlp: Vec<u8>,
+    merkle_proof: Vec<Bytes>,
+    receipt_pf_max_depth: usize,
+    transfer_log_index: Option<u64>,
+) -> EthBlockReceiptInput {
+    let block_number = block.number.unwrap().as_u64();
     let block_hash = block.hash.unwrap();
     let block_header = get_block_rlp(&block);
     let receipt_key =
@@ -147,12 +183,12 @@ pub fn get_receipt_input(
         block_number,
         block_hash,
         block_header,
-        receipt: EthReceiptInput { receipt_index, receipt_proofs },
+        receipt: EthReceiptInput { receipt_index, receipt_proofs, transfer_log_index },
     }
 }
 
 pub fn get_transaction_input(
-    provider: &Provider<RetryClient<Http>>,
+    provider: &Provider<RpcClient>,
     transaction_hash: H256,
     transaction_index_bytes: Option<Vec<u8>>,
     transaction_rlp: Vec<u8>,
@@ -164,6 +200,27 @@ pub fn get_transaction_input(
     let transaction_index = tx.transaction_index.unwrap().as_u64();
     let block_number = tx.block_number.unwrap().as_u64();
     let block = rt.block_on(provider.get_block(block_number)).unwrap().unwrap();
+    get_transaction_proof_input(
+        block,
+        transaction_index,
+        transaction_index_bytes,
+        transaction_rlp,
+        merkle_proof,
+        transaction_pf_max_depth,
+    )
+}
+
+/// The input of the transaction at `transaction_index` of `block`, `merkle_proof` being its proof in the
+/// transactions trie.
+pub fn get_transaction_proof_input(
+    block: Block<H256>,
+    transaction_index: u64,
+    transaction_index_bytes: Option<Vec<u8>>,
+    transaction_rlp: Vec<u8>,
+    merkle_proof: Vec<Bytes>,
+    transaction_pf_max_depth: usize,
+) -> EthBlockTransactionInput {
+    let block_number = block.number.unwrap().as_u64();
     let block_hash = block.hash.unwrap();
     let block_header = get_block_rlp(&block);
     let transaction_key =
@@ -175,14 +232,19 @@ pub fn get_transaction_input(
         root_hash: block.transactions_root,
         proof: merkle_proof.into_iter().map(|x| x.to_vec()).collect(),
         slot_is_empty,
-        value_max_byte_len: calculate_tx_max_len(transaction_rlp.to_vec().len()),
+        value_max_byte_len: calculate_tx_max_len_from_rlp(&transaction_rlp),
         max_depth: transaction_pf_max_depth,
         max_key_byte_len: TRANSACTION_INDEX_MAX_KEY_BYTES_LEN,
         key_byte_len: Some(transaction_key.len()),
     };
 
-    let transaction = Transaction::decode(&Rlp::new(&transaction_rlp)).unwrap();
-    let (signature, message, message_hash, public_key) = recover_tx_info(&transaction);
+    let (signature, message, message_hash, public_key) =
+        if transaction_rlp.first() == Some(&EIP_4844_TX_TYPE) {
+            recover_blob_tx_info(&transaction_rlp)
+        } else {
+            let transaction = Transaction::decode(&Rlp::new(&transaction_rlp)).unwrap();
+            recover_tx_info(&transaction)
+        };
     EthBlockTransactionInput {
         block,
         block_number,
@@ -201,8 +263,74 @@ pub fn get_transaction_input(
     }
 }
 
+/// Deposit transactions are not signed, so there is no ecdsa input to recover.
+pub fn get_optimism_deposit_transaction_input(
+    provider: &Provider<RpcClient>,
+    transaction_hash: H256,
+    transaction_index_bytes: Option<Vec<u8>>,
+    transaction_rlp: Vec<u8>,
+    merkle_proof: Vec<Bytes>,
+    transaction_pf_max_depth: usize,
+) -> OptimismBlockDepositTransactionInput {
+    let rt = Runtime::new().unwrap();
+    // The receipt is used instead of the transaction, the deposit envelope has no signature fields.
+    let receipt = rt.block_on(provider.get_transaction_receipt(transaction_hash)).unwrap().unwrap();
+    let transaction_index = receipt.transaction_index.as_u64();
+    let block_number = receipt.block_number.unwrap().as_u64();
+    let block = rt.block_on(provider.get_block(block_number)).unwrap().unwrap();
+    let chain_id = rt.block_on(provider.get_chainid()).unwrap().as_u64();
+    get_optimism_deposit_transaction_proof_input(
+        block,
+        transaction_index,
+        transaction_index_bytes,
+        transaction_rlp,
+        merkle_proof,
+        transaction_pf_max_depth,
+        chain_id,
+    )
+}
+
+/// The input of the deposit transaction at `transaction_index` of `block`, an OP-Stack block of chain `chain_id`.
+pub fn get_optimism_deposit_transaction_proof_input(
+    block: Block<H256>,
+    transaction_index: u64,
+    transaction_index_bytes: Option<Vec<u8>>,
+    transaction_rlp: Vec<u8>,
+    merkle_proof: Vec<Bytes>,
+    transaction_pf_max_depth: usize,
+    chain_id: u64,
+) -> OptimismBlockDepositTransactionInput {
+    let block_number = block.number.unwrap().as_u64();
+    let block_hash = block.hash.unwrap();
+    let block_header = get_block_rlp(&block);
+    let transaction_key =
+        transaction_index_bytes.unwrap_or(get_buffer_rlp(U256::from(transaction_index).as_u32()));
+    let transaction_proofs = MPTInput {
+        path: (&transaction_key).into(),
+        value: transaction_rlp.to_vec(),
+        root_hash: block.transactions_root,
+        proof: merkle_proof.into_iter().map(|x| x.to_vec()).collect(),
+        slot_is_empty: false,
+        value_max_byte_len: calculate_tx_max_len(transaction_rlp.len()),
+        max_depth: transaction_pf_max_depth,
+        max_key_byte_len: TRANSACTION_INDEX_MAX_KEY_BYTES_LEN,
+        key_byte_len: Some(transaction_key.len()),
+    };
+    OptimismBlockDepositTransactionInput {
+        block,
+        block_number,
+        block_hash,
+        block_header,
+        transaction: OptimismDepositTransactionInput {
+            transaction_index,
+            transaction_proofs,
+            chain_id,
+        },
+    }
+}
+
 pub fn get_storage_input(
-    provider: &Provider<RetryClient<Http>>,
+    provider: &Provider<RpcClient>,
     block_number: u32,
     addr: Address,
     slots: Vec<H256>,
@@ -218,12 +346,27 @@ pub fn get_storage_input(
         .block_on(provider.get_proof(addr, slots, Some(Number(BlockNumber::from(block_number)))))
         .unwrap();
 
+    let storage =
+        get_storage_proof_input(pf, block.state_root, acct_pf_max_depth, storage_pf_max_depth);
+
+    EthBlockStorageInput { block, block_number, block_hash, block_header, storage }
+}
+
+/// The account and storage proofs of `eth_getProof` against `state_root`. An account that doesn't exist or a slot that
+/// is unset has a non-inclusion proof, with `slot_is_empty` set.
+pub fn get_storage_proof_input(
+    pf: EIP1186ProofResponse,
+    state_root: H256,
+    acct_pf_max_depth: usize,
+    storage_pf_max_depth: usize,
+) -> EthStorageInput {
+    let addr = pf.address;
     let acct_key = H256(keccak256(addr));
     let slot_is_empty = !is_assigned_slot(&acct_key, &pf.account_proof);
     let acct_pf = MPTInput {
         path: acct_key.into(),
         value: get_acct_rlp(&pf),
-        root_hash: block.state_root,
+        root_hash: state_root,
         proof: pf.account_proof.into_iter().map(|x| x.to_vec()).collect(),
         value_max_byte_len: ACCOUNT_PROOF_VALUE_MAX_BYTE_LEN,
         max_depth: acct_pf_max_depth,
@@ -258,17 +401,11 @@ pub fn get_storage_input(
         })
         .collect();
 
-    EthBlockStorageInput {
-        block,
-        block_number,
-        block_hash,
-        block_header,
-        storage: EthStorageInput { addr, acct_pf, storage_pfs },
-    }
+    EthStorageInput { addr, acct_pf, storage_pfs }
 }
 
 pub fn get_contract_storage_input(
-    provider: &Provider<RetryClient<Http>>,
+    provider: &Provider<RpcClient>,
     constructor: MultiBlocksContractsStorageConstructor,
 ) -> ObContractsStorageBlockInput {
     let rt = Runtime::new().unwrap();
@@ -382,7 +519,7 @@ pub fn get_contract_storage_input(
 }
 
 pub fn get_zksync_era_block_with_txs_input(
-    provider: &Provider<RetryClient<Http>>,
+    provider: &Provider<RpcClient>,
     blocks_number: Vec<u64>,
 ) -> ZkSyncEraBlockHeadersInput {
     let rt = Runtime::new().unwrap();
@@ -401,7 +538,7 @@ pub fn get_zksync_era_block_with_txs_input(
 }
 
 pub fn get_zksync_era_transaction_input(
-    provider: &Provider<RetryClient<Http>>,
+    provider: &Provider<RpcClient>,
     tx_hash: H256,
 ) -> ZkSyncEraBlockTransactionInput {
     let rt = Runtime::new().unwrap();
@@ -418,7 +555,7 @@ pub fn get_zksync_era_transaction_input(
             transaction_index: tx.transaction_index.unwrap().as_u64(),
             transaction_status: tx_status.status.unwrap().as_u64(),
             transaction_value: tx.rlp().to_vec(),
-            transaction_value_max_bytes: calculate_tx_max_len(tx.rlp().len()),
+            transaction_value_max_bytes: calculate_tx_max_len_from_rlp(&tx.rlp()),
             transaction_ecdsa_verify: EthEcdsaInput {
                 signature,
                 message,
@@ -429,6 +566,64 @@ pub fn get_zksync_era_transaction_input(
     }
 }
 
+/// The diamond proxy storage is proven at the L1 block executing the batch after the one of the L2 block,
+/// so both batches are executed there.
+pub fn get_zksync_era_l1_batch_input(
+    l1_provider: &Provider<RpcClient>,
+    l2_provider: &Provider<RpcClient>,
+    l2_block_number: u64,
+    diamond_proxy: Address,
+) -> ZkSyncEraL1BatchInput {
+    let rt = Runtime::new().unwrap();
+    let l2_block = rt.block_on(l2_provider.get_block(l2_block_number)).unwrap().unwrap();
+    let l2_block_header =
+        get_zksync_era_block_with_txs_input(l2_provider, vec![l2_block_number]).headers.remove(0);
+    let block_details =
+        rt.block_on(l2_provider.get_block_details(l2_block_number)).unwrap().unwrap();
+    let batch_number = block_details.l1_batch_number as u64;
+
+    let (stored_batches, execute_block_numbers): (Vec<StoredBatchInfo>, Vec<u64>) =
+        [batch_number, batch_number + 1]
+            .into_iter()
+            .map(|batch_number| {
+                let batch_details =
+                    rt.block_on(l2_provider.get_l1_batch_details(batch_number)).unwrap();
+                let execute_tx_hash =
+                    batch_details.execute_tx_hash.expect("batch should be executed on L1");
+                let execute_tx =
+                    rt.block_on(l1_provider.get_transaction(execute_tx_hash)).unwrap().unwrap();
+                let stored_batch = decode_execute_batches_input(execute_tx.input)
+                    .unwrap()
+                    .into_iter()
+                    .find(|stored_batch| stored_batch.batch_number == batch_number)
+                    .unwrap();
+                (stored_batch, execute_tx.block_number.unwrap().as_u64())
+            })
+            .unzip();
+
+    let slots = vec![
+        H256::from_low_u64_be(TOTAL_BATCHES_EXECUTED_SLOT),
+        get_stored_batch_hash_slot(batch_number),
+        get_stored_batch_hash_slot(batch_number + 1),
+    ];
+    let l1_storage = get_storage_input(
+        l1_provider,
+        execute_block_numbers[1] as u32,
+        diamond_proxy,
+        slots,
+        ACCOUNT_PF_MAX_DEPTH,
+        STORAGE_PF_MAX_DEPTH,
+    );
+
+    ZkSyncEraL1BatchInput {
+        l1_storage,
+        stored_batches: stored_batches.try_into().unwrap(),
+        l2_block_number,
+        l2_block_hash: l2_block.hash.unwrap(),
+        l2_block_header,
+    }
+}
+
 pub fn is_assigned_slot(key: &H256, proof: &[Bytes]) -> bool {
     let mut key_nibbles = Vec::new();
     for &byte in key.as_bytes() {
@@ -480,9 +675,29 @@ pub fn get_acct_rlp(pf: &EIP1186ProofResponse) -> Vec<u8> {
 }
 
 pub fn get_block_rlp(block: &Block<H256>) -> Vec<u8> {
+    let encoding = encode_block_header(block);
+    assert_eq!(keccak256(&encoding), block.hash.unwrap().0);
+    encoding
+}
+
+/// The RLP encoding of the header of `block`, without checking it against the block hash.
+pub fn encode_block_header(block: &Block<H256>) -> Vec<u8> {
     let withdrawals_root: Option<H256> = block.withdrawals_root;
     let base_fee = block.base_fee_per_gas;
-    let rlp_len = 15 + usize::from(base_fee.is_some()) + usize::from(withdrawals_root.is_some());
+    // EIP-4844 and EIP-4788 fields are added together in Cancun
+    let blob_gas_used = block.blob_gas_used;
+    let excess_blob_gas = block.excess_blob_gas;
+    let parent_beacon_block_root = block.parent_beacon_block_root;
+    // EIP-7685 requests hash is added in Prague, ethers keeps it with the fields it doesn't know
+    let requests_hash: Option<H256> =
+        block.other.get_deserialized("requestsHash").map(|requests_hash| requests_hash.unwrap());
+    let rlp_len = 15
+        + usize::from(base_fee.is_some())
+        + usize::from(withdrawals_root.is_some())
+        + usize::from(blob_gas_used.is_some())
+        + usize::from(excess_blob_gas.is_some())
+        + usize::from(parent_beacon_block_root.is_some())
+        + usize::from(requests_hash.is_some());
     let mut rlp = RlpStream::new_list(rlp_len);
     rlp.append(&block.parent_hash);
     rlp.append(&block.uncles_hash);
@@ -501,9 +716,11 @@ pub fn get_block_rlp(block: &Block<H256>) -> Vec<u8> {
     rlp.append(&block.nonce.unwrap());
     base_fee.map(|base_fee| rlp.append(&base_fee));
     withdrawals_root.map(|withdrawals_root| rlp.append(&withdrawals_root));
-    let encoding: Vec<u8> = rlp.out().into();
-    assert_eq!(keccak256(&encoding), block.hash.unwrap().0);
-    encoding
+    blob_gas_used.map(|blob_gas_used| rlp.append(&blob_gas_used));
+    excess_blob_gas.map(|excess_blob_gas| rlp.append(&excess_blob_gas));
+    parent_beacon_block_root.map(|parent_beacon_block_root| rlp.append(&parent_beacon_block_root));
+    requests_hash.map(|requests_hash| rlp.append(&requests_hash));
+    rlp.out().into()
 }
 
 pub fn get_zksync_era_block_rlp(block: &Block<H256>) -> Vec<u8> {
@@ -539,63 +756,90 @@ pub struct ProcessedBlock {
     pub prev_hash: H256,
 }
 
-/// returns tuple of:
-///   * vector of RLP bytes of each block
-///   * tuple of
-///       * parentHash (H256)
-///       * endHash (H256)
-///       * startBlockNumber (u32)
-///       * endBlockNumber (u32)
-///       * merkleRoots (Vec<H256>)
-///   * where merkleRoots is a length `max_depth + 1` vector representing a merkle mountain range, ordered largest mountain first
-// second tuple `instance` is only used for debugging now
-pub fn get_blocks_input(
-    provider: &Provider<RetryClient<Http>>,
+/// Where `get_blocks_input` records the RLP of the blocks it fetches, so that later runs don't need an RPC.
+pub const CHAIN_DATA_DIR: &str = "data/chain";
+
+/// `{chain_data_dir}/chainid{chain_id}_{start:06x}_{end:06x}.json`
+pub fn get_chain_data_path(
+    chain_data_dir: impl AsRef<Path>,
+    chain_id: u64,
+    start_block_number: u32,
+    end_block_number: u32,
+) -> PathBuf {
+    chain_data_dir
+        .as_ref()
+        .join(format!("chainid{chain_id}_{start_block_number:06x}_{end_block_number:06x}.json"))
+}
+
+/// The pre-recorded RLP of the blocks, padded with dummies to `2^max_depth` blocks, `None` if they were never fetched.
+pub fn read_blocks_input(
+    chain_data_dir: impl AsRef<Path>,
+    chain_id: u64,
     start_block_number: u32,
     num_blocks: u32,
     max_depth: usize,
-) -> Vec<Vec<u8>> {
+) -> Option<Vec<Vec<u8>>> {
     assert!(num_blocks <= (1 << max_depth));
     assert!(num_blocks > 0);
-    let chain_data_dir = PathBuf::from("data/chain");
-    fs::create_dir_all(&chain_data_dir).unwrap();
     let end_block_number = start_block_number + num_blocks - 1;
-    let rt = Runtime::new().unwrap();
-    let chain_id = rt.block_on(provider.get_chainid()).unwrap();
-    let path = chain_data_dir
-        .join(format!("chainid{chain_id}_{start_block_number:06x}_{end_block_number:06x}.json"));
+    let path = get_chain_data_path(chain_data_dir, chain_id, start_block_number, end_block_number);
+    let f = File::open(path).ok()?;
     // block_hashes and prev_hash no longer used, but keeping this format for compatibility with old cached chaindata
     let ProcessedBlock { mut block_rlps, block_hashes: _, prev_hash: _ } =
-        if let Ok(f) = File::open(&path) {
-            serde_json::from_reader(f).unwrap()
-        } else {
-            let blocks = get_blocks(
-                provider,
-                start_block_number as u64..(start_block_number + num_blocks) as u64,
-            )
-            .unwrap_or_else(|e| panic!("get_blocks JSON-RPC call failed: {e}"));
-            let prev_hash = blocks[0].as_ref().expect("block not found").parent_hash;
-            let (block_rlps, block_hashes): (Vec<_>, Vec<_>) = blocks
-                .into_iter()
-                .map(|block| {
-                    let block = block.expect("block not found");
-                    (get_block_rlp(&block), block.hash.unwrap())
-                })
-                .unzip();
-            // write this to file
-            let file = File::create(&path).unwrap();
-            let payload = ProcessedBlock { block_rlps, block_hashes, prev_hash };
-            serde_json::to_writer(file, &payload).unwrap();
-            payload
-        };
+        serde_json::from_reader(f).unwrap();
+    assert_eq!(block_rlps.len(), num_blocks as usize);
     // pad to correct length with dummies
     let dummy_block_rlp = block_rlps[0].clone();
     block_rlps.resize(1 << max_depth, dummy_block_rlp);
+    Some(block_rlps)
+}
+
+/// The RLP of the blocks from [`CHAIN_DATA_DIR`], fetched and recorded there if they are not yet.
+///
+/// `chain_id` is the chain of `provider`, it is not requested so that recorded blocks are read without RPC.
+pub fn get_blocks_input(
+    provider: &Provider<RpcClient>,
+    chain_id: u64,
+    start_block_number: u32,
+    num_blocks: u32,
+    max_depth: usize,
+) -> Vec<Vec<u8>> {
+    if let Some(block_rlps) =
+        read_blocks_input(CHAIN_DATA_DIR, chain_id, start_block_number, num_blocks, max_depth)
+    {
+        return block_rlps;
+    }
+    let end_block_number = start_block_number + num_blocks - 1;
+    let path = get_chain_data_path(CHAIN_DATA_DIR, chain_id, start_block_number, end_block_number);
+    let blocks =
+        get_blocks(provider, start_block_number as u64..(start_block_number + num_blocks) as u64)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "{} is not recorded and get_blocks JSON-RPC call failed: {e}",
+                    path.display()
+                )
+            });
+    let prev_hash = blocks[0].as_ref().expect("block not found").parent_hash;
+    let (block_rlps, block_hashes): (Vec<_>, Vec<_>) = blocks
+        .into_iter()
+        .map(|block| {
+            let block = block.expect("block not found");
+            (get_block_rlp(&block), block.hash.unwrap())
+        })
+        .unzip();
+    // write this to file
+    fs::create_dir_all(CHAIN_DATA_DIR).unwrap();
+    let file = File::create(&path).unwrap();
+    let payload = ProcessedBlock { block_rlps, block_hashes, prev_hash };
+    serde_json::to_writer(file, &payload).unwrap();
+    let mut block_rlps = payload.block_rlps;
+    let dummy_block_rlp = block_rlps[0].clone();
+    block_rlps.resize(1 << max_depth, dummy_block_rlp);
     block_rlps
 }
 
 pub fn get_blocks(
-    provider: &Provider<RetryClient<Http>>,
+    provider: &Provider<RpcClient>,
     block_numbers: impl IntoIterator<Item = u64>,
 ) -> Result<Vec<Option<Block<H256>>>, ProviderError> {
     let rt = Runtime::new().unwrap();
@@ -624,4 +868,30 @@ mod tests {
         let block = rt.block_on(provider.get_block(17034973)).unwrap().unwrap();
         get_block_rlp(&block);
     }
+
+    #[test]
+    fn test_read_blocks_input_offline() {
+        let dir = std::env::temp_dir().join(format!("zkspv_chain_data_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let chain_id = crate::Network::Ethereum(crate::EthereumNetwork::Sepolia).chain_id();
+        assert_eq!(read_blocks_input(&dir, chain_id, 0x10, 3, 2), None);
+
+        let payload = ProcessedBlock {
+            block_rlps: vec![vec![1], vec![2], vec![3]],
+            block_hashes: vec![H256::zero(); 3],
+            prev_hash: H256::zero(),
+        };
+        let path = get_chain_data_path(&dir, chain_id, 0x10, 0x12);
+        assert!(path.ends_with("chainid11155111_000010_000012.json"));
+        serde_json::to_writer(File::create(path).unwrap(), &payload).unwrap();
+        // padded with the first block
+        assert_eq!(
+            read_blocks_input(&dir, chain_id, 0x10, 3, 2),
+            Some(vec![vec![1], vec![2], vec![3], vec![1]])
+        );
+        // another chain or range was never recorded
+        assert_eq!(read_blocks_input(&dir, 1, 0x10, 3, 2), None);
+        assert_eq!(read_blocks_input(&dir, chain_id, 0x10, 2, 2), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -1,10 +1,11 @@
-mod tests;
+#[cfg(test)]
+pub(crate) mod tests;
 pub mod util;
 
 use std::cell::RefCell;
 
 use ethers_core::types::{Block, Bytes, H256};
-use ethers_providers::{Http, Provider, RetryClient};
+use ethers_providers::Provider;
 use halo2_base::gates::builder::GateThreadBuilder;
 use halo2_base::gates::{GateInstructions, RangeChip, RangeInstructions};
 use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
@@ -23,8 +24,9 @@ use crate::providers::get_receipt_input;
 use crate::receipt::util::ReceiptConstructor;
 use crate::rlp::builder::{RlcThreadBreakPoints, RlcThreadBuilder};
 use crate::rlp::rlc::{RlcContextPair, FIRST_PHASE};
-use crate::rlp::{RlpArrayTraceWitness, RlpChip, RlpFieldTrace, RlpFieldWitness};
+use crate::rlp::{RlpArrayTrace, RlpArrayTraceWitness, RlpChip, RlpFieldTrace, RlpFieldWitness};
 use crate::transaction::{load_transaction_type, EIP_2718_TX_TYPE, EIP_TX_TYPE_CRITICAL_VALUE};
+use crate::util::rpc_client::RpcClient;
 use crate::util::{bytes_be_to_u128, bytes_be_to_uint, bytes_be_var_to_fixed, AssignedH256};
 use crate::{EthChip, EthCircuitBuilder, EthPreCircuit, Network, ETH_LOOKUP_BITS};
 
@@ -40,6 +42,20 @@ const RECEIPT_FIELDS_MAX_FIELDS_LEN: [usize; RECEIPT_FIELDS_NUM] =
     [32 + 1, 32 + 1, RECEIPT_LOGS_BLOOM_MAX_LEN + 3, RECEIPT_LOG_MAX_NUM * RECEIPT_LOG_MAX_LEN];
 pub(crate) const RECEIPT_MAX_LEN: usize =
     3 + 33 * 2 + RECEIPT_LOGS_BLOOM_MAX_LEN + 3 + RECEIPT_LOG_MAX_NUM * RECEIPT_LOG_MAX_LEN;
+const RECEIPT_LOGS_INDEX: usize = 3;
+
+/// [address,topics,data]
+const RECEIPT_LOG_FIELDS_NUM: usize = 3;
+const RECEIPT_LOG_FIELDS_MAX_FIELDS_LEN: [usize; RECEIPT_LOG_FIELDS_NUM] =
+    [20, 33 * RECEIPT_TOPIC_MAX_NUM, RECEIPT_DATA_MAX_BYTES];
+/// keccak256("Transfer(address,address,uint256)")
+pub const ERC20_TRANSFER_EVENT_TOPIC: [u8; 32] = [
+    221, 242, 82, 173, 27, 226, 200, 155, 105, 194, 176, 104, 252, 55, 141, 170, 149, 43, 167, 241,
+    99, 196, 161, 22, 40, 245, 90, 77, 245, 35, 179, 239,
+];
+/// ERC721 emits the same event with the token id as a 4th topic, so the number of topics is fixed.
+const ERC20_TRANSFER_TOPICS_NUM: usize = 3;
+const ERC20_TRANSFER_DATA_LEN: usize = 32;
 pub const TX_STATUS_SUCCESS: u8 = 1;
 const NUM_BITS: usize = 8;
 
@@ -47,20 +63,25 @@ const NUM_BITS: usize = 8;
 pub struct EthReceiptInput {
     pub receipt_index: u64,
     pub receipt_proofs: MPTInput,
+    // index of an ERC20 Transfer log to decode, the logs are not parsed when it is None
+    pub transfer_log_index: Option<u64>,
 }
 
 #[derive(Clone, Debug)]
 pub struct EthReceiptInputAssigned<F: Field> {
     pub receipt_index: AssignedValue<F>,
     pub receipt_proofs: MPTProof<F>,
+    pub transfer_log_index: Option<AssignedValue<F>>,
 }
 
 impl EthReceiptInput {
     pub fn assign<F: Field>(self, ctx: &mut Context<F>) -> EthReceiptInputAssigned<F> {
         let receipt_index = ctx.load_witness(F::from(self.receipt_index));
         let receipt_proofs = self.receipt_proofs.assign(ctx);
+        let transfer_log_index =
+            self.transfer_log_index.map(|log_index| ctx.load_witness(F::from(log_index)));
 
-        EthReceiptInputAssigned { receipt_index, receipt_proofs }
+        EthReceiptInputAssigned { receipt_index, receipt_proofs, transfer_log_index }
     }
 }
 
@@ -94,10 +115,7 @@ pub struct EthBlockReceiptCircuit {
 }
 
 impl EthBlockReceiptCircuit {
-    pub fn from_provider(
-        provider: &Provider<RetryClient<Http>>,
-        constructor: ReceiptConstructor,
-    ) -> Self {
+    pub fn from_provider(provider: &Provider<RpcClient>, constructor: ReceiptConstructor) -> Self {
         let inputs = get_receipt_input(
             provider,
             constructor.transaction_hash,
@@ -105,6 +123,7 @@ impl EthBlockReceiptCircuit {
             constructor.receipt_rlp,
             constructor.merkle_proof,
             constructor.receipt_pf_max_depth,
+            constructor.transfer_log_index,
         );
         let block_header_config = get_block_header_config(&constructor.network);
         Self { inputs, block_header_config }
@@ -131,9 +150,24 @@ impl EthPreCircuit for EthBlockReceiptCircuit {
             &self.block_header_config,
         );
 
-        let EIP1186ResponseDigest { block_hash, block_number, index, receipt_is_empty } = digest;
-
-        let assigned_instances = block_hash.into_iter().chain([index]).collect_vec();
+        let EIP1186ResponseDigest {
+            block_hash,
+            block_number,
+            index,
+            receipt_is_empty,
+            transfer_log,
+        } = digest;
+
+        let mut assigned_instances = block_hash.into_iter().chain([index]).collect_vec();
+        if let Some(transfer_log) = transfer_log {
+            assigned_instances.extend([
+                transfer_log.log_index,
+                transfer_log.token,
+                transfer_log.from,
+                transfer_log.to,
+                transfer_log.amount,
+            ]);
+        }
         {
             let ctx = builder.gate_builder.main(FIRST_PHASE);
             range.gate.assert_is_const(ctx, &receipt_is_empty, &Fr::zero());
@@ -164,11 +198,30 @@ pub struct EIP1186ResponseDigest<F: Field> {
     // the value U256 is interpreted as H256 (padded with 0s on left)
     // pub slots_values: Vec<AssignedValue<F>>,
     pub receipt_is_empty: AssignedValue<F>,
+    pub transfer_log: Option<EthReceiptTransferLog<F>>,
+}
+
+/// Fields of an ERC20 `Transfer(from,to,value)` event.
+#[derive(Clone, Debug)]
+pub struct EthReceiptTransferLog<F: Field> {
+    pub log_index: AssignedValue<F>,
+    pub token: AssignedValue<F>, // address of the contract that emitted the log
+    pub from: AssignedValue<F>,
+    pub to: AssignedValue<F>,
+    pub amount: AssignedValue<F>,
 }
 
 #[derive(Clone, Debug)]
 pub struct EthReceiptTrace<F: Field> {
     pub value_trace: Vec<RlpFieldTrace<F>>,
+    pub transfer_log_trace: Option<EthReceiptTransferLogTrace<F>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EthReceiptTransferLogTrace<F: Field> {
+    pub logs_trace: RlpArrayTrace<F>,
+    pub log_trace: RlpArrayTrace<F>,
+    pub topics_trace: RlpArrayTrace<F>,
 }
 
 #[derive(Clone, Debug)]
@@ -181,6 +234,16 @@ pub struct EthBlockReceiptTrace<F: Field> {
 pub struct EthReceiptTraceWitness<F: Field> {
     receipt_witness: RlpArrayTraceWitness<F>,
     mpt_witness: MPTProofWitness<F>,
+    transfer_log_witness: Option<EthReceiptTransferLogWitness<F>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EthReceiptTransferLogWitness<F: Field> {
+    // the logs field of the receipt, decomposed again as a list of logs
+    pub logs_rlp_field: Vec<AssignedValue<F>>,
+    pub logs_witness: RlpArrayTraceWitness<F>,
+    pub log_witness: RlpArrayTraceWitness<F>,
+    pub topics_witness: RlpArrayTraceWitness<F>,
 }
 
 impl<F: Field> EthReceiptTraceWitness<F> {
@@ -193,9 +256,9 @@ impl<F: Field> EthReceiptTraceWitness<F> {
     pub fn get_logs_bloom(&self) -> &RlpFieldWitness<F> {
         &self.receipt_witness.field_witness[2]
     }
-    // pub fn get_logs(&self) -> &RlpFieldWitness<F> {
-    //     &self.array_witness.field_witness[3]
-    // }
+    pub fn get_logs(&self) -> &RlpFieldWitness<F> {
+        &self.receipt_witness.field_witness[RECEIPT_LOGS_INDEX]
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -233,6 +296,13 @@ pub trait EthBlockReceiptChip<F: Field> {
         receipt_proofs: MPTProof<F>,
     ) -> EthReceiptTraceWitness<F>;
 
+    fn parse_receipt_transfer_log_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        receipt_witness: &RlpArrayTraceWitness<F>,
+        log_index: AssignedValue<F>,
+    ) -> (EthReceiptTransferLogWitness<F>, EthReceiptTransferLog<F>);
+
     // ================= SECOND PHASE ================
 
     fn parse_receipt_proof_from_block_phase1(
@@ -254,6 +324,13 @@ pub trait EthBlockReceiptChip<F: Field> {
         ctx: RlcContextPair<F>,
         witness: EthReceiptTraceWitness<F>,
     ) -> EthReceiptTrace<F>;
+
+    fn parse_receipt_transfer_log_phase1(
+        &self,
+        ctx: RlcContextPair<F>,
+        logs_trace: &RlpFieldTrace<F>,
+        witness: EthReceiptTransferLogWitness<F>,
+    ) -> EthReceiptTransferLogTrace<F>;
 }
 
 impl<'chip, F: Field> EthBlockReceiptChip<F> for EthChip<'chip, F> {
@@ -297,18 +374,31 @@ impl<'chip, F: Field> EthBlockReceiptChip<F> for EthChip<'chip, F> {
         );
 
         // drop ctx
-        let receipt_witness = self.parse_eip1186_proof_phase0(
+        let mut receipt_witness = self.parse_eip1186_proof_phase0(
             thread_pool,
             keccak,
             receipts_root,
             input.receipt.receipt_proofs,
         );
 
+        let mut transfer_log = None;
+        if let Some(log_index) = input.receipt.transfer_log_index {
+            let ctx = thread_pool.main(FIRST_PHASE);
+            let (transfer_log_witness, log) = self.parse_receipt_transfer_log_phase0(
+                ctx,
+                &receipt_witness.receipt_witness,
+                log_index,
+            );
+            receipt_witness.transfer_log_witness = Some(transfer_log_witness);
+            transfer_log = Some(log);
+        }
+
         let digest = EIP1186ResponseDigest {
             block_hash: block_hash.try_into().unwrap(),
             block_number,
             index: receipt_index,
             receipt_is_empty: receipt_witness.mpt_witness.slot_is_empty,
+            transfer_log,
         };
         (EthBlockReceiptTraceWitness { block_witness, receipt_witness }, digest)
     }
@@ -375,7 +465,120 @@ impl<'chip, F: Field> EthBlockReceiptChip<F> for EthChip<'chip, F> {
         // check MPT inclusion
         let mpt_witness = self.parse_mpt_inclusion_phase0(ctx, keccak, receipt_proofs);
 
-        EthReceiptTraceWitness { receipt_witness, mpt_witness }
+        EthReceiptTraceWitness { receipt_witness, mpt_witness, transfer_log_witness: None }
+    }
+
+    fn parse_receipt_transfer_log_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        receipt_witness: &RlpArrayTraceWitness<F>,
+        log_index: AssignedValue<F>,
+    ) -> (EthReceiptTransferLogWitness<F>, EthReceiptTransferLog<F>) {
+        // [address,topics,data] for every log of the receipt
+        let logs_field_witness = &receipt_witness.field_witness[RECEIPT_LOGS_INDEX];
+        let logs_witness = self.rlp().decompose_rlp_array_phase0(
+            ctx,
+            logs_field_witness.rlp_field.clone(),
+            &[RECEIPT_LOG_MAX_LEN; RECEIPT_LOG_MAX_NUM],
+            true,
+        );
+        ctx.constrain_equal(&logs_witness.rlp_len, &logs_field_witness.rlp_field_len);
+
+        // select the log, a log is never empty so a zero length means the index is out of the list
+        self.range().check_less_than_safe(ctx, log_index, RECEIPT_LOG_MAX_NUM as u64);
+        let log_len = self.gate().select_from_idx(
+            ctx,
+            logs_witness.field_witness.iter().map(|log| log.field_len),
+            log_index,
+        );
+        let log_is_empty = self.gate().is_zero(ctx, log_len);
+        self.gate().assert_is_const(ctx, &log_is_empty, &F::zero());
+        let log_rlp_len = self.gate().select_from_idx(
+            ctx,
+            logs_witness.field_witness.iter().map(|log| log.rlp_field_len),
+            log_index,
+        );
+        let log_rlp_max_len = logs_witness.field_witness[0].rlp_field.len();
+        let log_rlp = (0..log_rlp_max_len)
+            .map(|i| {
+                self.gate().select_from_idx(
+                    ctx,
+                    logs_witness.field_witness.iter().map(|log| log.rlp_field[i]),
+                    log_index,
+                )
+            })
+            .collect_vec();
+        let log_witness = self.rlp().decompose_rlp_array_phase0(
+            ctx,
+            log_rlp,
+            &RECEIPT_LOG_FIELDS_MAX_FIELDS_LEN,
+            false,
+        );
+        ctx.constrain_equal(&log_witness.rlp_len, &log_rlp_len);
+
+        let topics_field_witness = &log_witness.field_witness[1];
+        let topics_witness = self.rlp().decompose_rlp_array_phase0(
+            ctx,
+            topics_field_witness.rlp_field.clone(),
+            &[32; RECEIPT_TOPIC_MAX_NUM],
+            true,
+        );
+        ctx.constrain_equal(&topics_witness.rlp_len, &topics_field_witness.rlp_field_len);
+
+        // Transfer(address indexed from, address indexed to, uint256 value)
+        for (i, topic_witness) in topics_witness.field_witness.iter().enumerate() {
+            let topic_len = if i < ERC20_TRANSFER_TOPICS_NUM { 32u64 } else { 0 };
+            self.gate().assert_is_const(ctx, &topic_witness.field_len, &F::from(topic_len));
+        }
+        for (byte, topic_byte) in
+            topics_witness.field_witness[0].field_cells.iter().zip(ERC20_TRANSFER_EVENT_TOPIC)
+        {
+            self.gate().assert_is_const(ctx, byte, &F::from(topic_byte as u64));
+        }
+        let address_witness = &log_witness.field_witness[0];
+        let data_witness = &log_witness.field_witness[2];
+        self.gate().assert_is_const(ctx, &address_witness.field_len, &F::from(20));
+        self.gate().assert_is_const(
+            ctx,
+            &data_witness.field_len,
+            &F::from(ERC20_TRANSFER_DATA_LEN as u64),
+        );
+
+        let token = bytes_be_to_uint(ctx, self.gate(), &address_witness.field_cells, 20);
+        // indexed addresses are left padded to 32 bytes, the padding must be zero or the topic is not an address
+        for topic_witness in &topics_witness.field_witness[1..ERC20_TRANSFER_TOPICS_NUM] {
+            for byte in &topic_witness.field_cells[..12] {
+                self.gate().assert_is_const(ctx, byte, &F::zero());
+            }
+        }
+        let from = bytes_be_to_uint(
+            ctx,
+            self.gate(),
+            &topics_witness.field_witness[1].field_cells[12..],
+            20,
+        );
+        let to = bytes_be_to_uint(
+            ctx,
+            self.gate(),
+            &topics_witness.field_witness[2].field_cells[12..],
+            20,
+        );
+        let amount = bytes_be_to_uint(
+            ctx,
+            self.gate(),
+            &data_witness.field_cells[..ERC20_TRANSFER_DATA_LEN],
+            ERC20_TRANSFER_DATA_LEN,
+        );
+
+        (
+            EthReceiptTransferLogWitness {
+                logs_rlp_field: logs_field_witness.rlp_field.clone(),
+                logs_witness,
+                log_witness,
+                topics_witness,
+            },
+            EthReceiptTransferLog { log_index, token, from, to, amount },
+        )
     }
 
     // ================= SECOND PHASE ================
@@ -414,13 +617,64 @@ impl<'chip, F: Field> EthBlockReceiptChip<F> for EthChip<'chip, F> {
     ) -> EthReceiptTrace<F> {
         self.parse_mpt_inclusion_phase1((ctx_gate, ctx_rlc), witness.mpt_witness);
 
-        let value_trace = self
+        let value_trace: Vec<RlpFieldTrace<F>> = self
             .rlp()
             .decompose_rlp_array_phase1((ctx_gate, ctx_rlc), witness.receipt_witness, true)
             .field_trace
             .try_into()
             .unwrap();
 
-        EthReceiptTrace { value_trace }
+        let mut transfer_log_trace = None;
+        if let Some(transfer_log_witness) = witness.transfer_log_witness {
+            transfer_log_trace = Some(self.parse_receipt_transfer_log_phase1(
+                (ctx_gate, ctx_rlc),
+                &value_trace[RECEIPT_LOGS_INDEX],
+                transfer_log_witness,
+            ));
+        }
+
+        EthReceiptTrace { value_trace, transfer_log_trace }
+    }
+
+    fn parse_receipt_transfer_log_phase1(
+        &self,
+        (ctx_gate, ctx_rlc): RlcContextPair<F>,
+        logs_trace: &RlpFieldTrace<F>,
+        witness: EthReceiptTransferLogWitness<F>,
+    ) -> EthReceiptTransferLogTrace<F> {
+        let EthReceiptTransferLogWitness {
+            logs_rlp_field,
+            logs_witness,
+            log_witness,
+            topics_witness,
+        } = witness;
+        self.rlc().load_rlc_cache(
+            (ctx_gate, ctx_rlc),
+            self.gate(),
+            bit_length(logs_rlp_field.len() as u64),
+        );
+
+        // every log can be selected, so the encoding of each of them is bound to the logs list
+        self.rlp().constrain_rlp_field_phase1((ctx_gate, ctx_rlc), logs_rlp_field, logs_trace);
+        let logs_rlp_fields =
+            logs_witness.field_witness.iter().map(|log| log.rlp_field.clone()).collect_vec();
+        let logs_trace =
+            self.rlp().decompose_rlp_array_phase1((ctx_gate, ctx_rlc), logs_witness, true);
+        for (log_rlp_field, log_trace) in logs_rlp_fields.into_iter().zip(&logs_trace.field_trace) {
+            self.rlp().constrain_rlp_field_phase1((ctx_gate, ctx_rlc), log_rlp_field, log_trace);
+        }
+
+        let topics_rlp_field = log_witness.field_witness[1].rlp_field.clone();
+        let log_trace =
+            self.rlp().decompose_rlp_array_phase1((ctx_gate, ctx_rlc), log_witness, false);
+        self.rlp().constrain_rlp_field_phase1(
+            (ctx_gate, ctx_rlc),
+            topics_rlp_field,
+            &log_trace.field_trace[1],
+        );
+        let topics_trace =
+            self.rlp().decompose_rlp_array_phase1((ctx_gate, ctx_rlc), topics_witness, true);
+
+        EthReceiptTransferLogTrace { logs_trace, log_trace, topics_trace }
     }
 }
@@ -1,16 +1,31 @@
-use std::env::set_var;
+use std::str::FromStr;
 
-use ethers_core::types::{Bytes, H256};
+use ethers_core::types::{Address, Bloom, BloomInput, Bytes, H256, U256};
 use ethers_core::utils::hex::FromHex;
+use halo2_base::gates::RangeChip;
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::utils::biguint_to_fe;
+use num_bigint::BigUint;
+use rlp::RlpStream;
 
 use crate::halo2_proofs::dev::MockProver;
+use crate::keccak::{FixedLenRLCs, FnSynthesize, SharedKeccakChip, VarLenRLCs};
 use crate::receipt::util::{ReceiptConstructor, RECEIPT_PF_MAX_DEPTH};
-use crate::receipt::EthBlockReceiptCircuit;
+use crate::receipt::{
+    EthBlockReceiptChip, EthBlockReceiptCircuit, ERC20_TRANSFER_EVENT_TOPIC,
+    RECEIPT_FIELDS_MAX_FIELDS_LEN, RECEIPT_LOGS_INDEX, RECEIPT_MAX_LEN, TX_STATUS_SUCCESS,
+};
 use crate::rlp::builder::RlcThreadBuilder;
+use crate::rlp::rlc::FIRST_PHASE;
+use crate::rlp::RlpChip;
+use crate::transaction::EIP_1559_TX_TYPE;
 use crate::util::helpers::get_provider;
 use crate::util::EthConfigParams;
 use crate::Network::{Arbitrum, Ethereum};
-use crate::{ArbitrumNetwork, EthPreCircuit, EthereumNetwork, Network};
+use crate::{
+    ArbitrumNetwork, EthChip, EthCircuitBuilder, EthPreCircuit, EthereumNetwork, Network,
+    ETH_LOOKUP_BITS,
+};
 
 fn get_test_circuit(
     transaction_hash: H256,
@@ -26,6 +41,7 @@ fn get_test_circuit(
         merkle_proof,
         RECEIPT_PF_MAX_DEPTH,
         network,
+        None,
     );
     EthBlockReceiptCircuit::from_provider(&provider, constructor)
 }
@@ -33,7 +49,6 @@ fn get_test_circuit(
 #[test]
 pub fn test_receipt_mpt() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigParams::from_path("configs/tests/receipt.json");
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree;
     let transaction_hash =
         "0x6167c0b969859713aa2145e6d6ab2fd5fecc4d7781fd37ed19a2feb310ad6853".parse().unwrap();
@@ -55,8 +70,192 @@ pub fn test_receipt_mpt() -> Result<(), Box<dyn std::error::Error>> {
         merkle_proof,
         Ethereum(EthereumNetwork::Goerli),
     );
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
 
     Ok(())
 }
+
+/// `(address, topics, data)` of a log.
+pub type TestLog = (Address, Vec<H256>, Vec<u8>);
+
+/// keccak256("Approval(address,address,uint256)")
+const ERC20_APPROVAL_EVENT_TOPIC: &str =
+    "0x8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925";
+
+/// The log of an ERC20 event `topic` of `token`, from `from` to `to` for `amount`.
+pub fn erc20_log(topic: H256, token: Address, from: Address, to: Address, amount: u64) -> TestLog {
+    let topics = vec![topic, H256::from(from), H256::from(to)];
+    let mut data = [0u8; 32];
+    U256::from(amount).to_big_endian(&mut data);
+    (token, topics, data.to_vec())
+}
+
+pub fn erc20_transfer_log(token: Address, from: Address, to: Address, amount: u64) -> TestLog {
+    erc20_log(H256(ERC20_TRANSFER_EVENT_TOPIC), token, from, to, amount)
+}
+
+/// The EIP-1559 receipt of a successful transaction which emitted `logs`.
+pub fn receipt_rlp(logs: &[TestLog]) -> Vec<u8> {
+    let mut logs_bloom = Bloom::zero();
+    for (address, topics, _) in logs {
+        logs_bloom.accrue(BloomInput::Raw(address.as_bytes()));
+        for topic in topics {
+            logs_bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+        }
+    }
+    // [status,cumulativeGasUsed,logsBloom,logs]
+    let mut rlp = RlpStream::new_list(4);
+    rlp.append(&TX_STATUS_SUCCESS);
+    rlp.append(&0x0e7083u64);
+    rlp.append(&logs_bloom);
+    rlp.begin_list(logs.len());
+    for (address, topics, data) in logs {
+        rlp.begin_list(3);
+        rlp.append(address);
+        rlp.append_list(topics);
+        rlp.append(data);
+    }
+    [&[EIP_1559_TX_TYPE][..], &rlp.out()].concat()
+}
+
+fn address(hex: &str) -> Address {
+    Address::from_str(hex).unwrap()
+}
+
+/// Receipt with an `Approval` log followed by a `Transfer` log of the same token, both for 1234567 units
+/// from 0x5a873a4aa853302449a92d57b54378d4a5001458 to 0x1908e2bf4a88f91e4ef0dc72f02b8ea36bea2319.
+fn transfer_receipt_rlp() -> Vec<u8> {
+    let token = address("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+    let from = address("0x5a873a4aa853302449a92d57b54378d4a5001458");
+    let to = address("0x1908e2bf4a88f91e4ef0dc72f02b8ea36bea2319");
+    receipt_rlp(&[
+        erc20_log(H256::from_str(ERC20_APPROVAL_EVENT_TOPIC).unwrap(), token, from, to, 1234567),
+        erc20_transfer_log(token, from, to, 1234567),
+    ])
+}
+
+fn transfer_log_test_circuit(
+    mut builder: RlcThreadBuilder<Fr>,
+    config_params: &EthConfigParams,
+    receipt_rlp: Vec<u8>,
+    log_index: u64,
+) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
+    let range = RangeChip::default(ETH_LOOKUP_BITS);
+    let keccak = SharedKeccakChip::default();
+    let chip = EthChip::new(RlpChip::new(&range, None), None);
+
+    let ctx = builder.gate_builder.main(FIRST_PHASE);
+    let mut receipt_rlp = receipt_rlp[1..].to_vec();
+    receipt_rlp.resize(RECEIPT_MAX_LEN - 1, 0);
+    let receipt_rlp =
+        ctx.assign_witnesses(receipt_rlp.into_iter().map(|byte| Fr::from(byte as u64)));
+    let receipt_witness = chip.rlp().decompose_rlp_array_phase0(
+        ctx,
+        receipt_rlp,
+        &RECEIPT_FIELDS_MAX_FIELDS_LEN,
+        true,
+    );
+    let log_index = ctx.load_witness(Fr::from(log_index));
+    let (transfer_log_witness, transfer_log) =
+        chip.parse_receipt_transfer_log_phase0(ctx, &receipt_witness, log_index);
+
+    let assigned_instances = vec![
+        transfer_log.log_index,
+        transfer_log.token,
+        transfer_log.from,
+        transfer_log.to,
+        transfer_log.amount,
+    ];
+    let circuit = EthCircuitBuilder::new(
+        assigned_instances,
+        builder,
+        keccak,
+        range,
+        None,
+        move |builder: &mut RlcThreadBuilder<Fr>,
+              rlp: RlpChip<Fr>,
+              keccak_rlcs: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {
+            let chip = EthChip::new(rlp, Some(keccak_rlcs));
+            let (ctx_gate, ctx_rlc) = builder.rlc_ctx_pair();
+            let value_trace = chip
+                .rlp()
+                .decompose_rlp_array_phase1((ctx_gate, ctx_rlc), receipt_witness, true)
+                .field_trace;
+            chip.parse_receipt_transfer_log_phase1(
+                (ctx_gate, ctx_rlc),
+                &value_trace[RECEIPT_LOGS_INDEX],
+                transfer_log_witness,
+            );
+        },
+    );
+    circuit.config(config_params.degree as usize, Some(config_params.unusable_rows));
+    circuit
+}
+
+fn hex_to_fr(hex: &str) -> Fr {
+    biguint_to_fe(&BigUint::parse_bytes(hex.as_bytes(), 16).unwrap())
+}
+
+#[test]
+pub fn test_receipt_transfer_log_mock() {
+    let params = EthConfigParams::from_path("configs/tests/receipt.json");
+    let k = params.degree;
+
+    let circuit =
+        transfer_log_test_circuit(RlcThreadBuilder::mock(), &params, transfer_receipt_rlp(), 1);
+    let instance = circuit.instance();
+    let _scope = circuit.config_scope();
+    assert_eq!(
+        instance,
+        vec![
+            Fr::from(1),
+            hex_to_fr("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"),
+            hex_to_fr("5a873a4aa853302449a92d57b54378d4a5001458"),
+            hex_to_fr("1908e2bf4a88f91e4ef0dc72f02b8ea36bea2319"),
+            Fr::from(1234567),
+        ]
+    );
+    MockProver::run(k, &circuit, vec![instance]).unwrap().assert_satisfied();
+}
+
+#[test]
+pub fn test_receipt_transfer_log_wrong_event_mock() {
+    let params = EthConfigParams::from_path("configs/tests/receipt.json");
+    let k = params.degree;
+
+    // the first log is an Approval event
+    let circuit =
+        transfer_log_test_circuit(RlcThreadBuilder::mock(), &params, transfer_receipt_rlp(), 0);
+    let instance = circuit.instance();
+    let _scope = circuit.config_scope();
+    assert!(MockProver::run(k, &circuit, vec![instance]).unwrap().verify().is_err());
+
+    // there are only two logs in the receipt
+    let circuit =
+        transfer_log_test_circuit(RlcThreadBuilder::mock(), &params, transfer_receipt_rlp(), 2);
+    let instance = circuit.instance();
+    let _scope = circuit.config_scope();
+    assert!(MockProver::run(k, &circuit, vec![instance]).unwrap().verify().is_err());
+}
+
+#[test]
+pub fn test_receipt_transfer_log_unpadded_address_mock() {
+    let params = EthConfigParams::from_path("configs/tests/receipt.json");
+    let k = params.degree;
+
+    // the low 20 bytes of the from topic are an address, but its padding is not zero
+    let (token, mut topics, data) = erc20_transfer_log(
+        address("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"),
+        address("0x5a873a4aa853302449a92d57b54378d4a5001458"),
+        address("0x1908e2bf4a88f91e4ef0dc72f02b8ea36bea2319"),
+        1234567,
+    );
+    topics[1].0[0] = 1;
+    let receipt_rlp = receipt_rlp(&[(token, topics, data)]);
+    let circuit = transfer_log_test_circuit(RlcThreadBuilder::mock(), &params, receipt_rlp, 0);
+    let instance = circuit.instance();
+    let _scope = circuit.config_scope();
+    assert!(MockProver::run(k, &circuit, vec![instance]).unwrap().verify().is_err());
+}
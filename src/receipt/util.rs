@@ -1,7 +1,9 @@
-use crate::receipt::EthBlockReceiptCircuit;
+use crate::receipt::{EthBlockReceiptCircuit, ERC20_TRANSFER_EVENT_TOPIC, RECEIPT_LOGS_INDEX};
+use crate::transaction::{FUNCTION_SELECTOR_ERC20_TRANSFER, FUNCTION_SELECTOR_ERC20_TRANSFER_FROM};
 use crate::util::helpers::get_provider;
 use crate::Network;
-use ethers_core::types::{Bytes, H256};
+use ethers_core::types::{Address, Bytes, Transaction, H256};
+use rlp::{Decodable, Rlp};
 
 pub const RECEIPT_PF_MAX_DEPTH: usize = 6;
 #[derive(Clone, Debug)]
@@ -12,6 +14,7 @@ pub struct ReceiptConstructor {
     pub merkle_proof: Vec<Bytes>,
     pub receipt_pf_max_depth: usize,
     pub network: Network,
+    pub transfer_log_index: Option<u64>,
 }
 
 impl ReceiptConstructor {
@@ -22,6 +25,7 @@ impl ReceiptConstructor {
         merkle_proof: Vec<Bytes>,
         receipt_pf_max_depth: usize,
         network: Network,
+        transfer_log_index: Option<u64>,
     ) -> Self {
         Self {
             transaction_hash,
@@ -30,6 +34,7 @@ impl ReceiptConstructor {
             merkle_proof,
             receipt_pf_max_depth,
             network,
+            transfer_log_index,
         }
     }
 
@@ -38,3 +43,28 @@ impl ReceiptConstructor {
         EthBlockReceiptCircuit::from_provider(&provider, self)
     }
 }
+
+/// Index of the `Transfer` log of `receipt_rlp` emitted by the token that `transaction_rlp` calls, None
+/// when the transaction is not an ERC20 `transfer` or `transferFrom`.
+pub fn get_erc20_transfer_log_index(transaction_rlp: &[u8], receipt_rlp: &[u8]) -> Option<u64> {
+    let transaction = Transaction::decode(&Rlp::new(transaction_rlp)).ok()?;
+    let selector = transaction.input.get(..4)?;
+    if selector != FUNCTION_SELECTOR_ERC20_TRANSFER
+        && selector != FUNCTION_SELECTOR_ERC20_TRANSFER_FROM
+    {
+        return None;
+    }
+    let token = transaction.to?;
+    // typed receipts are prefixed by their type
+    let receipt_rlp = if *receipt_rlp.first()? < 0x80 { &receipt_rlp[1..] } else { receipt_rlp };
+    let logs = Rlp::new(receipt_rlp).at(RECEIPT_LOGS_INDEX).ok()?;
+    logs.iter()
+        .position(|log| {
+            let address: Option<Address> = log.val_at(0).ok();
+            let topics: Vec<H256> = log.list_at(1).unwrap_or_default();
+            address == Some(token)
+                && topics.len() == 3
+                && topics[0] == H256(ERC20_TRANSFER_EVENT_TOPIC)
+        })
+        .map(|log_index| log_index as u64)
+}
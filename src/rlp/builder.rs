@@ -1,6 +1,5 @@
 use std::{
     collections::{HashMap, HashSet},
-    env::var,
     iter, mem,
 };
 
@@ -25,7 +24,7 @@ use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use super::rlc::{RlcChip, RlcConfig, RlcContextPair, FIRST_PHASE, RLC_PHASE};
-use crate::util::EthConfigParams;
+use crate::util::{circuit_config::isolate_env, EthConfigParams};
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RlcThreadBreakPoints {
@@ -114,13 +113,14 @@ impl<F: ScalarField> RlcThreadBuilder<F> {
     /// Auto-calculate configuration parameters for the circuit
     pub fn config(&self, k: usize, minimum_rows: Option<usize>) -> EthConfigParams {
         // first auto-configure the basic gates and lookup advice columns
+        // (halo2-base also writes them to `FLEX_GATE_CONFIG_PARAMS`, which other threads may be using)
         let FlexGateConfigParams {
             strategy: _,
             num_advice_per_phase,
             num_lookup_advice_per_phase,
             num_fixed: _,
             k,
-        } = self.gate_builder.config(k, minimum_rows);
+        } = isolate_env(|| self.gate_builder.config(k, minimum_rows));
         // now calculate how many RLC advice columns are needed
         let max_rows = (1 << k) - minimum_rows.unwrap_or(0);
         let total_rlc_advice = self.threads_rlc.iter().map(|ctx| ctx.advice.len()).sum::<usize>();
@@ -147,14 +147,13 @@ impl<F: ScalarField> RlcThreadBuilder<F> {
             num_fixed,
             unusable_rows: minimum_rows.unwrap_or(0),
             keccak_rows_per_round: 0,
-            lookup_bits: var("LOOKUP_BITS").map(|s| s.parse().ok()).unwrap_or(None),
+            lookup_bits: None,
         };
         #[cfg(feature = "display")]
         {
             println!("RLC Chip | {total_rlc_advice} advice cells");
             log::info!("RlcThreadBuilder auto-calculated config params:\n {params:#?}");
         }
-        std::env::set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
         params
     }
 
@@ -745,6 +745,44 @@ impl<'range, F: ScalarField> RlpChip<'range, F> {
         (rlp_array_trace, rlp_trace)
     }
 
+    /// Constrains `rlp_field` of a decomposed field to be the RLP encoding traced by `field_trace`.
+    ///
+    /// `rlp_field` is only witnessed in `decompose_rlp_array_phase0`, so this MUST be called before
+    /// `rlp_field` is decomposed again as a nested list. Dummy fields of variable length lists have
+    /// all their traced lengths set to 0, so the constraint is trivially satisfied for them.
+    pub fn constrain_rlp_field_phase1(
+        &self,
+        (ctx_gate, ctx_rlc): RlcContextPair<F>,
+        rlp_field: Vec<AssignedValue<F>>,
+        field_trace: &RlpFieldTrace<F>,
+    ) {
+        let rlp_field_len = self.gate().sum(
+            ctx_gate,
+            [field_trace.prefix_len, field_trace.len_trace.len, field_trace.field_trace.len],
+        );
+        let rlp_field_rlc =
+            self.rlc().compute_rlc((ctx_gate, ctx_rlc), self.gate(), rlp_field, rlp_field_len);
+        let inputs = [
+            (field_trace.prefix, field_trace.prefix_len, 1),
+            (
+                field_trace.len_trace.rlc_val,
+                field_trace.len_trace.len,
+                field_trace.len_trace.max_len,
+            ),
+            (
+                field_trace.field_trace.rlc_val,
+                field_trace.field_trace.len,
+                field_trace.field_trace.max_len,
+            ),
+        ];
+        self.rlc().constrain_rlc_concat(
+            ctx_gate,
+            self.gate(),
+            inputs,
+            (&rlp_field_rlc.rlc_val, &rlp_field_rlc.len),
+        );
+    }
+
     pub fn decompose_recursive_rlp_phase0(
         &self,
         ctx: &mut Context<F>,
@@ -1,6 +1,8 @@
 use std::cell::RefCell;
 
+use crate::util::circuit_config::{eth_config_params, CircuitConfig, CircuitConfigScope};
 use crate::util::EthConfigParams;
+use crate::ETH_LOOKUP_BITS;
 
 use super::{
     builder::*,
@@ -98,6 +100,7 @@ mod rlc {
         let circuit = rlc_test_circuit(RlcThreadBuilder::mock(), input_bytes, len);
 
         circuit.config(k as usize, Some(6));
+        let _scope = circuit.config_scope();
         MockProver::run(k, &circuit, vec![]).unwrap().assert_satisfied();
     }
 
@@ -117,6 +120,8 @@ mod rlc {
         let params = ParamsKZG::<Bn256>::setup(k, &mut rng);
         let circuit = rlc_test_circuit(RlcThreadBuilder::keygen(), input_bytes.clone(), len);
         circuit.config(k as usize, Some(6));
+        // the prover below uses the same config
+        let _scope = circuit.config_scope();
 
         println!("vk gen started");
         let vk = keygen_vk(&params, &circuit)?;
@@ -165,7 +170,6 @@ mod rlp {
     };
     use ethers_core::utils::hex::FromHex;
     use halo2_base::halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
-    use std::env::set_var;
     use test_log::test;
 
     const DEGREE: u32 = 18;
@@ -178,7 +182,6 @@ mod rlp {
         let prover = builder.witness_gen_only();
         let ctx = builder.gate_builder.main(0);
         let inputs = ctx.assign_witnesses(encoded.iter().map(|x| F::from(*x as u64)));
-        set_var("LOOKUP_BITS", "8");
         let range = RangeChip::default(8);
         let chip = RlpChip::new(&range, None);
         let witness = chip.decompose_rlp_field_phase0(ctx, inputs, max_len);
@@ -207,7 +210,6 @@ mod rlp {
         let prover = builder.witness_gen_only();
         let ctx = builder.gate_builder.main(0);
         let inputs = ctx.assign_witnesses(encoded.iter().map(|x| F::from(*x as u64)));
-        set_var("LOOKUP_BITS", "8");
         let range = RangeChip::default(8);
         let chip = RlpChip::new(&range, None);
         let witness = chip.decompose_rlp_array_phase0(ctx, inputs, max_field_lens, is_var_len);
@@ -245,6 +247,7 @@ mod rlp {
                 &[15, 9, 11, 10, 17],
                 true,
             );
+            let _scope = circuit.config_scope();
             MockProver::run(k, &circuit, vec![]).unwrap().assert_satisfied();
         }
     }
@@ -256,6 +259,7 @@ mod rlp {
             Vec::from_hex("a012341234123412341234123412341234123412341234123412341234123412340000")
                 .unwrap();
         let circuit = rlp_string_circuit(RlcThreadBuilder::<Fr>::mock(), input_bytes, 34);
+        let _scope = circuit.config_scope();
         MockProver::run(k, &circuit, vec![]).unwrap().assert_satisfied();
     }
 
@@ -266,6 +270,7 @@ mod rlp {
         input_bytes.resize(35, 0);
 
         let circuit = rlp_string_circuit(RlcThreadBuilder::<Fr>::mock(), input_bytes, 34);
+        let _scope = circuit.config_scope();
         MockProver::run(k, &circuit, vec![]).unwrap().assert_satisfied();
     }
 
@@ -275,6 +280,7 @@ mod rlp {
         let mut input_bytes: Vec<u8> = vec![0];
         input_bytes.resize(33, 0);
         let circuit = rlp_string_circuit(RlcThreadBuilder::<Fr>::mock(), input_bytes, 32);
+        let _scope = circuit.config_scope();
         MockProver::run(k, &circuit, vec![]).unwrap().assert_satisfied();
     }
 
@@ -284,6 +290,7 @@ mod rlp {
         let input_bytes: Vec<u8> = Vec::from_hex("a09bdb004d9b1e7f3e5f86fbdc9856f21f9dcb07a44c42f5de8eec178514d279df0000000000000000000000000000000000000000000000000000000000").unwrap();
 
         let circuit = rlp_string_circuit(RlcThreadBuilder::<Fr>::mock(), input_bytes, 60);
+        let _scope = circuit.config_scope();
         MockProver::run(k, &circuit, vec![]).unwrap().assert_satisfied();
     }
 
@@ -293,6 +300,7 @@ mod rlp {
         let input_bytes: Vec<u8> = Vec::from_hex("b83adb004d9b1e7f3e5f86fbdc9856f21f9dcb07a44c42f5de8eec178514d279df0000000000000000000000000000000000000000000000000000000000").unwrap();
 
         let circuit = rlp_string_circuit(RlcThreadBuilder::<Fr>::mock(), input_bytes, 60);
+        let _scope = circuit.config_scope();
         MockProver::run(k, &circuit, vec![]).unwrap().assert_satisfied();
     }
 }
@@ -315,6 +323,7 @@ where
     pub break_points: RefCell<RlcThreadBreakPoints>, // `RefCell` allows the circuit to record break points in a keygen call of `synthesize` for use in later witness gen
     // we guarantee that `synthesize_phase1` is called *exactly once* during the proving stage, but since `Circuit::synthesize` takes `&self`, and `assign_region` takes a `Fn` instead of `FnOnce`, we need some extra engineering:
     pub synthesize_phase1: RefCell<Option<FnPhase1>>,
+    pub config_params: RefCell<EthConfigParams>,
 }
 
 impl<F: ScalarField, FnPhase1> RlcCircuitBuilder<F, FnPhase1>
@@ -330,6 +339,7 @@ where
             builder: RefCell::new(builder),
             break_points: RefCell::new(break_points.unwrap_or_default()),
             synthesize_phase1: RefCell::new(Some(synthesize_phase1)),
+            config_params: RefCell::new(EthConfigParams::default()),
         }
     }
 
@@ -338,7 +348,13 @@ where
         let mut builder = self.builder.borrow().clone();
         let f = self.synthesize_phase1.borrow().clone().expect("synthesize_phase1 should exist");
         f(&mut builder, &RlcChip::new(F::zero()));
-        builder.config(k, minimum_rows)
+        let params = builder.config(k, minimum_rows);
+        *self.config_params.borrow_mut() = params.clone();
+        params
+    }
+
+    pub fn config_scope(&self) -> CircuitConfigScope {
+        CircuitConfigScope::enter(CircuitConfig::Eth(self.config_params.borrow().clone()))
     }
 
     // re-usable function for synthesize
@@ -458,7 +474,7 @@ where
             unusable_rows: _,
             keccak_rows_per_round: _,
             lookup_bits: _,
-        } = serde_json::from_str(&std::env::var("ETH_CONFIG_PARAMS").unwrap()).unwrap();
+        } = eth_config_params();
         let mut gate = FlexGateConfig::configure(
             meta,
             GateStrategy::Vertical,
@@ -502,6 +518,10 @@ where
     pub fn config(&self, k: usize, minimum_rows: Option<usize>) -> EthConfigParams {
         self.0.config(k, minimum_rows)
     }
+
+    pub fn config_scope(&self) -> CircuitConfigScope {
+        self.0.config_scope()
+    }
 }
 
 impl<F: ScalarField, FnPhase1> Circuit<F> for RlpCircuitBuilder<F, FnPhase1>
@@ -524,16 +544,15 @@ where
             num_fixed,
             unusable_rows: _,
             keccak_rows_per_round: _,
-            lookup_bits: _,
-        } = serde_json::from_str(&std::env::var("ETH_CONFIG_PARAMS").unwrap()).unwrap();
-        let lookup_bits = std::env::var("LOOKUP_BITS").unwrap().parse().unwrap();
+            lookup_bits,
+        } = eth_config_params();
         RlpConfig::configure(
             meta,
             num_rlc_columns,
             &num_range_advice,
             &num_lookup_advice,
             num_fixed,
-            lookup_bits,
+            lookup_bits.unwrap_or(ETH_LOOKUP_BITS),
             degree as usize,
         )
     }
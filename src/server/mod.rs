@@ -1,19 +1,36 @@
 pub mod server;
+#[cfg(test)]
+mod tests;
 
+use crate::arbitration::types::ProofInput;
 use crate::config::api::get_internal_api;
-use crate::db::ChallengesStorage;
+use crate::db::{ChallengesStorage, JobFailure, JobStatus, ProofJob, ProofJobQueue};
 use ethers_core::types::H256;
 use hyper::Method;
-use jsonrpsee::server::{RpcModule, Server};
+use jsonrpsee::core::Error;
+use jsonrpsee::server::{RpcModule, Server, ServerHandle};
+use jsonrpsee::types::error::INVALID_PARAMS_CODE;
+use jsonrpsee::types::{ErrorObject, ErrorObjectOwned};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::fmt::Display;
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use tokio::signal::ctrl_c;
 use tokio::sync::mpsc::UnboundedSender;
 use tower_http::cors::{Any, CorsLayer};
 
-#[derive(Clone, Debug, Deserialize)]
+/// No job was received for the task id.
+pub const JOB_NOT_FOUND_CODE: i32 = -32001;
+/// The job is already finished, it can't be cancelled.
+pub const JOB_FINISHED_CODE: i32 = -32002;
+/// Reading or writing RocksDB failed.
+pub const STORAGE_ERROR_CODE: i32 = -32003;
+/// The job is stored but the prover is not receiving jobs anymore.
+pub const QUEUE_ERROR_CODE: i32 = -32004;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OriginalProof {
     pub task_id: H256,
     pub proof: String,
@@ -36,73 +53,229 @@ pub struct Response {
     pub status: u64,
 }
 
-pub async fn init_server(
-    tx: UnboundedSender<OriginalProof>,
-    challenges_storage: Arc<Mutex<ChallengesStorage>>,
-) -> std::io::Result<()> {
-    let tx = Arc::new(tx);
-    let cors = CorsLayer::new()
-        // Allow `POST` when accessing the resource
-        .allow_methods([Method::POST])
-        // Allow requests from any origin
-        .allow_origin(Any)
-        .allow_headers([hyper::header::CONTENT_TYPE]);
-    let middleware = tower::ServiceBuilder::new().layer(cors);
+#[derive(Clone, Debug, Deserialize)]
+pub struct QueryJob {
+    pub task_id: H256,
+}
 
-    let server = Server::builder()
-        .set_middleware(middleware)
-        .build(get_internal_api().parse::<SocketAddr>().unwrap())
-        .await
-        .unwrap();
-    let mut module = RpcModule::new(());
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct QueryJobs {
+    /// Only the jobs with this status, all of them if omitted.
+    #[serde(default)]
+    pub status: Option<JobStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StageProgress {
+    pub name: String,
+    pub done: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobSummary {
+    pub task_id: H256,
+    pub status: JobStatus,
+    pub retries: u32,
+    pub enqueued_at: u128,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobOutput {
+    pub task_id: H256,
+    pub status: JobStatus,
+    pub retries: u32,
+    pub enqueued_at: u128,
+    pub failures: Vec<JobFailure>,
+    /// Empty until the job has started.
+    pub progress: Vec<StageProgress>,
+}
+
+/// Whether the snark (or, for the final task, the calldata) of every stage is in `data_dir`.
+///
+/// The data directory is cleared before each job, so this only describes the job being proven.
+pub fn get_stage_progress(data_dir: &Path, stages: &[String]) -> Vec<StageProgress> {
+    stages
+        .iter()
+        .map(|name| StageProgress {
+            name: name.clone(),
+            done: data_dir.join(format!("{name}.snark")).exists()
+                || data_dir.join(format!("{name}.calldata")).exists(),
+        })
+        .collect()
+}
+
+impl JobSummary {
+    pub fn new(job: &ProofJob) -> Self {
+        Self {
+            task_id: job.task_id,
+            status: job.status,
+            retries: job.retries,
+            enqueued_at: job.enqueued_at,
+        }
+    }
+}
+
+impl JobOutput {
+    pub fn new(job: ProofJob, data_dir: &Path) -> Self {
+        let progress = match job.status {
+            JobStatus::Running => get_stage_progress(data_dir, &job.stages),
+            JobStatus::Done => job
+                .stages
+                .iter()
+                .map(|name| StageProgress { name: name.clone(), done: true })
+                .collect(),
+            _ => job
+                .stages
+                .iter()
+                .map(|name| StageProgress { name: name.clone(), done: false })
+                .collect(),
+        };
+        Self {
+            task_id: job.task_id,
+            status: job.status,
+            retries: job.retries,
+            enqueued_at: job.enqueued_at,
+            failures: job.failures,
+            progress,
+        }
+    }
+}
+
+/// Everything the RPC methods need, shared with the prover of `src/bin/services.rs`.
+pub struct ServerContext {
+    pub tx: UnboundedSender<OriginalProof>,
+    pub challenges_storage: Arc<Mutex<ChallengesStorage>>,
+    pub job_queue: Arc<Mutex<ProofJobQueue>>,
+    /// Where the prover writes the snarks of the running job.
+    pub data_dir: PathBuf,
+}
+
+fn storage_error(err: impl Display) -> ErrorObjectOwned {
+    ErrorObject::owned(STORAGE_ERROR_CODE, format!("storage error: {err}"), None::<()>)
+}
+
+fn job_not_found(task_id: H256) -> ErrorObjectOwned {
+    ErrorObject::owned(JOB_NOT_FOUND_CODE, format!("job {task_id:?} not found"), None::<()>)
+}
+
+fn serialize_response(response: &impl Serialize) -> Value {
+    Value::String(serde_json::to_string(response).expect("serialize response should succeed"))
+}
+
+pub fn rpc_module(context: ServerContext) -> RpcModule<ServerContext> {
+    let mut module = RpcModule::new(context);
 
     module
-        .register_method("generate_proof", move |params, c| {
-            let tx = tx.clone();
-            let original_proof: OriginalProof = params.parse().unwrap();
-            tokio::spawn(async move {
-                tx.send(original_proof).unwrap();
-            });
-
-            let response = Response { status: 200 };
-            let serialized = serde_json::to_string(&response).unwrap();
-            Value::String(serialized)
+        .register_method("generate_proof", |params, c| {
+            let original_proof: OriginalProof = params.parse()?;
+            ProofInput::from_request_json(&original_proof.proof).map_err(|err| {
+                ErrorObject::owned(INVALID_PARAMS_CODE, err.to_string(), None::<()>)
+            })?;
+            // the job is persisted before it is queued, so it is resumed if the services restart
+            c.job_queue.lock().unwrap().enqueue(&original_proof).map_err(storage_error)?;
+            c.tx.send(original_proof)
+                .map_err(|err| ErrorObject::owned(QUEUE_ERROR_CODE, err.to_string(), None::<()>))?;
+            Ok::<_, ErrorObjectOwned>(serialize_response(&Response { status: 200 }))
         })
         .unwrap();
 
     module
-        .register_method("get_challenge_proof", move |params, c| {
-            let challenges_storage = challenges_storage.clone();
-            let mut storage = challenges_storage.lock().unwrap();
-
-            let query_challenge: QueryChallenge = params.parse().unwrap();
-            let challenge_proof = storage.get_proof_by_challenge_id(query_challenge.challenge_id);
-            let mut status = 0;
-            let mut proof = None;
-            match challenge_proof {
-                Ok(Some(value)) => {
-                    if value.is_empty() {
-                        status = 2;
-                    } else {
-                        status = 1;
-                        proof = Some(String::from_utf8(value).unwrap());
-                    }
-                }
-                Ok(None) => status = 0,
-                Err(e) => println!("operational problem encountered: {}", e),
-            }
+        .register_method("get_challenge_proof", |params, c| {
+            let query_challenge: QueryChallenge = params.parse()?;
+            let challenge_proof = c
+                .challenges_storage
+                .lock()
+                .unwrap()
+                .get_proof_by_challenge_id(query_challenge.challenge_id)
+                .map_err(storage_error)?;
+            let (status, proof) = match challenge_proof {
+                Some(value) if value.is_empty() => (2, None),
+                Some(value) => (1, Some(String::from_utf8_lossy(&value).into_owned())),
+                None => (0, None),
+            };
             let response =
                 QueryChallengeOutput { challenge_id: query_challenge.challenge_id, proof, status };
-            let serialized = serde_json::to_string(&response).unwrap();
-            Value::String(serialized)
+            Ok::<_, ErrorObjectOwned>(serialize_response(&response))
         })
         .unwrap();
 
-    let addr = server.local_addr().unwrap();
+    module
+        .register_method("list_jobs", |params, c| {
+            let query = params.parse::<Option<QueryJobs>>()?.unwrap_or_default();
+            let jobs = c.job_queue.lock().unwrap().jobs().map_err(storage_error)?;
+            let summaries = jobs
+                .iter()
+                .filter(|job| query.status.map_or(true, |status| job.status == status))
+                .map(JobSummary::new)
+                .collect::<Vec<_>>();
+            Ok::<_, ErrorObjectOwned>(serialize_response(&summaries))
+        })
+        .unwrap();
 
-    info!(target: "app","Spv Pool server listening on {:?}",addr.to_string());
+    module
+        .register_method("get_job", |params, c| {
+            let query: QueryJob = params.parse()?;
+            let job = c
+                .job_queue
+                .lock()
+                .unwrap()
+                .get_job(query.task_id)
+                .map_err(storage_error)?
+                .ok_or_else(|| job_not_found(query.task_id))?;
+            Ok::<_, ErrorObjectOwned>(serialize_response(&JobOutput::new(job, &c.data_dir)))
+        })
+        .unwrap();
+
+    // discards the job: a pending job is skipped, a running proof is not interrupted but thrown away
+    module
+        .register_method("cancel_job", |params, c| {
+            let query: QueryJob = params.parse()?;
+            let job_queue = c.job_queue.lock().unwrap();
+            let status = job_queue
+                .get_job(query.task_id)
+                .map_err(storage_error)?
+                .ok_or_else(|| job_not_found(query.task_id))?
+                .status;
+            if status.is_finished() {
+                return Err(ErrorObject::owned(
+                    JOB_FINISHED_CODE,
+                    format!("job {:?} is already {status:?}", query.task_id),
+                    None::<()>,
+                ));
+            }
+            let job = job_queue
+                .cancel(query.task_id)
+                .map_err(storage_error)?
+                .ok_or_else(|| job_not_found(query.task_id))?;
+            Ok(serialize_response(&JobOutput::new(job, &c.data_dir)))
+        })
+        .unwrap();
+
+    module
+}
 
-    let handle = server.start(module);
+pub async fn start_server(
+    addr: SocketAddr,
+    module: RpcModule<ServerContext>,
+) -> Result<(SocketAddr, ServerHandle), Error> {
+    let cors = CorsLayer::new()
+        // Allow `POST` when accessing the resource
+        .allow_methods([Method::POST])
+        // Allow requests from any origin
+        .allow_origin(Any)
+        .allow_headers([hyper::header::CONTENT_TYPE]);
+    let middleware = tower::ServiceBuilder::new().layer(cors);
+
+    let server = Server::builder().set_middleware(middleware).build(addr).await?;
+    let addr = server.local_addr()?;
+    Ok((addr, server.start(module)))
+}
+
+pub async fn init_server(context: ServerContext) -> Result<(), Error> {
+    let addr = get_internal_api().parse::<SocketAddr>().unwrap();
+    let (addr, handle) = start_server(addr, rpc_module(context)).await?;
+
+    info!(target: "app","Spv Pool server listening on {:?}",addr.to_string());
 
     tokio::select! {
         _ = ctrl_c() => println!("receive Ctrl C"),
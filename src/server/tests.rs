@@ -0,0 +1,282 @@
+use crate::db::{ChallengesStorage, JobFailure, JobStatus, ProofJobQueue};
+use crate::server::{
+    rpc_module, start_server, JobOutput, JobSummary, OriginalProof, QueryChallengeOutput,
+    ServerContext, StageProgress, JOB_FINISHED_CODE, JOB_NOT_FOUND_CODE,
+};
+use ethers_core::types::H256;
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::params::ObjectParams;
+use jsonrpsee::core::Error;
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use jsonrpsee::server::ServerHandle;
+use jsonrpsee::types::error::INVALID_PARAMS_CODE;
+use jsonrpsee::types::ErrorObjectOwned;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::env::temp_dir;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+struct TestServer {
+    client: HttpClient,
+    challenges_storage: Arc<Mutex<ChallengesStorage>>,
+    job_queue: Arc<Mutex<ProofJobQueue>>,
+    rx: UnboundedReceiver<OriginalProof>,
+    data_dir: PathBuf,
+    handle: ServerHandle,
+    dir: PathBuf,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.handle.stop();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Runs the server on a free local port, with RocksDB and the data directory in a temporary directory.
+async fn start_test_server(name: &str) -> TestServer {
+    let dir = temp_dir().join(format!("zkspv_server_{name}_{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let data_dir = dir.join("data");
+    fs::create_dir_all(&data_dir).unwrap();
+
+    let (tx, rx) = unbounded_channel();
+    let challenges_storage = Arc::new(Mutex::new(ChallengesStorage::open(dir.join("challenges"))));
+    let job_queue = Arc::new(Mutex::new(ProofJobQueue::open(dir.join("jobs"))));
+    let context = ServerContext {
+        tx,
+        challenges_storage: challenges_storage.clone(),
+        job_queue: job_queue.clone(),
+        data_dir: data_dir.clone(),
+    };
+    let (addr, handle) =
+        start_server("127.0.0.1:0".parse().unwrap(), rpc_module(context)).await.unwrap();
+    let client = HttpClientBuilder::default().build(format!("http://{addr}")).unwrap();
+    TestServer { client, challenges_storage, job_queue, rx, data_dir, handle, dir }
+}
+
+fn params(entries: &[(&str, Value)]) -> ObjectParams {
+    let mut params = ObjectParams::new();
+    for (name, value) in entries {
+        params.insert(name, value).unwrap();
+    }
+    params
+}
+
+/// Every response is a serialized json string.
+async fn call<T: DeserializeOwned>(
+    client: &HttpClient,
+    method: &str,
+    params: ObjectParams,
+) -> Result<T, ErrorObjectOwned> {
+    match client.request::<String, _>(method, params).await {
+        Ok(response) => Ok(serde_json::from_str(&response).unwrap()),
+        Err(Error::Call(err)) => Err(err),
+        Err(err) => panic!("unexpected client error: {err}"),
+    }
+}
+
+fn task_id(id: u64) -> H256 {
+    H256::from_low_u64_be(id)
+}
+
+fn test_proof() -> String {
+    fs::read_to_string("test_data/from_ethereum_to_zksync_era_source.json").unwrap()
+}
+
+async fn generate_proof(server: &TestServer, task_id: H256) {
+    let response: Value = call(
+        &server.client,
+        "generate_proof",
+        params(&[("task_id", json!(task_id)), ("proof", json!(test_proof()))]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response["status"], 200);
+}
+
+fn stages(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_generate_proof_and_list_jobs() {
+    let mut server = start_test_server("list_jobs").await;
+    generate_proof(&server, task_id(1)).await;
+    generate_proof(&server, task_id(2)).await;
+
+    // the jobs are persisted and handed to the prover in order
+    assert_eq!(server.rx.recv().await.unwrap().task_id, task_id(1));
+    assert_eq!(server.rx.recv().await.unwrap().task_id, task_id(2));
+    server.job_queue.lock().unwrap().mark_running(task_id(1)).unwrap();
+
+    let jobs: Vec<JobSummary> = call(&server.client, "list_jobs", params(&[])).await.unwrap();
+    assert_eq!(
+        jobs.iter().map(|job| (job.task_id, job.status)).collect::<Vec<_>>(),
+        [(task_id(1), JobStatus::Running), (task_id(2), JobStatus::Pending)]
+    );
+
+    let jobs: Vec<JobSummary> =
+        call(&server.client, "list_jobs", params(&[("status", json!(JobStatus::Pending))]))
+            .await
+            .unwrap();
+    assert_eq!(jobs.iter().map(|job| job.task_id).collect::<Vec<_>>(), [task_id(2)]);
+}
+
+#[tokio::test]
+async fn test_generate_proof_malformed_request() {
+    let mut server = start_test_server("malformed_request").await;
+    let proof = test_proof().replace("\"isSource\"", "\"isSourceTypo\"");
+    let err = call::<Value>(
+        &server.client,
+        "generate_proof",
+        params(&[("task_id", json!(task_id(1))), ("proof", json!(proof))]),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), INVALID_PARAMS_CODE);
+    assert!(err.message().contains("isSource"), "{}", err.message());
+
+    // rejected before it is queued
+    assert!(server.job_queue.lock().unwrap().get_job(task_id(1)).unwrap().is_none());
+    assert!(server.rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_get_job_progress_and_failures() {
+    let server = start_test_server("get_job").await;
+    generate_proof(&server, task_id(1)).await;
+
+    let job: JobOutput =
+        call(&server.client, "get_job", params(&[("task_id", json!(task_id(1)))])).await.unwrap();
+    assert_eq!(job.status, JobStatus::Pending);
+    assert!(job.progress.is_empty());
+
+    {
+        let job_queue = server.job_queue.lock().unwrap();
+        job_queue.mark_running(task_id(1)).unwrap();
+        job_queue.set_stages(task_id(1), stages(&["tx", "mdc", "final"])).unwrap();
+    }
+    fs::write(server.data_dir.join("tx.snark"), b"").unwrap();
+    let job: JobOutput =
+        call(&server.client, "get_job", params(&[("task_id", json!(task_id(1)))])).await.unwrap();
+    assert_eq!(
+        job.progress,
+        [
+            StageProgress { name: "tx".to_string(), done: true },
+            StageProgress { name: "mdc".to_string(), done: false },
+            StageProgress { name: "final".to_string(), done: false },
+        ]
+    );
+
+    let failure = JobFailure { stage: Some("mdc".to_string()), message: "rpc timeout".to_string() };
+    server.job_queue.lock().unwrap().mark_failed(task_id(1), failure.clone()).unwrap();
+    let job: JobOutput =
+        call(&server.client, "get_job", params(&[("task_id", json!(task_id(1)))])).await.unwrap();
+    // retried, the progress of another attempt is not reported
+    assert_eq!((job.status, job.retries), (JobStatus::Pending, 1));
+    assert_eq!(job.failures, [failure]);
+    assert!(job.progress.iter().all(|stage| !stage.done));
+
+    let err =
+        call::<JobOutput>(&server.client, "get_job", params(&[("task_id", json!(task_id(2)))]))
+            .await
+            .unwrap_err();
+    assert_eq!(err.code(), JOB_NOT_FOUND_CODE);
+}
+
+#[tokio::test]
+async fn test_cancel_job() {
+    let server = start_test_server("cancel_job").await;
+    generate_proof(&server, task_id(1)).await;
+    generate_proof(&server, task_id(2)).await;
+
+    let job: JobOutput =
+        call(&server.client, "cancel_job", params(&[("task_id", json!(task_id(1)))]))
+            .await
+            .unwrap();
+    assert_eq!(job.status, JobStatus::Cancelled);
+    let err =
+        call::<JobOutput>(&server.client, "cancel_job", params(&[("task_id", json!(task_id(1)))]))
+            .await
+            .unwrap_err();
+    assert_eq!(err.code(), JOB_FINISHED_CODE);
+
+    // the running job is cancelled once its attempt ends, whatever the outcome
+    server.job_queue.lock().unwrap().mark_running(task_id(2)).unwrap();
+    let job: JobOutput =
+        call(&server.client, "cancel_job", params(&[("task_id", json!(task_id(2)))]))
+            .await
+            .unwrap();
+    assert_eq!(job.status, JobStatus::Cancelled);
+    let job = server.job_queue.lock().unwrap().mark_done(task_id(2)).unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Cancelled);
+
+    let err =
+        call::<JobOutput>(&server.client, "cancel_job", params(&[("task_id", json!(task_id(3)))]))
+            .await
+            .unwrap_err();
+    assert_eq!(err.code(), JOB_NOT_FOUND_CODE);
+
+    // cancelled jobs are not resumed
+    assert!(server.job_queue.lock().unwrap().recover().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_get_challenge_proof() {
+    let server = start_test_server("challenge_proof").await;
+    {
+        let storage = server.challenges_storage.lock().unwrap();
+        storage.storage_challenge_proof(task_id(1), "0x1234".to_string()).unwrap();
+        storage.storage_challenge_proof(task_id(2), "".to_string()).unwrap();
+    }
+
+    for (id, status, proof) in [(1, 1, Some("0x1234")), (2, 2, None), (3, 0, None)] {
+        let output: QueryChallengeOutput = call(
+            &server.client,
+            "get_challenge_proof",
+            params(&[("challenge_id", json!(task_id(id)))]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(output.status, status);
+        assert_eq!(output.proof, proof.map(String::from));
+    }
+
+    // malformed params are reported instead of panicking the server
+    let err = call::<QueryChallengeOutput>(
+        &server.client,
+        "get_challenge_proof",
+        params(&[("challenge_id", json!("not a hash"))]),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), INVALID_PARAMS_CODE);
+}
+
+#[test]
+fn test_recover_interrupted_jobs() {
+    let dir = temp_dir().join(format!("zkspv_server_recover_{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    {
+        let job_queue = ProofJobQueue::open(&dir);
+        for id in 1..=4 {
+            job_queue.enqueue(&OriginalProof { task_id: task_id(id), proof: "{}".into() }).unwrap();
+        }
+        job_queue.mark_running(task_id(1)).unwrap();
+        job_queue.mark_done(task_id(2)).unwrap();
+        job_queue.cancel(task_id(3)).unwrap();
+    }
+
+    // reopened as after a restart
+    let job_queue = ProofJobQueue::open(&dir);
+    let jobs = job_queue.recover().unwrap();
+    assert_eq!(jobs.iter().map(|job| job.task_id).collect::<Vec<_>>(), [task_id(1), task_id(4)]);
+    assert!(jobs.iter().all(|job| job.status == JobStatus::Pending));
+    drop(job_queue);
+    fs::remove_dir_all(&dir).unwrap();
+}
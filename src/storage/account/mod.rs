@@ -0,0 +1,160 @@
+//! Proves the state of an account at a block: nonce, balance, storage root and code hash, e.g. the ETH balance of a
+//! maker or that an address is a contract.
+
+use std::{cell::RefCell, str::FromStr};
+
+use ethers_core::types::{Address, H256, U256};
+#[cfg(feature = "providers")]
+use ethers_providers::Provider;
+use halo2_base::{gates::RangeChip, halo2_proofs::halo2curves::bn256::Fr};
+use itertools::Itertools;
+use rlp::Rlp;
+
+use crate::block_header::{get_block_header_config, BlockHeaderConfig};
+#[cfg(feature = "providers")]
+use crate::providers::get_storage_input;
+#[cfg(feature = "providers")]
+use crate::util::{helpers::get_provider, rpc_client::RpcClient};
+use crate::{
+    keccak::{FixedLenRLCs, FnSynthesize, KeccakChip, VarLenRLCs},
+    rlp::{
+        builder::{RlcThreadBreakPoints, RlcThreadBuilder},
+        rlc::FIRST_PHASE,
+        RlpChip,
+    },
+    storage::util::STORAGE_PF_MAX_DEPTH,
+    util::{encode_addr_to_field, encode_h256_to_field},
+    EthChip, EthCircuitBuilder, EthPreCircuit, Field, Network, ETH_LOOKUP_BITS,
+};
+
+use super::{
+    EIP1186ResponseDigest, EthAccountFields, EthBlockStorageInput, EthStorageChip, EthStorageInput,
+    EMPTY_CODE_HASH, EMPTY_STORAGE_ROOT,
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug)]
+pub struct AccountConstructor {
+    pub block_number: u32,
+    pub address: Address,
+    pub acct_pf_max_depth: usize,
+    pub network: Network,
+}
+
+#[cfg(feature = "providers")]
+pub fn get_account_circuit(constructor: AccountConstructor) -> EthBlockAccountCircuit {
+    let provider = get_provider(&constructor.network);
+    EthBlockAccountCircuit::from_provider(&provider, constructor)
+}
+
+/// The account proof of an [`EthBlockStorageCircuit`](super::EthBlockStorageCircuit) without slots, exposing the
+/// account fields instead.
+#[derive(Clone, Debug)]
+pub struct EthBlockAccountCircuit {
+    pub inputs: EthBlockStorageInput,
+    pub block_header_config: BlockHeaderConfig,
+}
+
+impl EthBlockAccountCircuit {
+    #[cfg(feature = "providers")]
+    pub fn from_provider(provider: &Provider<RpcClient>, constructor: AccountConstructor) -> Self {
+        let inputs = get_storage_input(
+            provider,
+            constructor.block_number,
+            constructor.address,
+            vec![],
+            constructor.acct_pf_max_depth,
+            STORAGE_PF_MAX_DEPTH,
+        );
+        let block_header_config = get_block_header_config(&constructor.network);
+        Self { inputs, block_header_config }
+    }
+
+    /// The fields of the account, those of an empty account if it doesn't exist.
+    pub fn account(&self) -> (U256, U256, H256, H256) {
+        let acct_pf = &self.inputs.storage.acct_pf;
+        if acct_pf.slot_is_empty {
+            return (
+                U256::zero(),
+                U256::zero(),
+                H256::from_str(EMPTY_STORAGE_ROOT).unwrap(),
+                H256::from_str(EMPTY_CODE_HASH).unwrap(),
+            );
+        }
+        let rlp = Rlp::new(&acct_pf.value);
+        (
+            rlp.val_at(0).unwrap(),
+            rlp.val_at(1).unwrap(),
+            rlp.val_at(2).unwrap(),
+            rlp.val_at(3).unwrap(),
+        )
+    }
+
+    // blockHash, blockNumber, address, nonce, balance, storageRoot, codeHash
+    // with H256 encoded as hi-lo (u128, u128)
+    pub fn instance<F: Field>(&self) -> Vec<F> {
+        let EthBlockStorageInput { block_number, block_hash, storage, .. } = &self.inputs;
+        let EthStorageInput { addr, .. } = storage;
+        let (nonce, balance, storage_root, code_hash) = self.account();
+        let mut instance = Vec::with_capacity(10);
+        instance.extend(encode_h256_to_field::<F>(block_hash));
+        instance.push(F::from(*block_number as u64));
+        instance.push(encode_addr_to_field(addr));
+        instance.push(F::from(nonce.as_u64()));
+        instance.push(F::from_u128(balance.as_u128()));
+        instance.extend(encode_h256_to_field::<F>(&storage_root));
+        instance.extend(encode_h256_to_field::<F>(&code_hash));
+        instance
+    }
+}
+
+impl EthPreCircuit for EthBlockAccountCircuit {
+    fn create(
+        self,
+        mut builder: RlcThreadBuilder<Fr>,
+        break_points: Option<RlcThreadBreakPoints>,
+    ) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
+        assert!(self.inputs.storage.storage_pfs.is_empty(), "the account circuit proves no slots");
+        let range = RangeChip::default(ETH_LOOKUP_BITS);
+        let chip = EthChip::new(RlpChip::new(&range, None), None);
+        let mut keccak = KeccakChip::default();
+
+        // ================= FIRST PHASE ================
+        let ctx = builder.gate_builder.main(FIRST_PHASE);
+        let input = self.inputs.assign(ctx);
+        let (witness, digest) = chip.parse_eip1186_proofs_from_block_phase0(
+            &mut builder.gate_builder,
+            &mut keccak,
+            input,
+            &self.block_header_config,
+        );
+        let EIP1186ResponseDigest { block_hash, block_number, address, .. } = digest;
+
+        let ctx = builder.gate_builder.main(FIRST_PHASE);
+        let EthAccountFields { nonce, balance, storage_root, code_hash } =
+            chip.parse_account_fields_phase0(ctx, &witness.acct_witness);
+        let assigned_instances = block_hash
+            .into_iter()
+            .chain([block_number, address, nonce, balance])
+            .chain(storage_root)
+            .chain(code_hash)
+            .collect_vec();
+
+        EthCircuitBuilder::new(
+            assigned_instances,
+            builder,
+            RefCell::new(keccak),
+            range,
+            break_points,
+            move |builder: &mut RlcThreadBuilder<Fr>,
+                  rlp: RlpChip<Fr>,
+                  keccak_rlcs: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {
+                // ======== SECOND PHASE ===========
+                let chip = EthChip::new(rlp, Some(keccak_rlcs));
+                let _trace = chip.parse_eip1186_proofs_from_block_phase1(builder, witness);
+            },
+        )
+    }
+}
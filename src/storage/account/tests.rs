@@ -0,0 +1,73 @@
+use halo2_base::halo2_proofs::dev::MockProver;
+use test_log::test;
+
+use super::*;
+use crate::{
+    storage::{
+        tests::{fixture_block_storage_input, read_proof_fixture},
+        util::ACCOUNT_PF_MAX_DEPTH,
+    },
+    util::EthConfigParams,
+    EthereumNetwork,
+};
+
+/// The account proof of a `scripts/input_gen` fixture, see [`fixture_block_storage_input`].
+fn get_fixture_circuit(name: &str) -> EthBlockAccountCircuit {
+    let mut pf = read_proof_fixture(name);
+    pf.storage_proof.clear();
+    let inputs = fixture_block_storage_input(pf);
+    let network = Network::Ethereum(EthereumNetwork::Mainnet);
+    EthBlockAccountCircuit { inputs, block_header_config: get_block_header_config(&network) }
+}
+
+fn account_mock(input: EthBlockAccountCircuit) {
+    let params = EthConfigParams::from_path("configs/tests/storage.json");
+    let k = params.degree;
+    let instance = input.instance();
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    assert_eq!(circuit.instance(), instance);
+    MockProver::run(k, &circuit, vec![instance]).unwrap().assert_satisfied();
+}
+
+#[test]
+pub fn test_account_mock() {
+    let input = get_fixture_circuit("default_storage_pf");
+    let (nonce, balance, storage_root, code_hash) = input.account();
+    assert_eq!((nonce, balance), (U256::one(), U256::zero()));
+    assert_eq!(
+        storage_root,
+        H256::from_str("0x57a2a1260d6af839abe3d4dbf62c48ca62e828f80fdbd9ff251ffeffa3480afb")
+            .unwrap()
+    );
+    // a contract
+    assert_ne!(code_hash, H256::from_str(EMPTY_CODE_HASH).unwrap());
+    account_mock(input);
+}
+
+#[test]
+pub fn test_account_with_balance_mock() {
+    let input = get_fixture_circuit("noninclusion_extension_pf");
+    assert_eq!(input.account().1, U256::from_str("0x12df5f56180f1e41a90").unwrap());
+    account_mock(input);
+}
+
+#[test]
+pub fn test_nonexistent_account_mock() {
+    let input = get_fixture_circuit("empty_storage_pf");
+    assert!(input.inputs.storage.acct_pf.slot_is_empty);
+    account_mock(input);
+}
+
+#[test]
+#[ignore = "fetches the account proof from a mainnet RPC"]
+pub fn test_mainnet_account_mock() {
+    // WETH
+    let constructor = AccountConstructor {
+        block_number: 17_000_000,
+        address: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".parse().unwrap(),
+        acct_pf_max_depth: ACCOUNT_PF_MAX_DEPTH,
+        network: Network::Ethereum(EthereumNetwork::Mainnet),
+    };
+    account_mock(get_account_circuit(constructor));
+}
@@ -1,3 +1,4 @@
+#[cfg(test)]
 mod tests;
 
 pub mod util;
@@ -17,12 +18,14 @@ use crate::storage::{
     EthAccountTrace, EthAccountTraceWitness, EthStorageChip, EthStorageInput,
     EthStorageInputAssigned, EthStorageTrace, EthStorageTraceWitness,
 };
+use crate::util::rpc_client::RpcClient;
 use crate::util::{
-    bytes_be_to_u128, bytes_be_to_uint, bytes_be_var_to_fixed, uint_to_bytes_be, AssignedH256,
+    bytes_be_to_u128, bytes_be_to_uint, bytes_be_var_to_fixed, encode_h256_to_field,
+    uint_to_bytes_be, AssignedH256, H256_INSTANCES_LEN,
 };
 use crate::{EthChip, EthCircuitBuilder, EthPreCircuit, ETH_LOOKUP_BITS};
 use ethers_core::types::{Block, H256};
-use ethers_providers::{Http, Provider, RetryClient};
+use ethers_providers::Provider;
 use futures::{AsyncReadExt, FutureExt};
 use halo2_base::gates::RangeInstructions;
 use halo2_base::QuantumCell::Constant;
@@ -42,6 +45,12 @@ const EBC_RULE_FIELDS_NUM: usize = 18;
 const EBC_RULE_FIELDS_MAX_FIELDS_LEN: [usize; EBC_RULE_FIELDS_NUM] =
     [8, 8, 1, 1, 32, 32, 16, 16, 16, 16, 16, 16, 4, 4, 4, 4, 4, 4];
 pub(crate) const EBC_RULE_PROOF_VALUE_MAX_BYTE_LEN: usize = 140;
+/// The contracts are read at the blocks of the current and next rules.
+pub const OB_CONTRACTS_STORAGE_BLOCKS_NUM: usize = 2;
+/// The instances end with `[block_hash, block_number]` of every block.
+pub const OB_CONTRACTS_STORAGE_BLOCK_INSTANCES_LEN: usize = H256_INSTANCES_LEN + 1;
+/// The instances start with the MDC address, followed by the manager address.
+pub const OB_CONTRACTS_STORAGE_MDC_ADDRESS_INSTANCE_OFFSET: usize = 0;
 
 #[derive(Clone, Debug)]
 pub struct BlockInput {
@@ -127,13 +136,33 @@ pub struct ObContractsStorageCircuit {
 impl ObContractsStorageCircuit {
     #[cfg(feature = "providers")]
     pub fn from_provider(
-        provider: &Provider<RetryClient<Http>>,
+        provider: &Provider<RpcClient>,
         constructor: MultiBlocksContractsStorageConstructor,
     ) -> Self {
         let inputs = get_contract_storage_input(provider, constructor.clone());
         let block_header_config = get_block_header_config(&constructor.network);
         Self { inputs, block_header_config }
     }
+
+    /// The last instances, `[block_hash, block_number]` of every block the contracts are read at.
+    pub fn block_instances<F: Field>(&self) -> Vec<F> {
+        block_instances(
+            self.inputs
+                .contract_storage_block
+                .iter()
+                .map(|(block, _)| (block.block_hash, block.block_number as u64)),
+        )
+    }
+}
+
+/// `[block_hash, block_number]` of every block, see [`OB_CONTRACTS_STORAGE_BLOCK_INSTANCES_LEN`].
+pub fn block_instances<F: Field>(blocks: impl IntoIterator<Item = (H256, u64)>) -> Vec<F> {
+    blocks
+        .into_iter()
+        .flat_map(|(block_hash, block_number)| {
+            encode_h256_to_field::<F>(&block_hash).into_iter().chain([F::from(block_number)])
+        })
+        .collect()
 }
 
 impl EthPreCircuit for ObContractsStorageCircuit {
@@ -217,20 +246,24 @@ impl EthPreCircuit for ObContractsStorageCircuit {
             .chain(slots_key.into_iter().flat_map(|slot| slot.into_iter()))
             .chain(slots_value.into_iter().flat_map(|value| value))
             .chain(digests.ebc_rule_hash.clone().into_iter())
-            // Todo: Since the current track block circuit and this circuit are separated, it is necessary to compare the blockhash temporarily.
+            .chain(digests.ebc_rule_fields.clone().into_iter())
+            // The final assembly checks these blocks against the block merkle inclusion and the transaction block.
             .chain(
                 digests
                     .multi_blocks_contracts_digest
                     .clone()
                     .into_iter()
-                    .flat_map(|d| d.block_hash.into_iter()),
+                    .flat_map(|d| d.block_hash.into_iter().chain([d.block_number])),
             )
             .collect();
 
         // For now this circuit is going to constrain that all slots are occupied. We can also create a circuit that exposes the bitmap of slot_is_empty
         {
             let ctx = builder.gate_builder.main(FIRST_PHASE);
-            assert_eq!(digests.multi_blocks_contracts_digest.len(), 2);
+            assert_eq!(
+                digests.multi_blocks_contracts_digest.len(),
+                OB_CONTRACTS_STORAGE_BLOCKS_NUM
+            );
 
             for (current_single_block_contracts_digest, next_single_block_contracts_digest) in
                 digests
@@ -246,6 +279,12 @@ impl EthPreCircuit for ObContractsStorageCircuit {
                     8 * 4,
                 );
 
+                // The next rule is read from the same MDC contract as the current one.
+                ctx.constrain_equal(
+                    &current_single_block_contracts_digest.contracts_address[0],
+                    &next_single_block_contracts_digest.contracts_address[0],
+                );
+
                 // Check mdc_current_rule_version and mdc_next_rule_version, that is, the difference between mdc_next_rule_version - mdc_current_rule_version must be less than or equal to 1.
                 let current_version = current_single_block_contracts_digest.slots_values[1].1;
                 let current_version = current_version.as_slice()[1];
@@ -300,6 +339,7 @@ pub struct ObEbcRuleTraceWitness<F: Field> {
     ebc_rule_rlp_witness: RlpArrayTraceWitness<F>,
     ebc_rule_mpt_witness: MPTProofWitness<F>,
     ebc_rule_hash: AssignedH256<F>,
+    ebc_rule_fields: Vec<AssignedValue<F>>,
 }
 
 impl<F: Field> ObEbcRuleTraceWitness<F> {
@@ -360,6 +400,27 @@ impl<F: Field> ObEbcRuleTraceWitness<F> {
     }
 }
 
+/// Converts the EBC rule fields to public values, in the order of the rule RLP.
+/// The token fields are 32 bytes and take a hi-lo pair, every other field fits in one value.
+pub fn ebc_rule_fields_from_witness<F: Field>(
+    ctx: &mut Context<F>,
+    gate: &impl GateInstructions<F>,
+    ebc_rule_rlp_witness: &RlpArrayTraceWitness<F>,
+) -> Vec<AssignedValue<F>> {
+    let mut fields = Vec::with_capacity(EBC_RULE_FIELDS_NUM + 2);
+    for (field, max_len) in
+        ebc_rule_rlp_witness.field_witness.iter().zip(EBC_RULE_FIELDS_MAX_FIELDS_LEN)
+    {
+        let bytes = bytes_be_var_to_fixed(ctx, gate, &field.field_cells, field.field_len, max_len);
+        if max_len > 16 {
+            fields.extend(bytes_be_to_u128(ctx, gate, &bytes));
+        } else {
+            fields.push(bytes_be_to_uint(ctx, gate, &bytes, max_len));
+        }
+    }
+    fields
+}
+
 #[derive(Clone, Debug)]
 pub struct ObSingleBlockContractsDigest<F: Field> {
     pub block_hash: AssignedH256<F>,
@@ -374,6 +435,8 @@ pub struct ObSingleBlockContractsDigest<F: Field> {
 pub struct ObMultiBlocksContractsDigest<F: Field> {
     pub multi_blocks_contracts_digest: Vec<ObSingleBlockContractsDigest<F>>,
     pub ebc_rule_hash: AssignedH256<F>,
+    /// The decoded rule fields, see [`ebc_rule_fields_from_witness`].
+    pub ebc_rule_fields: Vec<AssignedValue<F>>,
 }
 
 #[derive(Clone, Debug)]
@@ -523,6 +586,7 @@ impl<'chip, F: Field> ObContractsStorageChip<F> for EthChip<'chip, F> {
             ObMultiBlocksContractsDigest {
                 multi_blocks_contracts_digest: digests,
                 ebc_rule_hash: ebc_rule_trace_witness.ebc_rule_hash,
+                ebc_rule_fields: ebc_rule_trace_witness.ebc_rule_fields,
             },
         )
     }
@@ -677,9 +741,16 @@ impl<'chip, F: Field> ObContractsStorageChip<F> for EthChip<'chip, F> {
         let hash_bytes = keccak.var_len_queries[hash_idx].output_assigned.clone();
         let hash: [_; 2] = bytes_be_to_u128(ctx, self.gate(), &hash_bytes).try_into().unwrap();
 
+        let ebc_rule_fields = ebc_rule_fields_from_witness(ctx, self.gate(), &ebc_rule_rlp_witness);
+
         let ebc_rule_mpt_witness = self.parse_mpt_inclusion_phase0(ctx, keccak, proof);
 
-        ObEbcRuleTraceWitness { ebc_rule_rlp_witness, ebc_rule_mpt_witness, ebc_rule_hash: hash }
+        ObEbcRuleTraceWitness {
+            ebc_rule_rlp_witness,
+            ebc_rule_mpt_witness,
+            ebc_rule_hash: hash,
+            ebc_rule_fields,
+        }
     }
 
     fn parse_multi_blocks_contracts_storages_proofs_phase1(
@@ -1,6 +1,5 @@
 use std::str::FromStr;
 use std::{
-    env::set_var,
     fs::File,
     io::{BufReader, Write},
 };
@@ -156,11 +155,11 @@ pub fn get_test_circuit(network: Network, block_number: u32) -> ObContractsStora
 #[test]
 pub fn test_contract_mdc_storage() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigParams::from_path("configs/tests/ob_contracts_storage.json");
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree;
 
     let input = get_test_circuit(Network::Ethereum(EthereumNetwork::Goerli), 9927633);
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
     Ok(())
 }
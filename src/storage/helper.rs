@@ -1,5 +1,6 @@
 use std::path::Path;
 
+use crate::storage::EthBlockStorageCircuit;
 use crate::util::scheduler::{CircuitType, Task};
 use crate::util::{EthConfigPinning, Halo2ConfigPinning};
 use crate::Network;
@@ -11,36 +12,67 @@ pub enum StorageTask {
     BatchStorage(BatchStorageTask),
 }
 
+/// A single [`EthBlockStorageCircuit`].
+#[derive(Clone, Debug)]
 pub struct SingleStorageTask {
+    pub input: EthBlockStorageCircuit,
+    pub network: Network,
+}
+
+impl SingleStorageTask {
+    pub fn new(input: EthBlockStorageCircuit, network: Network) -> Self {
+        Self { input, network }
+    }
+}
+
+/// The exclusion flag changes the instances and the constraints of the circuit, so it is part of the name, and
+/// circuits with and without exclusion proofs have their own pinnings and keys.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct SingleStorageCircuitType {
     pub network: Network,
-    pub block_num: u32,
+    pub num_slots: usize,
+    pub exclusion_proofs: bool,
 }
 
-// impl CircuitType for (Network, u32) {
-//     fn name(&self) -> String {
-//         format!("single_storage_{}_{}", self.0, self.1)
-//     }
+impl CircuitType for SingleStorageCircuitType {
+    fn name(&self) -> String {
+        let name = format!("single_storage_{}_{}", self.network.to_string(), self.num_slots);
+        if self.exclusion_proofs {
+            format!("{name}_exclusion")
+        } else {
+            name
+        }
+    }
 
-//     fn get_degree_from_pinning(&self, path: impl AsRef<Path>) -> u32 {
-//         let pinning_path = path.as_ref();
-//         let pinning = EthConfigPinning::from_path(pinning_path);
-//         pinning.degree()
-//     }
-// }
+    fn get_degree_from_pinning(&self, path: impl AsRef<Path>) -> u32 {
+        let pinning_path = path.as_ref();
+        let pinning = EthConfigPinning::from_path(pinning_path);
+        pinning.degree()
+    }
+}
 
-// impl Task for SingleStorageTask {
-//     type CircuitType = (Network, u32);
+impl Task for SingleStorageTask {
+    type CircuitType = SingleStorageCircuitType;
 
-//     fn circuit_type(&self) -> Self::CircuitType {
-//         (self.network, self.block_num)
-//     }
-//     fn name(&self) -> String {
-//         format!("{}", self.circuit_type().name())
-//     }
-//     fn dependencies(&self) -> Vec<Self> {
-//         vec![]
-//     }
-// }
+    fn circuit_type(&self) -> Self::CircuitType {
+        SingleStorageCircuitType {
+            network: self.network,
+            num_slots: self.input.inputs.storage.storage_pfs.len(),
+            exclusion_proofs: self.input.exclusion_proofs,
+        }
+    }
+    fn name(&self) -> String {
+        format!(
+            "{}_{:?}_block_{}",
+            self.circuit_type().name(),
+            self.input.inputs.storage.addr,
+            self.input.inputs.block_number
+        )
+    }
+    fn dependencies(&self) -> Vec<Self> {
+        vec![]
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct BatchStorageTask {
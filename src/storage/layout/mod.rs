@@ -0,0 +1,359 @@
+//! Storage locations of Solidity state variables, following
+//! <https://docs.soliditylang.org/en/latest/internals/layout_in_storage.html>.
+//!
+//! A [`StorageLocation`] starts at the slot of a state variable and goes through mapping keys, struct members and
+//! array elements. Its slot is derived natively to request the storage proof, and in circuit with keccak so that the
+//! storage proof is checked against the mapping keys and array indices rather than against a slot witness.
+
+use ethers_core::{
+    types::{Address, BigEndianHash, Bytes, H256, U256},
+    utils::keccak256,
+};
+use halo2_base::{
+    gates::{GateInstructions, RangeChip, RangeInstructions},
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+use itertools::Itertools;
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    keccak::KeccakChip,
+    mpt::{AssignedBytes, MPTProof},
+    util::{
+        bytes_be_to_u128, bytes_be_var_to_fixed, encode_h256_to_field, u128s_to_bytes_be,
+        AssignedH256,
+    },
+    EthChip, Field,
+};
+
+use super::{EthStorageChip, EthStorageTraceWitness};
+
+#[cfg(test)]
+mod tests;
+
+/// The key of a mapping.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingKey {
+    /// A value type key, left padded to 32 bytes, e.g. an address or an integer.
+    Word(H256),
+    /// A `string` or `bytes` key, hashed as is.
+    Bytes(Bytes),
+}
+
+impl MappingKey {
+    pub fn address(address: Address) -> Self {
+        MappingKey::Word(H256::from(address))
+    }
+
+    pub fn uint(value: impl Into<U256>) -> Self {
+        MappingKey::Word(H256::from_uint(&value.into()))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            MappingKey::Word(word) => word.as_bytes().to_vec(),
+            MappingKey::Bytes(bytes) => bytes.to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageStep {
+    /// `slot = keccak(key . slot)`
+    Mapping(MappingKey),
+    /// `slot = slot + offset`, for a struct member or an element of a static array.
+    Offset(u64),
+    /// `slot = keccak(slot) + index * element_slots`, for an element of a dynamic array.
+    ArrayElement { index: u64, element_slots: u64 },
+}
+
+/// A value of `len` bytes at `offset` bytes from the right of its slot, for values that share a slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackedField {
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl PackedField {
+    /// The big-endian range of the field in its slot.
+    fn byte_range(&self) -> std::ops::Range<usize> {
+        32 - self.offset - self.len..32 - self.offset
+    }
+
+    pub fn extract(&self, word: H256) -> U256 {
+        U256::from_big_endian(&word.as_bytes()[self.byte_range()])
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageLocation {
+    /// The slot of the state variable.
+    pub slot: U256,
+    pub steps: Vec<StorageStep>,
+    /// Set if the value shares its slot with other values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packed: Option<PackedField>,
+}
+
+impl StorageLocation {
+    pub fn new(slot: impl Into<U256>) -> Self {
+        Self { slot: slot.into(), steps: vec![], packed: None }
+    }
+
+    pub fn mapping(mut self, key: MappingKey) -> Self {
+        self.steps.push(StorageStep::Mapping(key));
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.steps.push(StorageStep::Offset(offset));
+        self
+    }
+
+    pub fn array_element(mut self, index: u64, element_slots: u64) -> Self {
+        self.steps.push(StorageStep::ArrayElement { index, element_slots });
+        self
+    }
+
+    /// An element of a dynamic array of values of `element_bytes <= 16` bytes, several of which share a slot.
+    pub fn packed_array_element(self, index: u64, element_bytes: usize) -> Self {
+        assert!(
+            element_bytes > 0 && element_bytes <= 16,
+            "elements of {element_bytes} bytes are not packed"
+        );
+        let per_slot = (32 / element_bytes) as u64;
+        let offset = (index % per_slot) as usize * element_bytes;
+        self.array_element(index / per_slot, 1).packed(offset, element_bytes)
+    }
+
+    pub fn packed(mut self, offset: usize, len: usize) -> Self {
+        assert!(len > 0 && offset + len <= 32, "packed field out of its slot");
+        self.packed = Some(PackedField { offset, len });
+        self
+    }
+
+    /// The storage slot of the value, as requested from `eth_getProof`.
+    pub fn derive_slot(&self) -> H256 {
+        self.steps.iter().fold(H256::from_uint(&self.slot), |slot, step| match step {
+            StorageStep::Mapping(key) => {
+                H256(keccak256([key.to_bytes(), slot.as_bytes().to_vec()].concat()))
+            }
+            StorageStep::Offset(offset) => add_to_slot(slot, U256::from(*offset)),
+            StorageStep::ArrayElement { index, element_slots } => {
+                add_to_slot(H256(keccak256(slot)), U256::from(*index) * U256::from(*element_slots))
+            }
+        })
+    }
+
+    /// The value of the location in `word`, the value of its slot.
+    pub fn value(&self, word: H256) -> U256 {
+        match self.packed {
+            Some(field) => field.extract(word),
+            None => word.into_uint(),
+        }
+    }
+
+    /// The layout is fixed by the circuit, the mapping keys and array indices are witnesses so that they can be
+    /// constrained against other values of the circuit.
+    pub fn assign<F: Field>(self, ctx: &mut Context<F>) -> AssignedStorageLocation<F> {
+        let steps = self
+            .steps
+            .into_iter()
+            .map(|step| match step {
+                StorageStep::Mapping(MappingKey::Word(word)) => {
+                    AssignedStorageStep::Mapping(AssignedMappingKey::Word(
+                        encode_h256_to_field(&word).map(|x| ctx.load_witness(x)),
+                    ))
+                }
+                StorageStep::Mapping(MappingKey::Bytes(bytes)) => {
+                    AssignedStorageStep::Mapping(AssignedMappingKey::Bytes(
+                        ctx.assign_witnesses(bytes.iter().map(|b| F::from(*b as u64))),
+                    ))
+                }
+                StorageStep::Offset(offset) => AssignedStorageStep::Offset(offset),
+                StorageStep::ArrayElement { index, element_slots } => {
+                    AssignedStorageStep::ArrayElement {
+                        index: ctx.load_witness(F::from(index)),
+                        element_slots,
+                    }
+                }
+            })
+            .collect();
+        AssignedStorageLocation { slot: self.slot, steps, packed: self.packed }
+    }
+}
+
+/// Wraps around like Solidity does.
+fn add_to_slot(slot: H256, value: U256) -> H256 {
+    H256::from_uint(&slot.into_uint().overflowing_add(value).0)
+}
+
+#[derive(Clone, Debug)]
+pub enum AssignedMappingKey<F: Field> {
+    /// (hi, lo) of the 32 bytes key
+    Word(AssignedH256<F>),
+    /// The bytes of the key, whose length is fixed by the circuit.
+    Bytes(Vec<AssignedValue<F>>),
+}
+
+#[derive(Clone, Debug)]
+pub enum AssignedStorageStep<F: Field> {
+    Mapping(AssignedMappingKey<F>),
+    Offset(u64),
+    /// `index` is range checked to 64 bits.
+    ArrayElement {
+        index: AssignedValue<F>,
+        element_slots: u64,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct AssignedStorageLocation<F: Field> {
+    pub slot: U256,
+    pub steps: Vec<AssignedStorageStep<F>>,
+    pub packed: Option<PackedField>,
+}
+
+pub trait EthStorageLayoutChip<F: Field> {
+    /// The slot of `location` as 32 big-endian bytes, derived with keccak.
+    fn derive_storage_slot_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        keccak: &mut KeccakChip<F>,
+        location: &AssignedStorageLocation<F>,
+    ) -> AssignedBytes<F>;
+
+    /// Checks the storage proof of the slot of `location`, returning the slot with the witness of the proof.
+    fn parse_storage_location_proof_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        keccak: &mut KeccakChip<F>,
+        storage_root_bytes: &[AssignedValue<F>],
+        location: &AssignedStorageLocation<F>,
+        proof: MPTProof<F>,
+    ) -> (AssignedBytes<F>, EthStorageTraceWitness<F>);
+
+    /// The value of `location` as (hi, lo), extracted from its slot value if it is packed.
+    fn storage_location_value(
+        &self,
+        ctx: &mut Context<F>,
+        location: &AssignedStorageLocation<F>,
+        witness: &EthStorageTraceWitness<F>,
+    ) -> AssignedH256<F>;
+
+    /// The field `packed` of the 32 big-endian bytes `word` as (hi, lo).
+    fn extract_packed_field(
+        &self,
+        ctx: &mut Context<F>,
+        word: &[AssignedValue<F>],
+        packed: Option<PackedField>,
+    ) -> AssignedH256<F>;
+}
+
+/// `slot + value` mod 2^256 as 32 big-endian bytes, where `value < 2^129`.
+fn add_to_slot_bytes<F: Field>(
+    ctx: &mut Context<F>,
+    range: &RangeChip<F>,
+    slot: &[AssignedValue<F>],
+    value: AssignedValue<F>,
+) -> AssignedBytes<F> {
+    let [hi, lo]: [_; 2] = bytes_be_to_u128(ctx, range.gate(), slot).try_into().unwrap();
+    let lo = range.gate().add(ctx, lo, value);
+    let (carry, lo) = range.div_mod(ctx, lo, BigUint::from(1u64) << 128, 130);
+    let hi = range.gate().add(ctx, hi, carry);
+    let (_, hi) = range.div_mod(ctx, hi, BigUint::from(1u64) << 128, 130);
+    u128s_to_bytes_be(ctx, range, &[hi, lo])
+}
+
+impl<'chip, F: Field> EthStorageLayoutChip<F> for EthChip<'chip, F> {
+    fn derive_storage_slot_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        keccak: &mut KeccakChip<F>,
+        location: &AssignedStorageLocation<F>,
+    ) -> AssignedBytes<F> {
+        let mut slot_bytes = [0u8; 32];
+        location.slot.to_big_endian(&mut slot_bytes);
+        let mut slot =
+            slot_bytes.iter().map(|b| ctx.load_constant(F::from(*b as u64))).collect_vec();
+        for step in &location.steps {
+            slot = match step {
+                AssignedStorageStep::Mapping(key) => {
+                    let key = match key {
+                        AssignedMappingKey::Word(key) => u128s_to_bytes_be(ctx, self.range(), key),
+                        AssignedMappingKey::Bytes(key) => {
+                            for byte in key {
+                                self.range().range_check(ctx, *byte, 8);
+                            }
+                            key.clone()
+                        }
+                    };
+                    let query_idx =
+                        keccak.keccak_fixed_len(ctx, self.gate(), [key, slot].concat(), None);
+                    keccak.fixed_len_queries[query_idx].output_assigned.clone()
+                }
+                AssignedStorageStep::Offset(offset) => {
+                    let offset = ctx.load_constant(F::from(*offset));
+                    add_to_slot_bytes(ctx, self.range(), &slot, offset)
+                }
+                AssignedStorageStep::ArrayElement { index, element_slots } => {
+                    self.range().range_check(ctx, *index, 64);
+                    let query_idx = keccak.keccak_fixed_len(ctx, self.gate(), slot, None);
+                    let start = keccak.fixed_len_queries[query_idx].output_assigned.clone();
+                    let offset = self.gate().mul(ctx, *index, Constant(F::from(*element_slots)));
+                    add_to_slot_bytes(ctx, self.range(), &start, offset)
+                }
+            };
+        }
+        slot
+    }
+
+    fn parse_storage_location_proof_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        keccak: &mut KeccakChip<F>,
+        storage_root_bytes: &[AssignedValue<F>],
+        location: &AssignedStorageLocation<F>,
+        proof: MPTProof<F>,
+    ) -> (AssignedBytes<F>, EthStorageTraceWitness<F>) {
+        let slot = self.derive_storage_slot_phase0(ctx, keccak, location);
+        let witness =
+            self.parse_storage_proof_phase0(ctx, keccak, storage_root_bytes, slot.clone(), proof);
+        (slot, witness)
+    }
+
+    fn storage_location_value(
+        &self,
+        ctx: &mut Context<F>,
+        location: &AssignedStorageLocation<F>,
+        witness: &EthStorageTraceWitness<F>,
+    ) -> AssignedH256<F> {
+        let value_bytes = &witness.value_witness.witness.field_cells;
+        let value_len = witness.value_witness.witness.field_len;
+        let word = bytes_be_var_to_fixed(ctx, self.gate(), value_bytes, value_len, 32);
+        self.extract_packed_field(ctx, &word, location.packed)
+    }
+
+    fn extract_packed_field(
+        &self,
+        ctx: &mut Context<F>,
+        word: &[AssignedValue<F>],
+        packed: Option<PackedField>,
+    ) -> AssignedH256<F> {
+        assert_eq!(word.len(), 32);
+        let bytes = match packed {
+            Some(field) => {
+                let zero = ctx.load_constant(F::zero());
+                let mut bytes = vec![zero; 32 - field.len];
+                bytes.extend_from_slice(&word[field.byte_range()]);
+                bytes
+            }
+            None => word.to_vec(),
+        };
+        bytes_be_to_u128(ctx, self.gate(), &bytes).try_into().unwrap()
+    }
+}
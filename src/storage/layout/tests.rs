@@ -0,0 +1,252 @@
+use std::{cell::RefCell, fs::File};
+
+use ethers_core::types::{Address, Bytes, H256, U256};
+use halo2_base::{
+    gates::RangeChip,
+    halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr},
+};
+use rlp::{Encodable, RlpStream};
+use serde::Deserialize;
+
+use super::*;
+use crate::{
+    keccak::{FixedLenRLCs, VarLenRLCs},
+    mpt::MPTInput,
+    rlp::{builder::RlcThreadBuilder, rlc::FIRST_PHASE, RlpChip},
+    storage::{util::STORAGE_PF_MAX_DEPTH, STORAGE_PROOF_VALUE_MAX_BYTE_LEN},
+    util::{
+        helpers::calculate_storage_mapping_key, EthConfigParams, EthConfigPinning,
+        Halo2ConfigPinning,
+    },
+    EthCircuitBuilder, ETH_LOOKUP_BITS,
+};
+
+/// Slots and values of `test_data/storage_layout/StorageLayout.sol`.
+#[derive(Debug, Deserialize)]
+struct StorageLayoutFixture {
+    locations: Vec<LocationFixture>,
+    packed: Vec<PackedFixture>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LocationFixture {
+    name: String,
+    location: StorageLocation,
+    slot: H256,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackedFixture {
+    name: String,
+    field: PackedField,
+    word: H256,
+    value: U256,
+}
+
+fn read_fixture() -> StorageLayoutFixture {
+    let file = File::open("test_data/storage_layout/storage_layout.json").unwrap();
+    serde_json::from_reader(file).unwrap()
+}
+
+fn location(fixture: &StorageLayoutFixture, name: &str) -> StorageLocation {
+    fixture.locations.iter().find(|l| l.name == name).unwrap().location.clone()
+}
+
+#[test]
+fn test_derive_slot() {
+    let fixture = read_fixture();
+    for LocationFixture { name, location, slot } in &fixture.locations {
+        assert_eq!(location.derive_slot(), *slot, "{name}");
+    }
+}
+
+#[test]
+fn test_location_builder() {
+    let fixture = read_fixture();
+    let a: Address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".parse().unwrap();
+    let status = StorageLocation::new(2)
+        .mapping(MappingKey::address(a))
+        .mapping(MappingKey::uint(10))
+        .offset(1)
+        .packed(20, 1);
+    assert_eq!(status, location(&fixture, "rules[A][10].status"));
+    assert_eq!(
+        StorageLocation::new(5).packed_array_element(9, 8),
+        location(&fixture, "timestamps[9]")
+    );
+    let name = StorageLocation::new(6).mapping(MappingKey::Bytes(b"orbiter".to_vec().into()));
+    assert_eq!(name, location(&fixture, "names[\"orbiter\"]"));
+
+    let balance = location(&fixture, "balances[A]");
+    assert_eq!(calculate_storage_mapping_key(H256::from_low_u64_be(1), a), balance.derive_slot());
+}
+
+#[test]
+fn test_extract_packed_field() {
+    for PackedFixture { name, field, word, value } in read_fixture().packed {
+        assert_eq!(field.extract(word), value, "{name}");
+    }
+}
+
+#[test]
+fn test_storage_layout_mock() {
+    let params = EthConfigPinning::from_path("configs/tests/one_block.json").params;
+    let k = params.degree;
+    let fixture = read_fixture();
+
+    let mut builder = RlcThreadBuilder::<Fr>::mock();
+    let range = RangeChip::default(ETH_LOOKUP_BITS);
+    let chip = EthChip::new(RlpChip::new(&range, None), None);
+    let mut keccak = KeccakChip::default();
+    let ctx = builder.gate_builder.main(FIRST_PHASE);
+
+    let mut assigned_instances = vec![];
+    let mut instances = vec![];
+    for LocationFixture { location, slot, .. } in fixture.locations {
+        let location = location.assign(ctx);
+        let slot_bytes = chip.derive_storage_slot_phase0(ctx, &mut keccak, &location);
+        assigned_instances.extend(bytes_be_to_u128(ctx, chip.gate(), &slot_bytes));
+        instances.extend(encode_h256_to_field::<Fr>(&slot));
+    }
+    for PackedFixture { field, word, value, .. } in fixture.packed {
+        let word = ctx.assign_witnesses(word.as_bytes().iter().map(|b| Fr::from(*b as u64)));
+        assigned_instances.extend(chip.extract_packed_field(ctx, &word, Some(field)));
+        instances.extend(encode_h256_to_field::<Fr>(&H256::from_uint(&value)));
+    }
+
+    let circuit = EthCircuitBuilder::new(
+        assigned_instances,
+        builder,
+        RefCell::new(keccak),
+        range,
+        None,
+        |_: &mut RlcThreadBuilder<Fr>, _: RlpChip<Fr>, _: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {},
+    );
+    circuit.config(k as usize, Some(params.unusable_rows));
+    let _scope = circuit.config_scope();
+    MockProver::run(k, &circuit, vec![instances]).unwrap().assert_satisfied();
+}
+
+/// The hex prefix encoding of a path, see appendix C of the yellow paper.
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = 2 * leaf as u8 + (nibbles.len() % 2) as u8;
+    let nibbles = if nibbles.len() % 2 == 1 {
+        [&[flag][..], nibbles].concat()
+    } else {
+        [&[flag, 0][..], nibbles].concat()
+    };
+    nibbles.chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect()
+}
+
+/// The storage trie of two slots: a branch, below an extension if their keys share a prefix, and their leaves.
+/// Returns the storage root and the proof of each slot.
+fn two_slot_trie(slots: [(H256, U256); 2]) -> (H256, [Vec<Bytes>; 2]) {
+    let nibbles = slots.map(|(slot, _)| {
+        keccak256(slot).iter().flat_map(|byte| [byte >> 4, byte & 0xf]).collect_vec()
+    });
+    let prefix = nibbles[0].iter().zip(&nibbles[1]).take_while(|(a, b)| a == b).count();
+    let leaves = [0, 1].map(|i| {
+        let mut leaf = RlpStream::new_list(2);
+        leaf.append(&hex_prefix(&nibbles[i][prefix + 1..], true));
+        leaf.append(&slots[i].1.rlp_bytes().to_vec());
+        leaf.out().to_vec()
+    });
+    let mut branch = RlpStream::new_list(17);
+    for nibble in 0..16 {
+        match (0..2).find(|&i| nibbles[i][prefix] == nibble) {
+            Some(i) => branch.append(&keccak256(&leaves[i]).to_vec()),
+            None => branch.append_empty_data(),
+        };
+    }
+    branch.append_empty_data();
+    let mut nodes = vec![branch.out().to_vec()];
+    if prefix > 0 {
+        let mut extension = RlpStream::new_list(2);
+        extension.append(&hex_prefix(&nibbles[0][..prefix], false));
+        extension.append(&keccak256(&nodes[0]).to_vec());
+        nodes.insert(0, extension.out().to_vec());
+    }
+    let root = H256(keccak256(&nodes[0]));
+    let proofs = leaves.map(|leaf| nodes.iter().chain([&leaf]).cloned().map(Bytes::from).collect());
+    (root, proofs)
+}
+
+#[test]
+fn test_storage_location_proof_mock() {
+    let params = EthConfigParams::from_path("configs/tests/storage.json");
+    let k = params.degree;
+    let fixture = read_fixture();
+    let a: Address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".parse().unwrap();
+    // a nested mapping to a packed field, `token` and `status` share the slot
+    let status = location(&fixture, "rules[A][10].status");
+    let mut status_word = [0; 32];
+    status_word[11] = 1;
+    status_word[12..].copy_from_slice(a.as_bytes());
+    let balance = location(&fixture, "balances[A]");
+    let balance_word = H256::from_low_u64_be(1000);
+    let slots = [
+        (status.derive_slot(), U256::from_big_endian(&status_word)),
+        (balance.derive_slot(), balance_word.into_uint()),
+    ];
+    let (storage_root, proofs) = two_slot_trie(slots);
+    assert_eq!(status.value(H256(status_word)), U256::one());
+
+    let mut builder = RlcThreadBuilder::<Fr>::mock();
+    let range = RangeChip::default(ETH_LOOKUP_BITS);
+    let chip = EthChip::new(RlpChip::new(&range, None), None);
+    let mut keccak = KeccakChip::default();
+    let ctx = builder.gate_builder.main(FIRST_PHASE);
+    let storage_root_bytes =
+        ctx.assign_witnesses(storage_root.as_bytes().iter().map(|b| Fr::from(*b as u64)));
+
+    let mut assigned_instances = vec![];
+    let mut instances = vec![];
+    let mut witnesses = vec![];
+    for ((location, (slot, value)), proof) in [status, balance].into_iter().zip(slots).zip(proofs) {
+        let expected_value = location.value(H256::from_uint(&value));
+        let proof = MPTInput {
+            path: H256(keccak256(slot)).into(),
+            value: value.rlp_bytes().to_vec(),
+            root_hash: storage_root,
+            proof: proof.into_iter().map(|node| node.to_vec()).collect(),
+            value_max_byte_len: STORAGE_PROOF_VALUE_MAX_BYTE_LEN,
+            max_depth: STORAGE_PF_MAX_DEPTH,
+            max_key_byte_len: 32,
+            slot_is_empty: false,
+            key_byte_len: None,
+        }
+        .assign(ctx);
+        let location = location.assign(ctx);
+        let (slot_bytes, witness) = chip.parse_storage_location_proof_phase0(
+            ctx,
+            &mut keccak,
+            &storage_root_bytes,
+            &location,
+            proof,
+        );
+        assigned_instances.extend(bytes_be_to_u128(ctx, chip.gate(), &slot_bytes));
+        instances.extend(encode_h256_to_field::<Fr>(&slot));
+        assigned_instances.extend(chip.storage_location_value(ctx, &location, &witness));
+        instances.extend(encode_h256_to_field::<Fr>(&H256::from_uint(&expected_value)));
+        witnesses.push(witness);
+    }
+
+    let circuit = EthCircuitBuilder::new(
+        assigned_instances,
+        builder,
+        RefCell::new(keccak),
+        range,
+        None,
+        move |builder: &mut RlcThreadBuilder<Fr>,
+              rlp: RlpChip<Fr>,
+              keccak_rlcs: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {
+            let chip = EthChip::new(rlp, Some(keccak_rlcs));
+            for witness in witnesses {
+                chip.parse_storage_proof_phase1(builder.rlc_ctx_pair(), witness);
+            }
+        },
+    );
+    circuit.config(k as usize, Some(params.unusable_rows));
+    let _scope = circuit.config_scope();
+    MockProver::run(k, &circuit, vec![instances]).unwrap().assert_satisfied();
+}
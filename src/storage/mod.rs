@@ -1,14 +1,15 @@
 use std::io::Read;
+use std::str::FromStr;
 use std::{cell::RefCell, env::var, fs::File, path::Path};
 
 use ethers_core::types::{Address, Block, H256, U256};
-use ethers_providers::RetryClient;
 #[cfg(feature = "providers")]
-use ethers_providers::{Http, Provider};
+use ethers_providers::Provider;
 use halo2_base::{
     gates::{builder::GateThreadBuilder, GateInstructions, RangeChip},
     halo2_proofs::halo2curves::bn256::Fr,
     AssignedValue, Context,
+    QuantumCell::Constant,
 };
 use itertools::Itertools;
 use rayon::prelude::*;
@@ -23,6 +24,7 @@ use crate::mpt::{MPTInput, MPTProof, MPTProofWitness};
 use crate::providers::get_storage_input;
 use crate::rlp::builder::parallelize_phase1;
 use crate::storage::util::StorageConstructor;
+use crate::util::rpc_client::RpcClient;
 use crate::{
     keccak::{FixedLenRLCs, FnSynthesize, KeccakChip, VarLenRLCs},
     mpt::AssignedBytes,
@@ -40,8 +42,11 @@ use crate::{
 };
 
 // #[cfg(all(test, feature = "providers"))]
+pub mod account;
 pub mod contract_storage;
 pub mod helper;
+pub mod layout;
+#[cfg(test)]
 pub mod tests;
 pub mod util;
 
@@ -61,6 +66,13 @@ pub(crate) const ACCOUNT_STATE_FIELDS_MAX_BYTES: [usize; NUM_ACCOUNT_STATE_FIELD
 pub(crate) const ACCOUNT_PROOF_VALUE_MAX_BYTE_LEN: usize = 90;
 pub(crate) const STORAGE_PROOF_VALUE_MAX_BYTE_LEN: usize = 33;
 
+/// keccak(rlp("")), the storage root of an account without storage.
+pub const EMPTY_STORAGE_ROOT: &str =
+    "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421";
+/// keccak(""), the code hash of an account without code.
+pub const EMPTY_CODE_HASH: &str =
+    "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+
 const CACHE_BITS: usize = 10;
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
@@ -115,6 +127,16 @@ impl<F: Field> EthAccountTraceWitness<F> {
     }
 }
 
+/// The fields of an account. An account that doesn't exist has the fields of an empty account:
+/// nonce and balance 0, [`EMPTY_STORAGE_ROOT`] and [`EMPTY_CODE_HASH`].
+#[derive(Clone, Debug)]
+pub struct EthAccountFields<F: Field> {
+    pub nonce: AssignedValue<F>,
+    pub balance: AssignedValue<F>,
+    pub storage_root: AssignedH256<F>,
+    pub code_hash: AssignedH256<F>,
+}
+
 #[derive(Clone, Debug)]
 pub struct EthStorageTrace<F: Field> {
     pub value_trace: RlcTrace<F>,
@@ -180,6 +202,13 @@ pub trait EthStorageChip<F: Field> {
         witness: EthAccountTraceWitness<F>,
     ) -> EthAccountTrace<F>;
 
+    /// The account fields from its account proof, with nonce and balance as integers and hashes as (hi, lo).
+    fn parse_account_fields_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        witness: &EthAccountTraceWitness<F>,
+    ) -> EthAccountFields<F>;
+
     fn parse_storage_proof_phase0(
         &self,
         ctx: &mut Context<F>,
@@ -195,6 +224,24 @@ pub trait EthStorageChip<F: Field> {
         witness: EthStorageTraceWitness<F>,
     ) -> EthStorageTrace<F>;
 
+    /// The slot value from its storage proof as (hi, lo).
+    fn parse_storage_value_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        witness: &EthStorageTraceWitness<F>,
+    ) -> AssignedH256<F>;
+
+    /// The slot value and whether the slot is unset, for a slot that may be unset or whose account may not exist.
+    /// The slot of an account that doesn't exist is unset: its storage proof is against an arbitrary storage root.
+    /// The value of an unset slot is 0.
+    fn exclude_unset_slot_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        address_is_empty: AssignedValue<F>,
+        value: AssignedH256<F>,
+        slot_is_empty: AssignedValue<F>,
+    ) -> (AssignedH256<F>, AssignedValue<F>);
+
     fn parse_eip1186_proofs_phase0(
         &self,
         thread_pool: &mut GateThreadBuilder<F>,
@@ -309,6 +356,43 @@ impl<'chip, F: Field> EthStorageChip<F> for EthChip<'chip, F> {
         EthAccountTrace { nonce_trace, balance_trace, storage_root_trace, code_hash_trace }
     }
 
+    fn parse_account_fields_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        witness: &EthAccountTraceWitness<F>,
+    ) -> EthAccountFields<F> {
+        let address_is_empty = witness.mpt_witness.slot_is_empty;
+        let [nonce, balance]: [_; 2] = [witness.get_nonce(), witness.get_balance()]
+            .into_iter()
+            .zip(ACCOUNT_STATE_FIELDS_MAX_BYTES)
+            .map(|(field, max_bytes)| {
+                let bytes = bytes_be_var_to_fixed(
+                    ctx,
+                    self.gate(),
+                    &field.field_cells,
+                    field.field_len,
+                    max_bytes,
+                );
+                let value = bytes_be_to_uint(ctx, self.gate(), &bytes, max_bytes);
+                self.gate().mul_not(ctx, address_is_empty, value)
+            })
+            .collect_vec()
+            .try_into()
+            .unwrap();
+        let [storage_root, code_hash] = [
+            (witness.get_storage_root(), EMPTY_STORAGE_ROOT),
+            (witness.get_code_hash(), EMPTY_CODE_HASH),
+        ]
+        .map(|(field, empty)| {
+            let bytes =
+                bytes_be_var_to_fixed(ctx, self.gate(), &field.field_cells, field.field_len, 32);
+            let hash = bytes_be_to_u128(ctx, self.gate(), &bytes);
+            let empty = encode_h256_to_field::<F>(&H256::from_str(empty).unwrap());
+            [0, 1].map(|i| self.gate().select(ctx, Constant(empty[i]), hash[i], address_is_empty))
+        });
+        EthAccountFields { nonce, balance, storage_root, code_hash }
+    }
+
     fn parse_storage_proof_phase0(
         &self,
         ctx: &mut Context<F>,
@@ -357,6 +441,30 @@ impl<'chip, F: Field> EthStorageChip<F> for EthChip<'chip, F> {
         EthStorageTrace { value_trace }
     }
 
+    fn parse_storage_value_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        witness: &EthStorageTraceWitness<F>,
+    ) -> AssignedH256<F> {
+        // get value as U256 from RLP decoding, convert to H256, then to hi-lo
+        let value_bytes = &witness.value_witness.witness.field_cells;
+        let value_len = witness.value_witness.witness.field_len;
+        let value_bytes = bytes_be_var_to_fixed(ctx, self.gate(), value_bytes, value_len, 32);
+        bytes_be_to_u128(ctx, self.gate(), &value_bytes).try_into().unwrap()
+    }
+
+    fn exclude_unset_slot_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        address_is_empty: AssignedValue<F>,
+        value: AssignedH256<F>,
+        slot_is_empty: AssignedValue<F>,
+    ) -> (AssignedH256<F>, AssignedValue<F>) {
+        let slot_is_empty = self.gate().or(ctx, address_is_empty, slot_is_empty);
+        let value = value.map(|limb| self.gate().mul_not(ctx, slot_is_empty, limb));
+        (value, slot_is_empty)
+    }
+
     fn parse_eip1186_proofs_phase0(
         &self,
         thread_pool: &mut GateThreadBuilder<F>,
@@ -460,16 +568,7 @@ impl<'chip, F: Field> EthStorageChip<F> for EthChip<'chip, F> {
         let slots_values = slots
             .into_iter()
             .zip(storage_witness.iter())
-            .map(|(slot, witness)| {
-                // get value as U256 from RLP decoding, convert to H256, then to hi-lo
-                let value_bytes = &witness.value_witness.witness.field_cells;
-                let value_len = witness.value_witness.witness.field_len;
-                let value_bytes =
-                    bytes_be_var_to_fixed(ctx, self.gate(), value_bytes, value_len, 32);
-                let value: [_; 2] =
-                    bytes_be_to_u128(ctx, self.gate(), &value_bytes).try_into().unwrap();
-                (slot, value)
-            })
+            .map(|(slot, witness)| (slot, self.parse_storage_value_phase0(ctx, witness)))
             .collect_vec();
 
         let digest = EIP1186ResponseDigest {
@@ -608,14 +707,14 @@ impl EthBlockStorageInput {
 pub struct EthBlockStorageCircuit {
     pub inputs: EthBlockStorageInput,
     pub block_header_config: BlockHeaderConfig,
+    /// Allows the account not to exist and the slots to be unset, see [`EthBlockStorageCircuit::instance`].
+    /// Otherwise they are constrained to exist.
+    pub exclusion_proofs: bool,
 }
 
 impl EthBlockStorageCircuit {
     #[cfg(feature = "providers")]
-    pub fn from_provider(
-        provider: &Provider<RetryClient<Http>>,
-        constructor: StorageConstructor,
-    ) -> Self {
+    pub fn from_provider(provider: &Provider<RpcClient>, constructor: StorageConstructor) -> Self {
         let inputs = get_storage_input(
             provider,
             constructor.block_number,
@@ -625,22 +724,36 @@ impl EthBlockStorageCircuit {
             constructor.storage_pf_max_depth,
         );
         let block_header_config = get_block_header_config(&constructor.network);
-        Self { inputs, block_header_config }
+        Self { inputs, block_header_config, exclusion_proofs: constructor.exclusion_proofs }
     }
 
     // MAYBE UNUSED
     // blockHash, blockNumber, address, (slot, value)s
     // with H256 encoded as hi-lo (u128, u128)
+    //
+    // with exclusion proofs:
+    // blockHash, blockNumber, address, addressIsEmpty, (slot, value, slotIsEmpty)s
+    // where the value of an unset slot, or of any slot of an account that doesn't exist, is 0
     pub fn instance<F: Field>(&self) -> Vec<F> {
         let EthBlockStorageInput { block_number, block_hash, storage, .. } = &self.inputs;
-        let EthStorageInput { addr, storage_pfs, .. } = storage;
-        let mut instance = Vec::with_capacity(4 + 4 * storage_pfs.len());
+        let EthStorageInput { addr, acct_pf, storage_pfs } = storage;
+        let mut instance = Vec::with_capacity(5 + 5 * storage_pfs.len());
         instance.extend(encode_h256_to_field::<F>(block_hash));
         instance.push(F::from(*block_number as u64));
         instance.push(encode_addr_to_field(addr));
-        for (slot, value, _) in storage_pfs {
+        if self.exclusion_proofs {
+            instance.push(F::from(acct_pf.slot_is_empty as u64));
+        }
+        for (slot, value, storage_pf) in storage_pfs {
             instance.extend(encode_h256_to_field::<F>(slot));
-            instance.extend(encode_u256_to_field::<F>(value));
+            if self.exclusion_proofs {
+                let slot_is_empty = acct_pf.slot_is_empty || storage_pf.slot_is_empty;
+                let value = if slot_is_empty { U256::zero() } else { *value };
+                instance.extend(encode_u256_to_field::<F>(&value));
+                instance.push(F::from(slot_is_empty as u64));
+            } else {
+                instance.extend(encode_u256_to_field::<F>(value));
+            }
             // instance for input
         }
         instance
@@ -675,19 +788,24 @@ impl EthPreCircuit for EthBlockStorageCircuit {
             slot_is_empty,
         } = digest;
 
-        let assigned_instances = block_hash
-            .into_iter()
-            .chain([block_number, address])
-            .chain(
+        let mut assigned_instances =
+            block_hash.into_iter().chain([block_number, address]).collect_vec();
+
+        let ctx = builder.gate_builder.main(FIRST_PHASE);
+        if self.exclusion_proofs {
+            assigned_instances.push(address_is_empty);
+            for ((slot, value), slot_is_empty) in slots_values.into_iter().zip(slot_is_empty) {
+                let (value, slot_is_empty) =
+                    chip.exclude_unset_slot_phase0(ctx, address_is_empty, value, slot_is_empty);
+                assigned_instances.extend(slot.into_iter().chain(value).chain([slot_is_empty]));
+            }
+        } else {
+            assigned_instances.extend(
                 slots_values
                     .into_iter()
                     .flat_map(|(slot, value)| slot.into_iter().chain(value.into_iter())),
-            )
-            .collect_vec();
-
-        // For now this circuit is going to constrain that all slots are occupied. We can also create a circuit that exposes the bitmap of slot_is_empty
-        {
-            let ctx = builder.gate_builder.main(FIRST_PHASE);
+            );
+            // constrain that the account exists and all slots are occupied
             range.gate.assert_is_const(ctx, &address_is_empty, &Fr::zero());
             for slot_is_empty in slot_is_empty {
                 range.gate.assert_is_const(ctx, &slot_is_empty, &Fr::zero());
@@ -1,20 +1,23 @@
 use std::str::FromStr;
 use std::{
-    env::set_var,
     fs::File,
     io::{BufReader, Write},
 };
 
 use ark_std::{end_timer, start_timer};
 use circuit_derive;
-use ethers_core::types::Bytes;
+use ethers_core::types::{Bloom, Bytes, EIP1186ProofResponse, H64};
 use ethers_core::utils::keccak256;
 use halo2_base::utils::fs::gen_srs;
 use hex::FromHex;
 use serde::{Deserialize, Serialize};
 use test_log::test;
 
+use crate::providers::{encode_block_header, get_storage_proof_input};
+use crate::storage::helper::SingleStorageTask;
+use crate::storage::util::{ACCOUNT_PF_MAX_DEPTH, STORAGE_PF_MAX_DEPTH};
 use crate::util::helpers::get_provider;
+use crate::util::u128s_to_bytes_be;
 use crate::{
     halo2_proofs::{
         dev::MockProver,
@@ -30,7 +33,7 @@ use crate::{
             Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
         },
     },
-    util::scheduler::Scheduler,
+    util::scheduler::{CircuitType, Scheduler, Task},
     ArbitrumNetwork, EthereumNetwork,
 };
 
@@ -59,6 +62,7 @@ pub fn get_test_circuit(network: Network, block_number: u32) -> EthBlockStorageC
         acct_pf_max_depth: 9,
         storage_pf_max_depth: 8,
         network,
+        exclusion_proofs: false,
     };
     EthBlockStorageCircuit::from_provider(&provider, constructor)
 }
@@ -66,11 +70,11 @@ pub fn get_test_circuit(network: Network, block_number: u32) -> EthBlockStorageC
 #[test]
 pub fn test_mdc_storage() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigParams::from_path("configs/tests/storage.json");
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree;
 
     let input = get_test_circuit(Network::Ethereum(EthereumNetwork::Goerli), 9927633);
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
     Ok(())
 }
@@ -82,6 +86,7 @@ struct BenchParams(EthConfigParams, usize); // (params, num_slots)
 #[cfg(feature = "evm")]
 pub fn bench_evm_eip1186() -> Result<(), Box<dyn std::error::Error>> {
     use crate::util::circuit::custom_gen_evm_verifier_shplonk;
+    use crate::util::circuit_config::{isolate_env, CircuitConfig, CircuitConfigScope};
     use halo2_base::gates::builder::CircuitBuilderStage;
     use snark_verifier_sdk::{
         evm::{evm_verify, gen_evm_proof_shplonk, write_calldata},
@@ -110,26 +115,33 @@ pub fn bench_evm_eip1186() -> Result<(), Box<dyn std::error::Error>> {
             bench_params.0.degree
         );
 
-        set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&bench_params.0).unwrap());
-
-        let (storage_snark, storage_proof_time) = {
+        let (storage_snark, storage_proof_time, storage_params) = {
             let k = bench_params.0.degree;
             let block_number = bench_params.1 as u32;
             let input = get_test_circuit(Network::Ethereum(EthereumNetwork::Goerli), block_number);
-            let circuit = input.clone().create_circuit(RlcThreadBuilder::keygen(), None);
+            let circuit = input.clone().create_circuit(
+                RlcThreadBuilder::keygen(),
+                bench_params.0.clone(),
+                None,
+            );
+            let _scope = circuit.config_scope();
             let params = gen_srs(k);
             let pk = gen_pk(&params, &circuit, None);
+            let config_params = circuit.config_params();
             let break_points = circuit.circuit.break_points.take();
             let storage_proof_time = start_timer!(|| "Storage Proof SHPLONK");
-            let circuit = input.create_circuit(RlcThreadBuilder::prover(), Some(break_points));
+            let circuit = input.create_circuit(
+                RlcThreadBuilder::prover(),
+                config_params.clone(),
+                Some(break_points),
+            );
             let snark = gen_snark_shplonk(&params, &pk, circuit, None::<&str>);
             end_timer!(storage_proof_time);
-            (snark, storage_proof_time)
+            (snark, storage_proof_time, config_params)
         };
 
         let k = evm_params.degree;
         let params = gen_srs(k);
-        set_var("LOOKUP_BITS", evm_params.lookup_bits.to_string());
         let evm_circuit = AggregationCircuit::public::<SHPLONK>(
             CircuitBuilderStage::Keygen,
             None,
@@ -138,7 +150,10 @@ pub fn bench_evm_eip1186() -> Result<(), Box<dyn std::error::Error>> {
             vec![storage_snark.clone()],
             false,
         );
-        evm_circuit.config(k, Some(10));
+        let _scope = CircuitConfigScope::enter(CircuitConfig::Gate {
+            params: isolate_env(|| evm_circuit.config(k, Some(10))),
+            lookup_bits: evm_params.lookup_bits,
+        });
         let pk = gen_pk(&params, &evm_circuit, None);
         let break_points = evm_circuit.break_points();
 
@@ -171,8 +186,7 @@ pub fn bench_evm_eip1186() -> Result<(), Box<dyn std::error::Error>> {
             .unwrap_or_else(|_| "0".to_string())
             .parse::<usize>()
             .unwrap();
-        let bench_params: EthConfigParams =
-            serde_json::from_str(var("ETH_CONFIG_PARAMS").unwrap().as_str()).unwrap();
+        let bench_params = storage_params;
         writeln!(
             fs_results,
             "{},{},{},{:?},{:?},{},{:.2}s,{:?}",
@@ -192,3 +206,188 @@ pub fn bench_evm_eip1186() -> Result<(), Box<dyn std::error::Error>> {
     }
     Ok(())
 }
+
+/// An `eth_getProof` response of `scripts/input_gen`, whose slot keys are not left padded.
+pub fn read_proof_fixture(name: &str) -> EIP1186ProofResponse {
+    let file = File::open(format!("scripts/input_gen/{name}.json")).unwrap();
+    let mut pf: serde_json::Value = serde_json::from_reader(BufReader::new(file)).unwrap();
+    for storage_pf in pf["storageProof"].as_array_mut().unwrap() {
+        let key = storage_pf["key"].as_str().unwrap().trim_start_matches("0x");
+        storage_pf["key"] = format!("0x{key:0>64}").into();
+    }
+    serde_json::from_value(pf).unwrap()
+}
+
+const FIXTURE_BLOCK_NUMBER: u32 = 16_000_000;
+
+/// The proofs of a `scripts/input_gen` fixture in a mainnet London block whose state root is the root of its account
+/// proof. The block is not one of the chain.
+pub fn fixture_block_storage_input(pf: EIP1186ProofResponse) -> EthBlockStorageInput {
+    let state_root = H256(keccak256(&pf.account_proof[0]));
+    let mut block = Block::<H256> {
+        author: Some(Address::zero()),
+        state_root,
+        number: Some(FIXTURE_BLOCK_NUMBER.into()),
+        gas_limit: 30_000_000.into(),
+        timestamp: 1_668_000_000.into(),
+        logs_bloom: Some(Bloom::zero()),
+        mix_hash: Some(H256::zero()),
+        nonce: Some(H64::zero()),
+        base_fee_per_gas: Some(10_000_000_000u64.into()),
+        ..Default::default()
+    };
+    let block_header = encode_block_header(&block);
+    let block_hash = H256(keccak256(&block_header));
+    block.hash = Some(block_hash);
+
+    let storage =
+        get_storage_proof_input(pf, state_root, ACCOUNT_PF_MAX_DEPTH, STORAGE_PF_MAX_DEPTH);
+    EthBlockStorageInput {
+        block,
+        block_number: FIXTURE_BLOCK_NUMBER,
+        block_hash,
+        block_header,
+        storage,
+    }
+}
+
+/// Proves the account and storage proofs of a fixture against the state root its account proof starts from,
+/// returning `addressIsEmpty, (value, slotIsEmpty)s` as in [`EthBlockStorageCircuit`] with exclusion proofs.
+fn storage_exclusion_mock(name: &str) -> Vec<Fr> {
+    let params = EthConfigParams::from_path("configs/tests/storage.json");
+    let k = params.degree;
+    let pf = read_proof_fixture(name);
+    let state_root = H256(keccak256(&pf.account_proof[0]));
+    let input = get_storage_proof_input(pf, state_root, ACCOUNT_PF_MAX_DEPTH, STORAGE_PF_MAX_DEPTH);
+
+    let mut builder = RlcThreadBuilder::<Fr>::mock();
+    let range = RangeChip::default(ETH_LOOKUP_BITS);
+    let chip = EthChip::new(RlpChip::new(&range, None), None);
+    let mut keccak = KeccakChip::default();
+
+    let ctx = builder.gate_builder.main(FIRST_PHASE);
+    let state_root =
+        ctx.assign_witnesses(state_root.as_bytes().iter().map(|b| Fr::from(*b as u64)));
+    let EthStorageInputAssigned { address, acct_pf, storage_pfs } = input.assign(ctx);
+    let addr_bytes = uint_to_bytes_be(ctx, &range, &address, 20);
+    let storage_pfs = storage_pfs
+        .into_iter()
+        .map(|(slot, pf)| (u128s_to_bytes_be(ctx, &range, &slot), pf))
+        .collect();
+    let witness = chip.parse_eip1186_proofs_phase0(
+        &mut builder.gate_builder,
+        &mut keccak,
+        &state_root,
+        addr_bytes,
+        acct_pf,
+        storage_pfs,
+    );
+
+    let ctx = builder.gate_builder.main(FIRST_PHASE);
+    let address_is_empty = witness.0.mpt_witness.slot_is_empty;
+    let mut assigned_instances = vec![address_is_empty];
+    for storage_witness in &witness.1 {
+        let value = chip.parse_storage_value_phase0(ctx, storage_witness);
+        let slot_is_empty = storage_witness.mpt_witness.slot_is_empty;
+        let (value, slot_is_empty) =
+            chip.exclude_unset_slot_phase0(ctx, address_is_empty, value, slot_is_empty);
+        assigned_instances.extend(value.into_iter().chain([slot_is_empty]));
+    }
+
+    let circuit = EthCircuitBuilder::new(
+        assigned_instances,
+        builder,
+        RefCell::new(keccak),
+        range,
+        None,
+        move |builder: &mut RlcThreadBuilder<Fr>,
+              rlp: RlpChip<Fr>,
+              keccak_rlcs: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {
+            let chip = EthChip::new(rlp, Some(keccak_rlcs));
+            let _trace = chip.parse_eip1186_proofs_phase1(builder, witness);
+        },
+    );
+    circuit.config(k as usize, Some(params.unusable_rows));
+    let _scope = circuit.config_scope();
+    let instances = circuit.instance();
+    MockProver::run(k, &circuit, vec![instances.clone()]).unwrap().assert_satisfied();
+    instances
+}
+
+#[test]
+pub fn test_storage_inclusion_mock() {
+    let value = U256::from_str("0xf0e3b9aada6d89ddeb34aab7e9cd1744cf90d82f").unwrap();
+    let expected =
+        [vec![Fr::zero()], encode_u256_to_field::<Fr>(&value).to_vec(), vec![Fr::zero()]];
+    assert_eq!(storage_exclusion_mock("default_storage_pf"), expected.concat());
+}
+
+#[test]
+pub fn test_storage_noninclusion_branch_mock() {
+    let expected = vec![Fr::zero(), Fr::zero(), Fr::zero(), Fr::one()];
+    assert_eq!(storage_exclusion_mock("noninclusion_branch_pf"), expected);
+}
+
+#[test]
+pub fn test_storage_noninclusion_leaf_mock() {
+    // the proof ends at a leaf of another key
+    let expected = vec![Fr::zero(), Fr::zero(), Fr::zero(), Fr::one()];
+    assert_eq!(storage_exclusion_mock("noninclusion_extension_pf"), expected);
+}
+
+#[test]
+pub fn test_account_noninclusion_mock() {
+    // the account doesn't exist, its storage proof is empty against the empty storage root
+    let expected = vec![Fr::one(), Fr::zero(), Fr::zero(), Fr::one()];
+    assert_eq!(storage_exclusion_mock("empty_storage_pf"), expected);
+}
+
+/// Runs the [`EthBlockStorageCircuit`] with exclusion proofs over a fixture, returning its instances.
+fn storage_circuit_exclusion_mock(name: &str) -> Vec<Fr> {
+    let params = EthConfigParams::from_path("configs/tests/storage.json");
+    let k = params.degree;
+    let network = Network::Ethereum(EthereumNetwork::Mainnet);
+    let input = EthBlockStorageCircuit {
+        inputs: fixture_block_storage_input(read_proof_fixture(name)),
+        block_header_config: get_block_header_config(&network),
+        exclusion_proofs: true,
+    };
+    let instance = input.instance();
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    assert_eq!(circuit.instance(), instance);
+    MockProver::run(k, &circuit, vec![instance.clone()]).unwrap().assert_satisfied();
+    instance
+}
+
+#[test]
+pub fn test_storage_circuit_noninclusion_branch_mock() {
+    let instance = storage_circuit_exclusion_mock("noninclusion_branch_pf");
+    // blockHash, blockNumber, address, addressIsEmpty, slot, value, slotIsEmpty
+    assert_eq!(instance.len(), 10);
+    assert_eq!(instance[4], Fr::zero());
+    assert_eq!(instance[7..], [Fr::zero(), Fr::zero(), Fr::one()]);
+}
+
+#[test]
+pub fn test_storage_circuit_noninclusion_extension_mock() {
+    let instance = storage_circuit_exclusion_mock("noninclusion_extension_pf");
+    assert_eq!(instance.len(), 10);
+    assert_eq!(instance[4], Fr::zero());
+    assert_eq!(instance[7..], [Fr::zero(), Fr::zero(), Fr::one()]);
+}
+
+#[test]
+pub fn test_storage_circuit_name_has_exclusion_flag() {
+    let network = Network::Ethereum(EthereumNetwork::Mainnet);
+    let mut input = EthBlockStorageCircuit {
+        inputs: fixture_block_storage_input(read_proof_fixture("noninclusion_branch_pf")),
+        block_header_config: get_block_header_config(&network),
+        exclusion_proofs: false,
+    };
+    let circuit_type = SingleStorageTask::new(input.clone(), network).circuit_type();
+    assert_eq!(circuit_type.name(), "single_storage_ethereum_1");
+    input.exclusion_proofs = true;
+    let circuit_type = SingleStorageTask::new(input, network).circuit_type();
+    assert_eq!(circuit_type.name(), "single_storage_ethereum_1_exclusion");
+}
@@ -15,6 +15,8 @@ pub struct StorageConstructor {
     pub acct_pf_max_depth: usize,
     pub storage_pf_max_depth: usize,
     pub network: Network,
+    /// Proves that the account doesn't exist or that slots are unset, instead of requiring them to be set.
+    pub exclusion_proofs: bool,
 }
 
 pub fn get_mdc_storage_circuit(constructor: StorageConstructor) -> EthBlockStorageCircuit {
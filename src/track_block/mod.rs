@@ -5,7 +5,7 @@ use serde::Serialize;
 use std::cell::RefCell;
 
 use ethers_core::types::{Block, H256};
-use ethers_providers::{Http, Provider, RetryClient};
+use ethers_providers::Provider;
 use futures::AsyncReadExt;
 use halo2_base::gates::builder::GateThreadBuilder;
 use halo2_base::gates::RangeChip;
@@ -20,7 +20,6 @@ use crate::block_header::{
     get_block_header_config, BlockHeaderConfig, EthBlockHeaderChip, EthBlockHeaderTrace,
     EthBlockHeaderTraceWitness,
 };
-use crate::keccak::tests::get_block_data_hashes_from_json;
 use crate::keccak::{
     parallelize_keccak_phase0, FixedLenRLCs, FnSynthesize, KeccakChip, VarLenRLCs,
 };
@@ -31,16 +30,25 @@ use crate::rlp::rlc::FIRST_PHASE;
 use crate::rlp::RlpChip;
 use crate::storage::EthStorageChip;
 use crate::track_block::util::TrackBlockConstructor;
-use crate::util::helpers::{bytes_to_u8, get_block_batch_hashes, get_provider};
+use crate::util::helpers::{
+    bytes_to_u8, get_block_batch_hashes, get_block_data_hashes_from_json, get_provider,
+};
+use crate::util::rpc_client::RpcClient;
 use crate::util::{
-    bytes_be_to_u128, encode_h256_to_bytes_field, encode_merkle_path_to_field,
-    h256_non_standard_tree_root_and_proof, AssignedH256,
+    bytes_be_to_u128, encode_h256_to_bytes_field, encode_h256_to_field,
+    encode_merkle_path_to_field, h256_non_standard_tree_root_and_proof, AssignedH256,
 };
 use crate::{EthChip, EthCircuitBuilder, EthPreCircuit, Network, ETH_LOOKUP_BITS};
 
+#[cfg(test)]
 mod tests;
 pub mod util;
 
+/// `BlockMerkleInclusionCircuit` exposes `[merkle_root, target_leaf]` as hi-lo pairs for every batch.
+pub const BLOCK_MERKLE_INCLUSION_INSTANCES_PER_BATCH: usize = 4;
+/// Offset of the target leaf in the instances of a batch.
+pub const BLOCK_MERKLE_INCLUSION_TARGET_LEAF_OFFSET: usize = 2;
+
 #[derive(Clone, Debug, Serialize)]
 pub struct BlockMerkleInclusionInputSingle {
     pub merkle_root: H256,
@@ -128,17 +136,26 @@ impl BlockMerkleInclusionCircuit {
         //         get_block_batch_hashes(&provider, start_block_num.clone(), end_block_num.clone());
         // }
 
+        Self::from_batches(
+            constructors
+                .iter()
+                .map(|c| {
+                    (
+                        get_block_batch_hashes(&provider, c.start_block_num, c.end_block_num),
+                        (c.target_block_num - c.start_block_num),
+                    )
+                })
+                .collect_vec(),
+        )
+    }
+
+    /// Every batch is the block hashes of a block range and the index of the target block in it.
+    pub fn from_batches(batches: Vec<(Vec<H256>, u32)>) -> Self {
+        let block_range_length = batches[0].0.len() as u64;
+        let block_batch_num = batches.len() as u64;
         Self {
             inclusion_proof: BlockMerkleInclusionInput {
-                input: constructors
-                    .iter()
-                    .map(|c| {
-                        (
-                            get_block_batch_hashes(&provider, c.start_block_num, c.end_block_num),
-                            (c.target_block_num - c.start_block_num),
-                        )
-                    })
-                    .collect_vec()
+                input: batches
                     .iter()
                     .map(|(leaves, target_index)| {
                         let ((proof_root, proof, path), target_leaf) = (
@@ -154,11 +171,23 @@ impl BlockMerkleInclusionCircuit {
                     })
                     .collect_vec(),
             },
-            block_range_length: (constructors[0].end_block_num - constructors[0].start_block_num
-                + 1) as u64,
-            block_batch_num: constructors.len() as u64,
+            block_range_length,
+            block_batch_num,
         }
     }
+
+    /// `[merkle_root, target_leaf]` of every batch, as hi-lo pairs.
+    pub fn instance<F: Field>(&self) -> Vec<F> {
+        self.inclusion_proof
+            .input
+            .iter()
+            .flat_map(|input| {
+                encode_h256_to_field::<F>(&input.merkle_root)
+                    .into_iter()
+                    .chain(encode_h256_to_field::<F>(&input.target_leaf))
+            })
+            .collect()
+    }
 }
 
 impl EthPreCircuit for BlockMerkleInclusionCircuit {
@@ -191,6 +220,10 @@ impl EthPreCircuit for BlockMerkleInclusionCircuit {
 
         let assigned_instances =
             result.iter().map(|r| r.0.iter().chain(r.1.iter()).cloned()).flatten().collect_vec();
+        assert_eq!(
+            assigned_instances.len(),
+            result.len() * BLOCK_MERKLE_INCLUSION_INSTANCES_PER_BATCH
+        );
         println!("BlockMerkleInclusionCircuit pis cnt {}", assigned_instances.len());
         for i in 0..result.len() {
             println!("result {} {:?}", i, result[i]);
@@ -241,7 +274,7 @@ pub struct EthTrackBlockCircuit {
 
 impl EthTrackBlockCircuit {
     pub fn from_provider(
-        provider: &Provider<RetryClient<Http>>,
+        provider: &Provider<RpcClient>,
         constructor: TrackBlockConstructor,
     ) -> Self {
         let inputs = get_block_track_input(provider, &constructor);
@@ -1,11 +1,18 @@
+use ethers_core::types::H256;
+use ethers_core::utils::keccak256;
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use itertools::Itertools;
+
 use crate::halo2_proofs::dev::MockProver;
 use crate::rlp::builder::RlcThreadBuilder;
 use crate::track_block::util::TrackBlockConstructor;
-use crate::track_block::EthTrackBlockCircuit;
+use crate::track_block::{
+    BlockMerkleInclusionCircuit, EthTrackBlockCircuit, BLOCK_MERKLE_INCLUSION_INSTANCES_PER_BATCH,
+    BLOCK_MERKLE_INCLUSION_TARGET_LEAF_OFFSET,
+};
 use crate::util::helpers::get_provider;
-use crate::util::EthConfigParams;
+use crate::util::{encode_h256_to_field, EthConfigParams};
 use crate::{EthPreCircuit, EthereumNetwork, Network};
-use std::env::set_var;
 
 fn get_test_circuit(blocks_number: Vec<u64>, network: Network) -> EthTrackBlockCircuit {
     let provider = get_provider(&network);
@@ -16,12 +23,31 @@ fn get_test_circuit(blocks_number: Vec<u64>, network: Network) -> EthTrackBlockC
 #[test]
 pub fn test_track_block() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigParams::from_path("configs/tests/track_block.json");
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree;
     let blocks_number = vec![17113952, 17113957, 17113959];
 
     let input = get_test_circuit(blocks_number, Network::Ethereum(EthereumNetwork::Mainnet));
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
     Ok(())
 }
+
+#[test]
+pub fn test_block_merkle_inclusion_instance() {
+    let params = EthConfigParams::from_path("configs/tests/track_block.json");
+    let k = params.degree;
+    let leaves = (0..8u64).map(|i| H256(keccak256(i.to_be_bytes()))).collect_vec();
+    let input =
+        BlockMerkleInclusionCircuit::from_batches(vec![(leaves.clone(), 3), (leaves.clone(), 6)]);
+    let instance: Vec<Fr> = input.instance();
+    assert_eq!(instance.len(), 2 * BLOCK_MERKLE_INCLUSION_INSTANCES_PER_BATCH);
+    let target_leaf =
+        BLOCK_MERKLE_INCLUSION_INSTANCES_PER_BATCH + BLOCK_MERKLE_INCLUSION_TARGET_LEAF_OFFSET;
+    assert_eq!(instance[target_leaf..target_leaf + 2], encode_h256_to_field::<Fr>(&leaves[6]));
+
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    assert_eq!(circuit.instance(), instance);
+    MockProver::run(k, &circuit, vec![instance]).unwrap().assert_satisfied();
+}
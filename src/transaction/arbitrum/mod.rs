@@ -0,0 +1,257 @@
+use std::cell::RefCell;
+
+use ethers_providers::Provider;
+use halo2_base::gates::builder::GateThreadBuilder;
+use halo2_base::gates::{GateInstructions, RangeChip};
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::AssignedValue;
+use itertools::Itertools;
+use snark_verifier::loader::halo2::halo2_ecc::secp256k1::{FpChip, FqChip};
+use zkevm_keccak::util::eth_types::Field;
+
+use crate::block_header::{get_block_header_config, BlockHeaderConfig, EthBlockHeaderChip};
+use crate::ecdsa::EcdsaChip;
+use crate::keccak::{FixedLenRLCs, FnSynthesize, KeccakChip, VarLenRLCs};
+use crate::providers::get_transaction_input;
+use crate::rlp::builder::{RlcThreadBreakPoints, RlcThreadBuilder};
+use crate::rlp::rlc::FIRST_PHASE;
+use crate::rlp::RlpChip;
+use crate::transaction::constrain_signed_transaction_type;
+use crate::transaction::ethereum::{
+    EIP1186ResponseDigest, EthBlockTransactionChip, EthBlockTransactionInput,
+    EthBlockTransactionInputAssigned, EthBlockTransactionTrace, EthBlockTransactionTraceWitness,
+    EthTransactionField,
+};
+use crate::transaction::util::TransactionConstructor;
+use crate::util::rpc_client::RpcClient;
+use crate::util::{bytes_be_to_uint, AssignedH256};
+use crate::{
+    EthChip, EthCircuitBuilder, EthPreCircuit, ETH_LIMB_BITS, ETH_LOOKUP_BITS, ETH_NUM_LIMBS,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Arbitrum specific transaction envelopes, see https://github.com/OffchainLabs/go-ethereum/blob/master/core/types/transaction.go
+/// They are produced by the sequencer / ArbOS and are never signed by a user, so they can not be a cross-chain transfer.
+pub const ARBITRUM_DEPOSIT_TX_TYPE: u8 = 0x64;
+pub const ARBITRUM_UNSIGNED_TX_TYPE: u8 = 0x65;
+pub const ARBITRUM_CONTRACT_TX_TYPE: u8 = 0x66;
+pub const ARBITRUM_RETRY_TX_TYPE: u8 = 0x68;
+pub const ARBITRUM_SUBMIT_RETRYABLE_TX_TYPE: u8 = 0x69;
+pub const ARBITRUM_INTERNAL_TX_TYPE: u8 = 0x6a;
+pub const ARBITRUM_LEGACY_TX_TYPE: u8 = 0x78;
+
+/**
+Arbitrum Nitro keeps the Ethereum header layout (without withdrawalsRoot), but repurposes some fields,
+see https://github.com/OffchainLabs/go-ethereum/blob/master/core/types/arb_types.go#L434
+
+| Field                        | Arbitrum meaning                                                   |
+|------------------------------|--------------------------------------------------------------------|
+| difficulty                   | always 1                                                           |
+| gasLimit                     | always 0x4000000000000 (7 bytes)                                   |
+| extraData                    | sendRoot (32 bytes)                                                |
+| mixHash                      | sendCount (8) + l1BlockNumber (8) + arbosVersion (8) + zeros (8)   |
+| nonce                        | number of delayed inbox messages read                              |
+ */
+const ARBITRUM_MIX_HASH_L1_BLOCK_NUMBER_OFFSET: usize = 8;
+const ARBITRUM_MIX_HASH_L1_BLOCK_NUMBER_BYTES_LEN: usize = 8;
+
+#[derive(Clone, Debug)]
+pub struct ArbitrumBlockTransactionCircuit {
+    pub inputs: EthBlockTransactionInput,
+    pub block_header_config: BlockHeaderConfig,
+}
+
+impl ArbitrumBlockTransactionCircuit {
+    pub fn from_provider(
+        provider: &Provider<RpcClient>,
+        constructor: TransactionConstructor,
+    ) -> Self {
+        let inputs = get_transaction_input(
+            provider,
+            constructor.transaction_hash,
+            constructor.transaction_index_bytes,
+            constructor.transaction_rlp.unwrap(),
+            constructor.merkle_proof.unwrap(),
+            constructor.transaction_pf_max_depth.unwrap(),
+        );
+        let block_header_config = get_block_header_config(&constructor.network);
+        Self { inputs, block_header_config }
+    }
+}
+
+impl EthPreCircuit for ArbitrumBlockTransactionCircuit {
+    fn create(
+        self,
+        mut builder: RlcThreadBuilder<Fr>,
+        break_points: Option<RlcThreadBreakPoints>,
+    ) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
+        let range = RangeChip::default(ETH_LOOKUP_BITS);
+        let chip = EthChip::new(RlpChip::new(&range, None), None);
+        let mut keccak = KeccakChip::default();
+        let fp_chip = FpChip::new(&range, ETH_LIMB_BITS, ETH_NUM_LIMBS);
+        let fq_chip = FqChip::new(&range, ETH_LIMB_BITS, ETH_NUM_LIMBS);
+        let ecdsa = EcdsaChip::new(&fp_chip, &fq_chip);
+
+        // ================= FIRST PHASE ================
+        let ctx = builder.gate_builder.main(FIRST_PHASE);
+        let input = self.inputs.assign(ctx);
+        let (witness, digest) = chip.parse_arbitrum_transaction_proof_from_block_phase0(
+            &mut builder.gate_builder,
+            &mut keccak,
+            &ecdsa,
+            input,
+            &self.block_header_config,
+        );
+
+        let ArbitrumTransactionDigest {
+            index,
+            block_hash,
+            transaction_is_empty,
+            transaction_field,
+            l1_block_number,
+        } = digest;
+
+        let assigned_instances = block_hash
+            .into_iter()
+            .chain(transaction_field.hash)
+            .chain([
+                transaction_field.chain_id,
+                index,
+                transaction_field.from,
+                transaction_field.to,
+                transaction_field.token,
+                transaction_field.amount,
+                transaction_field.nonce,
+                transaction_field.time_stamp,
+                transaction_field.dest_transfer_address,
+                transaction_field.dest_transfer_token,
+                l1_block_number,
+            ])
+            .collect_vec();
+
+        {
+            let ctx = builder.gate_builder.main(FIRST_PHASE);
+            range.gate.assert_is_const(ctx, &transaction_is_empty, &Fr::zero());
+        }
+
+        EthCircuitBuilder::new(
+            assigned_instances,
+            builder,
+            RefCell::new(keccak),
+            range,
+            break_points,
+            move |builder: &mut RlcThreadBuilder<Fr>,
+                  rlp: RlpChip<Fr>,
+                  keccak_rlcs: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {
+                // ======== SECOND PHASE ===========
+                let chip = EthChip::new(rlp, Some(keccak_rlcs));
+                let _trace =
+                    chip.parse_arbitrum_transaction_proof_from_block_phase1(builder, witness);
+            },
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArbitrumTransactionDigest<F: Field> {
+    pub index: AssignedValue<F>,
+    pub block_hash: AssignedH256<F>,
+    pub transaction_is_empty: AssignedValue<F>,
+    pub transaction_field: EthTransactionField<F>,
+    // the L1 block number the sequencer has seen when producing the L2 block, read from mixHash
+    pub l1_block_number: AssignedValue<F>,
+}
+
+pub trait ArbitrumBlockTransactionChip<F: Field> {
+    // ================= FIRST PHASE ================
+
+    fn parse_arbitrum_transaction_proof_from_block_phase0(
+        &self,
+        thread_pool: &mut GateThreadBuilder<F>,
+        keccak: &mut KeccakChip<F>,
+        ecdsa: &EcdsaChip<F>,
+        input: EthBlockTransactionInputAssigned<F>,
+        block_header_config: &BlockHeaderConfig,
+    ) -> (EthBlockTransactionTraceWitness<F>, ArbitrumTransactionDigest<F>)
+    where
+        Self: EthBlockHeaderChip<F> + EthBlockTransactionChip<F>;
+
+    // ================= SECOND PHASE ================
+
+    fn parse_arbitrum_transaction_proof_from_block_phase1(
+        &self,
+        thread_pool: &mut RlcThreadBuilder<F>,
+        witness: EthBlockTransactionTraceWitness<F>,
+    ) -> EthBlockTransactionTrace<F>
+    where
+        Self: EthBlockHeaderChip<F> + EthBlockTransactionChip<F>;
+}
+
+impl<'chip, F: Field> ArbitrumBlockTransactionChip<F> for EthChip<'chip, F> {
+    // ================= FIRST PHASE ================
+
+    fn parse_arbitrum_transaction_proof_from_block_phase0(
+        &self,
+        thread_pool: &mut GateThreadBuilder<F>,
+        keccak: &mut KeccakChip<F>,
+        ecdsa: &EcdsaChip<F>,
+        input: EthBlockTransactionInputAssigned<F>,
+        block_header_config: &BlockHeaderConfig,
+    ) -> (EthBlockTransactionTraceWitness<F>, ArbitrumTransactionDigest<F>)
+    where
+        Self: EthBlockHeaderChip<F> + EthBlockTransactionChip<F>,
+    {
+        {
+            let ctx = thread_pool.main(FIRST_PHASE);
+            let transaction_type =
+                *input.transaction.transaction_proofs.value_bytes.first().unwrap();
+            constrain_signed_transaction_type(ctx, self.range(), transaction_type);
+        }
+
+        // The transactions trie, the signature and the transaction encoding are the same as Ethereum.
+        let (witness, digest) = self.parse_transaction_proof_from_block_phase0(
+            thread_pool,
+            keccak,
+            ecdsa,
+            input,
+            block_header_config,
+        );
+        let EIP1186ResponseDigest { index, block_hash, transaction_is_empty, transaction_field } =
+            digest;
+
+        let ctx = thread_pool.main(FIRST_PHASE);
+        let mix_hash = &witness.block_witness.get_mix_hash().field_cells;
+        let l1_block_number = bytes_be_to_uint(
+            ctx,
+            self.gate(),
+            &mix_hash[ARBITRUM_MIX_HASH_L1_BLOCK_NUMBER_OFFSET
+                ..ARBITRUM_MIX_HASH_L1_BLOCK_NUMBER_OFFSET
+                    + ARBITRUM_MIX_HASH_L1_BLOCK_NUMBER_BYTES_LEN],
+            ARBITRUM_MIX_HASH_L1_BLOCK_NUMBER_BYTES_LEN,
+        );
+
+        let digest = ArbitrumTransactionDigest {
+            index,
+            block_hash,
+            transaction_is_empty,
+            transaction_field,
+            l1_block_number,
+        };
+        (witness, digest)
+    }
+
+    // ================= SECOND PHASE ================
+
+    fn parse_arbitrum_transaction_proof_from_block_phase1(
+        &self,
+        thread_pool: &mut RlcThreadBuilder<F>,
+        witness: EthBlockTransactionTraceWitness<F>,
+    ) -> EthBlockTransactionTrace<F>
+    where
+        Self: EthBlockHeaderChip<F> + EthBlockTransactionChip<F>,
+    {
+        self.parse_transaction_proof_from_block_phase1(thread_pool, witness)
+    }
+}
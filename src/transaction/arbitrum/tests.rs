@@ -0,0 +1,117 @@
+use ethers_core::types::transaction::eip2718::TypedTransaction;
+use ethers_core::types::{
+    Address, Block, Bloom, Bytes, Eip1559TransactionRequest, H256, H64, U256,
+};
+use halo2_base::gates::builder::{GateThreadBuilder, RangeCircuitBuilder};
+use halo2_base::gates::RangeChip;
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+
+use crate::block_header::get_block_header_config;
+use crate::halo2_proofs::dev::MockProver;
+use crate::rlp::builder::RlcThreadBuilder;
+use crate::rlp::rlc::FIRST_PHASE;
+use crate::transaction::arbitrum::{
+    ArbitrumBlockTransactionCircuit, ARBITRUM_CONTRACT_TX_TYPE, ARBITRUM_DEPOSIT_TX_TYPE,
+    ARBITRUM_INTERNAL_TX_TYPE, ARBITRUM_LEGACY_TX_TYPE, ARBITRUM_RETRY_TX_TYPE,
+    ARBITRUM_SUBMIT_RETRYABLE_TX_TYPE, ARBITRUM_UNSIGNED_TX_TYPE,
+};
+use crate::transaction::tests::{sign_transaction, single_transaction_block_input};
+use crate::transaction::{
+    constrain_signed_transaction_type, EIP_1559_TX_TYPE, EIP_2718_TX_TYPE, EIP_2930_TX_TYPE,
+};
+use crate::util::circuit_config::{isolate_env, CircuitConfig, CircuitConfigScope};
+use crate::util::{encode_h256_to_field, EthConfigParams};
+use crate::{ArbitrumNetwork, EthPreCircuit, Network, ETH_LOOKUP_BITS};
+
+const K: u32 = 10;
+
+fn transaction_type_circuit(transaction_type: u8) -> (RangeCircuitBuilder<Fr>, CircuitConfigScope) {
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(ETH_LOOKUP_BITS);
+    let ctx = builder.main(FIRST_PHASE);
+    let transaction_type = ctx.load_witness(Fr::from(transaction_type as u64));
+    constrain_signed_transaction_type(ctx, &range, transaction_type);
+    let params = isolate_env(|| builder.config(K as usize, Some(20)));
+    let scope =
+        CircuitConfigScope::enter(CircuitConfig::Gate { params, lookup_bits: ETH_LOOKUP_BITS });
+    (RangeCircuitBuilder::mock(builder), scope)
+}
+
+#[test]
+pub fn test_arbitrum_user_transaction_type() {
+    for transaction_type in [EIP_2718_TX_TYPE, EIP_2930_TX_TYPE, EIP_1559_TX_TYPE] {
+        let (circuit, _scope) = transaction_type_circuit(transaction_type);
+        MockProver::run(K, &circuit, vec![]).unwrap().assert_satisfied();
+    }
+}
+
+#[test]
+pub fn test_arbitrum_system_transaction_type_rejected() {
+    for transaction_type in [
+        ARBITRUM_DEPOSIT_TX_TYPE,
+        ARBITRUM_UNSIGNED_TX_TYPE,
+        ARBITRUM_CONTRACT_TX_TYPE,
+        ARBITRUM_RETRY_TX_TYPE,
+        ARBITRUM_SUBMIT_RETRYABLE_TX_TYPE,
+        ARBITRUM_INTERNAL_TX_TYPE,
+        ARBITRUM_LEGACY_TX_TYPE,
+    ] {
+        let (circuit, _scope) = transaction_type_circuit(transaction_type);
+        assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+    }
+}
+
+const L1_BLOCK_NUMBER: u64 = 19_000_000;
+
+/// A Nitro block whose only transaction is a signed EIP-1559 transfer.
+fn get_arbitrum_transfer_circuit() -> ArbitrumBlockTransactionCircuit {
+    let tx = TypedTransaction::Eip1559(
+        Eip1559TransactionRequest::new()
+            .chain_id(ArbitrumNetwork::Mainnet as u64)
+            .nonce(7)
+            .to(Address::from_low_u64_be(0xbeef))
+            .value(U256::exp10(16))
+            .gas(21_000)
+            .max_priority_fee_per_gas(0)
+            .max_fee_per_gas(100_000_000),
+    );
+    let transaction_rlp = sign_transaction(&tx).to_vec();
+
+    // sendCount, l1BlockNumber, arbosVersion and zeros
+    let mut mix_hash = [0u8; 32];
+    mix_hash[..8].copy_from_slice(&1_000u64.to_be_bytes());
+    mix_hash[8..16].copy_from_slice(&L1_BLOCK_NUMBER.to_be_bytes());
+    mix_hash[16..24].copy_from_slice(&11u64.to_be_bytes());
+    let block = Block::<H256> {
+        author: Some("0xa4b000000000000000000073657175656e636572".parse().unwrap()),
+        number: Some(150_000_000u64.into()),
+        difficulty: U256::one(),
+        gas_limit: U256::from(0x4000000000000u64),
+        gas_used: 21_000.into(),
+        timestamp: 1_700_000_000.into(),
+        // sendRoot
+        extra_data: Bytes::from(H256::repeat_byte(0x5e).0.to_vec()),
+        logs_bloom: Some(Bloom::zero()),
+        mix_hash: Some(H256(mix_hash)),
+        nonce: Some(H64::from_low_u64_be(1_500)),
+        base_fee_per_gas: Some(10_000_000.into()),
+        ..Default::default()
+    };
+    let inputs = single_transaction_block_input(block, transaction_rlp);
+    let block_header_config = get_block_header_config(&Network::Arbitrum(ArbitrumNetwork::Mainnet));
+    ArbitrumBlockTransactionCircuit { inputs, block_header_config }
+}
+
+#[test]
+pub fn test_arbitrum_transaction_mock() {
+    let params = EthConfigParams::from_path("configs/tests/transaction.json");
+    let k = params.degree;
+    let input = get_arbitrum_transfer_circuit();
+    let block_hash = input.inputs.block_hash;
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    let instance = circuit.instance();
+    assert_eq!(instance[..2], encode_h256_to_field::<Fr>(&block_hash));
+    assert_eq!(*instance.last().unwrap(), Fr::from(L1_BLOCK_NUMBER));
+    MockProver::run(k, &circuit, vec![instance]).unwrap().assert_satisfied();
+}
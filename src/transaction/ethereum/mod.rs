@@ -2,7 +2,7 @@ use ethers_core::abi::AbiEncode;
 use std::cell::RefCell;
 
 use ethers_core::types::{Block, Bytes, H256};
-use ethers_providers::{Http, Provider, RetryClient};
+use ethers_providers::Provider;
 use halo2_base::gates::builder::GateThreadBuilder;
 use halo2_base::gates::{GateInstructions, RangeChip, RangeInstructions};
 use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
@@ -24,23 +24,23 @@ use crate::mpt::{AssignedBytes, MPTInput, MPTProof, MPTProofWitness};
 use crate::providers::get_transaction_input;
 use crate::rlp::builder::{RlcThreadBreakPoints, RlcThreadBuilder};
 use crate::rlp::rlc::{RlcContextPair, FIRST_PHASE};
-use crate::rlp::{RlpArrayTraceWitness, RlpChip, RlpFieldTrace, RlpFieldWitness};
+use crate::rlp::{RlpArrayTrace, RlpArrayTraceWitness, RlpChip, RlpFieldTrace, RlpFieldWitness};
 use crate::storage::EthStorageChip;
 use crate::transaction::util::TransactionConstructor;
 use crate::transaction::{
-    calculate_tx_max_fields_len, load_transaction_type, CALLDATA_BYTES_LEN,
-    EIP_1559_TX_TYPE_FIELDS_MAX_FIELDS_LEN, EIP_2718_TX_TYPE,
-    EIP_2718_TX_TYPE_FIELDS_MAX_FIELDS_LEN, EIP_TX_TYPE_CRITICAL_VALUE, ERC20_TO_ADDRESS_BYTES_LEN,
-    FUNCTION_SELECTOR_BYTES_LEN, FUNCTION_SELECTOR_ERC20_TRANSFER,
-};
-use crate::util::helpers::load_bytes;
-use crate::util::{
-    bytes_be_to_u128, bytes_be_to_uint, bytes_be_var_to_fixed, encode_h256_to_field, AssignedH256,
+    calculate_blob_tx_max_fields_len, calculate_legacy_tx_max_fields_len,
+    calculate_tx_max_fields_len, load_transaction_type, transaction_envelope, EthTransactionType,
+    ABI_WORD_BYTES_LEN, CALLDATA_BYTES_LEN, EIP_4844_BLOB_VERSIONED_HASHES_INDEX, EIP_4844_TX_TYPE,
+    EIP_TX_TYPE_CRITICAL_VALUE, FUNCTION_SELECTOR_BYTES_LEN, FUNCTION_SELECTOR_ERC20_TRANSFER,
+    FUNCTION_SELECTOR_ERC20_TRANSFER_FROM, MAX_BLOBS_PER_TX, TRANSFER_FROM_CALLDATA_BYTES_LEN,
 };
+use crate::util::rpc_client::RpcClient;
+use crate::util::{bytes_be_to_u128, bytes_be_to_uint, encode_h256_to_field, AssignedH256};
 use crate::{
     EthChip, EthCircuitBuilder, EthPreCircuit, ETH_LIMB_BITS, ETH_LOOKUP_BITS, ETH_NUM_LIMBS,
 };
 
+#[cfg(test)]
 pub mod tests;
 // lazy_static! {
 //     static ref KECCAK_RLP_EMPTY_STRING: Vec<u8> =
@@ -67,8 +67,11 @@ pub struct EthTransactionInputAssigned<F: Field> {
 impl EthTransactionInput {
     pub fn assign<F: Field>(self, ctx: &mut Context<F>) -> EthTransactionInputAssigned<F> {
         let transaction_index = ctx.load_witness(F::from(self.transaction_index));
+        let transaction_value_max_len = self.transaction_proofs.value_max_byte_len;
         let transaction_proofs = self.transaction_proofs.assign(ctx);
-        let transaction_ecdsa_verify = self.transaction_ecdsa_verify.assign(ctx);
+        // the signing payload is never longer than the signed transaction
+        let transaction_ecdsa_verify =
+            self.transaction_ecdsa_verify.assign(ctx, Some(transaction_value_max_len));
         EthTransactionInputAssigned {
             transaction_index,
             transaction_proofs,
@@ -108,7 +111,7 @@ pub struct EthBlockTransactionCircuit {
 
 impl EthBlockTransactionCircuit {
     pub fn from_provider(
-        provider: &Provider<RetryClient<Http>>,
+        provider: &Provider<RpcClient>,
         constructor: TransactionConstructor,
     ) -> Self {
         let inputs = get_transaction_input(
@@ -231,6 +234,7 @@ pub struct EIP1186ResponseDigest<F: Field> {
 #[derive(Clone, Debug)]
 pub struct EthTransactionTrace<F: Field> {
     pub value_trace: Vec<RlpFieldTrace<F>>,
+    pub blob_versioned_hashes_trace: Option<RlpArrayTrace<F>>,
 }
 
 #[derive(Clone, Debug)]
@@ -250,13 +254,18 @@ pub struct EthTransactionExtraWitness<F: Field> {
     pub nonce: AssignedValue<F>,
     pub dest_transfer_address: AssignedValue<F>,
     pub dest_transfer_token: AssignedValue<F>,
+    // EIP-4844 only: always `MAX_BLOBS_PER_TX` hashes, the unused ones are 0
+    pub blob_versioned_hashes: Vec<AssignedH256<F>>,
+    pub blob_versioned_hashes_witness: Option<RlpArrayTraceWitness<F>>,
+    // the unsigned signing payload, `None` for transactions without a signature
+    pub message_witness: Option<RlpArrayTraceWitness<F>>,
 }
 
 #[derive(Clone, Debug)]
 pub struct EthTransactionTraceWitness<F: Field> {
-    transaction_witness: RlpArrayTraceWitness<F>,
-    mpt_witness: MPTProofWitness<F>,
-    extra_witness: EthTransactionExtraWitness<F>,
+    pub transaction_witness: RlpArrayTraceWitness<F>,
+    pub mpt_witness: MPTProofWitness<F>,
+    pub extra_witness: EthTransactionExtraWitness<F>,
 }
 
 impl<F: Field> EthTransactionTraceWitness<F> {
@@ -357,6 +366,21 @@ pub trait EthBlockTransactionChip<F: Field> {
         ctx: RlcContextPair<F>,
         witness: EthTransactionTraceWitness<F>,
     ) -> EthTransactionTrace<F>;
+
+    fn parse_blob_versioned_hashes_phase1(
+        &self,
+        ctx: RlcContextPair<F>,
+        blob_field_witness: RlpFieldWitness<F>,
+        blob_field_trace: &RlpFieldTrace<F>,
+        blob_witness: RlpArrayTraceWitness<F>,
+    ) -> RlpArrayTrace<F>;
+
+    fn parse_transaction_signing_payload_phase1(
+        &self,
+        ctx: RlcContextPair<F>,
+        value_trace: &[RlpFieldTrace<F>],
+        message_witness: RlpArrayTraceWitness<F>,
+    ) -> RlpArrayTrace<F>;
 }
 
 impl<'chip, F: Field> EthBlockTransactionChip<F> for EthChip<'chip, F> {
@@ -493,24 +517,45 @@ impl<'chip, F: Field> EthBlockTransactionChip<F> for EthChip<'chip, F> {
 
         let zero = ctx.load_constant(F::from(0));
         let one = ctx.load_constant(F::from(1));
-        let is_not_legacy_transaction =
-            self.range().is_less_than(ctx, *transaction_type, tx_type_critical_value, NUM_BITS);
-
-        let mut transaction_rlp_bytes = transaction_value.to_vec();
-        let mut field_lens = EIP_2718_TX_TYPE_FIELDS_MAX_FIELDS_LEN.to_vec();
-        let mut join_hash_len = zero;
 
-        if is_not_legacy_transaction.value == zero.value {
-            let legacy_transaction_type = load_transaction_type(ctx, EIP_2718_TX_TYPE);
-            ctx.constrain_equal(transaction_type, &legacy_transaction_type);
-        } else {
-            field_lens = calculate_tx_max_fields_len(transaction_rlp_bytes.len());
-
-            println!("field_lens:{:?}", field_lens);
-            transaction_rlp_bytes = transaction_rlp_bytes[1..].to_vec();
+        // the envelope is part of the config, legacy and blob transactions have their own tx_max_len
+        let envelope = transaction_envelope(transaction_value.len());
+        let is_legacy_transaction = envelope == EthTransactionType::LegacyTxType;
+        let is_blob_transaction = envelope == EthTransactionType::BlobTxType;
+        let is_typed_transaction =
+            self.range().is_less_than(ctx, *transaction_type, tx_type_critical_value, NUM_BITS);
+        self.gate().assert_is_const(
+            ctx,
+            &is_typed_transaction,
+            &F::from(!is_legacy_transaction as u64),
+        );
+        let blob_transaction_type = load_transaction_type(ctx, EIP_4844_TX_TYPE);
+        let is_blob_transaction_type =
+            self.gate().is_equal(ctx, *transaction_type, blob_transaction_type);
+        self.gate().assert_is_const(
+            ctx,
+            &is_blob_transaction_type,
+            &F::from(is_blob_transaction as u64),
+        );
 
-            join_hash_len = one;
-        }
+        // typed transactions are hashed with their type byte, which is not part of the rlp list
+        let (transaction_rlp_bytes, field_lens, join_hash_len) = match envelope {
+            EthTransactionType::LegacyTxType => (
+                transaction_value.to_vec(),
+                calculate_legacy_tx_max_fields_len(transaction_value.len()),
+                zero,
+            ),
+            EthTransactionType::BlobTxType => (
+                transaction_value[1..].to_vec(),
+                calculate_blob_tx_max_fields_len(transaction_value.len()),
+                one,
+            ),
+            _ => (
+                transaction_value[1..].to_vec(),
+                calculate_tx_max_fields_len(transaction_value.len()),
+                one,
+            ),
+        };
 
         let transaction_witness = self.rlp().decompose_rlp_array_phase0(
             ctx,
@@ -519,49 +564,90 @@ impl<'chip, F: Field> EthBlockTransactionChip<F> for EthChip<'chip, F> {
             true,
         );
 
+        // blobVersionedHashes is a list itself, it is decomposed from the bytes of its own rlp field
+        let mut blob_versioned_hashes = Vec::new();
+        let mut blob_versioned_hashes_witness = None;
+        if is_blob_transaction {
+            let blob_field_witness =
+                &transaction_witness.field_witness[EIP_4844_BLOB_VERSIONED_HASHES_INDEX];
+            let witness = self.rlp().decompose_rlp_array_phase0(
+                ctx,
+                blob_field_witness.rlp_field.clone(),
+                &[32; MAX_BLOBS_PER_TX],
+                true,
+            );
+            ctx.constrain_equal(&witness.rlp_len, &blob_field_witness.rlp_field_len);
+            blob_versioned_hashes = witness
+                .field_witness
+                .iter()
+                .map(|hash_witness| {
+                    bytes_be_to_u128(ctx, self.gate(), &hash_witness.field_cells)
+                        .try_into()
+                        .unwrap()
+                })
+                .collect_vec();
+            blob_versioned_hashes_witness = Some(witness);
+        }
+
+        // The signature is over the signing payload, which is decomposed here and bound field by field
+        // to the signed transaction in phase1:
+        // legacy: rlp([nonce,gasPrice,gasLimit,to,value,data,chainId,0,0]) (EIP-155)
+        // typed: type || rlp(fields without v,r,s) (EIP-2718)
+        let signed_fields_num = field_lens.len() - 3;
+        // the payload is zero padded to the length of the signed transaction when it is assigned
+        let message = transaction_ecdsa_verify.message.clone();
+        assert_eq!(message.len(), transaction_value.len());
+        for byte in message.iter() {
+            self.range().range_check(ctx, *byte, 8);
+        }
+        let (message_rlp_bytes, message_field_lens) = if is_legacy_transaction {
+            (message.clone(), [&field_lens[..signed_fields_num], &[32, 0, 0][..]].concat())
+        } else {
+            ctx.constrain_equal(&message[0], transaction_type);
+            (message[1..].to_vec(), field_lens[..signed_fields_num].to_vec())
+        };
+        let message_witness = self.rlp().decompose_rlp_array_phase0(
+            ctx,
+            message_rlp_bytes,
+            &message_field_lens,
+            false,
+        );
+
         // parse calldata Todo:Need to separate 2718 from 1559
         let mut calldata_witness;
         let mut tx_chain_id;
-        let mut tx_token_address = zero; // Eth is 0x00;Erc20 is tx's to
         let mut tx_to_witness;
         let mut tx_amount_witness;
         let mut tx_nonce_witness;
 
-        if is_not_legacy_transaction.value == zero.value {
+        if is_legacy_transaction {
             // [nonce,gasPrice,gasLimit,to,value,data,v,r,s]
             calldata_witness = &transaction_witness.field_witness[5];
             tx_to_witness = &transaction_witness.field_witness[3];
             tx_amount_witness = &transaction_witness.field_witness[4];
             tx_nonce_witness = &transaction_witness.field_witness[0];
 
-            // Derive the original chain ID
-            //         let numSub
-            //         if ((v - 35) % 2 === 0) {
-            //           numSub = 35
-            //         } else {
-            //           numSub = 36
-            //         }
-            //         // Use derived chain ID to create a proper Common
-            //         chainIdBigInt = BigInt(v - numSub) / BigInt(2)
+            // Only EIP-155 signatures are supported, v = 35 + 2 * chainId + recoveryId.
+            // A pre EIP-155 v of 27 or 28 wraps around in v - 35 and fails the range check.
             let tx_v_witness = &transaction_witness.field_witness[6];
             let tx_v = self.rlp_field_witnesses_to_uint(ctx, vec![tx_v_witness], vec![32])[0];
-            // v - 35
             let dividend = self.gate().sub(ctx, tx_v, Constant(F::from(35)));
-            // (v - 35) % 2
-            let divisor = 2u64;
-            let divisor_assigned = Constant(F::from(divisor));
-            let (quotient, remainder) = self.range().div_mod(ctx, dividend, divisor, 32);
-            // (v - 35) % 2 === 0
-            // Whether the result of multiplying the quotient by the divisor and adding the remainder is equal to the dividend
-            let divisor_mul_quotient = self.gate().mul(ctx, divisor_assigned, quotient);
-            let expect_dividend = self.gate().add(ctx, divisor_mul_quotient, remainder);
-            let is_equal = self.gate().is_equal(ctx, dividend, expect_dividend);
-            // num_sub = 36 - ( is_equal )
-            let num_sub = self.gate().sub(ctx, Constant(F::from(36)), is_equal);
-            let tx_v_sub_num_sub = self.gate().sub(ctx, tx_v, num_sub);
-            tx_chain_id = self.gate().div_unsafe(ctx, tx_v_sub_num_sub, divisor_assigned);
+            let (quotient, _recovery_id) = self.range().div_mod(ctx, dividend, 2u64, 64);
+            tx_chain_id = quotient;
+
+            // EIP-155 signing payload ends with [chainId,0,0] instead of [v,r,s]
+            let message_chain_id = self.rlp_field_witnesses_to_uint(
+                ctx,
+                vec![&message_witness.field_witness[signed_fields_num]],
+                vec![32],
+            )[0];
+            ctx.constrain_equal(&message_chain_id, &tx_chain_id);
+            for field_witness in &message_witness.field_witness[signed_fields_num + 1..] {
+                ctx.constrain_equal(&field_witness.field_len, &zero);
+            }
         } else {
             // [chainId,nonce,maxPriorityFeePerGas,maxFeePerGas,gasLimit,to,value,data,accessList,v,r,s]
+            // EIP-4844 shares the first 9 fields
             calldata_witness = &transaction_witness.field_witness[7];
             tx_to_witness = &transaction_witness.field_witness[5];
             tx_amount_witness = &transaction_witness.field_witness[6];
@@ -582,65 +668,8 @@ impl<'chip, F: Field> EthBlockTransactionChip<F> for EthChip<'chip, F> {
             vec![&tx_to_witness, &tx_amount_witness],
             vec![32, 32],
         );
-        let mut tx_to = tx_fields[0];
-        let mut tx_amount = tx_fields[1];
-
-        let function_selector = load_bytes(ctx, &FUNCTION_SELECTOR_ERC20_TRANSFER);
-
-        let mut new_calldata = Vec::with_capacity(CALLDATA_BYTES_LEN);
-        let calldata_is_erc20_bytes_len = ctx.load_constant(F::from(CALLDATA_BYTES_LEN as u64));
-
-        // Determine whether the length of the calldata meets the length required by ERC20
-        if calldata_witness.field_len.value == calldata_is_erc20_bytes_len.value {
-            let calldata = calldata_witness.field_cells[0..CALLDATA_BYTES_LEN - 1].to_vec();
-            let mut is_function_selector = ctx.load_constant(F::from(1));
-
-            for i in 0..CALLDATA_BYTES_LEN - 1 {
-                let val_byte = self.gate().select(ctx, calldata[i + 1], calldata[i], zero);
-
-                if i >= 0 && i <= FUNCTION_SELECTOR_BYTES_LEN - 1 {
-                    let byte_is_equal =
-                        self.gate().is_equal(ctx, calldata[i], function_selector[i]);
-                    is_function_selector =
-                        self.gate().mul(ctx, is_function_selector, byte_is_equal);
-                }
-                new_calldata.push(val_byte);
-            }
-
-            let val_byte = self.gate().select(ctx, zero, calldata[CALLDATA_BYTES_LEN - 1], zero);
-            new_calldata.push(val_byte);
-
-            // is erc20 transaction
-            if is_function_selector.value != zero.value {
-                let erc20_to_address_bytes = &new_calldata[FUNCTION_SELECTOR_BYTES_LEN
-                    ..FUNCTION_SELECTOR_BYTES_LEN + ERC20_TO_ADDRESS_BYTES_LEN];
-                let erc20_to_address_len = ctx.load_constant(
-                    (F::from(erc20_to_address_bytes.len() as u64)).try_into().unwrap(),
-                );
-                let _erc20_to_address = bytes_be_var_to_fixed(
-                    ctx,
-                    self.gate(),
-                    &erc20_to_address_bytes,
-                    erc20_to_address_len,
-                    32,
-                );
-                tx_token_address = tx_to;
-                tx_to = bytes_be_to_uint(ctx, self.gate(), &_erc20_to_address, 32);
-
-                let erc20_amount_bytes = &new_calldata
-                    [FUNCTION_SELECTOR_BYTES_LEN + ERC20_TO_ADDRESS_BYTES_LEN..CALLDATA_BYTES_LEN];
-                let erc20_amount_len = ctx
-                    .load_constant((F::from(erc20_amount_bytes.len() as u64)).try_into().unwrap());
-                let _erc20_amount = bytes_be_var_to_fixed(
-                    ctx,
-                    self.gate(),
-                    &erc20_amount_bytes,
-                    erc20_amount_len,
-                    32,
-                );
-                tx_amount = bytes_be_to_uint(ctx, self.gate(), &_erc20_amount, 32);
-            }
-        }
+        let tx_to = tx_fields[0];
+        let tx_amount = tx_fields[1];
 
         let real_join_hash_len = self.gate().add(ctx, transaction_witness.rlp_len, join_hash_len);
 
@@ -656,20 +685,25 @@ impl<'chip, F: Field> EthBlockTransactionChip<F> for EthChip<'chip, F> {
         let hash_bytes = keccak.var_len_queries[hash_idx].output_assigned.clone();
         let hash: [_; 2] = bytes_be_to_u128(ctx, self.gate(), &hash_bytes).try_into().unwrap();
 
-        // ecdsa verify
-        let ecdsa_verify_result = ecdsa.ecdsa_pubkey_verify(ctx, transaction_ecdsa_verify.clone());
-        ctx.constrain_equal(&ecdsa_verify_result, &one);
-        let from_idx = keccak.keccak_fixed_len(
+        // ecdsa verify, the message hash must be the keccak of the signing payload
+        let message_len = self.gate().add(ctx, message_witness.rlp_len, join_hash_len);
+        let message_hash_idx =
+            keccak.keccak_var_len(ctx, self.range(), message, None, message_len, 0);
+        let message_hash_bytes = keccak.var_len_queries[message_hash_idx].output_assigned.clone();
+        let (ecdsa_verify_result, from_bytes) = ecdsa.ecdsa_address_verify(
             ctx,
-            self.range().gate(),
-            transaction_ecdsa_verify.public_key_bytes.to_vec(),
-            None,
+            keccak,
+            transaction_ecdsa_verify,
+            Some(&message_hash_bytes),
         );
-        let from_bytes = keccak.fixed_len_queries[from_idx].output_assigned.clone();
-        let from_bytes = &from_bytes[12..]; // Only take the lower 160bits of the hash
+        ctx.constrain_equal(&ecdsa_verify_result, &one);
         let address_len = ctx.load_constant(F::from(20));
         // tx from
-        let tx_from = self.assigned_value_to_uint(ctx, from_bytes.to_vec(), address_len, 20);
+        let tx_from = self.assigned_value_to_uint(ctx, from_bytes, address_len, 20);
+
+        // the call kind is selected in constraints, every kind gets the same circuit shape
+        let [tx_from, tx_to, tx_token_address, tx_amount] =
+            select_transfer_fields(ctx, self.gate(), calldata_witness, [tx_from, tx_to, tx_amount]);
 
         // tx nonce
         let tx_nonce = self.rlp_field_witnesses_to_uint(ctx, vec![&tx_nonce_witness], vec![32])[0];
@@ -690,6 +724,9 @@ impl<'chip, F: Field> EthBlockTransactionChip<F> for EthChip<'chip, F> {
                 nonce: tx_nonce,
                 dest_transfer_address,
                 dest_transfer_token,
+                blob_versioned_hashes,
+                blob_versioned_hashes_witness,
+                message_witness: Some(message_witness),
             },
         )
     }
@@ -729,12 +766,126 @@ impl<'chip, F: Field> EthBlockTransactionChip<F> for EthChip<'chip, F> {
         witness: EthTransactionTraceWitness<F>,
     ) -> EthTransactionTrace<F> {
         self.parse_mpt_inclusion_phase1((ctx_gate, ctx_rlc), witness.mpt_witness);
-        let value_trace = self
+        let blob_field_witness = witness
+            .transaction_witness
+            .field_witness
+            .get(EIP_4844_BLOB_VERSIONED_HASHES_INDEX)
+            .cloned();
+        let value_trace: Vec<RlpFieldTrace<F>> = self
             .rlp()
             .decompose_rlp_array_phase1((ctx_gate, ctx_rlc), witness.transaction_witness, true)
             .field_trace
             .try_into()
             .unwrap();
-        EthTransactionTrace { value_trace }
+        let mut blob_versioned_hashes_trace = None;
+        if let Some(blob_witness) = witness.extra_witness.blob_versioned_hashes_witness {
+            blob_versioned_hashes_trace = Some(self.parse_blob_versioned_hashes_phase1(
+                (ctx_gate, ctx_rlc),
+                blob_field_witness.unwrap(),
+                &value_trace[EIP_4844_BLOB_VERSIONED_HASHES_INDEX],
+                blob_witness,
+            ));
+        }
+        if let Some(message_witness) = witness.extra_witness.message_witness {
+            self.parse_transaction_signing_payload_phase1(
+                (ctx_gate, ctx_rlc),
+                &value_trace,
+                message_witness,
+            );
+        }
+        EthTransactionTrace { value_trace, blob_versioned_hashes_trace }
+    }
+
+    fn parse_blob_versioned_hashes_phase1(
+        &self,
+        (ctx_gate, ctx_rlc): RlcContextPair<F>,
+        blob_field_witness: RlpFieldWitness<F>,
+        blob_field_trace: &RlpFieldTrace<F>,
+        blob_witness: RlpArrayTraceWitness<F>,
+    ) -> RlpArrayTrace<F> {
+        self.rlp().constrain_rlp_field_phase1(
+            (ctx_gate, ctx_rlc),
+            blob_field_witness.rlp_field,
+            blob_field_trace,
+        );
+        self.rlp().decompose_rlp_array_phase1((ctx_gate, ctx_rlc), blob_witness, true)
+    }
+
+    fn parse_transaction_signing_payload_phase1(
+        &self,
+        (ctx_gate, ctx_rlc): RlcContextPair<F>,
+        value_trace: &[RlpFieldTrace<F>],
+        message_witness: RlpArrayTraceWitness<F>,
+    ) -> RlpArrayTrace<F> {
+        let message_trace =
+            self.rlp().decompose_rlp_array_phase1((ctx_gate, ctx_rlc), message_witness, false);
+        // every field before v,r,s is signed as is
+        for (message_field, field) in
+            message_trace.field_trace.iter().zip(value_trace.iter()).take(value_trace.len() - 3)
+        {
+            ctx_gate.constrain_equal(&message_field.field_trace.len, &field.field_trace.len);
+            ctx_rlc.constrain_equal(&message_field.field_trace.rlc_val, &field.field_trace.rlc_val);
+        }
+        message_trace
+    }
+}
+
+/// Returns `[from, to, token, amount]` of the transfer made by the transaction:
+/// * native transfer: `from`, `to` and `amount` of the transaction, `token` is 0.
+/// * `transfer(to, amount)`: `token` is the `to` of the transaction, `to` and `amount` are the call arguments.
+/// * `transferFrom(from, to, amount)`: same as `transfer`, `from` is the owner of the tokens.
+///
+/// All kinds are decoded and the result is selected in constraints.
+fn select_transfer_fields<F: Field>(
+    ctx: &mut Context<F>,
+    gate: &impl GateInstructions<F>,
+    calldata_witness: &RlpFieldWitness<F>,
+    [from, to, amount]: [AssignedValue<F>; 3],
+) -> [AssignedValue<F>; 4] {
+    let zero = ctx.load_zero();
+    let calldata = &calldata_witness.field_cells;
+    // the data field of this config can't hold any ERC20 call
+    if calldata.len() < TRANSFER_FROM_CALLDATA_BYTES_LEN {
+        return [from, to, zero, amount];
+    }
+
+    let mut is_call = |selector: [u8; FUNCTION_SELECTOR_BYTES_LEN], calldata_len: usize| {
+        let mut is_call =
+            gate.is_equal(ctx, calldata_witness.field_len, Constant(F::from(calldata_len as u64)));
+        for (byte, selector_byte) in calldata.iter().zip(selector) {
+            let is_selector_byte =
+                gate.is_equal(ctx, *byte, Constant(F::from(selector_byte as u64)));
+            is_call = gate.and(ctx, is_call, is_selector_byte);
+        }
+        is_call
+    };
+    let is_transfer = is_call(FUNCTION_SELECTOR_ERC20_TRANSFER, CALLDATA_BYTES_LEN);
+    let is_transfer_from =
+        is_call(FUNCTION_SELECTOR_ERC20_TRANSFER_FROM, TRANSFER_FROM_CALLDATA_BYTES_LEN);
+    let is_erc20 = gate.add(ctx, is_transfer, is_transfer_from);
+
+    let words = calldata[FUNCTION_SELECTOR_BYTES_LEN..TRANSFER_FROM_CALLDATA_BYTES_LEN]
+        .chunks(ABI_WORD_BYTES_LEN)
+        .collect_vec();
+    // an address is the lower 20 bytes of its word, the padding must be zero or the word is not an address
+    for (word, is_address) in words.iter().zip([is_erc20, is_transfer_from]) {
+        for byte in &word[..12] {
+            let padding = gate.mul(ctx, *byte, is_address);
+            gate.assert_is_const(ctx, &padding, &F::zero());
+        }
     }
+    let addresses =
+        words.iter().map(|word| bytes_be_to_uint(ctx, gate, &word[12..], 20)).collect_vec();
+    let uints = words
+        .iter()
+        .map(|word| bytes_be_to_uint(ctx, gate, word, ABI_WORD_BYTES_LEN))
+        .collect_vec();
+
+    let token = gate.select(ctx, to, zero, is_erc20);
+    let from = gate.select(ctx, addresses[0], from, is_transfer_from);
+    let transfer_to = gate.select(ctx, addresses[0], to, is_transfer);
+    let to = gate.select(ctx, addresses[1], transfer_to, is_transfer_from);
+    let transfer_amount = gate.select(ctx, uints[1], amount, is_transfer);
+    let amount = gate.select(ctx, uints[2], transfer_amount, is_transfer_from);
+    [from, to, token, amount]
 }
@@ -1,25 +1,55 @@
 use ark_std::{end_timer, start_timer};
-use std::env::{set_var, var};
+use std::cell::RefCell;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 use std::str::FromStr;
 use std::{fs, iter};
 
-use ethers_core::types::{Bytes, H256};
+use ethers_core::types::{
+    Address, Block, Bloom, Bytes, Eip1559TransactionRequest, Transaction, TransactionRequest, H256,
+    H64, U256,
+};
 use ethers_core::utils::hex::FromHex;
+use ethers_core::utils::keccak256;
 use ff::PrimeField;
 use halo2_base::utils::fs::gen_srs;
 use itertools::Itertools;
-use snark_verifier_sdk::CircuitExt;
+use rlp::{Decodable, Rlp, RlpStream};
+use snark_verifier::loader::halo2::halo2_ecc::secp256k1::{FpChip, FqChip};
+use snark_verifier_sdk::halo2::gen_snark_shplonk;
+use snark_verifier_sdk::{gen_pk, CircuitExt};
 
+use halo2_base::gates::RangeChip;
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+
+use crate::block_header::get_block_header_config;
+use crate::ecdsa::util::recover_tx_info;
+use crate::ecdsa::{EcdsaChip, EthEcdsaInput};
 use crate::halo2_proofs::dev::MockProver;
-use crate::rlp::builder::RlcThreadBuilder;
-use crate::transaction::ethereum::EthBlockTransactionCircuit;
+use crate::keccak::{FixedLenRLCs, FnSynthesize, KeccakChip, SharedKeccakChip, VarLenRLCs};
+use crate::rlp::builder::{RlcThreadBreakPoints, RlcThreadBuilder};
+use crate::rlp::rlc::FIRST_PHASE;
+use crate::rlp::RlpChip;
+use crate::transaction::ethereum::{
+    EthBlockTransactionChip, EthBlockTransactionCircuit, CACHE_BITS,
+};
+use crate::transaction::tests::{
+    sign_prehash, sign_transaction, single_transaction_block_input, test_signer_address,
+};
 use crate::transaction::util::TransactionConstructor;
-use crate::util::helpers::get_provider;
-use crate::util::EthConfigParams;
-use crate::{EthPreCircuit, EthereumNetwork, Network};
+use crate::transaction::{
+    calculate_blob_tx_max_fields_len, calculate_legacy_tx_max_len, calculate_tx_max_len_from_rlp,
+    EIP_4844_BLOB_VERSIONED_HASHES_INDEX, EIP_4844_TX_TYPE, EIP_4844_TX_TYPE_FIELDS_NUM,
+    MAX_BLOBS_PER_TX,
+};
+use crate::util::helpers::{get_provider, load_bytes};
+use crate::util::verify::verify_snark_with_vk;
+use crate::util::{encode_addr_to_field, encode_h256_to_field, EthConfigParams};
+use crate::{
+    EthChip, EthCircuitBuilder, EthPreCircuit, EthereumNetwork, Network, ETH_LIMB_BITS,
+    ETH_LOOKUP_BITS, ETH_NUM_LIMBS,
+};
 
 pub fn get_test_circuit(
     transaction_hash: H256,
@@ -42,7 +72,6 @@ pub fn get_test_circuit(
 #[test]
 pub fn test_2718_transaction_mpt() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigParams::from_path("configs/tests/transaction.json");
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree;
     let transaction_hash =
         H256::from_str("0x9fe482ff766f354529914bba6eef4bb6abcd288aa42de0ea00db2ec12f343fc4")
@@ -66,7 +95,8 @@ pub fn test_2718_transaction_mpt() -> Result<(), Box<dyn std::error::Error>> {
         Network::Ethereum(EthereumNetwork::Goerli),
     );
 
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
     Ok(())
 }
@@ -74,7 +104,6 @@ pub fn test_2718_transaction_mpt() -> Result<(), Box<dyn std::error::Error>> {
 #[test]
 pub fn test_1559_source_transaction_mpt() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigParams::from_path("configs/tests/transaction.json");
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree;
     let transaction_hash =
         H256::from_str("0x855a26127e84fa3311f2e1df0e9eb74966c87290ff97ab013bed6899b41e2d70")
@@ -103,7 +132,8 @@ pub fn test_1559_source_transaction_mpt() -> Result<(), Box<dyn std::error::Erro
         merkle_proof,
         Network::Ethereum(EthereumNetwork::Goerli),
     );
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
     Ok(())
 }
@@ -111,7 +141,6 @@ pub fn test_1559_source_transaction_mpt() -> Result<(), Box<dyn std::error::Erro
 #[test]
 pub fn test_1559_dest_transaction_mpt() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigParams::from_path("configs/tests/transaction.json");
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree;
     let transaction_hash =
         H256::from_str("0xdf92729bc172d5acb27bdda6537e6ffb3ec4f866628c6a88582a3386412aaf37")
@@ -134,15 +163,472 @@ pub fn test_1559_dest_transaction_mpt() -> Result<(), Box<dyn std::error::Error>
         merkle_proof,
         Network::Ethereum(EthereumNetwork::Goerli),
     );
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
     Ok(())
 }
 
+/// Signed type 3 transfer of mainnet with two blob versioned hashes, [0x01, 0xa1 * 31] and [0x01, 0xb2 * 31].
+fn blob_transaction_rlp() -> Vec<u8> {
+    let blob_versioned_hashes = [0xa1u8, 0xb2].map(|byte| {
+        let mut hash = [byte; 32];
+        hash[0] = 1;
+        H256(hash)
+    });
+    // [chainId,nonce,maxPriorityFeePerGas,maxFeePerGas,gasLimit,to,value,data,accessList,maxFeePerBlobGas,blobVersionedHashes]
+    let append_unsigned_fields = |rlp: &mut RlpStream| {
+        rlp.append(&1u64);
+        rlp.append(&7u64);
+        rlp.append(&U256::exp10(9));
+        rlp.append(&(U256::from(30) * U256::exp10(9)));
+        rlp.append(&21_000u64);
+        rlp.append(&Address::from_str("0x5a873a4aa853302449a92d57b54378d4a5001458").unwrap());
+        rlp.append(&U256::exp10(15));
+        rlp.append_empty_data();
+        rlp.begin_list(0);
+        rlp.append(&U256::exp10(9));
+        rlp.append_list(&blob_versioned_hashes);
+    };
+    let mut unsigned = RlpStream::new_list(EIP_4844_TX_TYPE_FIELDS_NUM - 3);
+    append_unsigned_fields(&mut unsigned);
+    let sighash = keccak256([&[EIP_4844_TX_TYPE][..], &unsigned.out()].concat());
+    let (r, s, recovery_id) = sign_prehash(H256(sighash));
+
+    let mut signed = RlpStream::new_list(EIP_4844_TX_TYPE_FIELDS_NUM);
+    append_unsigned_fields(&mut signed);
+    signed.append(&recovery_id);
+    signed.append(&r);
+    signed.append(&s);
+    [&[EIP_4844_TX_TYPE][..], &signed.out()].concat()
+}
+
+/// Decomposes the blob transaction and its versioned hashes, if `forged_hashes` is set
+/// the hashes are decomposed from a list that is not the one in the transaction.
+fn blob_transaction_test_circuit(
+    mut builder: RlcThreadBuilder<Fr>,
+    config_params: &EthConfigParams,
+    transaction_rlp: Vec<u8>,
+    forged_hashes: Option<Vec<u8>>,
+) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
+    let range = RangeChip::default(ETH_LOOKUP_BITS);
+    let keccak = SharedKeccakChip::default();
+    let chip = EthChip::new(RlpChip::new(&range, None), None);
+
+    let tx_max_len = calculate_tx_max_len_from_rlp(&transaction_rlp);
+    let field_lens = calculate_blob_tx_max_fields_len(tx_max_len);
+    let ctx = builder.gate_builder.main(FIRST_PHASE);
+    let mut transaction_rlp = transaction_rlp[1..].to_vec();
+    transaction_rlp.resize(tx_max_len - 1, 0);
+    let transaction_rlp =
+        ctx.assign_witnesses(transaction_rlp.into_iter().map(|byte| Fr::from(byte as u64)));
+    let transaction_witness =
+        chip.rlp().decompose_rlp_array_phase0(ctx, transaction_rlp, &field_lens, true);
+
+    let blob_field_witness =
+        transaction_witness.field_witness[EIP_4844_BLOB_VERSIONED_HASHES_INDEX].clone();
+    let (blob_rlp, hash_bytes) = match forged_hashes {
+        Some(mut forged_hashes) => {
+            forged_hashes.resize(blob_field_witness.rlp_field.len(), 0);
+            let forged_hashes =
+                ctx.assign_witnesses(forged_hashes.into_iter().map(|byte| Fr::from(byte as u64)));
+            (forged_hashes, vec![0xb2, 0xa1])
+        }
+        None => (blob_field_witness.rlp_field.clone(), vec![0xa1, 0xb2]),
+    };
+    let mut hash_bytes = hash_bytes;
+    hash_bytes.resize(MAX_BLOBS_PER_TX, 0);
+    let blob_witness =
+        chip.rlp().decompose_rlp_array_phase0(ctx, blob_rlp, &[32; MAX_BLOBS_PER_TX], true);
+    for (hash_witness, hash_byte) in blob_witness.field_witness.iter().zip(hash_bytes) {
+        let hash = hash_witness.field_cells.iter().map(|byte| byte.value()).collect_vec();
+        if hash_byte != 0 {
+            assert_eq!(*hash[0], Fr::from(1));
+            assert!(hash[1..].iter().all(|byte| **byte == Fr::from(hash_byte)));
+        } else {
+            assert!(hash.iter().all(|byte| **byte == Fr::from(0)));
+        }
+    }
+
+    let circuit = EthCircuitBuilder::new(
+        vec![],
+        builder,
+        keccak,
+        range,
+        None,
+        move |builder: &mut RlcThreadBuilder<Fr>,
+              rlp: RlpChip<Fr>,
+              keccak_rlcs: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {
+            let chip = EthChip::new(rlp, Some(keccak_rlcs));
+            let (ctx_gate, ctx_rlc) = builder.rlc_ctx_pair();
+            chip.rlc().load_rlc_cache((ctx_gate, ctx_rlc), chip.gate(), CACHE_BITS);
+            let value_trace = chip
+                .rlp()
+                .decompose_rlp_array_phase1((ctx_gate, ctx_rlc), transaction_witness, true)
+                .field_trace;
+            chip.parse_blob_versioned_hashes_phase1(
+                (ctx_gate, ctx_rlc),
+                blob_field_witness,
+                &value_trace[EIP_4844_BLOB_VERSIONED_HASHES_INDEX],
+                blob_witness,
+            );
+        },
+    );
+    circuit.config(config_params.degree as usize, Some(config_params.unusable_rows));
+    circuit
+}
+
+#[test]
+pub fn test_4844_blob_versioned_hashes_mock() {
+    let params = EthConfigParams::from_path("configs/tests/transaction.json");
+    let k = params.degree;
+    let transaction_rlp = blob_transaction_rlp();
+    assert_eq!(calculate_tx_max_len_from_rlp(&transaction_rlp), 1118);
+
+    let circuit =
+        blob_transaction_test_circuit(RlcThreadBuilder::mock(), &params, transaction_rlp, None);
+    let _scope = circuit.config_scope();
+    MockProver::run(k, &circuit, vec![vec![]]).unwrap().assert_satisfied();
+}
+
+#[test]
+pub fn test_4844_forged_blob_versioned_hashes_mock() {
+    let params = EthConfigParams::from_path("configs/tests/transaction.json");
+    let k = params.degree;
+    let transaction_rlp = blob_transaction_rlp();
+    // same hashes in the opposite order, the list has the same length as the one in the transaction
+    let mut forged_hashes = vec![0xf8, 0x42, 0xa0, 0x01];
+    forged_hashes.extend([0xb2; 31]);
+    forged_hashes.extend([0xa0, 0x01]);
+    forged_hashes.extend([0xa1; 31]);
+
+    let circuit = blob_transaction_test_circuit(
+        RlcThreadBuilder::mock(),
+        &params,
+        transaction_rlp,
+        Some(forged_hashes),
+    );
+    let _scope = circuit.config_scope();
+    assert!(MockProver::run(k, &circuit, vec![vec![]]).unwrap().verify().is_err());
+}
+
+/// A Cancun block of mainnet whose only transaction is the blob transaction.
+#[test]
+pub fn test_4844_transaction_mock() {
+    let params = EthConfigParams::from_path("configs/tests/transaction.json");
+    let k = params.degree;
+    let transaction_rlp = blob_transaction_rlp();
+    let block = Block::<H256> {
+        author: Some(Address::from_low_u64_be(0xc0ffee)),
+        number: Some(19_500_000u64.into()),
+        gas_limit: 30_000_000.into(),
+        gas_used: 21_000.into(),
+        timestamp: 1_710_338_135.into(),
+        logs_bloom: Some(Bloom::zero()),
+        // prevRandao
+        mix_hash: Some(H256::repeat_byte(0x3c)),
+        nonce: Some(H64::zero()),
+        base_fee_per_gas: Some(U256::exp10(10)),
+        withdrawals_root: Some(H256::repeat_byte(0x56)),
+        // two blobs of 2^17 gas
+        blob_gas_used: Some(U256::from(0x40000)),
+        excess_blob_gas: Some(U256::zero()),
+        parent_beacon_block_root: Some(H256::repeat_byte(0x4b)),
+        ..Default::default()
+    };
+    let inputs = single_transaction_block_input(block, transaction_rlp.clone());
+    let block_hash = inputs.block_hash;
+    let block_header_config = get_block_header_config(&Network::Ethereum(EthereumNetwork::Mainnet));
+    let input = EthBlockTransactionCircuit { inputs, block_header_config };
+    assert_eq!(input.inputs.transaction.transaction_proofs.value_max_byte_len, 1118);
+
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    let instance = circuit.instance();
+    assert_eq!(instance[..2], encode_h256_to_field::<Fr>(&block_hash));
+    assert_eq!(instance[2..4], encode_h256_to_field::<Fr>(&H256(keccak256(&transaction_rlp))));
+    assert_eq!(instance[4], Fr::one());
+    assert_eq!(
+        instance[7],
+        encode_addr_to_field(
+            &Address::from_str("0x5a873a4aa853302449a92d57b54378d4a5001458").unwrap()
+        )
+    );
+    assert_eq!(instance[9], Fr::from(1_000_000_000_000_000));
+    MockProver::run(k, &circuit, vec![instance]).unwrap().assert_satisfied();
+}
+
+/// Signed EIP-1559 transaction, the one below only differs in the last byte of the value.
+const SIGNED_TRANSACTION_HEX: &str = "02f873010285020a08fb2885020a08fb2882520894a79ed52d6774259535428f2533a8420703a4078f87054e13428c955280c080a02a3222ebb694535ee03ced3a0bc75a7c37b5053be9dcccc15894e014b1fd3a81a079250a246c8846c86cc24a84d2966752d9999ab4f05b5cca98762400e0a0f813";
+const TAMPERED_TRANSACTION_HEX: &str = "02f873010285020a08fb2885020a08fb2882520894a79ed52d6774259535428f2533a8420703a4078f87054e13428c955380c080a02a3222ebb694535ee03ced3a0bc75a7c37b5053be9dcccc15894e014b1fd3a81a079250a246c8846c86cc24a84d2966752d9999ab4f05b5cca98762400e0a0f813";
+
+fn ecdsa_input_from_rlp(transaction_rlp: &[u8]) -> EthEcdsaInput {
+    let transaction = Transaction::decode(&Rlp::new(transaction_rlp)).unwrap();
+    let (signature, message, message_hash, public_key) = recover_tx_info(&transaction);
+    EthEcdsaInput { signature, message, message_hash, public_key }
+}
+
+/// Parses the transaction fields and verifies its signature against `ecdsa_input`, without the MPT proof.
+/// The instances are `[from, to, token, amount]`, a prover uses `config_params` as they are.
+fn transaction_signature_test_circuit(
+    mut builder: RlcThreadBuilder<Fr>,
+    config_params: &EthConfigParams,
+    transaction_rlp: Vec<u8>,
+    ecdsa_input: EthEcdsaInput,
+    break_points: Option<RlcThreadBreakPoints>,
+) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
+    let range = RangeChip::default(ETH_LOOKUP_BITS);
+    let chip = EthChip::new(RlpChip::new(&range, None), None);
+    let mut keccak = KeccakChip::default();
+    let fp_chip = FpChip::new(&range, ETH_LIMB_BITS, ETH_NUM_LIMBS);
+    let fq_chip = FqChip::new(&range, ETH_LIMB_BITS, ETH_NUM_LIMBS);
+    let ecdsa = EcdsaChip::new(&fp_chip, &fq_chip);
+    let prover = builder.witness_gen_only();
+
+    let ctx = builder.gate_builder.main(FIRST_PHASE);
+    let tx_max_len = calculate_tx_max_len_from_rlp(&transaction_rlp);
+    let mut transaction_value = transaction_rlp.clone();
+    transaction_value.resize(tx_max_len, 0);
+    let transaction_value = load_bytes(ctx, &transaction_value);
+    let ecdsa_input = ecdsa_input.assign(ctx, Some(tx_max_len));
+    let (transaction_witness, extra_witness) = chip.parse_transaction_extra_proof(
+        ctx,
+        &mut keccak,
+        &ecdsa,
+        transaction_value,
+        ecdsa_input,
+    );
+
+    let instances =
+        vec![extra_witness.from, extra_witness.to, extra_witness.token, extra_witness.amount];
+
+    let circuit = EthCircuitBuilder::new(
+        instances,
+        builder,
+        RefCell::new(keccak),
+        range,
+        break_points,
+        move |builder: &mut RlcThreadBuilder<Fr>,
+              rlp: RlpChip<Fr>,
+              keccak_rlcs: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {
+            let chip = EthChip::new(rlp, Some(keccak_rlcs));
+            let (ctx_gate, ctx_rlc) = builder.rlc_ctx_pair();
+            chip.rlc().load_rlc_cache((ctx_gate, ctx_rlc), chip.gate(), CACHE_BITS);
+            let value_trace = chip
+                .rlp()
+                .decompose_rlp_array_phase1((ctx_gate, ctx_rlc), transaction_witness, true)
+                .field_trace;
+            chip.parse_transaction_signing_payload_phase1(
+                (ctx_gate, ctx_rlc),
+                &value_trace,
+                extra_witness.message_witness.unwrap(),
+            );
+        },
+    );
+    if prover {
+        circuit.set_config_params(config_params.clone());
+    } else {
+        circuit.config(config_params.degree as usize, Some(config_params.unusable_rows));
+    }
+    circuit
+}
+
+#[test]
+pub fn test_transaction_signing_payload_mock() {
+    let params = EthConfigParams::from_path("configs/tests/transaction.json");
+    let k = params.degree;
+    let transaction_rlp = Vec::from_hex(SIGNED_TRANSACTION_HEX).unwrap();
+    let ecdsa_input = ecdsa_input_from_rlp(&transaction_rlp);
+
+    let circuit = transaction_signature_test_circuit(
+        RlcThreadBuilder::mock(),
+        &params,
+        transaction_rlp,
+        ecdsa_input,
+        None,
+    );
+    let _scope = circuit.config_scope();
+    MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
+}
+
+#[test]
+pub fn test_transaction_forged_message_hash_mock() {
+    let params = EthConfigParams::from_path("configs/tests/transaction.json");
+    let k = params.degree;
+    let signed_rlp = Vec::from_hex(SIGNED_TRANSACTION_HEX).unwrap();
+    let tampered_rlp = Vec::from_hex(TAMPERED_TRANSACTION_HEX).unwrap();
+    let signed_input = ecdsa_input_from_rlp(&signed_rlp);
+    let tampered_input = ecdsa_input_from_rlp(&tampered_rlp);
+
+    // the signature is valid for the hash, but the hash is not the keccak of the signing payload
+    let forged_input =
+        EthEcdsaInput { message: tampered_input.message.clone(), ..signed_input.clone() };
+    let circuit = transaction_signature_test_circuit(
+        RlcThreadBuilder::mock(),
+        &params,
+        tampered_rlp.clone(),
+        forged_input,
+        None,
+    );
+    let _scope = circuit.config_scope();
+    assert!(MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().verify().is_err());
+
+    // the signing payload matches the hash, but it is not the payload of the parsed transaction
+    let circuit = transaction_signature_test_circuit(
+        RlcThreadBuilder::mock(),
+        &params,
+        tampered_rlp,
+        signed_input,
+        None,
+    );
+    let _scope = circuit.config_scope();
+    assert!(MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().verify().is_err());
+}
+
+#[test]
+pub fn test_legacy_transaction_signing_payload_mock() {
+    let params = EthConfigParams::from_path("configs/tests/transaction.json");
+    let k = params.degree;
+    let recipient = Address::from_str("0x1908e2bf4a88f91e4ef0dc72f02b8ea36bea2319").unwrap();
+    let transaction = TransactionRequest::new()
+        .chain_id(1)
+        .nonce(5)
+        .gas_price(20_000_000_000u64)
+        .gas(21_000)
+        .to(recipient)
+        .value(1_000_000_000_000_000u64);
+    let transaction_rlp = sign_transaction(&transaction.into()).to_vec();
+    assert_eq!(
+        calculate_tx_max_len_from_rlp(&transaction_rlp),
+        calculate_legacy_tx_max_len(transaction_rlp.len())
+    );
+    let ecdsa_input = ecdsa_input_from_rlp(&transaction_rlp);
+    // the signing payload of an EIP-155 transaction is shorter than the transaction
+    assert!(ecdsa_input.message.len() < transaction_rlp.len());
+
+    let circuit = transaction_signature_test_circuit(
+        RlcThreadBuilder::mock(),
+        &params,
+        transaction_rlp,
+        ecdsa_input,
+        None,
+    );
+    let _scope = circuit.config_scope();
+    let instance = circuit.instance();
+    // [from, to, token, amount]
+    assert_eq!(
+        instance,
+        vec![
+            encode_addr_to_field(&test_signer_address()),
+            encode_addr_to_field(&recipient),
+            Fr::zero(),
+            Fr::from(1_000_000_000_000_000)
+        ]
+    );
+    MockProver::run(k, &circuit, vec![instance]).unwrap().assert_satisfied();
+}
+
+/// EIP-1559 transactions of the same signer: a native transfer, an ERC20 `transfer` and an ERC20 `transferFrom`.
+const NATIVE_TRANSFER_HEX: &str = "02f8730107843b9aca008506fc23ac00830186a0941908e2bf4a88f91e4ef0dc72f02b8ea36bea231987038d7ea4c6800080c001a01262698ccf8525a4a476dbbbc4e106273ceb0c3aee87cc5e6a1845935b266562a07151c926540b2e6fb3d60f13c6c2e88c3f0798f0917139e3eb4a9e28762fadaa";
+const ERC20_TRANSFER_HEX: &str = "02f8b10108843b9aca008506fc23ac00830186a094a0b86991c6218b36c1d19d4a2e9eb0ce3606eb4880b844a9059cbb0000000000000000000000001908e2bf4a88f91e4ef0dc72f02b8ea36bea2319000000000000000000000000000000000000000000000000000000000012d687c001a068aac08c1ec037d8a9b8f97571ab1048460d5c7001b61a15df18b0df02eff700a00f91e8f4649cdbca8a8b2f7bbef8ecbf3b10dced9accf0d99a74e1b927efffa0";
+const ERC20_TRANSFER_FROM_HEX: &str = "02f8d10109843b9aca008506fc23ac00830186a094a0b86991c6218b36c1d19d4a2e9eb0ce3606eb4880b86423b872dd0000000000000000000000005a873a4aa853302449a92d57b54378d4a50014580000000000000000000000001908e2bf4a88f91e4ef0dc72f02b8ea36bea2319000000000000000000000000000000000000000000000000000000000012d687c001a0a7fe9f79b2d39a30b952c5549d559dd9fd5204b5aab32e8a2d712cc55100702ea0407b83147d54b55e11261a9c479ca6a373b2931da40b6367d0b196ac9f232306";
+
+#[test]
+pub fn test_transaction_call_kinds_one_keygen() {
+    let params = EthConfigParams::from_path("configs/tests/transaction.json");
+    let k = params.degree;
+    let address = |hex: &str| encode_addr_to_field::<Fr>(&Address::from_str(hex).unwrap());
+    let signer = address("0xf52b978d064a638e263ad7b397172e414c20ba84");
+    let owner = address("0x5a873a4aa853302449a92d57b54378d4a5001458");
+    let recipient = address("0x1908e2bf4a88f91e4ef0dc72f02b8ea36bea2319");
+    let token = address("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+    // [from, to, token, amount]
+    let call_kinds = [
+        (NATIVE_TRANSFER_HEX, vec![signer, recipient, Fr::zero(), Fr::from(1_000_000_000_000_000)]),
+        (ERC20_TRANSFER_HEX, vec![signer, recipient, token, Fr::from(1234567)]),
+        (ERC20_TRANSFER_FROM_HEX, vec![owner, recipient, token, Fr::from(1234567)]),
+    ];
+
+    let srs = gen_srs(k);
+    let keygen_rlp = Vec::from_hex(NATIVE_TRANSFER_HEX).unwrap();
+    let circuit = transaction_signature_test_circuit(
+        RlcThreadBuilder::keygen(),
+        &params,
+        keygen_rlp.clone(),
+        ecdsa_input_from_rlp(&keygen_rlp),
+        None,
+    );
+    let _scope = circuit.config_scope();
+    let pk = gen_pk(&srs, &circuit, None);
+    let keygen_params = circuit.config_params();
+    let break_points = circuit.circuit.break_points.take();
+
+    for (transaction_hex, instance) in call_kinds {
+        let transaction_rlp = Vec::from_hex(transaction_hex).unwrap();
+        let ecdsa_input = ecdsa_input_from_rlp(&transaction_rlp);
+        let circuit = transaction_signature_test_circuit(
+            RlcThreadBuilder::mock(),
+            &params,
+            transaction_rlp.clone(),
+            ecdsa_input.clone(),
+            None,
+        );
+        assert_eq!(circuit.instance(), instance);
+        let _scope = circuit.config_scope();
+        MockProver::run(k, &circuit, vec![instance.clone()]).unwrap().assert_satisfied();
+
+        // the signing payload is padded to the tx max length, so the break points of the keygen fit
+        // every call kind and the proof is verified against the keygen of the native transfer
+        let circuit = transaction_signature_test_circuit(
+            RlcThreadBuilder::prover(),
+            &keygen_params,
+            transaction_rlp,
+            ecdsa_input,
+            Some(break_points.clone()),
+        );
+        let _scope = circuit.config_scope();
+        let snark = gen_snark_shplonk(&srs, &pk, circuit, None::<&str>);
+        assert_eq!(snark.instances, vec![instance]);
+        assert!(verify_snark_with_vk(&srs, pk.get_vk(), &snark));
+    }
+}
+
+/// An ERC20 call whose address words have a non zero byte in their padding is rejected.
+#[test]
+pub fn test_transaction_erc20_unpadded_address_mock() {
+    let params = EthConfigParams::from_path("configs/tests/transaction.json");
+    let k = params.degree;
+    let transfer_from = Vec::from_hex(ERC20_TRANSFER_FROM_HEX).unwrap();
+    let calldata = Transaction::decode(&Rlp::new(&transfer_from)).unwrap().input;
+    // the first byte of the `from` word, then of the `to` word
+    for padding_idx in [4, 36] {
+        let mut calldata = calldata.to_vec();
+        calldata[padding_idx] = 1;
+        let transaction = Eip1559TransactionRequest::new()
+            .chain_id(1)
+            .nonce(9)
+            .max_priority_fee_per_gas(1_000_000_000u64)
+            .max_fee_per_gas(30_000_000_000u64)
+            .gas(100_000)
+            .to(Address::from_str("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48").unwrap())
+            .data(calldata);
+        let transaction_rlp = sign_transaction(&transaction.into()).to_vec();
+        let ecdsa_input = ecdsa_input_from_rlp(&transaction_rlp);
+        let circuit = transaction_signature_test_circuit(
+            RlcThreadBuilder::mock(),
+            &params,
+            transaction_rlp,
+            ecdsa_input,
+            None,
+        );
+        let _scope = circuit.config_scope();
+        assert!(MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().verify().is_err());
+    }
+}
+
 #[test]
 pub fn test_zksync_commit_transaction_mpt() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigParams::from_path("configs/tests/transaction.json");
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree;
     let transaction_hash =
         H256::from_str("0x6167c0b969859713aa2145e6d6ab2fd5fecc4d7781fd37ed19a2feb310ad6853")
@@ -165,7 +651,8 @@ pub fn test_zksync_commit_transaction_mpt() -> Result<(), Box<dyn std::error::Er
         merkle_proof,
         Network::Ethereum(EthereumNetwork::Goerli),
     );
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
     Ok(())
 }
@@ -174,6 +661,7 @@ pub fn test_zksync_commit_transaction_mpt() -> Result<(), Box<dyn std::error::Er
 #[cfg(feature = "evm")]
 pub fn evm_gen_yul() -> Result<(), Box<dyn std::error::Error>> {
     use crate::util::circuit::custom_gen_evm_verifier_shplonk;
+    use crate::util::circuit_config::{isolate_env, CircuitConfig, CircuitConfigScope};
     use halo2_base::gates::builder::CircuitBuilderStage;
     use snark_verifier_sdk::{
         evm::{evm_verify, gen_evm_proof_shplonk, write_calldata},
@@ -196,8 +684,6 @@ pub fn evm_gen_yul() -> Result<(), Box<dyn std::error::Error>> {
         transaction_param.degree
     );
 
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&transaction_param).unwrap());
-
     let (storage_snark, storage_proof_time) = {
         let transaction_hash =
             H256::from_str("0x9fe482ff766f354529914bba6eef4bb6abcd288aa42de0ea00db2ec12f343fc4")
@@ -220,13 +706,17 @@ pub fn evm_gen_yul() -> Result<(), Box<dyn std::error::Error>> {
             merkle_proof,
             Network::Ethereum(EthereumNetwork::Goerli),
         );
-        let circuit = input.clone().create_circuit(RlcThreadBuilder::keygen(), None);
         let k = transaction_param.degree;
+        let circuit =
+            input.clone().create_circuit(RlcThreadBuilder::keygen(), transaction_param, None);
+        let _scope = circuit.config_scope();
         let params = gen_srs(k);
         let pk = gen_pk(&params, &circuit, None);
+        let config_params = circuit.config_params();
         let break_points = circuit.circuit.break_points.take();
         let storage_proof_time = start_timer!(|| "Storage Proof SHPLONK");
-        let circuit = input.create_circuit(RlcThreadBuilder::prover(), Some(break_points));
+        let circuit =
+            input.create_circuit(RlcThreadBuilder::prover(), config_params, Some(break_points));
         let snark = gen_snark_shplonk(&params, &pk, circuit, None::<&str>);
         end_timer!(storage_proof_time);
         (snark, storage_proof_time)
@@ -234,7 +724,6 @@ pub fn evm_gen_yul() -> Result<(), Box<dyn std::error::Error>> {
 
     let k = evm_param.degree;
     let params = gen_srs(k);
-    set_var("LOOKUP_BITS", evm_param.lookup_bits.to_string());
     let evm_circuit = AggregationCircuit::public::<SHPLONK>(
         CircuitBuilderStage::Keygen,
         None,
@@ -243,7 +732,10 @@ pub fn evm_gen_yul() -> Result<(), Box<dyn std::error::Error>> {
         vec![storage_snark.clone()],
         false,
     );
-    evm_circuit.config(k, Some(10));
+    let _scope = CircuitConfigScope::enter(CircuitConfig::Gate {
+        params: isolate_env(|| evm_circuit.config(k, Some(10))),
+        lookup_bits: evm_param.lookup_bits,
+    });
     let pk = gen_pk(&params, &evm_circuit, None);
     let break_points = evm_circuit.break_points();
 
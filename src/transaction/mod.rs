@@ -3,15 +3,22 @@ use crate::transaction::zksync_era::ZkSyncEraBlockTransactionCircuit;
 use crate::Network;
 use ethers_core::types::{Bytes, H256};
 use ethers_providers::Provider;
+use halo2_base::gates::{GateInstructions, RangeChip, RangeInstructions};
+use halo2_base::QuantumCell::Constant;
 use halo2_base::{AssignedValue, Context};
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use zkevm_keccak::util::eth_types::Field;
 
+pub mod arbitrum;
 pub mod ethereum;
+pub mod optimism;
 pub mod util;
 pub mod zksync_era;
 
+#[cfg(test)]
+pub(crate) mod tests;
+
 /// The theoretical maximum value of Ethereum transaction type is 7f (Except for Legacy transactions:f8) https://ethereum.org/zh/developers/docs/transactions/#typed-transaction-envelope
 pub const EIP_TX_TYPE_CRITICAL_VALUE: u8 = 0x80;
 
@@ -19,6 +26,14 @@ pub const EIP_TX_TYPE_CRITICAL_VALUE: u8 = 0x80;
 pub const EIP_2718_TX_TYPE: u8 = 0xf8;
 pub const EIP_2930_TX_TYPE: u8 = 0x01;
 pub const EIP_1559_TX_TYPE: u8 = 0x02;
+pub const EIP_4844_TX_TYPE: u8 = 0x03;
+
+/// Transaction, receipt and transaction receipt circuits start their instances with the block hash.
+pub const BLOCK_HASH_INSTANCE_OFFSET: usize = 0;
+/// The `to` instance of every transaction circuit, the receiver of the transfer.
+pub const TO_INSTANCE_OFFSET: usize = 7;
+/// The instances every transaction circuit exposes, network specific ones follow them.
+pub const TRANSACTION_INSTANCES_LEN: usize = 14;
 
 const TX_INDEX_MAX_LEN: usize = 3;
 
@@ -27,28 +42,63 @@ const TX_NORMAL_DATA_MAX_LEN: usize = 512;
 const TX_COMMIT_DATA_MAX_LEN: usize = 21000;
 const TX_ACCESS_LIST_MAX_LEN: usize = 0;
 
+/// EIP-4844 has no per-transaction blob limit, a transaction is only bounded by the blob gas of its block.
+/// Prague raised that to 9 blobs per block (EIP-7691), so a blob transaction of any block up to Osaka,
+/// which caps transactions at 6 blobs (EIP-7594), references at most 9.
+pub const MAX_BLOBS_PER_TX: usize = 9;
+/// Every versioned hash is encoded as 0xa0 followed by the 32 bytes hash.
+const TX_BLOB_VERSIONED_HASHES_MAX_LEN: usize = MAX_BLOBS_PER_TX * 33;
+/// Index of blobVersionedHashes in the EIP-4844 transaction fields.
+pub const EIP_4844_BLOB_VERSIONED_HASHES_INDEX: usize = 10;
+
 pub const EIP_2718_TX_TYPE_FIELDS_NUM: usize = 9;
 pub const EIP_1559_TX_TYPE_FIELDS_NUM: usize = 12;
+pub const EIP_4844_TX_TYPE_FIELDS_NUM: usize = 14;
 
 pub const EIP_2718_TX_TYPE_FIELDS_MAX_FIELDS_LEN: [usize; EIP_2718_TX_TYPE_FIELDS_NUM] =
     [32, 32, 32, 20, 32, TX_DATA_MAX_LEN, 32, 32, 32];
 pub const EIP_1559_TX_TYPE_FIELDS_MAX_FIELDS_LEN: [usize; EIP_1559_TX_TYPE_FIELDS_NUM] =
     [32, 32, 32, 32, 32, 20, 32, TX_DATA_MAX_LEN, TX_ACCESS_LIST_MAX_LEN, 1, 32, 32];
+/// [chainId,nonce,maxPriorityFeePerGas,maxFeePerGas,gasLimit,to,value,data,accessList,maxFeePerBlobGas,blobVersionedHashes,v,r,s]
+pub const EIP_4844_TX_TYPE_FIELDS_MAX_FIELDS_LEN: [usize; EIP_4844_TX_TYPE_FIELDS_NUM] = [
+    32,
+    32,
+    32,
+    32,
+    32,
+    20,
+    32,
+    TX_DATA_MAX_LEN,
+    TX_ACCESS_LIST_MAX_LEN,
+    32,
+    TX_BLOB_VERSIONED_HASHES_MAX_LEN,
+    1,
+    32,
+    32,
+];
 
 pub const TX_MAX_LEN: usize = 32 * 8 + 20 + 1 + TX_DATA_MAX_LEN + TX_ACCESS_LIST_MAX_LEN;
 
 const FUNCTION_SELECTOR_BYTES_LEN: usize = 4;
-const ERC20_TO_ADDRESS_BYTES_LEN: usize = 32;
-const ERC20_AMOUNT_BYTES_LEN: usize = 32;
-const FUNCTION_SELECTOR_ERC20_TRANSFER: [u8; FUNCTION_SELECTOR_BYTES_LEN] = [169, 5, 156, 187];
-const CALLDATA_BYTES_LEN: usize =
-    FUNCTION_SELECTOR_BYTES_LEN + ERC20_TO_ADDRESS_BYTES_LEN + ERC20_AMOUNT_BYTES_LEN;
+/// Every ABI encoded argument takes a 32 bytes word.
+const ABI_WORD_BYTES_LEN: usize = 32;
+/// transfer(address,uint256)
+pub(crate) const FUNCTION_SELECTOR_ERC20_TRANSFER: [u8; FUNCTION_SELECTOR_BYTES_LEN] =
+    [169, 5, 156, 187];
+/// transferFrom(address,address,uint256)
+pub(crate) const FUNCTION_SELECTOR_ERC20_TRANSFER_FROM: [u8; FUNCTION_SELECTOR_BYTES_LEN] =
+    [35, 184, 114, 221];
+const CALLDATA_BYTES_LEN: usize = FUNCTION_SELECTOR_BYTES_LEN + 2 * ABI_WORD_BYTES_LEN;
+const TRANSFER_FROM_CALLDATA_BYTES_LEN: usize =
+    FUNCTION_SELECTOR_BYTES_LEN + 3 * ABI_WORD_BYTES_LEN;
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum EthTransactionType {
     LegacyTxType,     // 0x00
     AccessListTxType, // 0x01
     DynamicFeeTxType, // 0x02
+    BlobTxType,       // 0x03
+    DepositTxType,    // 0x7e, OP-Stack only
 }
 
 impl ToString for EthTransactionType {
@@ -57,6 +107,8 @@ impl ToString for EthTransactionType {
             EthTransactionType::LegacyTxType => String::from("legacy_tx_type"),
             EthTransactionType::AccessListTxType => String::from("access_list_tx_type"),
             EthTransactionType::DynamicFeeTxType => String::from("dynamic_fee_tx_type"),
+            EthTransactionType::BlobTxType => String::from("blob_tx_type"),
+            EthTransactionType::DepositTxType => String::from("deposit_tx_type"),
         }
     }
 }
@@ -67,6 +119,29 @@ pub fn load_transaction_type<F: Field>(ctx: &mut Context<F>, tx_type: u8) -> Ass
     ctx.load_constant(type_value)
 }
 
+/// Constrains the first byte of the transaction to be a user signed envelope (legacy, EIP-2930 or EIP-1559).
+/// L2 specific envelopes (Arbitrum system txs, OP-Stack deposits) are typed transactions below `EIP_TX_TYPE_CRITICAL_VALUE`, so they are rejected.
+pub fn constrain_signed_transaction_type<F: Field>(
+    ctx: &mut Context<F>,
+    range: &RangeChip<F>,
+    transaction_type: AssignedValue<F>,
+) {
+    let gate = range.gate();
+    let is_typed_transaction = range.is_less_than(
+        ctx,
+        transaction_type,
+        Constant(F::from(EIP_TX_TYPE_CRITICAL_VALUE as u64)),
+        8,
+    );
+    let is_access_list_transaction =
+        gate.is_equal(ctx, transaction_type, Constant(F::from(EIP_2930_TX_TYPE as u64)));
+    let is_dynamic_fee_transaction =
+        gate.is_equal(ctx, transaction_type, Constant(F::from(EIP_1559_TX_TYPE as u64)));
+    let is_supported_typed_transaction =
+        gate.add(ctx, is_access_list_transaction, is_dynamic_fee_transaction);
+    ctx.constrain_equal(&is_typed_transaction, &is_supported_typed_transaction);
+}
+
 pub fn calculate_tx_max_len(tx_len: usize) -> usize {
     let mut tx_max_len = 0;
     if tx_len <= 512 {
@@ -98,3 +173,78 @@ pub fn calculate_tx_max_fields_len(assigned_tx_len: usize) -> Vec<usize> {
     }
     base
 }
+
+/// Blob transactions carry two more fields than dynamic fee transactions, so they get their own `tx_max_len`.
+pub fn calculate_blob_tx_max_len(tx_len: usize) -> usize {
+    let tx_data_max_len = if tx_len <= TX_NORMAL_DATA_MAX_LEN {
+        TX_NORMAL_DATA_MAX_LEN
+    } else {
+        TX_COMMIT_DATA_MAX_LEN
+    };
+    tx_data_max_len + EIP_4844_TX_TYPE_FIELDS_MAX_FIELDS_LEN.iter().sum::<usize>()
+}
+
+/// Inverse of `calculate_blob_tx_max_len`, the data field gets whatever the other fields leave.
+pub fn calculate_blob_tx_max_fields_len(assigned_tx_len: usize) -> Vec<usize> {
+    let mut base = EIP_4844_TX_TYPE_FIELDS_MAX_FIELDS_LEN.to_vec();
+    base[7] = assigned_tx_len - EIP_4844_TX_TYPE_FIELDS_MAX_FIELDS_LEN.iter().sum::<usize>();
+    base
+}
+
+/// Legacy transactions have 9 fields, so they get their own `tx_max_len` as well.
+pub fn calculate_legacy_tx_max_len(tx_len: usize) -> usize {
+    let tx_data_max_len = if tx_len <= TX_NORMAL_DATA_MAX_LEN {
+        TX_NORMAL_DATA_MAX_LEN
+    } else {
+        TX_COMMIT_DATA_MAX_LEN
+    };
+    tx_data_max_len + EIP_2718_TX_TYPE_FIELDS_MAX_FIELDS_LEN.iter().sum::<usize>()
+}
+
+/// Inverse of `calculate_legacy_tx_max_len`, the data field gets whatever the other fields leave.
+pub fn calculate_legacy_tx_max_fields_len(assigned_tx_len: usize) -> Vec<usize> {
+    let mut base = EIP_2718_TX_TYPE_FIELDS_MAX_FIELDS_LEN.to_vec();
+    base[5] = assigned_tx_len - EIP_2718_TX_TYPE_FIELDS_MAX_FIELDS_LEN.iter().sum::<usize>();
+    base
+}
+
+/// Blob transactions have their own `tx_max_len`, so the envelope is known from the circuit config alone.
+pub fn is_blob_tx_max_len(tx_max_len: usize) -> bool {
+    [TX_NORMAL_DATA_MAX_LEN, TX_COMMIT_DATA_MAX_LEN]
+        .iter()
+        .any(|tx_data_max_len| calculate_blob_tx_max_len(*tx_data_max_len) == tx_max_len)
+}
+
+pub fn is_legacy_tx_max_len(tx_max_len: usize) -> bool {
+    [TX_NORMAL_DATA_MAX_LEN, TX_COMMIT_DATA_MAX_LEN]
+        .iter()
+        .any(|tx_data_max_len| calculate_legacy_tx_max_len(*tx_data_max_len) == tx_max_len)
+}
+
+/// The envelope of the transactions of a circuit with this `tx_max_len`: legacy, blob, or dynamic fee
+/// for any other length.
+pub fn transaction_envelope(tx_max_len: usize) -> EthTransactionType {
+    if is_legacy_tx_max_len(tx_max_len) {
+        EthTransactionType::LegacyTxType
+    } else if is_blob_tx_max_len(tx_max_len) {
+        EthTransactionType::BlobTxType
+    } else {
+        EthTransactionType::DynamicFeeTxType
+    }
+}
+
+/// Whether the raw transaction is a legacy one, the rlp list itself instead of a typed envelope.
+pub fn is_legacy_transaction_rlp(transaction_rlp: &[u8]) -> bool {
+    transaction_rlp.first().map_or(false, |first_byte| *first_byte >= EIP_TX_TYPE_CRITICAL_VALUE)
+}
+
+/// Picks the `tx_max_len` matching the envelope of the raw transaction.
+pub fn calculate_tx_max_len_from_rlp(transaction_rlp: &[u8]) -> usize {
+    if is_legacy_transaction_rlp(transaction_rlp) {
+        calculate_legacy_tx_max_len(transaction_rlp.len())
+    } else if transaction_rlp.first() == Some(&EIP_4844_TX_TYPE) {
+        calculate_blob_tx_max_len(transaction_rlp.len())
+    } else {
+        calculate_tx_max_len(transaction_rlp.len())
+    }
+}
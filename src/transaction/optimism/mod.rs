@@ -0,0 +1,478 @@
+use std::cell::RefCell;
+
+use ethers_core::types::{Address, Block, H256};
+use ethers_providers::Provider;
+use halo2_base::gates::builder::GateThreadBuilder;
+use halo2_base::gates::{GateInstructions, RangeChip};
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::{AssignedValue, Context, QuantumCell::Constant};
+use itertools::Itertools;
+use snark_verifier::loader::halo2::halo2_ecc::secp256k1::{FpChip, FqChip};
+use zkevm_keccak::util::eth_types::Field;
+
+use crate::block_header::{get_block_header_config, BlockHeaderConfig, EthBlockHeaderChip};
+use crate::ecdsa::EcdsaChip;
+use crate::keccak::{FixedLenRLCs, FnSynthesize, KeccakChip, VarLenRLCs};
+use crate::mpt::{MPTInput, MPTProof};
+use crate::providers::{get_optimism_deposit_transaction_input, get_transaction_input};
+use crate::rlp::builder::{RlcThreadBreakPoints, RlcThreadBuilder};
+use crate::rlp::rlc::FIRST_PHASE;
+use crate::rlp::RlpChip;
+use crate::storage::EthStorageChip;
+use crate::transaction::ethereum::{
+    EIP1186ResponseDigest, EthBlockTransactionChip, EthBlockTransactionInput,
+    EthBlockTransactionTrace, EthBlockTransactionTraceWitness, EthTransactionExtraWitness,
+    EthTransactionField, EthTransactionTraceWitness,
+};
+use crate::transaction::util::TransactionConstructor;
+use crate::transaction::{
+    calculate_tx_max_len, constrain_signed_transaction_type, load_transaction_type,
+    EthTransactionType, TX_COMMIT_DATA_MAX_LEN, TX_DATA_MAX_LEN, TX_NORMAL_DATA_MAX_LEN,
+};
+use crate::util::rpc_client::RpcClient;
+use crate::util::{bytes_be_to_u128, encode_addr_to_field};
+use crate::{
+    EthChip, EthCircuitBuilder, EthPreCircuit, ETH_LIMB_BITS, ETH_LOOKUP_BITS, ETH_NUM_LIMBS,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Deposit transactions are derived from L1 by the rollup node, see https://github.com/ethereum-optimism/specs/blob/main/specs/protocol/deposits.md
+/// They are not signed, the sender is part of the payload and is authenticated by the L1 OptimismPortal.
+pub const OPTIMISM_DEPOSIT_TX_TYPE: u8 = 0x7e;
+
+pub const OPTIMISM_DEPOSIT_TX_TYPE_FIELDS_NUM: usize = 8;
+
+/// [sourceHash,from,to,mint,value,gas,isSystemTx,data]
+pub const OPTIMISM_DEPOSIT_TX_TYPE_FIELDS_MAX_FIELDS_LEN: [usize;
+    OPTIMISM_DEPOSIT_TX_TYPE_FIELDS_NUM] = [32, 20, 20, 32, 32, 8, 1, TX_DATA_MAX_LEN];
+
+/// The sender of the L1 attributes deposit, which opens every OP-Stack block, see
+/// https://github.com/ethereum-optimism/specs/blob/main/specs/protocol/deposits.md#l1-attributes-deposited-transaction
+pub const L1_ATTRIBUTES_DEPOSITOR: &str = "0xDeaDDEaDDeAdDeAdDEAdDEaddeAddEAdDEAd0001";
+/// The `L1Block` predeploy, the receiver of the L1 attributes deposit.
+pub const L1_BLOCK_PREDEPLOY: &str = "0x4200000000000000000000000000000000000015";
+
+/// Same data length tiers as `calculate_tx_max_len`, so deposit and signed transactions share `tx_max_len`.
+/// Returns `None` if `assigned_tx_len` is not the `tx_max_len` of a tier.
+pub fn calculate_deposit_tx_max_fields_len(assigned_tx_len: usize) -> Option<Vec<usize>> {
+    let tx_data_max_field_len = [TX_NORMAL_DATA_MAX_LEN, TX_COMMIT_DATA_MAX_LEN]
+        .into_iter()
+        .find(|tx_data_max_len| calculate_tx_max_len(*tx_data_max_len) == assigned_tx_len)?;
+    let mut base = OPTIMISM_DEPOSIT_TX_TYPE_FIELDS_MAX_FIELDS_LEN.to_vec();
+    base[OPTIMISM_DEPOSIT_TX_TYPE_FIELDS_NUM - 1] = tx_data_max_field_len;
+    Some(base)
+}
+
+pub fn get_optimism_transaction_type(transaction_rlp: &[u8]) -> EthTransactionType {
+    if transaction_rlp.first() == Some(&OPTIMISM_DEPOSIT_TX_TYPE) {
+        EthTransactionType::DepositTxType
+    } else {
+        EthTransactionType::DynamicFeeTxType
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OptimismDepositTransactionInput {
+    pub transaction_index: u64,
+    pub transaction_proofs: MPTInput,
+    // deposit transactions carry no chain id, it is fixed by the circuit
+    pub chain_id: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct OptimismDepositTransactionInputAssigned<F: Field> {
+    pub transaction_index: AssignedValue<F>,
+    pub transaction_proofs: MPTProof<F>,
+    pub chain_id: AssignedValue<F>,
+}
+
+impl OptimismDepositTransactionInput {
+    pub fn assign<F: Field>(
+        self,
+        ctx: &mut Context<F>,
+    ) -> OptimismDepositTransactionInputAssigned<F> {
+        let transaction_index = ctx.load_witness(F::from(self.transaction_index));
+        let transaction_proofs = self.transaction_proofs.assign(ctx);
+        let chain_id = ctx.load_constant(F::from(self.chain_id));
+        OptimismDepositTransactionInputAssigned { transaction_index, transaction_proofs, chain_id }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OptimismBlockDepositTransactionInput {
+    pub block: Block<H256>,
+    pub block_number: u64,
+    pub block_hash: H256,
+    // provided for convenience, actual block_hash is computed from block_header
+    pub block_header: Vec<u8>,
+    pub transaction: OptimismDepositTransactionInput,
+}
+
+#[derive(Clone, Debug)]
+pub struct OptimismBlockDepositTransactionInputAssigned<F: Field> {
+    pub block_header: Vec<u8>,
+    pub transaction: OptimismDepositTransactionInputAssigned<F>,
+}
+
+impl OptimismBlockDepositTransactionInput {
+    pub fn assign<F: Field>(
+        self,
+        ctx: &mut Context<F>,
+    ) -> OptimismBlockDepositTransactionInputAssigned<F> {
+        let transaction = self.transaction.assign(ctx);
+        OptimismBlockDepositTransactionInputAssigned {
+            block_header: self.block_header,
+            transaction,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum OptimismBlockTransactionInput {
+    Signed(EthBlockTransactionInput),
+    Deposit(OptimismBlockDepositTransactionInput),
+}
+
+#[derive(Clone, Debug)]
+pub struct OptimismBlockTransactionCircuit {
+    pub inputs: OptimismBlockTransactionInput,
+    pub block_header_config: BlockHeaderConfig,
+}
+
+impl OptimismBlockTransactionCircuit {
+    /// The envelopes are decoded by different constraints, so each one is a circuit of its own.
+    pub fn tx_type(&self) -> EthTransactionType {
+        match self.inputs {
+            OptimismBlockTransactionInput::Signed(_) => EthTransactionType::DynamicFeeTxType,
+            OptimismBlockTransactionInput::Deposit(_) => EthTransactionType::DepositTxType,
+        }
+    }
+
+    pub fn from_provider(
+        provider: &Provider<RpcClient>,
+        constructor: TransactionConstructor,
+    ) -> Self {
+        let transaction_rlp = constructor.transaction_rlp.unwrap();
+        let inputs = match get_optimism_transaction_type(&transaction_rlp) {
+            EthTransactionType::DepositTxType => {
+                OptimismBlockTransactionInput::Deposit(get_optimism_deposit_transaction_input(
+                    provider,
+                    constructor.transaction_hash,
+                    constructor.transaction_index_bytes,
+                    transaction_rlp,
+                    constructor.merkle_proof.unwrap(),
+                    constructor.transaction_pf_max_depth.unwrap(),
+                ))
+            }
+            _ => OptimismBlockTransactionInput::Signed(get_transaction_input(
+                provider,
+                constructor.transaction_hash,
+                constructor.transaction_index_bytes,
+                transaction_rlp,
+                constructor.merkle_proof.unwrap(),
+                constructor.transaction_pf_max_depth.unwrap(),
+            )),
+        };
+        let block_header_config = get_block_header_config(&constructor.network);
+        Self { inputs, block_header_config }
+    }
+}
+
+impl EthPreCircuit for OptimismBlockTransactionCircuit {
+    fn create(
+        self,
+        mut builder: RlcThreadBuilder<Fr>,
+        break_points: Option<RlcThreadBreakPoints>,
+    ) -> EthCircuitBuilder<Fr, impl FnSynthesize<Fr>> {
+        let range = RangeChip::default(ETH_LOOKUP_BITS);
+        let chip = EthChip::new(RlpChip::new(&range, None), None);
+        let mut keccak = KeccakChip::default();
+
+        // ================= FIRST PHASE ================
+        let ctx = builder.gate_builder.main(FIRST_PHASE);
+        let (witness, digest) = match self.inputs {
+            OptimismBlockTransactionInput::Signed(inputs) => {
+                let fp_chip = FpChip::new(&range, ETH_LIMB_BITS, ETH_NUM_LIMBS);
+                let fq_chip = FqChip::new(&range, ETH_LIMB_BITS, ETH_NUM_LIMBS);
+                let ecdsa = EcdsaChip::new(&fp_chip, &fq_chip);
+                let input = inputs.assign(ctx);
+                let transaction_type =
+                    *input.transaction.transaction_proofs.value_bytes.first().unwrap();
+                constrain_signed_transaction_type(ctx, &range, transaction_type);
+                chip.parse_transaction_proof_from_block_phase0(
+                    &mut builder.gate_builder,
+                    &mut keccak,
+                    &ecdsa,
+                    input,
+                    &self.block_header_config,
+                )
+            }
+            OptimismBlockTransactionInput::Deposit(inputs) => {
+                let input = inputs.assign(ctx);
+                chip.parse_deposit_transaction_proof_from_block_phase0(
+                    &mut builder.gate_builder,
+                    &mut keccak,
+                    input,
+                    &self.block_header_config,
+                )
+            }
+        };
+
+        let EIP1186ResponseDigest { index, block_hash, transaction_is_empty, transaction_field } =
+            digest;
+
+        let assigned_instances = block_hash
+            .into_iter()
+            .chain(transaction_field.hash)
+            .chain([
+                transaction_field.chain_id,
+                index,
+                transaction_field.from,
+                transaction_field.to,
+                transaction_field.token,
+                transaction_field.amount,
+                transaction_field.nonce,
+                transaction_field.time_stamp,
+                transaction_field.dest_transfer_address,
+                transaction_field.dest_transfer_token,
+            ])
+            .collect_vec();
+
+        {
+            let ctx = builder.gate_builder.main(FIRST_PHASE);
+            range.gate.assert_is_const(ctx, &transaction_is_empty, &Fr::zero());
+        }
+
+        EthCircuitBuilder::new(
+            assigned_instances,
+            builder,
+            RefCell::new(keccak),
+            range,
+            break_points,
+            move |builder: &mut RlcThreadBuilder<Fr>,
+                  rlp: RlpChip<Fr>,
+                  keccak_rlcs: (FixedLenRLCs<Fr>, VarLenRLCs<Fr>)| {
+                // ======== SECOND PHASE ===========
+                // Both envelopes are an RLP list in the transactions trie, so the second phase is shared.
+                let chip = EthChip::new(rlp, Some(keccak_rlcs));
+                let _trace =
+                    chip.parse_optimism_transaction_proof_from_block_phase1(builder, witness);
+            },
+        )
+    }
+}
+
+/// Constrains the first byte of the transaction to be `OPTIMISM_DEPOSIT_TX_TYPE`.
+pub fn constrain_deposit_transaction_type<F: Field>(
+    ctx: &mut Context<F>,
+    transaction_type: AssignedValue<F>,
+) {
+    let deposit_transaction_type = load_transaction_type(ctx, OPTIMISM_DEPOSIT_TX_TYPE);
+    ctx.constrain_equal(&transaction_type, &deposit_transaction_type);
+}
+
+pub trait OptimismBlockTransactionChip<F: Field> {
+    // ================= FIRST PHASE ================
+
+    fn parse_deposit_transaction_proof_from_block_phase0(
+        &self,
+        thread_pool: &mut GateThreadBuilder<F>,
+        keccak: &mut KeccakChip<F>,
+        input: OptimismBlockDepositTransactionInputAssigned<F>,
+        block_header_config: &BlockHeaderConfig,
+    ) -> (EthBlockTransactionTraceWitness<F>, EIP1186ResponseDigest<F>)
+    where
+        Self: EthBlockHeaderChip<F>;
+
+    fn parse_deposit_transaction_proof_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        keccak: &mut KeccakChip<F>,
+        transactions_root: &[AssignedValue<F>],
+        transaction_input: OptimismDepositTransactionInputAssigned<F>,
+    ) -> EthTransactionTraceWitness<F>;
+
+    // ================= SECOND PHASE ================
+
+    fn parse_optimism_transaction_proof_from_block_phase1(
+        &self,
+        thread_pool: &mut RlcThreadBuilder<F>,
+        witness: EthBlockTransactionTraceWitness<F>,
+    ) -> EthBlockTransactionTrace<F>
+    where
+        Self: EthBlockHeaderChip<F> + EthBlockTransactionChip<F>;
+}
+
+impl<'chip, F: Field> OptimismBlockTransactionChip<F> for EthChip<'chip, F> {
+    // ================= FIRST PHASE ================
+
+    fn parse_deposit_transaction_proof_from_block_phase0(
+        &self,
+        thread_pool: &mut GateThreadBuilder<F>,
+        keccak: &mut KeccakChip<F>,
+        input: OptimismBlockDepositTransactionInputAssigned<F>,
+        block_header_config: &BlockHeaderConfig,
+    ) -> (EthBlockTransactionTraceWitness<F>, EIP1186ResponseDigest<F>)
+    where
+        Self: EthBlockHeaderChip<F>,
+    {
+        let transaction_index = input.transaction.transaction_index;
+
+        let block_witness = {
+            let ctx = thread_pool.main(FIRST_PHASE);
+            let mut block_header = input.block_header;
+            block_header.resize(block_header_config.block_header_rlp_max_bytes, 0);
+            self.decompose_block_header_phase0(ctx, keccak, &block_header, block_header_config)
+        };
+        let ctx = thread_pool.main(FIRST_PHASE);
+        let block_hash = bytes_be_to_u128(ctx, self.gate(), &block_witness.block_hash);
+
+        let transactions_root = &block_witness.get_transactions_root().field_cells;
+
+        let time_stamp =
+            self.rlp_field_witnesses_to_uint(ctx, vec![&block_witness.get_timestamp()], vec![8])[0];
+
+        let transaction_witness = self.parse_deposit_transaction_proof_phase0(
+            ctx,
+            keccak,
+            transactions_root,
+            input.transaction,
+        );
+
+        let digest = EIP1186ResponseDigest {
+            index: transaction_index,
+            block_hash: block_hash.try_into().unwrap(),
+            transaction_is_empty: transaction_witness.mpt_witness.slot_is_empty,
+            transaction_field: EthTransactionField {
+                hash: transaction_witness.extra_witness.hash,
+                chain_id: transaction_witness.extra_witness.chain_id,
+                from: transaction_witness.extra_witness.from,
+                to: transaction_witness.extra_witness.to,
+                token: transaction_witness.extra_witness.token,
+                amount: transaction_witness.extra_witness.amount,
+                nonce: transaction_witness.extra_witness.nonce,
+                time_stamp,
+                dest_transfer_address: transaction_witness.extra_witness.dest_transfer_address,
+                dest_transfer_token: transaction_witness.extra_witness.dest_transfer_token,
+            },
+        };
+        (EthBlockTransactionTraceWitness { block_witness, transaction_witness }, digest)
+    }
+
+    fn parse_deposit_transaction_proof_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        keccak: &mut KeccakChip<F>,
+        transactions_root: &[AssignedValue<F>],
+        transaction_input: OptimismDepositTransactionInputAssigned<F>,
+    ) -> EthTransactionTraceWitness<F> {
+        // check MPT root is transactions_root
+        for (pf_root, root) in transaction_input
+            .transaction_proofs
+            .root_hash_bytes
+            .iter()
+            .zip(transactions_root.iter())
+        {
+            ctx.constrain_equal(pf_root, root);
+        }
+
+        // check MPT inclusion
+        let mpt_witness = self.parse_mpt_inclusion_phase0(
+            ctx,
+            keccak,
+            transaction_input.transaction_proofs.clone(),
+        );
+
+        let transaction_value = transaction_input.transaction_proofs.value_bytes;
+        constrain_deposit_transaction_type(ctx, *transaction_value.first().unwrap());
+
+        let field_lens = calculate_deposit_tx_max_fields_len(transaction_value.len())
+            .unwrap_or_else(|| {
+                panic!(
+                    "deposit transactions of max length {} are not supported",
+                    transaction_value.len()
+                )
+            });
+        let transaction_witness = self.rlp().decompose_rlp_array_phase0(
+            ctx,
+            transaction_value[1..].to_vec(),
+            &field_lens,
+            true,
+        );
+
+        // [sourceHash,from,to,mint,value,gas,isSystemTx,data]
+        // `mint` is credited to `from` before the deposit executes, `to` only receives `value`, so the amount of the
+        // transfer is `value` and `mint` is not read.
+        let tx_fields = self.rlp_field_witnesses_to_uint(
+            ctx,
+            vec![
+                &transaction_witness.field_witness[1],
+                &transaction_witness.field_witness[2],
+                &transaction_witness.field_witness[4],
+            ],
+            vec![20, 20, 32],
+        );
+        let (tx_from, tx_to, tx_amount) = (tx_fields[0], tx_fields[1], tx_fields[2]);
+
+        // The L1 attributes deposit is not a user transfer. Since Regolith its `isSystemTx` is false, so it is
+        // recognized by its sender and receiver instead.
+        for (address, system_address) in
+            [(tx_from, L1_ATTRIBUTES_DEPOSITOR), (tx_to, L1_BLOCK_PREDEPLOY)]
+        {
+            let system_address = encode_addr_to_field(&system_address.parse::<Address>().unwrap());
+            let is_system_address = self.gate().is_equal(ctx, address, Constant(system_address));
+            self.gate().assert_is_const(ctx, &is_system_address, &F::zero());
+        }
+
+        // Only native deposits are supported, the bridged ERC20 is minted by a contract call instead of a transfer.
+        let zero = ctx.load_constant(F::zero());
+
+        let one = ctx.load_constant(F::one());
+        let real_join_hash_len = self.gate().add(ctx, transaction_witness.rlp_len, one);
+        let hash_idx = keccak.keccak_var_len(
+            ctx,
+            self.range(),
+            transaction_value.to_vec(),
+            None,
+            real_join_hash_len,
+            0,
+        );
+        let hash_bytes = keccak.var_len_queries[hash_idx].output_assigned.clone();
+        let hash: [_; 2] = bytes_be_to_u128(ctx, self.gate(), &hash_bytes).try_into().unwrap();
+
+        EthTransactionTraceWitness {
+            transaction_witness,
+            mpt_witness,
+            extra_witness: EthTransactionExtraWitness {
+                hash,
+                chain_id: transaction_input.chain_id,
+                from: tx_from,
+                to: tx_to,
+                token: zero,
+                amount: tx_amount,
+                // deposits have no account nonce, the sender nonce is not bumped on L2
+                nonce: zero,
+                dest_transfer_address: zero,
+                dest_transfer_token: zero,
+                blob_versioned_hashes: vec![],
+                blob_versioned_hashes_witness: None,
+                message_witness: None,
+            },
+        }
+    }
+
+    // ================= SECOND PHASE ================
+
+    fn parse_optimism_transaction_proof_from_block_phase1(
+        &self,
+        thread_pool: &mut RlcThreadBuilder<F>,
+        witness: EthBlockTransactionTraceWitness<F>,
+    ) -> EthBlockTransactionTrace<F>
+    where
+        Self: EthBlockHeaderChip<F> + EthBlockTransactionChip<F>,
+    {
+        self.parse_transaction_proof_from_block_phase1(thread_pool, witness)
+    }
+}
@@ -0,0 +1,185 @@
+use ethers_core::types::{Address, Block, Bloom, H256, H64, U256};
+use ethers_core::utils::keccak256;
+use halo2_base::gates::builder::{GateThreadBuilder, RangeCircuitBuilder};
+use halo2_base::gates::RangeChip;
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use rlp::RlpStream;
+
+use crate::block_header::get_block_header_config;
+use crate::halo2_proofs::dev::MockProver;
+use crate::providers::get_optimism_deposit_transaction_proof_input;
+use crate::rlp::builder::RlcThreadBuilder;
+use crate::rlp::rlc::FIRST_PHASE;
+use crate::transaction::optimism::{
+    calculate_deposit_tx_max_fields_len, constrain_deposit_transaction_type,
+    get_optimism_transaction_type, OptimismBlockTransactionCircuit, OptimismBlockTransactionInput,
+    L1_ATTRIBUTES_DEPOSITOR, L1_BLOCK_PREDEPLOY, OPTIMISM_DEPOSIT_TX_TYPE,
+};
+use crate::transaction::tests::single_transaction_block;
+use crate::transaction::{
+    calculate_tx_max_len, constrain_signed_transaction_type, EthTransactionType, EIP_1559_TX_TYPE,
+    EIP_2718_TX_TYPE, TX_COMMIT_DATA_MAX_LEN, TX_NORMAL_DATA_MAX_LEN,
+};
+use crate::util::circuit_config::{isolate_env, CircuitConfig, CircuitConfigScope};
+use crate::util::{encode_addr_to_field, encode_h256_to_field, EthConfigParams};
+use crate::{EthPreCircuit, Network, OptimismNetwork, ETH_LOOKUP_BITS};
+
+const K: u32 = 10;
+
+fn transaction_type_circuit(
+    transaction_type: u8,
+    is_deposit: bool,
+) -> (RangeCircuitBuilder<Fr>, CircuitConfigScope) {
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(ETH_LOOKUP_BITS);
+    let ctx = builder.main(FIRST_PHASE);
+    let transaction_type = ctx.load_witness(Fr::from(transaction_type as u64));
+    if is_deposit {
+        constrain_deposit_transaction_type(ctx, transaction_type);
+    } else {
+        constrain_signed_transaction_type(ctx, &range, transaction_type);
+    }
+    let params = isolate_env(|| builder.config(K as usize, Some(20)));
+    let scope =
+        CircuitConfigScope::enter(CircuitConfig::Gate { params, lookup_bits: ETH_LOOKUP_BITS });
+    (RangeCircuitBuilder::mock(builder), scope)
+}
+
+#[test]
+pub fn test_optimism_deposit_transaction_type() {
+    let (circuit, _scope) = transaction_type_circuit(OPTIMISM_DEPOSIT_TX_TYPE, true);
+    MockProver::run(K, &circuit, vec![]).unwrap().assert_satisfied();
+
+    for transaction_type in [EIP_2718_TX_TYPE, EIP_1559_TX_TYPE] {
+        let (circuit, _scope) = transaction_type_circuit(transaction_type, true);
+        assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+    }
+}
+
+#[test]
+pub fn test_optimism_deposit_transaction_not_signed() {
+    let (circuit, _scope) = transaction_type_circuit(OPTIMISM_DEPOSIT_TX_TYPE, false);
+    assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+}
+
+#[test]
+pub fn test_optimism_transaction_type() {
+    assert_eq!(
+        get_optimism_transaction_type(&[OPTIMISM_DEPOSIT_TX_TYPE, 0xf8]),
+        EthTransactionType::DepositTxType
+    );
+    assert_eq!(
+        get_optimism_transaction_type(&[EIP_1559_TX_TYPE, 0xf8]),
+        EthTransactionType::DynamicFeeTxType
+    );
+}
+
+#[test]
+pub fn test_optimism_deposit_transaction_fields_len() {
+    for tx_data_max_len in [TX_NORMAL_DATA_MAX_LEN, TX_COMMIT_DATA_MAX_LEN] {
+        let tx_max_len = calculate_tx_max_len(tx_data_max_len);
+        let field_lens = calculate_deposit_tx_max_fields_len(tx_max_len).unwrap();
+        assert_eq!(field_lens.last(), Some(&tx_data_max_len));
+        // the deposit envelope always fits in the value bytes reserved for a dynamic fee transaction
+        assert!(field_lens.iter().sum::<usize>() < tx_max_len);
+    }
+    assert_eq!(
+        calculate_deposit_tx_max_fields_len(calculate_tx_max_len(TX_NORMAL_DATA_MAX_LEN) + 1),
+        None
+    );
+}
+
+/// A user deposit of 0.01 ETH bridged by the OptimismPortal.
+fn deposit_transaction_rlp(from: Address, to: Address, value: U256) -> Vec<u8> {
+    // [sourceHash,from,to,mint,value,gas,isSystemTx,data]
+    let mut rlp = RlpStream::new_list(8);
+    rlp.append(&H256::repeat_byte(0x5c));
+    rlp.append(&from);
+    rlp.append(&to);
+    rlp.append(&value);
+    rlp.append(&value);
+    rlp.append(&100_000u64);
+    rlp.append_empty_data();
+    rlp.append_empty_data();
+    [vec![OPTIMISM_DEPOSIT_TX_TYPE], rlp.out().to_vec()].concat()
+}
+
+/// The circuit of the only transaction of a Canyon block, a deposit.
+fn deposit_transaction_circuit(transaction_rlp: &[u8]) -> OptimismBlockTransactionCircuit {
+    let network = Network::Optimism(OptimismNetwork::Mainnet);
+    // a Canyon block: no difficulty, extra data nor nonce, and empty withdrawals
+    let block = Block::<H256> {
+        author: Some("0x4200000000000000000000000000000000000011".parse().unwrap()),
+        number: Some(115_000_000u64.into()),
+        gas_limit: 30_000_000.into(),
+        gas_used: 100_000.into(),
+        timestamp: 1_705_000_000.into(),
+        logs_bloom: Some(Bloom::zero()),
+        mix_hash: Some(H256::repeat_byte(0x7a)),
+        nonce: Some(H64::zero()),
+        base_fee_per_gas: Some(1_000_000.into()),
+        withdrawals_root: Some(
+            "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421".parse().unwrap(),
+        ),
+        ..Default::default()
+    };
+    let (block, merkle_proof) = single_transaction_block(block, transaction_rlp);
+    let inputs = get_optimism_deposit_transaction_proof_input(
+        block,
+        0,
+        None,
+        transaction_rlp.to_vec(),
+        merkle_proof,
+        8,
+        OptimismNetwork::Mainnet as u64,
+    );
+    OptimismBlockTransactionCircuit {
+        inputs: OptimismBlockTransactionInput::Deposit(inputs),
+        block_header_config: get_block_header_config(&network),
+    }
+}
+
+#[test]
+pub fn test_optimism_deposit_transaction_mock() {
+    let params = EthConfigParams::from_path("configs/tests/transaction.json");
+    let k = params.degree;
+    let (from, to) = (Address::from_low_u64_be(0xa11ce), Address::from_low_u64_be(0xb0b));
+    let value = U256::exp10(16);
+    let transaction_rlp = deposit_transaction_rlp(from, to, value);
+    let input = deposit_transaction_circuit(&transaction_rlp);
+    let block_hash = match &input.inputs {
+        OptimismBlockTransactionInput::Deposit(inputs) => inputs.block_hash,
+        OptimismBlockTransactionInput::Signed(_) => unreachable!(),
+    };
+    assert_eq!(input.tx_type(), EthTransactionType::DepositTxType);
+
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    let instance = circuit.instance();
+    assert_eq!(instance[..2], encode_h256_to_field::<Fr>(&block_hash));
+    assert_eq!(instance[2..4], encode_h256_to_field::<Fr>(&H256(keccak256(&transaction_rlp))));
+    assert_eq!(instance[4], Fr::from(OptimismNetwork::Mainnet as u64));
+    assert_eq!(instance[6..8], [encode_addr_to_field(&from), encode_addr_to_field(&to)]);
+    assert_eq!(instance[9], Fr::from(value.as_u64()));
+    MockProver::run(k, &circuit, vec![instance]).unwrap().assert_satisfied();
+}
+
+#[test]
+pub fn test_optimism_l1_attributes_deposit_is_rejected() {
+    let params = EthConfigParams::from_path("configs/tests/transaction.json");
+    let k = params.degree;
+    // since Regolith the L1 attributes deposit has `isSystemTx` false, like a user deposit
+    let transaction_rlp = deposit_transaction_rlp(
+        L1_ATTRIBUTES_DEPOSITOR.parse().unwrap(),
+        L1_BLOCK_PREDEPLOY.parse().unwrap(),
+        U256::zero(),
+    );
+    let circuit = deposit_transaction_circuit(&transaction_rlp).create_circuit(
+        RlcThreadBuilder::mock(),
+        params,
+        None,
+    );
+    let _scope = circuit.config_scope();
+    let instance = circuit.instance();
+    assert!(MockProver::run(k, &circuit, vec![instance]).unwrap().verify().is_err());
+}
@@ -0,0 +1,71 @@
+use ethers_core::k256::ecdsa::SigningKey;
+use ethers_core::types::transaction::eip2718::TypedTransaction;
+use ethers_core::types::{Address, Block, Bytes, Signature, H256, U256};
+use ethers_core::utils::{keccak256, secret_key_to_address};
+use rlp::RlpStream;
+
+use crate::providers::{encode_block_header, get_transaction_proof_input};
+use crate::transaction::ethereum::EthBlockTransactionInput;
+
+/// The private key signing the transactions of the offline fixtures.
+pub const TEST_SIGNER_KEY: u64 = 0x5a17;
+
+fn test_signer() -> SigningKey {
+    SigningKey::from_bytes(&H256::from_low_u64_be(TEST_SIGNER_KEY).0.into()).unwrap()
+}
+
+/// The address of [`TEST_SIGNER_KEY`].
+pub fn test_signer_address() -> Address {
+    secret_key_to_address(&test_signer())
+}
+
+/// Signs `sighash` with [`TEST_SIGNER_KEY`] and returns `(r, s, recovery id)`.
+pub fn sign_prehash(sighash: H256) -> (U256, U256, u8) {
+    let (signature, recovery_id) =
+        test_signer().sign_prehash_recoverable(sighash.as_bytes()).unwrap();
+    (
+        U256::from_big_endian(&signature.r().to_bytes()),
+        U256::from_big_endian(&signature.s().to_bytes()),
+        recovery_id.to_byte(),
+    )
+}
+
+/// Signs `tx` with [`TEST_SIGNER_KEY`] and returns its signed envelope.
+pub fn sign_transaction(tx: &TypedTransaction) -> Bytes {
+    let (r, s, recovery_id) = sign_prehash(tx.sighash());
+    let chain_id = tx.chain_id().expect("fixtures are EIP-155 transactions").as_u64();
+    tx.rlp_signed(&Signature { r, s, v: recovery_id as u64 + 35 + chain_id * 2 })
+}
+
+/// Returns the root and the proof of a trie whose only leaf is `value`, at key `rlp(0)`.
+///
+/// The trie of a single transaction or receipt is that leaf, so the proof is the leaf itself.
+pub fn single_leaf_trie(value: &[u8]) -> (H256, Vec<Bytes>) {
+    let mut leaf = RlpStream::new_list(2);
+    // hex prefix of the even leaf path [8, 0]
+    leaf.append(&vec![0x20u8, 0x80]);
+    leaf.append(&value.to_vec());
+    let leaf: Vec<u8> = leaf.out().into();
+    (H256(keccak256(&leaf)), vec![leaf.into()])
+}
+
+/// Sets the transactions root and the hash of `block` to those of a block whose only transaction is
+/// `transaction_rlp`, and returns the proof of that transaction.
+pub fn single_transaction_block(
+    mut block: Block<H256>,
+    transaction_rlp: &[u8],
+) -> (Block<H256>, Vec<Bytes>) {
+    let (transactions_root, merkle_proof) = single_leaf_trie(transaction_rlp);
+    block.transactions_root = transactions_root;
+    block.hash = Some(H256(keccak256(encode_block_header(&block))));
+    (block, merkle_proof)
+}
+
+/// The input of the only, signed, transaction of `block`.
+pub fn single_transaction_block_input(
+    block: Block<H256>,
+    transaction_rlp: Vec<u8>,
+) -> EthBlockTransactionInput {
+    let (block, merkle_proof) = single_transaction_block(block, &transaction_rlp);
+    get_transaction_proof_input(block, 0, None, transaction_rlp, merkle_proof, 8)
+}
@@ -1,6 +1,8 @@
-use crate::transaction::calculate_tx_max_len;
+use crate::transaction::arbitrum::ArbitrumBlockTransactionCircuit;
 use crate::transaction::ethereum::EthBlockTransactionCircuit;
+use crate::transaction::optimism::OptimismBlockTransactionCircuit;
 use crate::transaction::zksync_era::ZkSyncEraBlockTransactionCircuit;
+use crate::transaction::{calculate_tx_max_len_from_rlp, transaction_envelope, EthTransactionType};
 use crate::util::errors::ErrorType;
 use crate::util::helpers::get_provider;
 use crate::Network;
@@ -40,7 +42,12 @@ impl TransactionConstructor {
     }
 
     pub fn tx_max_len(&self) -> usize {
-        calculate_tx_max_len(self.transaction_rlp.as_ref().unwrap().len())
+        calculate_tx_max_len_from_rlp(self.transaction_rlp.as_ref().unwrap())
+    }
+
+    /// Legacy and blob transactions have their own circuits, the typed envelopes share the dynamic fee ones.
+    pub fn tx_type(&self) -> EthTransactionType {
+        transaction_envelope(self.tx_max_len())
     }
 }
 
@@ -133,3 +140,17 @@ pub fn get_zksync_transaction_circuit(
     let provider = get_provider(&constructor.network);
     ZkSyncEraBlockTransactionCircuit::from_provider(&provider, constructor)
 }
+
+pub fn get_arbitrum_transaction_circuit(
+    constructor: TransactionConstructor,
+) -> ArbitrumBlockTransactionCircuit {
+    let provider = get_provider(&constructor.network);
+    ArbitrumBlockTransactionCircuit::from_provider(&provider, constructor)
+}
+
+pub fn get_optimism_transaction_circuit(
+    constructor: TransactionConstructor,
+) -> OptimismBlockTransactionCircuit {
+    let provider = get_provider(&constructor.network);
+    OptimismBlockTransactionCircuit::from_provider(&provider, constructor)
+}
@@ -17,6 +17,7 @@ use crate::transaction::ethereum::{
 use crate::receipt::TX_STATUS_SUCCESS;
 use crate::transaction::util::TransactionConstructor;
 use crate::util::helpers::{bytes_to_u8, bytes_to_vec_u8, load_bytes};
+use crate::util::rpc_client::RpcClient;
 use crate::util::{
     bytes_be_to_u128, bytes_be_to_uint, bytes_be_var_to_fixed, u128s_to_bytes_be, AssignedH256,
 };
@@ -24,7 +25,7 @@ use crate::{
     EthChip, EthCircuitBuilder, EthPreCircuit, ETH_LIMB_BITS, ETH_LOOKUP_BITS, ETH_NUM_LIMBS,
 };
 use ethers_core::types::{Block, H256};
-use ethers_providers::{Http, Provider, RetryClient};
+use ethers_providers::Provider;
 use halo2_base::gates::builder::GateThreadBuilder;
 use halo2_base::gates::{GateInstructions, RangeChip, RangeInstructions};
 use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
@@ -35,6 +36,7 @@ use std::cell::RefCell;
 use zkevm_keccak::util::eth_types::Field;
 use zksync_web3_rs::zks_provider::types::BlockDetails;
 
+#[cfg(test)]
 mod tests;
 
 const CACHE_BITS: usize = 12;
@@ -66,7 +68,8 @@ impl ZkSyncEraTransactionInput {
             ctx.load_witness(F::from(self.transaction_value_max_bytes as u64));
         transaction_value.resize(self.transaction_value_max_bytes, 0);
         let transaction_value = load_bytes(ctx, transaction_value.as_slice());
-        let transaction_ecdsa_verify = self.transaction_ecdsa_verify.assign(ctx);
+        let transaction_ecdsa_verify =
+            self.transaction_ecdsa_verify.assign(ctx, Some(self.transaction_value_max_bytes));
         ZkSyncEraTransactionInputAssigned {
             transaction_index,
             transaction_status,
@@ -107,7 +110,7 @@ pub struct ZkSyncEraBlockTransactionCircuit {
 
 impl ZkSyncEraBlockTransactionCircuit {
     pub fn from_provider(
-        provider: &Provider<RetryClient<Http>>,
+        provider: &Provider<RpcClient>,
         constructor: TransactionConstructor,
     ) -> Self {
         let inputs = get_zksync_era_transaction_input(provider, constructor.transaction_hash);
@@ -374,12 +377,19 @@ impl<'chip, F: Field> ZkSyncEraBlockTransactionChip<F> for EthChip<'chip, F> {
         // self.parse_mpt_inclusion_phase1((ctx_gate, ctx_rlc), witness.mpt_witness);
         let (ctx_gate, ctx_rlc) = thread_pool.rlc_ctx_pair();
         self.rlc().load_rlc_cache((ctx_gate, ctx_rlc), self.gate(), CACHE_BITS);
-        let value_trace = self
+        let value_trace: Vec<RlpFieldTrace<F>> = self
             .rlp()
             .decompose_rlp_array_phase1((ctx_gate, ctx_rlc), witness.transaction_witness, true)
             .field_trace
             .try_into()
             .unwrap();
+        if let Some(message_witness) = witness.extra_witness.message_witness {
+            self.parse_transaction_signing_payload_phase1(
+                (ctx_gate, ctx_rlc),
+                &value_trace,
+                message_witness,
+            );
+        }
         ZkSyncEraTransactionTrace { value_trace }
     }
 }
@@ -6,7 +6,6 @@ use crate::util::helpers::get_provider;
 use crate::util::EthConfigParams;
 use crate::{EthPreCircuit, Network, ZkSyncEraNetwork};
 use ethers_core::types::H256;
-use std::env::set_var;
 use std::str::FromStr;
 
 fn get_test_circuit(tx_hash: H256, network: Network) -> ZkSyncEraBlockTransactionCircuit {
@@ -25,12 +24,12 @@ fn get_test_circuit(tx_hash: H256, network: Network) -> ZkSyncEraBlockTransactio
 #[test]
 pub fn test_1559_transaction() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigParams::from_path("configs/tests/zksync_era_transaction.json");
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree;
     let tx = H256::from_str("0x508331b82868cbe0d4dcfaf123660c2cbe412216d37e62802184c2128002401e")
         .unwrap();
     let input = get_test_circuit(tx, Network::ZkSync(ZkSyncEraNetwork::Goerli));
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
     Ok(())
 }
@@ -1,4 +1,5 @@
-mod tests;
+#[cfg(test)]
+pub(crate) mod tests;
 pub mod util;
 
 use crate::block_header::{get_block_header_config, BlockHeaderConfig};
@@ -11,10 +12,11 @@ use crate::rlp::rlc::FIRST_PHASE;
 use crate::rlp::RlpChip;
 use crate::transaction::ethereum::{EthBlockTransactionChip, EthBlockTransactionInput};
 use crate::transaction_receipt::util::TransactionReceiptConstructor;
+use crate::util::rpc_client::RpcClient;
 use crate::{
     EthChip, EthCircuitBuilder, EthPreCircuit, ETH_LIMB_BITS, ETH_LOOKUP_BITS, ETH_NUM_LIMBS,
 };
-use ethers_providers::{Http, Provider, RetryClient};
+use ethers_providers::Provider;
 use halo2_base::gates::{GateInstructions, RangeChip};
 use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
 use itertools::Itertools;
@@ -31,7 +33,7 @@ pub struct TransactionReceiptCircuit {
 
 impl TransactionReceiptCircuit {
     pub fn from_provider(
-        provider: &Provider<RetryClient<Http>>,
+        provider: &Provider<RpcClient>,
         constructor: TransactionReceiptConstructor,
     ) -> Self {
         let eth_transaction_input = get_transaction_input(
@@ -49,6 +51,7 @@ impl TransactionReceiptCircuit {
             constructor.eth_receipt.receipt_rlp,
             constructor.eth_receipt.merkle_proof,
             constructor.eth_receipt.receipt_pf_max_depth,
+            constructor.eth_receipt.transfer_log_index,
         );
         let block_header_config = get_block_header_config(&constructor.eth_transaction.network);
         Self { eth_transaction_input, eth_receipt_input, block_header_config }
@@ -103,6 +106,8 @@ impl EthPreCircuit for TransactionReceiptCircuit {
                 eth_transaction_digest.transaction_field.time_stamp,
                 eth_transaction_digest.transaction_field.dest_transfer_address,
                 eth_transaction_digest.transaction_field.dest_transfer_token,
+                // the final assembly checks the MDC rule was enabled at this block
+                eth_receipt_digest.block_number,
             ])
             .collect_vec();
 
@@ -124,6 +129,19 @@ impl EthPreCircuit for TransactionReceiptCircuit {
             {
                 ctx.constrain_equal(transaction_block_hash, receipt_block_hash);
             }
+
+            // An ERC20 transfer only happened if the token emitted the same Transfer log
+            if let Some(transfer_log) = &eth_receipt_digest.transfer_log {
+                let transaction_field = &eth_transaction_digest.transaction_field;
+                for (log_field, transaction_field) in [
+                    (&transfer_log.token, &transaction_field.token),
+                    (&transfer_log.from, &transaction_field.from),
+                    (&transfer_log.to, &transaction_field.to),
+                    (&transfer_log.amount, &transaction_field.amount),
+                ] {
+                    ctx.constrain_equal(log_field, transaction_field);
+                }
+            }
         }
 
         EthCircuitBuilder::new(
@@ -1,15 +1,26 @@
+use crate::block_header::get_block_header_config;
 use crate::halo2_proofs::dev::MockProver;
-use crate::receipt::util::{ReceiptConstructor, RECEIPT_PF_MAX_DEPTH};
+use crate::providers::{get_receipt_proof_input, get_transaction_proof_input};
+use crate::receipt::tests::{erc20_transfer_log, receipt_rlp};
+use crate::receipt::util::{
+    get_erc20_transfer_log_index, ReceiptConstructor, RECEIPT_PF_MAX_DEPTH,
+};
 use crate::rlp::builder::RlcThreadBuilder;
+use crate::transaction::tests::{
+    sign_transaction, single_leaf_trie, single_transaction_block, test_signer_address,
+};
 use crate::transaction::util::{TransactionConstructor, TRANSACTION_PF_MAX_DEPTH};
+use crate::transaction::FUNCTION_SELECTOR_ERC20_TRANSFER;
 use crate::transaction_receipt::util::TransactionReceiptConstructor;
 use crate::transaction_receipt::TransactionReceiptCircuit;
 use crate::util::helpers::get_provider;
-use crate::util::EthConfigParams;
+use crate::util::{encode_addr_to_field, EthConfigParams};
 use crate::{EthPreCircuit, EthereumNetwork, Network};
-use ethers_core::types::{Bytes, H256};
+use ethers_core::types::{
+    Address, Block, Bloom, Bytes, Eip1559TransactionRequest, H256, H64, U256,
+};
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
 use hex::FromHex;
-use std::env::set_var;
 use std::str::FromStr;
 
 pub fn get_test_circuit(
@@ -36,6 +47,7 @@ pub fn get_test_circuit(
         receipt_merkle_proof,
         RECEIPT_PF_MAX_DEPTH,
         network,
+        None,
     );
     let constructor =
         TransactionReceiptConstructor::new(transaction_constructor, receipt_constructor);
@@ -45,7 +57,6 @@ pub fn get_test_circuit(
 #[test]
 pub fn test_eth_transaction_receipt_1559() -> Result<(), Box<dyn std::error::Error>> {
     let params = EthConfigParams::from_path("configs/tests/transaction_receipt.json");
-    set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&params).unwrap());
     let k = params.degree;
     let transaction_hash =
         H256::from_str("0x4bec5ffb56c6fe79a662d74fb937dfa1cae0183c6f51692c2aa172b32a5e801c")
@@ -87,7 +98,96 @@ pub fn test_eth_transaction_receipt_1559() -> Result<(), Box<dyn std::error::Err
         receipt_merkle_proof,
         Network::Ethereum(EthereumNetwork::Goerli),
     );
-    let circuit = input.create_circuit(RlcThreadBuilder::mock(), None);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
     MockProver::run(k, &circuit, vec![circuit.instance()]).unwrap().assert_satisfied();
     Ok(())
 }
+
+/// A mainnet block whose only transaction is a signed ERC20 `transfer(to, 1234567)` and whose
+/// receipt holds the Transfer log of `log_amount` units emitted by the token.
+pub(crate) fn erc20_transfer_test_circuit(log_amount: u64) -> TransactionReceiptCircuit {
+    let token = Address::from_str("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48").unwrap();
+    let to = Address::from_str("0x1908e2bf4a88f91e4ef0dc72f02b8ea36bea2319").unwrap();
+    let mut calldata = FUNCTION_SELECTOR_ERC20_TRANSFER.to_vec();
+    calldata.extend(H256::from(to).as_bytes());
+    let mut amount = [0u8; 32];
+    U256::from(1234567).to_big_endian(&mut amount);
+    calldata.extend(amount);
+    let transaction = Eip1559TransactionRequest::new()
+        .chain_id(1)
+        .nonce(3)
+        .max_priority_fee_per_gas(1_000_000_000)
+        .max_fee_per_gas(30_000_000_000u64)
+        .gas(60_000)
+        .to(token)
+        .data(calldata);
+    let transaction_rlp = sign_transaction(&transaction.into()).to_vec();
+    let receipt_rlp =
+        receipt_rlp(&[erc20_transfer_log(token, test_signer_address(), to, log_amount)]);
+
+    let (receipts_root, receipt_merkle_proof) = single_leaf_trie(&receipt_rlp);
+    let block = Block::<H256> {
+        author: Some(Address::from_low_u64_be(0xc0ffee)),
+        number: Some(17_000_000u64.into()),
+        gas_limit: 30_000_000.into(),
+        gas_used: 51_000.into(),
+        timestamp: 1_680_911_891.into(),
+        logs_bloom: Some(Bloom::zero()),
+        receipts_root,
+        // prevRandao
+        mix_hash: Some(H256::repeat_byte(0x3c)),
+        nonce: Some(H64::zero()),
+        base_fee_per_gas: Some(U256::exp10(10)),
+        ..Default::default()
+    };
+    let (block, transaction_merkle_proof) = single_transaction_block(block, &transaction_rlp);
+    let transfer_log_index = get_erc20_transfer_log_index(&transaction_rlp, &receipt_rlp);
+    assert_eq!(transfer_log_index, Some(0));
+
+    let eth_transaction_input = get_transaction_proof_input(
+        block.clone(),
+        0,
+        None,
+        transaction_rlp,
+        transaction_merkle_proof,
+        TRANSACTION_PF_MAX_DEPTH,
+    );
+    let eth_receipt_input = get_receipt_proof_input(
+        block,
+        0,
+        None,
+        receipt_rlp,
+        receipt_merkle_proof,
+        RECEIPT_PF_MAX_DEPTH,
+        transfer_log_index,
+    );
+    let block_header_config = get_block_header_config(&Network::Ethereum(EthereumNetwork::Mainnet));
+    TransactionReceiptCircuit { eth_transaction_input, eth_receipt_input, block_header_config }
+}
+
+#[test]
+pub fn test_eth_transaction_receipt_erc20_transfer_mock() {
+    let params = EthConfigParams::from_path("configs/tests/transaction_receipt.json");
+    let k = params.degree;
+    let input = erc20_transfer_test_circuit(1234567);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    let instance = circuit.instance();
+    // [from, to, token, amount] are those of the call, not of the transaction
+    assert_eq!(instance[6], encode_addr_to_field(&test_signer_address()));
+    assert_eq!(instance[9], Fr::from(1234567));
+    MockProver::run(k, &circuit, vec![instance]).unwrap().assert_satisfied();
+}
+
+#[test]
+pub fn test_eth_transaction_receipt_erc20_transfer_log_mismatch_mock() {
+    let params = EthConfigParams::from_path("configs/tests/transaction_receipt.json");
+    let k = params.degree;
+    // the calldata transfers 1234567 units, the token only moved 1234566
+    let input = erc20_transfer_test_circuit(1234566);
+    let circuit = input.create_circuit(RlcThreadBuilder::mock(), params, None);
+    let _scope = circuit.config_scope();
+    let instance = circuit.instance();
+    assert!(MockProver::run(k, &circuit, vec![instance]).unwrap().verify().is_err());
+}
@@ -1,8 +1,13 @@
-use super::{AggregationConfigPinning, EthConfigPinning, Halo2ConfigPinning};
+use super::{
+    circuit_config::{isolate_env, CircuitConfig, CircuitConfigScope},
+    AggregationConfigPinning, EthConfigPinning, Halo2ConfigPinning,
+};
+#[cfg(feature = "evm")]
+use crate::gen::solidity::write_solidity_calldata;
 use crate::{
     keccak::FnSynthesize,
     rlp::builder::{RlcThreadBreakPoints, RlcThreadBuilder},
-    EthCircuitBuilder, EthPreCircuit, Field,
+    EthCircuitBuilder, EthPreCircuit, Field, AGGREGATION_MINIMUM_ROWS,
 };
 use halo2_base::{
     gates::builder::{
@@ -30,9 +35,10 @@ pub trait PinnableCircuit<F: ff::Field>: CircuitExt<F> {
 
     fn break_points(&self) -> <Self::Pinning as Halo2ConfigPinning>::BreakPoints;
 
-    fn write_pinning(&self, path: impl AsRef<Path>) {
+    /// `config` is the config the circuit was keygen'd with.
+    fn write_pinning(&self, config: CircuitConfig, path: impl AsRef<Path>) {
         let break_points = self.break_points();
-        let pinning: Self::Pinning = Halo2ConfigPinning::from_var(break_points);
+        let pinning: Self::Pinning = Halo2ConfigPinning::from_config(config, break_points);
         serde_json::to_writer_pretty(File::create(path).unwrap(), &pinning).unwrap();
     }
 }
@@ -85,6 +91,7 @@ pub trait AnyCircuit: Sized {
         self,
         params: &ParamsKZG<Bn256>,
         pk: &ProvingKey<G1Affine>,
+        pinning_path: impl AsRef<Path>,
         yul_path: impl AsRef<Path>,
     ) -> Vec<u8>;
 
@@ -97,12 +104,24 @@ pub trait AnyCircuit: Sized {
         path: impl AsRef<Path>,
         deployment_code: Option<Vec<u8>>,
     ) -> String;
+
+    /// `vk_address` is the address of the verifying key contract of [`crate::util::scheduler::Scheduler::gen_solidity_verifier`].
+    #[cfg(feature = "evm")]
+    fn gen_solidity_calldata(
+        self,
+        params: &ParamsKZG<Bn256>,
+        pk: &ProvingKey<G1Affine>,
+        pinning_path: impl AsRef<Path>,
+        vk_address: [u8; 20],
+        path: impl AsRef<Path>,
+    ) -> String;
 }
 
 pub trait PreCircuit: Sized {
     type Pinning: Halo2ConfigPinning;
 
-    /// Creates a [`PinnableCircuit`], auto-configuring the circuit if not in production or prover mode.
+    /// Creates a [`PinnableCircuit`] and its config, auto-configuring the circuit if not in production or prover mode.
+    /// The break points of `pinning` are only used by the prover.
     ///
     /// `params` should be the universal trusted setup for the present aggregation circuit.
     /// We assume the trusted setup for the previous SNARKs is compatible with `params` in the sense that
@@ -110,14 +129,21 @@ pub trait PreCircuit: Sized {
     fn create_circuit(
         self,
         stage: CircuitBuilderStage,
-        pinning: Option<Self::Pinning>,
+        pinning: Self::Pinning,
         params: &ParamsKZG<Bn256>,
-    ) -> impl PinnableCircuit<Fr>;
+    ) -> (impl PinnableCircuit<Fr>, CircuitConfig);
 
     /// Reads the proving key for the pre-circuit.
     /// If `read_only` is true, then it is assumed that the proving key exists and can be read from `path` (otherwise the program will panic).
-    fn read_pk(self, params: &ParamsKZG<Bn256>, path: impl AsRef<Path>) -> ProvingKey<G1Affine> {
-        let circuit = self.create_circuit(CircuitBuilderStage::Keygen, None, params);
+    fn read_pk(
+        self,
+        params: &ParamsKZG<Bn256>,
+        path: impl AsRef<Path>,
+        pinning_path: impl AsRef<Path>,
+    ) -> ProvingKey<G1Affine> {
+        let pinning = Self::Pinning::from_path(pinning_path);
+        let (circuit, config) = self.create_circuit(CircuitBuilderStage::Keygen, pinning, params);
+        let _scope = CircuitConfigScope::enter(config);
         custom_read_pk(path, &circuit)
     }
 
@@ -129,12 +155,14 @@ pub trait PreCircuit: Sized {
         pk_path: impl AsRef<Path>,
         pinning_path: impl AsRef<Path>,
     ) -> ProvingKey<G1Affine> {
-        let circuit = self.create_circuit(CircuitBuilderStage::Keygen, None, params);
+        let pinning = Self::Pinning::from_path(&pinning_path);
+        let (circuit, config) = self.create_circuit(CircuitBuilderStage::Keygen, pinning, params);
+        let _scope = CircuitConfigScope::enter(config.clone());
         let pk_exists = pk_path.as_ref().exists();
         let pk = gen_pk(params, &circuit, Some(pk_path.as_ref()));
         if !pk_exists {
             // should only write pinning data if we created a new pkey
-            circuit.write_pinning(pinning_path);
+            circuit.write_pinning(config, pinning_path);
         }
         pk
     }
@@ -151,12 +179,15 @@ impl<C: EthPreCircuit> PreCircuit for C {
     fn create_circuit(
         self,
         stage: CircuitBuilderStage,
-        pinning: Option<Self::Pinning>,
+        pinning: Self::Pinning,
         _: &ParamsKZG<Bn256>,
-    ) -> impl PinnableCircuit<Fr> {
+    ) -> (impl PinnableCircuit<Fr>, CircuitConfig) {
         let builder = RlcThreadBuilder::from_stage(stage);
-        let break_points = pinning.map(|p| p.break_points());
-        EthPreCircuit::create_circuit(self, builder, break_points)
+        let EthConfigPinning { params: config_params, break_points } = pinning;
+        let break_points = (stage == CircuitBuilderStage::Prover).then_some(break_points);
+        let circuit = EthPreCircuit::create_circuit(self, builder, config_params, break_points);
+        let config = CircuitConfig::Eth(circuit.config_params());
+        (circuit, config)
     }
 }
 
@@ -169,7 +200,7 @@ impl<C: PreCircuit> AnyCircuit for C {
         read_only: bool,
     ) -> ProvingKey<G1Affine> {
         if read_only {
-            self.read_pk(params, pk_path)
+            self.read_pk(params, pk_path, pinning_path)
         } else {
             self.create_pk(params, pk_path, pinning_path)
         }
@@ -183,7 +214,8 @@ impl<C: PreCircuit> AnyCircuit for C {
         path: Option<impl AsRef<Path>>,
     ) -> Snark {
         let pinning = C::Pinning::from_path(pinning_path);
-        let circuit = self.create_circuit(CircuitBuilderStage::Prover, Some(pinning), params);
+        let (circuit, config) = self.create_circuit(CircuitBuilderStage::Prover, pinning, params);
+        let _scope = CircuitConfigScope::enter(config);
         gen_snark_shplonk(params, pk, circuit, path)
     }
 
@@ -192,9 +224,11 @@ impl<C: PreCircuit> AnyCircuit for C {
         self,
         params: &ParamsKZG<Bn256>,
         pk: &ProvingKey<G1Affine>,
+        pinning_path: impl AsRef<Path>,
         yul_path: impl AsRef<Path>,
     ) -> Vec<u8> {
-        let circuit = self.create_circuit(CircuitBuilderStage::Keygen, None, params);
+        let pinning = C::Pinning::from_path(pinning_path);
+        let (circuit, _) = self.create_circuit(CircuitBuilderStage::Keygen, pinning, params);
         custom_gen_evm_verifier_shplonk(params, pk.get_vk(), &circuit, Some(yul_path))
     }
 
@@ -208,9 +242,25 @@ impl<C: PreCircuit> AnyCircuit for C {
         deployment_code: Option<Vec<u8>>,
     ) -> String {
         let pinning = C::Pinning::from_path(pinning_path);
-        let circuit = self.create_circuit(CircuitBuilderStage::Prover, Some(pinning), params);
+        let (circuit, config) = self.create_circuit(CircuitBuilderStage::Prover, pinning, params);
+        let _scope = CircuitConfigScope::enter(config);
         write_calldata_generic(params, pk, circuit, path, deployment_code)
     }
+
+    #[cfg(feature = "evm")]
+    fn gen_solidity_calldata(
+        self,
+        params: &ParamsKZG<Bn256>,
+        pk: &ProvingKey<G1Affine>,
+        pinning_path: impl AsRef<Path>,
+        vk_address: [u8; 20],
+        path: impl AsRef<Path>,
+    ) -> String {
+        let pinning = C::Pinning::from_path(pinning_path);
+        let (circuit, config) = self.create_circuit(CircuitBuilderStage::Prover, pinning, params);
+        let _scope = CircuitConfigScope::enter(config);
+        write_solidity_calldata(params, pk, circuit, vk_address, path)
+    }
 }
 
 /// Aggregates snarks and re-exposes previous public inputs.
@@ -253,32 +303,25 @@ impl PreCircuit for PublicAggregationCircuit {
     fn create_circuit(
         self,
         stage: CircuitBuilderStage,
-        pinning: Option<Self::Pinning>,
+        pinning: Self::Pinning,
         params: &ParamsKZG<Bn256>,
-    ) -> impl PinnableCircuit<Fr> {
-        // look for lookup_bits either from pinning, if available, or from env var
-        let lookup_bits = pinning
-            .as_ref()
-            .map(|p| p.params.lookup_bits)
-            .or_else(|| var("LOOKUP_BITS").map(|v| v.parse().unwrap()).ok())
-            .expect("LOOKUP_BITS is not set");
-        let break_points = pinning.map(|p| p.break_points());
+    ) -> (impl PinnableCircuit<Fr>, CircuitConfig) {
+        let config = pinning.config();
+        let lookup_bits = pinning.params.lookup_bits;
+        let break_points = (stage == CircuitBuilderStage::Prover).then(|| pinning.break_points());
         let mut private = self.private(stage, break_points, lookup_bits, params);
         for prev in &private.previous_instances {
             private.inner.assigned_instances.extend_from_slice(prev);
         }
 
         #[cfg(not(feature = "production"))]
-        match stage {
-            CircuitBuilderStage::Prover => {}
-            _ => {
-                private.config(
-                    params.k(),
-                    Some(var("MINIMUM_ROWS").unwrap_or_else(|_| "10".to_string()).parse().unwrap()),
-                );
-            }
+        if stage != CircuitBuilderStage::Prover {
+            let minimum_rows =
+                var("MINIMUM_ROWS").map_or(AGGREGATION_MINIMUM_ROWS, |v| v.parse().unwrap());
+            let gate_params = isolate_env(|| private.config(params.k(), Some(minimum_rows)));
+            return (private, CircuitConfig::Gate { params: gate_params, lookup_bits });
         }
-        private
+        (private, config)
     }
 }
 
@@ -0,0 +1,257 @@
+//! The configuration that `Circuit::configure` of a circuit needs.
+//!
+//! `configure` is a static function, so the configuration of a circuit can't be read from `self`.
+//! Our circuits read it from the innermost [`CircuitConfigScope`] of the current thread, instead of a
+//! configuration shared by every circuit of the process.
+//!
+//! The zkEVM keccak chip and the halo2-base gate circuits still read their configuration from the
+//! `KECCAK_DEGREE`, `KECCAK_ROWS`, `FLEX_GATE_CONFIG_PARAMS` and `LOOKUP_BITS` environment variables,
+//! also from the rayon threads of the prover, so a scope also sets these variables until it is dropped.
+//! The environment belongs to the whole process: threads whose scopes agree on the value of a variable
+//! hold it at the same time, a thread setting another value waits until they are dropped. Eth and gate
+//! circuits therefore keygen and prove concurrently, as do circuits with the same params, but two keccak
+//! circuits of different degrees are still serialized until zkevm-keccak takes its params explicitly.
+
+use super::EthConfigParams;
+use halo2_base::gates::builder::FlexGateConfigParams;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::env::{remove_var, set_var, var, var_os};
+use std::ffi::OsString;
+use std::marker::PhantomData;
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::thread::{self, ThreadId};
+
+#[derive(Clone, Debug)]
+pub enum CircuitConfig {
+    /// Circuits built with [`crate::EthCircuitBuilder`] or [`crate::keccak::KeccakCircuitBuilder`].
+    Eth(EthConfigParams),
+    /// Circuits built with halo2-base's `GateThreadBuilder`, such as the snark-verifier aggregation circuits.
+    Gate { params: FlexGateConfigParams, lookup_bits: usize },
+}
+
+impl CircuitConfig {
+    fn env_vars(&self) -> Vec<(&'static str, String)> {
+        match self {
+            CircuitConfig::Eth(params) => vec![
+                ("KECCAK_DEGREE", params.degree.to_string()),
+                ("KECCAK_ROWS", params.keccak_rows_per_round.to_string()),
+            ],
+            CircuitConfig::Gate { params, lookup_bits } => vec![
+                ("FLEX_GATE_CONFIG_PARAMS", serde_json::to_string(params).unwrap()),
+                ("LOOKUP_BITS", lookup_bits.to_string()),
+            ],
+        }
+    }
+}
+
+/// The scopes holding a configuration variable.
+struct EnvVarHold {
+    /// The value of the variable before the first scope set it.
+    original: Option<OsString>,
+    /// The values set by the scopes, `None` if the holder may write the variable itself. Only the
+    /// thread holding a nested value can hold it again, other threads share the first value.
+    values: Vec<Option<String>>,
+    /// The threads holding the variable, once per guard.
+    holders: Vec<ThreadId>,
+}
+
+impl EnvVarHold {
+    /// Whether a scope setting `value` shares the variable with its holders.
+    fn shares(&self, value: &Option<String>) -> bool {
+        value.is_some() && self.values.len() == 1 && &self.values[0] == value
+    }
+
+    /// Whether `thread` can hold the variable with `value`, i.e. share it or nest another value.
+    fn admits(&self, thread: ThreadId, value: &Option<String>) -> bool {
+        self.shares(value) || self.holders.iter().all(|holder| *holder == thread)
+    }
+}
+
+#[derive(Default)]
+struct EnvLock {
+    holds: HashMap<&'static str, EnvVarHold>,
+    /// The threads waiting for a variable, with the other threads holding it.
+    waiting: HashMap<ThreadId, Vec<ThreadId>>,
+}
+
+impl EnvLock {
+    /// Whether one of `threads` waits for `thread`, directly or through other waiting threads.
+    fn waits_for(&self, threads: &[ThreadId], thread: ThreadId) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = threads.to_vec();
+        while let Some(waiting) = pending.pop() {
+            if waiting == thread {
+                return true;
+            }
+            if visited.insert(waiting) {
+                pending.extend(self.waiting.get(&waiting).into_iter().flatten());
+            }
+        }
+        false
+    }
+}
+
+lazy_static! {
+    static ref ENV_LOCK: (Mutex<EnvLock>, Condvar) = Default::default();
+}
+
+thread_local! {
+    static CONFIGS: RefCell<Vec<CircuitConfig>> = RefCell::new(Vec::new());
+}
+
+/// Holds configuration variables until it is dropped.
+///
+/// Threads setting a variable to the same value hold it at the same time, e.g. the scopes of two
+/// circuits of the same degree, and the keccak and gate variables are independent of each other. A
+/// thread waits while the other threads hold a variable with another value, and panics instead if they
+/// wait for it. The thread holding a variable alone can nest guards, which must be dropped in the
+/// reverse order they were created.
+struct EnvGuard {
+    /// The variables, with their previous value if the guard set them.
+    vars: Vec<(&'static str, Option<Option<OsString>>)>,
+}
+
+impl EnvGuard {
+    /// Sets the variables with a value, the others are only restored when the last guard is dropped.
+    fn set(vars: Vec<(&'static str, Option<String>)>) -> Self {
+        let (lock, released) = &*ENV_LOCK;
+        let id = thread::current().id();
+        // a panicking prover must not block the other threads forever
+        let mut lock = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        while let Some((key, _)) = vars
+            .iter()
+            .find(|(key, value)| lock.holds.get(key).map_or(false, |hold| !hold.admits(id, value)))
+        {
+            let holders = lock.holds[key]
+                .holders
+                .iter()
+                .filter(|holder| **holder != id)
+                .copied()
+                .collect_vec();
+            // e.g. two threads creating a circuit inside the scope of another circuit
+            if lock.waits_for(&holders, id) {
+                panic!(
+                    "deadlock on `{key}`: create circuits before entering the scope of a circuit"
+                );
+            }
+            lock.waiting.insert(id, holders);
+            lock = released.wait(lock).unwrap_or_else(PoisonError::into_inner);
+            lock.waiting.remove(&id);
+        }
+        let holds = &mut lock.holds;
+        let vars = vars
+            .into_iter()
+            .map(|(key, value)| {
+                let hold = holds.entry(key).or_insert_with(|| EnvVarHold {
+                    original: var_os(key),
+                    values: vec![],
+                    holders: vec![],
+                });
+                hold.holders.push(id);
+                if hold.shares(&value) {
+                    return (key, None);
+                }
+                let saved = var_os(key);
+                if let Some(value) = &value {
+                    set_var(key, value);
+                }
+                hold.values.push(value);
+                (key, Some(saved))
+            })
+            .collect();
+        Self { vars }
+    }
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        let (lock, released) = &*ENV_LOCK;
+        let id = thread::current().id();
+        let mut lock = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let holds = &mut lock.holds;
+        for (key, saved) in self.vars.drain(..).rev() {
+            let hold = holds.get_mut(key).expect("configuration variable not held");
+            let holder = hold.holders.iter().rposition(|holder| *holder == id).unwrap();
+            hold.holders.remove(holder);
+            let restored = if hold.holders.is_empty() {
+                holds.remove(key).unwrap().original
+            } else if let Some(saved) = saved {
+                hold.values.pop();
+                saved
+            } else {
+                continue;
+            };
+            match restored {
+                Some(value) => set_var(key, value),
+                None => remove_var(key),
+            }
+        }
+        released.notify_all();
+    }
+}
+
+/// Makes `config` the configuration of the circuits configured by the current thread until it is dropped.
+///
+/// Keygen, mock proving and proving must all happen inside the scope of the circuit's configuration.
+#[must_use]
+pub struct CircuitConfigScope {
+    _env: EnvGuard,
+    // the scope belongs to the thread that entered it
+    _not_send: PhantomData<*const ()>,
+}
+
+impl CircuitConfigScope {
+    pub fn enter(config: CircuitConfig) -> Self {
+        let env = EnvGuard::set(
+            config.env_vars().into_iter().map(|(key, value)| (key, Some(value))).collect(),
+        );
+        CONFIGS.with(|configs| configs.borrow_mut().push(config));
+        Self { _env: env, _not_send: PhantomData }
+    }
+}
+
+impl Drop for CircuitConfigScope {
+    fn drop(&mut self) {
+        CONFIGS.with(|configs| configs.borrow_mut().pop());
+    }
+}
+
+/// The configuration of the innermost scope of the current thread, if any.
+pub fn current_circuit_config() -> Option<CircuitConfig> {
+    CONFIGS.with(|configs| configs.borrow().last().cloned())
+}
+
+/// The configuration of the innermost scope, which must be [`CircuitConfig::Eth`].
+pub fn eth_config_params() -> EthConfigParams {
+    match current_circuit_config() {
+        Some(CircuitConfig::Eth(params)) => params,
+        config => panic!("expected an Eth circuit config scope, found {config:?}"),
+    }
+}
+
+/// Runs `f`, which may write `FLEX_GATE_CONFIG_PARAMS` (e.g. halo2-base's auto-configuration), without
+/// affecting the other threads.
+pub(crate) fn isolate_env<T>(f: impl FnOnce() -> T) -> T {
+    let _env = EnvGuard::set(vec![("FLEX_GATE_CONFIG_PARAMS", None)]);
+    f()
+}
+
+/// Runs `f` with the configuration variable `key` set to `value`, e.g. to call zkevm-keccak outside of a
+/// scope.
+pub(crate) fn with_env_var<T>(key: &'static str, value: String, f: impl FnOnce() -> T) -> T {
+    let _env = EnvGuard::set(vec![(key, Some(value))]);
+    f()
+}
+
+/// Reads the environment variable `key` as it is outside of the scopes, e.g. the `KECCAK_DEGREE` of a
+/// test command line.
+pub fn read_env_var(key: &str) -> Option<String> {
+    let lock = ENV_LOCK.0.lock().unwrap_or_else(PoisonError::into_inner);
+    match lock.holds.get(key) {
+        Some(hold) => hold.original.clone().map(|value| value.into_string().unwrap()),
+        None => var(key).ok(),
+    }
+}
@@ -33,7 +33,7 @@ pub fn circuit_derive(input: TokenStream) -> TokenStream {
     let gen_evm_verifier_shplonk_arms = variants.iter().map(|variant| {
         let ident = &variant.ident;
         quote! {
-            Self::#ident(pre_circuit) => pre_circuit.gen_evm_verifier_shplonk(params, pk, yul_path)
+            Self::#ident(pre_circuit) => pre_circuit.gen_evm_verifier_shplonk(params, pk, pinning_path, yul_path)
         }
     });
 
@@ -44,6 +44,13 @@ pub fn circuit_derive(input: TokenStream) -> TokenStream {
         }
     });
 
+    let gen_solidity_calldata_arms = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        quote! {
+            Self::#ident(pre_circuit) => pre_circuit.gen_solidity_calldata(params, pk, pinning_path, vk_address, path)
+        }
+    });
+
     let expanded = quote! {
         impl #impl_generics AnyCircuit for #name #ty_generics #where_clause {
             fn read_or_create_pk(
@@ -74,6 +81,7 @@ pub fn circuit_derive(input: TokenStream) -> TokenStream {
                 self,
                 params: &ParamsKZG<Bn256>,
                 pk: &ProvingKey<G1Affine>,
+                pinning_path: impl AsRef<Path>,
                 yul_path: impl AsRef<Path>,
             ) -> Vec<u8> {
                 match self {
@@ -93,6 +101,19 @@ pub fn circuit_derive(input: TokenStream) -> TokenStream {
                     #(#gen_calldata_arms,)*
                 }
             }
+
+            fn gen_solidity_calldata(
+                self,
+                params: &ParamsKZG<Bn256>,
+                pk: &ProvingKey<G1Affine>,
+                pinning_path: impl AsRef<Path>,
+                vk_address: [u8; 20],
+                path: impl AsRef<Path>,
+            ) -> String {
+                match self {
+                    #(#gen_solidity_calldata_arms,)*
+                }
+            }
         }
     };
 
@@ -129,7 +150,7 @@ pub fn any_circuit_derive(input: TokenStream) -> TokenStream {
     let gen_evm_verifier_shplonk_arms = variants.iter().map(|variant| {
         let ident = &variant.ident;
         quote! {
-            Self::#ident(pre_circuit) => pre_circuit.gen_evm_verifier_shplonk(params, pk, yul_path)
+            Self::#ident(pre_circuit) => pre_circuit.gen_evm_verifier_shplonk(params, pk, pinning_path, yul_path)
         }
     });
 
@@ -140,6 +161,13 @@ pub fn any_circuit_derive(input: TokenStream) -> TokenStream {
         }
     });
 
+    let gen_solidity_calldata_arms = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        quote! {
+            Self::#ident(pre_circuit) => pre_circuit.gen_solidity_calldata(params, pk, pinning_path, vk_address, path)
+        }
+    });
+
     let expanded = quote! {
         impl #impl_generics AnyCircuit for #name #ty_generics #where_clause {
             fn read_or_create_pk(
@@ -170,6 +198,7 @@ pub fn any_circuit_derive(input: TokenStream) -> TokenStream {
                 self,
                 params: &ParamsKZG<Bn256>,
                 pk: &ProvingKey<G1Affine>,
+                pinning_path: impl AsRef<Path>,
                 yul_path: impl AsRef<Path>,
             ) -> Vec<u8> {
                 match self {
@@ -189,6 +218,19 @@ pub fn any_circuit_derive(input: TokenStream) -> TokenStream {
                     #(#gen_calldata_arms,)*
                 }
             }
+
+            fn gen_solidity_calldata(
+                self,
+                params: &ParamsKZG<Bn256>,
+                pk: &ProvingKey<G1Affine>,
+                pinning_path: impl AsRef<Path>,
+                vk_address: [u8; 20],
+                path: impl AsRef<Path>,
+            ) -> String {
+                match self {
+                    #(#gen_solidity_calldata_arms,)*
+                }
+            }
         }
     };
 
@@ -1 +1,2 @@
 pub mod erc20;
+pub mod zksync_era;
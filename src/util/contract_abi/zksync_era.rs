@@ -0,0 +1,123 @@
+use std::borrow::ToOwned;
+
+use ethers_core::abi::{encode, Function, Param, ParamType, StateMutability, Token};
+use ethers_core::types::{Bytes, H256, U256};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error)]
+pub enum ZkSyncEraAbiError {
+    /// Does not conform to the signature.
+    #[error("does not conform to the signature")]
+    SignatureNotConform,
+    /// The calldata can't be decoded as the function inputs.
+    #[error("invalid calldata")]
+    InvalidCalldata,
+}
+
+/// `StoredBatchInfo` of the zkSync Era diamond proxy, its keccak is kept in `storedBatchHashes`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredBatchInfo {
+    pub batch_number: u64,
+    pub batch_hash: H256,
+    pub index_repeated_storage_changes: u64,
+    pub number_of_layer1_txs: U256,
+    pub priority_operations_hash: H256,
+    pub l2_logs_tree_root: H256,
+    pub timestamp: U256,
+    pub commitment: H256,
+}
+
+impl StoredBatchInfo {
+    pub fn param_type() -> ParamType {
+        ParamType::Tuple(vec![
+            ParamType::Uint(64),
+            ParamType::FixedBytes(32),
+            ParamType::Uint(64),
+            ParamType::Uint(256),
+            ParamType::FixedBytes(32),
+            ParamType::FixedBytes(32),
+            ParamType::Uint(256),
+            ParamType::FixedBytes(32),
+        ])
+    }
+
+    pub fn from_token(token: Token) -> Result<Self, ZkSyncEraAbiError> {
+        let fields = token.into_tuple().ok_or(ZkSyncEraAbiError::InvalidCalldata)?;
+        let [
+            batch_number,
+            batch_hash,
+            index_repeated_storage_changes,
+            number_of_layer1_txs,
+            priority_operations_hash,
+            l2_logs_tree_root,
+            timestamp,
+            commitment,
+        ]: [Token; 8] = fields.try_into().map_err(|_| ZkSyncEraAbiError::InvalidCalldata)?;
+        let uint = |token: Token| token.into_uint().ok_or(ZkSyncEraAbiError::InvalidCalldata);
+        let bytes32 = |token: Token| {
+            token
+                .into_fixed_bytes()
+                .map(|bytes| H256::from_slice(&bytes))
+                .ok_or(ZkSyncEraAbiError::InvalidCalldata)
+        };
+        Ok(Self {
+            batch_number: uint(batch_number)?.as_u64(),
+            batch_hash: bytes32(batch_hash)?,
+            index_repeated_storage_changes: uint(index_repeated_storage_changes)?.as_u64(),
+            number_of_layer1_txs: uint(number_of_layer1_txs)?,
+            priority_operations_hash: bytes32(priority_operations_hash)?,
+            l2_logs_tree_root: bytes32(l2_logs_tree_root)?,
+            timestamp: uint(timestamp)?,
+            commitment: bytes32(commitment)?,
+        })
+    }
+
+    /// `abi.encode(StoredBatchInfo)`, every field takes one word.
+    pub fn abi_encode(&self) -> Vec<u8> {
+        encode(&[Token::Tuple(vec![
+            Token::Uint(self.batch_number.into()),
+            Token::FixedBytes(self.batch_hash.as_bytes().to_vec()),
+            Token::Uint(self.index_repeated_storage_changes.into()),
+            Token::Uint(self.number_of_layer1_txs),
+            Token::FixedBytes(self.priority_operations_hash.as_bytes().to_vec()),
+            Token::FixedBytes(self.l2_logs_tree_root.as_bytes().to_vec()),
+            Token::Uint(self.timestamp),
+            Token::FixedBytes(self.commitment.as_bytes().to_vec()),
+        ])])
+    }
+}
+
+pub fn get_execute_batches_abi() -> Function {
+    Function {
+        name: "executeBatches".to_owned(),
+        inputs: vec![Param {
+            name: "_batchesData".to_owned(),
+            kind: ParamType::Array(Box::new(StoredBatchInfo::param_type())),
+            internal_type: Some("struct IExecutor.StoredBatchInfo[]".to_owned()),
+        }],
+        outputs: vec![],
+        constant: None,
+        state_mutability: StateMutability::NonPayable,
+    }
+}
+
+/// Decodes the batches of an `executeBatches` transaction sent to the diamond proxy.
+pub fn decode_execute_batches_input(
+    input: Bytes,
+) -> Result<Vec<StoredBatchInfo>, ZkSyncEraAbiError> {
+    let execute_batches_signature = get_execute_batches_abi().short_signature().to_vec();
+    let no_signature = input
+        .strip_prefix(execute_batches_signature.as_slice())
+        .ok_or(ZkSyncEraAbiError::SignatureNotConform)?;
+    let tokens = get_execute_batches_abi()
+        .decode_input(no_signature)
+        .map_err(|_| ZkSyncEraAbiError::InvalidCalldata)?;
+    tokens
+        .into_iter()
+        .next()
+        .and_then(Token::into_array)
+        .ok_or(ZkSyncEraAbiError::InvalidCalldata)?
+        .into_iter()
+        .map(StoredBatchInfo::from_token)
+        .collect()
+}
@@ -1,3 +1,5 @@
+use thiserror::Error;
+
 #[derive(Debug)]
 pub enum ErrorType {
     NetworkNotSupported,
@@ -5,3 +7,47 @@ pub enum ErrorType {
 
 pub const COMMIT_TRANSACTION_IS_EMPTY: &str =
     "CommitTransaction value cannot be empty in a specific network";
+
+pub const MDC_STATE_TASK_IS_EMPTY: &str =
+    "MDC state task cannot be empty in a source final assembly";
+
+pub const OP_STACK_SOURCE_IS_NOT_SUPPORTED: &str =
+    "a source final assembly cannot bind an OP-Stack block to its L1 origin";
+
+/// Why the sub-tasks of a final assembly cannot be assembled.
+#[derive(Debug, Error)]
+pub enum FinalAssemblyError {
+    #[error("{}", MDC_STATE_TASK_IS_EMPTY)]
+    MdcStateTaskIsEmpty,
+    /// The transaction block of an OP-Stack chain has no L1 block number to compare with the block batch.
+    #[error("{}", OP_STACK_SOURCE_IS_NOT_SUPPORTED)]
+    OpStackSourceIsNotSupported,
+}
+
+/// Why the proof json of a `generate_proof` request is rejected, `field` is the json path of the bad value.
+#[derive(Debug, Error)]
+pub enum ProofRequestError {
+    /// Not json, or a field is missing or has the wrong type.
+    #[error("malformed proof request: {0}")]
+    MalformedJson(#[from] serde_json::Error),
+    #[error("{field} is required")]
+    Missing { field: String },
+    #[error("{field} should not be empty")]
+    Empty { field: String },
+    #[error("{field} is not a hex string")]
+    InvalidHex { field: String },
+    #[error("{field} should be {expected} bytes, got {actual}")]
+    InvalidLength { field: String, expected: usize, actual: usize },
+    #[error("{field} should have {expected} items, got {actual}")]
+    InvalidCount { field: String, expected: usize, actual: usize },
+    #[error("{field} is {index} but the batch has {len} blocks")]
+    TargetBlockIndexOutOfRange { field: String, index: u32, len: usize },
+    #[error("chain id {0} is not supported")]
+    UnsupportedNetwork(u64),
+    #[error(
+        "arbitration from chain id {source_network} to chain id {dest_network} is not supported"
+    )]
+    UnsupportedNetworkPair { source_network: u64, dest_network: u64 },
+    #[error(transparent)]
+    FinalAssembly(#[from] FinalAssemblyError),
+}
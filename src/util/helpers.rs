@@ -1,20 +1,22 @@
-use ethers_core::abi;
-use ethers_core::abi::{AbiEncode, Token, Uint};
 use ethers_core::types::{Address, BigEndianHash, H256};
-use ethers_core::utils::keccak256;
-use ethers_providers::{Http, Middleware, Provider, RetryClient, RetryClientBuilder};
+use ethers_providers::{Middleware, Provider};
 use halo2_base::{AssignedValue, Context};
-use std::ops::Add;
+use std::fs;
 use tokio::runtime::Runtime;
 use zkevm_keccak::util::eth_types::Field;
 
+use crate::arbitration::types::BatchBlocksInput;
 use crate::config::rpcs::get_rpcs_config;
 use crate::keccak::get_bytes;
 use crate::mpt::AssignedBytes;
-use crate::{ArbitrumNetwork, EthereumNetwork, Network, OptimismNetwork, ZkSyncEraNetwork};
+use crate::storage::layout::{MappingKey, StorageLocation};
+use crate::util::rpc_client::{get_rpc_fixture_path, RpcClient, RpcFixtureMode};
+use crate::{
+    ArbitrumNetwork, BaseNetwork, EthereumNetwork, Network, OptimismNetwork, ZkSyncEraNetwork,
+};
 
 pub fn get_block_batch_hashes(
-    provider: &Provider<RetryClient<Http>>,
+    provider: &Provider<RpcClient>,
     start_block_num: u32,
     end_block_num: u32,
 ) -> Vec<H256> {
@@ -30,12 +32,20 @@ pub fn get_block_batch_hashes(
     leaves
 }
 
-pub fn get_provider(network: &Network) -> Provider<RetryClient<Http>> {
+/// The block batches of `test_data/block_batch_data.json`.
+pub fn get_block_data_hashes_from_json() -> BatchBlocksInput {
+    let data = fs::read_to_string("test_data/block_batch_data.json").unwrap();
+    BatchBlocksInput::from_json(&serde_json::from_str(&data).unwrap(), "").unwrap()
+}
+
+pub fn get_provider(network: &Network) -> Provider<RpcClient> {
     let rpcs = get_rpcs_config();
     let provider_url = match network {
         Network::Ethereum(ethereum_network) => match ethereum_network {
             EthereumNetwork::Mainnet => rpcs.ethereum.mainnet,
             EthereumNetwork::Goerli => rpcs.ethereum.goerli,
+            EthereumNetwork::Sepolia => rpcs.ethereum.sepolia,
+            EthereumNetwork::Holesky => rpcs.ethereum.holesky,
         },
         Network::Arbitrum(arbitrum_network) => match arbitrum_network {
             ArbitrumNetwork::Mainnet => rpcs.arbitrum.mainnet,
@@ -45,14 +55,33 @@ pub fn get_provider(network: &Network) -> Provider<RetryClient<Http>> {
             OptimismNetwork::Mainnet => rpcs.optimism.mainnet,
             OptimismNetwork::Goerli => rpcs.optimism.goerli,
         },
+        Network::Base(base_network) => match base_network {
+            BaseNetwork::Mainnet => rpcs.base.mainnet,
+            BaseNetwork::Goerli => rpcs.base.goerli,
+        },
         Network::ZkSync(zksync_network) => match zksync_network {
             ZkSyncEraNetwork::Mainnet => rpcs.zksync_era.mainnet,
             ZkSyncEraNetwork::Goerli => rpcs.zksync_era.goerli,
+            ZkSyncEraNetwork::Sepolia => rpcs.zksync_era.sepolia,
         },
     };
-    let provider = Provider::new_client(provider_url.as_str(), 10, 1)
+    let client = RpcClient::new(provider_url.as_str(), &get_rpc_fixture_name(network))
         .expect("could not instantiate HTTP Provider");
-    provider
+    Provider::new(client)
+}
+
+/// A provider that never contacts a node and doesn't need `spv.toml`, it only answers the requests recorded in
+/// the RPC fixture of the network.
+pub fn get_offline_provider(network: &Network) -> Provider<RpcClient> {
+    let path = get_rpc_fixture_path(&get_rpc_fixture_name(network));
+    let client = RpcClient::with_fixture(RpcFixtureMode::Replay, "", path)
+        .expect("could not open RPC fixture");
+    Provider::new(client)
+}
+
+/// Recorded per chain, e.g. `test_data/rpc_fixtures/ethereum_1.json`.
+fn get_rpc_fixture_name(network: &Network) -> String {
+    format!("{}_{}", network.to_string(), network.chain_id())
 }
 
 pub fn bytes_to_vec_u8<F: Field>(bytes_value: &AssignedBytes<F>) -> Vec<u8> {
@@ -99,9 +128,9 @@ pub fn load_bytes<F: Field>(ctx: &mut Context<F>, bytes: &[u8]) -> Vec<AssignedV
 
 /// keccak(LeftPad32(key, 0), LeftPad32(map position, 0))
 pub fn calculate_storage_mapping_key(mapping_layout: H256, address: Address) -> H256 {
-    let internal_bytes =
-        [H256::from(address).to_fixed_bytes(), mapping_layout.to_fixed_bytes()].concat();
-    H256::from(keccak256(internal_bytes))
+    StorageLocation::new(mapping_layout.into_uint())
+        .mapping(MappingKey::address(address))
+        .derive_slot()
 }
 
 /// used for mapping(address => struct)
@@ -111,13 +140,15 @@ pub fn calculate_storage_mapping_key(mapping_layout: H256, address: Address) ->
 /// mapping_position:Map the storage location in the contract
 ///
 /// slot_position:The position of the structure element to be obtained.
+///
+/// See [`StorageLocation`] for nested mappings, arrays and packed fields.
 pub fn calculate_mk_address_struct(
     key: Address,
     mapping_position: usize,
     slot_position: usize,
 ) -> H256 {
-    let encoded = abi::encode(&[Token::Address(key), Token::Uint(Uint::from(mapping_position))]);
-    let slot = keccak256(encoded);
-    let slot = Uint::from(slot).add(slot_position);
-    H256::from_uint(&slot)
+    StorageLocation::new(mapping_position)
+        .mapping(MappingKey::address(key))
+        .offset(slot_position as u64)
+        .derive_slot()
 }
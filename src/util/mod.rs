@@ -1,5 +1,6 @@
 use super::Field;
-use crate::{rlp::builder::RlcThreadBreakPoints, ETH_LOOKUP_BITS};
+use crate::rlp::builder::RlcThreadBreakPoints;
+use circuit_config::CircuitConfig;
 use ethers_core::{
     types::{Address, H256, U256},
     utils::keccak256,
@@ -18,21 +19,17 @@ use halo2_base::{
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use snark_verifier_sdk::halo2::aggregation::AggregationConfigParams;
-use std::{
-    env::{set_var, var},
-    fs::File,
-    iter,
-    path::Path,
-};
-pub mod concur_var;
-
-use concur_var::{set_var_thread_safe, var_thread_safe};
+use std::{fs::File, iter, path::Path};
 #[cfg(feature = "aggregation")]
 pub mod circuit;
+pub mod circuit_config;
 pub mod contract_abi;
 pub mod helpers;
+pub mod rpc_client;
 #[cfg(feature = "aggregation")]
 pub mod scheduler;
+#[cfg(feature = "aggregation")]
+pub mod verify;
 
 pub mod errors;
 pub mod zk_provider;
@@ -40,6 +37,8 @@ pub mod zk_provider;
 pub(crate) const NUM_BYTES_IN_U128: usize = 16;
 
 pub type AssignedH256<F> = [AssignedValue<F>; 2]; // H256 as hi-lo (u128, u128)
+/// Number of instances taken by an `AssignedH256`.
+pub const H256_INSTANCES_LEN: usize = 2;
 
 pub fn is_leaf_zero_pad(bytes: &Vec<u8>) -> bool {
     let zero_pad = vec![0u8; 32];
@@ -76,14 +75,14 @@ impl EthConfigParams {
 
 pub trait Halo2ConfigPinning: Serialize {
     type BreakPoints;
-    /// Loads configuration parameters from a file and sets environmental variables.
+    /// Loads configuration parameters from a file.
     fn from_path<P: AsRef<Path>>(path: P) -> Self;
-    /// Loads configuration parameters into environment variables.
-    fn set_var(&self);
     /// Returns break points
     fn break_points(self) -> Self::BreakPoints;
-    /// Constructs `Self` from environmental variables and break points
-    fn from_var(break_points: Self::BreakPoints) -> Self;
+    /// Configuration of the pinned circuit, see [`circuit_config`].
+    fn config(&self) -> CircuitConfig;
+    /// Constructs `Self` from the configuration of a circuit and its break points
+    fn from_config(config: CircuitConfig, break_points: Self::BreakPoints) -> Self;
     /// Degree of the circuit, log_2(number of rows)
     fn degree(&self) -> u32;
 }
@@ -99,36 +98,26 @@ impl Halo2ConfigPinning for EthConfigPinning {
 
     fn from_path<P: AsRef<Path>>(path: P) -> Self {
         println!("path:{:?}", &path.as_ref());
-        let pinning: Self = serde_json::from_reader(
+        serde_json::from_reader(
             File::open(&path)
                 .unwrap_or_else(|e| panic!("{:?} does not exist: {e:?}", path.as_ref())),
         )
-        .unwrap();
-        pinning.set_var();
-        pinning
-    }
-
-    fn set_var(&self) {
-        // set_var("ETH_CONFIG_PARAMS", serde_json::to_string(&self.params).unwrap());
-        // set_var("KECCAK_ROWS", self.params.keccak_rows_per_round.to_string());
-        // let bits = self.params.lookup_bits.unwrap_or(ETH_LOOKUP_BITS);
-        // set_var("LOOKUP_BITS", bits.to_string());
-        set_var_thread_safe("ETH_CONFIG_PARAMS", serde_json::to_string(&self.params).unwrap());
-        set_var_thread_safe("KECCAK_ROWS", self.params.keccak_rows_per_round.to_string());
-        let bits = self.params.lookup_bits.unwrap_or(ETH_LOOKUP_BITS);
-        set_var_thread_safe("LOOKUP_BITS", bits.to_string());
+        .unwrap()
     }
 
     fn break_points(self) -> RlcThreadBreakPoints {
         self.break_points
     }
 
-    fn from_var(break_points: RlcThreadBreakPoints) -> Self {
-        // let params: EthConfigParams =
-        //     serde_json::from_str(&var("ETH_CONFIG_PARAMS").unwrap()).unwrap();
-        let params: EthConfigParams =
-            serde_json::from_str(&var_thread_safe("ETH_CONFIG_PARAMS").unwrap()).unwrap();
-        Self { params, break_points }
+    fn config(&self) -> CircuitConfig {
+        CircuitConfig::Eth(self.params.clone())
+    }
+
+    fn from_config(config: CircuitConfig, break_points: RlcThreadBreakPoints) -> Self {
+        match config {
+            CircuitConfig::Eth(params) => Self { params, break_points },
+            config => panic!("expected an Eth circuit config, found {config:?}"),
+        }
     }
 
     fn degree(&self) -> u32 {
@@ -146,49 +135,41 @@ impl Halo2ConfigPinning for AggregationConfigPinning {
     type BreakPoints = MultiPhaseThreadBreakPoints;
 
     fn from_path<P: AsRef<Path>>(path: P) -> Self {
-        let pinning: Self = serde_json::from_reader(
+        serde_json::from_reader(
             File::open(&path)
                 .unwrap_or_else(|e| panic!("{:?} does not exist: {e:?}", path.as_ref())),
         )
-        .unwrap();
-        pinning.set_var();
-        pinning
+        .unwrap()
     }
 
-    fn set_var(&self) {
-        let gate_params = FlexGateConfigParams {
+    fn break_points(self) -> MultiPhaseThreadBreakPoints {
+        self.break_points
+    }
+
+    fn config(&self) -> CircuitConfig {
+        let params = FlexGateConfigParams {
             k: self.params.degree as usize,
             num_advice_per_phase: vec![self.params.num_advice],
             num_lookup_advice_per_phase: vec![self.params.num_lookup_advice],
             strategy: GateStrategy::Vertical,
             num_fixed: self.params.num_fixed,
         };
-        // set_var("FLEX_GATE_CONFIG_PARAMS", serde_json::to_string(&gate_params).unwrap());
-        // set_var("LOOKUP_BITS", self.params.lookup_bits.to_string());
-        set_var_thread_safe(
-            "FLEX_GATE_CONFIG_PARAMS",
-            serde_json::to_string(&gate_params).unwrap(),
-        );
-        set_var_thread_safe("LOOKUP_BITS", self.params.lookup_bits.to_string());
-    }
-
-    fn break_points(self) -> MultiPhaseThreadBreakPoints {
-        self.break_points
+        CircuitConfig::Gate { params, lookup_bits: self.params.lookup_bits }
     }
 
-    fn from_var(break_points: MultiPhaseThreadBreakPoints) -> Self {
-        let params: FlexGateConfigParams =
-            serde_json::from_str(&var("FLEX_GATE_CONFIG_PARAMS").unwrap()).unwrap();
-        let lookup_bits = var("LOOKUP_BITS").unwrap().parse().unwrap();
-        Self {
-            params: AggregationConfigParams {
-                degree: params.k as u32,
-                num_advice: params.num_advice_per_phase[0],
-                num_lookup_advice: params.num_lookup_advice_per_phase[0],
-                num_fixed: params.num_fixed,
-                lookup_bits,
+    fn from_config(config: CircuitConfig, break_points: MultiPhaseThreadBreakPoints) -> Self {
+        match config {
+            CircuitConfig::Gate { params, lookup_bits } => Self {
+                params: AggregationConfigParams {
+                    degree: params.k as u32,
+                    num_advice: params.num_advice_per_phase[0],
+                    num_lookup_advice: params.num_lookup_advice_per_phase[0],
+                    num_fixed: params.num_fixed,
+                    lookup_bits,
+                },
+                break_points,
             },
-            break_points,
+            config => panic!("expected a gate circuit config, found {config:?}"),
         }
     }
 
@@ -0,0 +1,256 @@
+#[cfg(test)]
+mod tests;
+
+use async_trait::async_trait;
+use ethers_providers::{
+    Http, HttpRateLimitRetryPolicy, JsonRpcClient, JsonRpcError, ProviderError, RetryClient,
+    RetryClientError, RpcError,
+};
+use lazy_static::lazy_static;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::env::var;
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// Bumped whenever the layout of the fixture files changes, older files must be recorded again.
+pub const RPC_FIXTURE_VERSION: u32 = 1;
+pub const RPC_FIXTURE_MODE_ENV: &str = "RPC_FIXTURE_MODE";
+pub const RPC_FIXTURE_DIR_ENV: &str = "RPC_FIXTURE_DIR";
+pub const DEFAULT_RPC_FIXTURE_DIR: &str = "test_data/rpc_fixtures";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RpcFixtureMode {
+    /// Every request goes to the node.
+    Live,
+    /// Every request goes to the node and its response is written to the fixture file.
+    Record,
+    /// Every request is answered from the fixture file, the node is never contacted.
+    Replay,
+}
+
+impl RpcFixtureMode {
+    /// Reads `RPC_FIXTURE_MODE`, `live` if it is not set.
+    pub fn from_env() -> Result<Self, RpcClientError> {
+        match var(RPC_FIXTURE_MODE_ENV) {
+            Ok(mode) => mode.parse(),
+            Err(_) => Ok(RpcFixtureMode::Live),
+        }
+    }
+}
+
+impl FromStr for RpcFixtureMode {
+    type Err = RpcClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "live" => Ok(RpcFixtureMode::Live),
+            "record" => Ok(RpcFixtureMode::Record),
+            "replay" => Ok(RpcFixtureMode::Replay),
+            _ => Err(RpcClientError::InvalidMode(s.to_string())),
+        }
+    }
+}
+
+/// The fixtures of one network, `{RPC_FIXTURE_DIR}/{name}.json`.
+pub fn get_rpc_fixture_path(name: &str) -> PathBuf {
+    let dir = var(RPC_FIXTURE_DIR_ENV).unwrap_or_else(|_| DEFAULT_RPC_FIXTURE_DIR.to_string());
+    Path::new(&dir).join(format!("{name}.json"))
+}
+
+#[derive(Error, Debug)]
+pub enum RpcClientError {
+    #[error(transparent)]
+    Live(#[from] RetryClientError),
+    #[error("no recorded response for `{key}` in {path}, record it with RPC_FIXTURE_MODE=record")]
+    MissingResponse { key: String, path: String },
+    #[error("{path} has fixture version {version}, expected {}", RPC_FIXTURE_VERSION)]
+    UnsupportedVersion { path: String, version: u32 },
+    #[error("invalid {} `{0}`, expected live, record or replay", RPC_FIXTURE_MODE_ENV)]
+    InvalidMode(String),
+    #[error("invalid rpc url: {0}")]
+    InvalidUrl(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+}
+
+impl RpcError for RpcClientError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            RpcClientError::Live(err) => err.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            RpcClientError::Live(err) => err.as_serde_error(),
+            RpcClientError::SerdeJson(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<RpcClientError> for ProviderError {
+    fn from(src: RpcClientError) -> Self {
+        match src {
+            RpcClientError::Live(err) => err.into(),
+            _ => ProviderError::JsonRpcClientError(Box::new(src)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RpcFixtureFile {
+    pub version: u32,
+    /// Keyed by [`get_request_key`], sorted so that recording again gives a readable diff.
+    pub responses: BTreeMap<String, Value>,
+}
+
+/// `{method} {params}`, with the params serialized as they are sent to the node.
+pub fn get_request_key<T: Serialize>(method: &str, params: &T) -> Result<String, RpcClientError> {
+    Ok(format!("{method} {}", serde_json::to_string(params)?))
+}
+
+/// The recorded responses of a fixture file.
+#[derive(Debug)]
+pub struct RpcFixture {
+    path: PathBuf,
+    responses: Mutex<BTreeMap<String, Value>>,
+}
+
+impl RpcFixture {
+    /// A missing file is an empty fixture, it is created by the first recorded response.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RpcClientError> {
+        let path = path.as_ref().to_path_buf();
+        let responses = if path.exists() {
+            let file: RpcFixtureFile = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            if file.version != RPC_FIXTURE_VERSION {
+                return Err(RpcClientError::UnsupportedVersion {
+                    path: path.display().to_string(),
+                    version: file.version,
+                });
+            }
+            file.responses
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, responses: Mutex::new(responses) })
+    }
+
+    /// Every provider of a network shares the same fixture, so that recording from several
+    /// providers at once doesn't lose responses.
+    pub fn shared(path: impl AsRef<Path>) -> Result<Arc<Self>, RpcClientError> {
+        lazy_static! {
+            static ref FIXTURES: Mutex<HashMap<PathBuf, Arc<RpcFixture>>> =
+                Mutex::new(HashMap::new());
+        }
+        let mut fixtures = FIXTURES.lock().unwrap();
+        let path = path.as_ref().to_path_buf();
+        if let Some(fixture) = fixtures.get(&path) {
+            return Ok(fixture.clone());
+        }
+        let fixture = Arc::new(RpcFixture::open(&path)?);
+        fixtures.insert(path, fixture.clone());
+        Ok(fixture)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        self.responses.lock().unwrap().get(key).cloned()
+    }
+
+    /// Stores the response and writes the whole fixture file again.
+    pub fn insert(&self, key: String, response: Value) -> Result<(), RpcClientError> {
+        let mut responses = self.responses.lock().unwrap();
+        responses.insert(key, response);
+        let file = RpcFixtureFile { version: RPC_FIXTURE_VERSION, responses: responses.clone() };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+}
+
+/// The transport of every provider returned by `get_provider`, see [`RpcFixtureMode`].
+#[derive(Debug)]
+pub enum RpcClient {
+    Live(RetryClient<Http>),
+    Record { inner: RetryClient<Http>, fixture: Arc<RpcFixture> },
+    Replay(Arc<RpcFixture>),
+}
+
+impl RpcClient {
+    /// Uses the mode of `RPC_FIXTURE_MODE` and the fixture `name` in `RPC_FIXTURE_DIR`.
+    pub fn new(url: &str, name: &str) -> Result<Self, RpcClientError> {
+        Self::with_fixture(RpcFixtureMode::from_env()?, url, get_rpc_fixture_path(name))
+    }
+
+    /// The url is not used when replaying, it may be a placeholder.
+    pub fn with_fixture(
+        mode: RpcFixtureMode,
+        url: &str,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, RpcClientError> {
+        let live = || {
+            let http =
+                Http::from_str(url).map_err(|err| RpcClientError::InvalidUrl(err.to_string()))?;
+            Ok::<_, RpcClientError>(RetryClient::new(
+                http,
+                Box::new(HttpRateLimitRetryPolicy),
+                10,
+                1,
+            ))
+        };
+        match mode {
+            RpcFixtureMode::Live => Ok(RpcClient::Live(live()?)),
+            RpcFixtureMode::Record => {
+                Ok(RpcClient::Record { inner: live()?, fixture: RpcFixture::shared(path)? })
+            }
+            RpcFixtureMode::Replay => Ok(RpcClient::Replay(RpcFixture::shared(path)?)),
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for RpcClient {
+    type Error = RpcClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            RpcClient::Live(inner) => Ok(inner.request(method, params).await?),
+            RpcClient::Record { inner, fixture } => {
+                let key = get_request_key(method, &params)?;
+                let response: Value = inner.request(method, params).await?;
+                fixture.insert(key, response.clone())?;
+                Ok(serde_json::from_value(response)?)
+            }
+            RpcClient::Replay(fixture) => {
+                let key = get_request_key(method, &params)?;
+                match fixture.get(&key) {
+                    Some(response) => Ok(serde_json::from_value(response)?),
+                    None => Err(RpcClientError::MissingResponse {
+                        key,
+                        path: fixture.path().display().to_string(),
+                    }),
+                }
+            }
+        }
+    }
+}
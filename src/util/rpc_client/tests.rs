@@ -0,0 +1,75 @@
+use crate::util::rpc_client::{
+    RpcClient, RpcClientError, RpcFixture, RpcFixtureFile, RpcFixtureMode, RPC_FIXTURE_VERSION,
+};
+use ethers_core::types::U64;
+use ethers_providers::{Middleware, Provider};
+use jsonrpsee::server::{RpcModule, Server, ServerHandle};
+use jsonrpsee::types::ErrorObjectOwned;
+use std::env::temp_dir;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+fn fixture_path(name: &str) -> PathBuf {
+    let path = temp_dir().join(format!("zkspv_rpc_fixture_{name}_{}.json", process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+/// A node that only knows its block number.
+async fn start_test_node() -> (String, ServerHandle) {
+    let mut module = RpcModule::new(());
+    module.register_method("eth_blockNumber", |_, _| Ok::<_, ErrorObjectOwned>("0x10")).unwrap();
+    let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", server.local_addr().unwrap());
+    (url, server.start(module))
+}
+
+#[tokio::test]
+async fn test_record_and_replay() {
+    let path = fixture_path("record");
+    let (url, handle) = start_test_node().await;
+    let provider =
+        Provider::new(RpcClient::with_fixture(RpcFixtureMode::Record, &url, &path).unwrap());
+    assert_eq!(provider.get_block_number().await.unwrap(), U64::from(16));
+
+    let file: RpcFixtureFile = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(file.version, RPC_FIXTURE_VERSION);
+    assert_eq!(file.responses.keys().collect::<Vec<_>>(), ["eth_blockNumber null"]);
+
+    // the node is gone, the response comes from the file
+    handle.stop().unwrap();
+    handle.stopped().await;
+    let provider =
+        Provider::new(RpcClient::with_fixture(RpcFixtureMode::Replay, "not a url", &path).unwrap());
+    assert_eq!(provider.get_block_number().await.unwrap(), U64::from(16));
+    fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_replay_missing_response() {
+    let path = fixture_path("missing");
+    let provider =
+        Provider::new(RpcClient::with_fixture(RpcFixtureMode::Replay, "not a url", &path).unwrap());
+    let err = provider.get_chainid().await.unwrap_err().to_string();
+    assert!(err.contains("eth_chainId") && err.contains("RPC_FIXTURE_MODE=record"), "{err}");
+    // replaying never writes the fixture
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_fixture_version_mismatch() {
+    let path = fixture_path("version");
+    fs::write(&path, r#"{"version": 0, "responses": {}}"#).unwrap();
+    assert!(matches!(
+        RpcFixture::open(&path),
+        Err(RpcClientError::UnsupportedVersion { version: 0, .. })
+    ));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_fixture_mode() {
+    assert_eq!("replay".parse::<RpcFixtureMode>().unwrap(), RpcFixtureMode::Replay);
+    assert!(matches!("offline".parse::<RpcFixtureMode>(), Err(RpcClientError::InvalidMode(_))));
+}
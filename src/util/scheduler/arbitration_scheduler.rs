@@ -10,11 +10,16 @@ use crate::{
 
 use super::EthScheduler;
 use crate::arbitration::circuit_types::FinalAssemblyFinality;
+use crate::arbitration::final_assembly::aggregation_circuit::FinalAssemblyAggregationCircuit;
+use crate::block_header::zksync_era::l1_batch::ZkSyncEraL1BatchCircuit;
 use crate::receipt::EthBlockReceiptCircuit;
+use crate::storage::account::EthBlockAccountCircuit;
 use crate::storage::contract_storage::ObContractsStorageCircuit;
 use crate::storage::util::StorageConstructor;
 use crate::track_block::util::TrackBlockConstructor;
+use crate::transaction::arbitrum::ArbitrumBlockTransactionCircuit;
 use crate::transaction::ethereum::EthBlockTransactionCircuit;
+use crate::transaction::optimism::OptimismBlockTransactionCircuit;
 use crate::transaction::zksync_era::ZkSyncEraBlockTransactionCircuit;
 use crate::transaction_receipt::TransactionReceiptCircuit;
 use crate::util::scheduler::{self, AnyCircuit, Task};
@@ -37,6 +42,12 @@ pub enum CircuitRouter {
     ZkSyncTransaction(ZkSyncEraBlockTransactionCircuit),
     AggreateZkSyncTransactions(PublicAggregationCircuit),
 
+    ArbitrumTransaction(ArbitrumBlockTransactionCircuit),
+    AggreateArbitrumTransactions(PublicAggregationCircuit),
+
+    OptimismTransaction(OptimismBlockTransactionCircuit),
+    AggreateOptimismTransactions(PublicAggregationCircuit),
+
     EthReceipt(EthBlockReceiptCircuit),
     AggreateEthReceipt(PublicAggregationCircuit),
 
@@ -48,13 +59,22 @@ pub enum CircuitRouter {
     MdcStorage(ObContractsStorageCircuit),
     AggreateMdcStorages(PublicAggregationCircuit),
 
+    ZkSyncL1Batch(ZkSyncEraL1BatchCircuit),
+
+    EthAccount(EthBlockAccountCircuit),
+
     // FinalAssembly(FinalAssemblyCircuit),
     Passthrough(PublicAggregationCircuit),
-    FinalAssemblyThroughAggregation(PublicAggregationCircuit),
+    FinalAssemblyThroughAggregation(FinalAssemblyAggregationCircuit),
 }
 
 pub type ArbitrationScheduler = EthScheduler<ArbitrationTask>;
 
+/// Where the snarks and calldata of the arbitration tasks are written.
+pub const ARBITRATION_DATA_DIR: &str = "data/arbitration/";
+/// Where the Solidity verifier of the final arbitration circuit is written by default.
+pub const ARBITRATION_SOLIDITY_DIR: &str = "data/arbitration/solidity/";
+
 impl ArbitrationScheduler {
     pub fn default(network: Network) -> Self {
         ArbitrationScheduler::new(
@@ -62,7 +82,7 @@ impl ArbitrationScheduler {
             false,
             false,
             PathBuf::from("configs/arbitration/"),
-            PathBuf::from("data/arbitration/"),
+            PathBuf::from(ARBITRATION_DATA_DIR),
             PathBuf::from("cache_data/arbitration/"),
         )
     }
@@ -117,6 +137,28 @@ impl scheduler::Scheduler for ArbitrationScheduler {
                     CircuitRouter::ZkSyncTransaction(task.input)
                 }
             }
+            ArbitrationTask::ArbitrumTransaction(task) => {
+                if task.circuit_type().is_aggregated() {
+                    let prev_snarks =
+                        prev_snarks.into_iter().map(|snark| (snark, false)).collect_vec();
+                    CircuitRouter::AggreateArbitrumTransactions(PublicAggregationCircuit::new(
+                        prev_snarks,
+                    ))
+                } else {
+                    CircuitRouter::ArbitrumTransaction(task.input)
+                }
+            }
+            ArbitrationTask::OptimismTransaction(task) => {
+                if task.circuit_type().is_aggregated() {
+                    let prev_snarks =
+                        prev_snarks.into_iter().map(|snark| (snark, false)).collect_vec();
+                    CircuitRouter::AggreateOptimismTransactions(PublicAggregationCircuit::new(
+                        prev_snarks,
+                    ))
+                } else {
+                    CircuitRouter::OptimismTransaction(task.input)
+                }
+            }
             ArbitrationTask::EthReceipt(task) => {
                 if task.circuit_type().is_aggregated() {
                     println!("EthReceipt AGGREGATION ====== prev_snarks len {}", prev_snarks.len());
@@ -180,6 +222,8 @@ impl scheduler::Scheduler for ArbitrationScheduler {
             ArbitrationTask::BlockMerkleInclusion(task) => {
                 CircuitRouter::BlockerMerkleInclusion(task.input)
             }
+            ArbitrationTask::ZkSyncL1Batch(task) => CircuitRouter::ZkSyncL1Batch(task.input),
+            ArbitrationTask::EthAccount(task) => CircuitRouter::EthAccount(task.input),
             ArbitrationTask::Final(final_task) => {
                 println!("FINAL ====== prev_snarks len {}", prev_snarks.len());
                 if final_task.circuit_type().round != 0 {
@@ -196,15 +240,17 @@ impl scheduler::Scheduler for ArbitrationScheduler {
                         (snark, false)
                     })
                     .collect_vec();
-                CircuitRouter::FinalAssemblyThroughAggregation(PublicAggregationCircuit::new(
-                    prev_snarks,
-                ))
+                CircuitRouter::FinalAssemblyThroughAggregation(
+                    FinalAssemblyAggregationCircuit::new(prev_snarks, final_task.instance_layout()),
+                )
 
                 // FinalAssemblyCircuit
-                // let [transaction_snark, block_snark]: [_; 2] = prev_snarks.try_into().unwrap();
+                // let [transaction_snark, block_snark, mdc_state_snark]: [_; 3] =
+                //     prev_snarks.try_into().unwrap();
                 // CircuitRouter::FinalAssembly(FinalAssemblyCircuit::new(
                 //     (transaction_snark, false),
-                //     (block_snark, false), // (mdc_state_snark, false),
+                //     (block_snark, false),
+                //     (mdc_state_snark, false),
                 // ))
             }
         }
@@ -222,6 +268,12 @@ pub trait ArbitrationBus {
         get_mdc_storage_circuit(constructor)
     }
 
+    // for MDC config on L1(Ethereum) that may be unset, e.g. a rule a maker never configured:
+    // proves the slots are unset instead of failing, see [`EthBlockStorageCircuit::instance`]
+    fn get_storage_exclusion_circuit(constructor: StorageConstructor) -> EthBlockStorageCircuit {
+        get_mdc_storage_circuit(StorageConstructor { exclusion_proofs: true, ..constructor })
+    }
+
     // Track Block from L1(Ethereum)
     fn get_track_block_circuit(constructor: TrackBlockConstructor) -> EthTrackBlockCircuit {
         get_eth_track_block_circuit(constructor)
@@ -140,13 +140,16 @@ impl<C: PreCircuit> AnyCircuit for WrapperRouter<C> {
         self,
         params: &ParamsKZG<Bn256>,
         pk: &ProvingKey<G1Affine>,
+        pinning_path: impl AsRef<Path>,
         yul_path: impl AsRef<Path>,
     ) -> Vec<u8> {
         match self {
             Self::Initial(pre_circuit) => {
-                pre_circuit.gen_evm_verifier_shplonk(params, pk, yul_path)
+                pre_circuit.gen_evm_verifier_shplonk(params, pk, pinning_path, yul_path)
+            }
+            Self::ForEvm(pre_circuit) => {
+                pre_circuit.gen_evm_verifier_shplonk(params, pk, pinning_path, yul_path)
             }
-            Self::ForEvm(pre_circuit) => pre_circuit.gen_evm_verifier_shplonk(params, pk, yul_path),
         }
     }
 
@@ -167,4 +170,22 @@ impl<C: PreCircuit> AnyCircuit for WrapperRouter<C> {
             }
         }
     }
+
+    fn gen_solidity_calldata(
+        self,
+        params: &ParamsKZG<Bn256>,
+        pk: &ProvingKey<G1Affine>,
+        pinning_path: impl AsRef<Path>,
+        vk_address: [u8; 20],
+        path: impl AsRef<Path>,
+    ) -> String {
+        match self {
+            Self::Initial(pre_circuit) => {
+                pre_circuit.gen_solidity_calldata(params, pk, pinning_path, vk_address, path)
+            }
+            Self::ForEvm(pre_circuit) => {
+                pre_circuit.gen_solidity_calldata(params, pk, pinning_path, vk_address, path)
+            }
+        }
+    }
 }
@@ -0,0 +1,253 @@
+//! Records what the SRS and proving keys written by the scheduler were generated from.
+//!
+//! Proving keys are otherwise found by file name only, so a key of an older version of a circuit would be reused
+//! after its pinning changed. Before a proving key is loaded it is checked against its entry in `manifest.json`:
+//! a mismatched key is regenerated, or refused in readonly mode.
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    env::var,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+use thiserror::Error;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Where `gen_srs` and `read_params` of halo2-base find the SRS of degree `k`.
+pub fn srs_path(k: u32) -> PathBuf {
+    let dir = var("PARAMS_DIR").unwrap_or_else(|_| "./params".to_string());
+    Path::new(&dir).join(format!("kzg_bn254_{k}.srs"))
+}
+
+/// What an artifact was generated from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    /// The name of the circuit type, `None` for the SRS.
+    pub circuit: Option<String>,
+    /// The digest of the pinning the proving key was generated with.
+    pub pinning_hash: Option<String>,
+    pub k: u32,
+    /// The digest of the SRS the proving key was generated with.
+    pub srs_digest: Option<String>,
+}
+
+impl Artifact {
+    pub fn srs(k: u32) -> Self {
+        Self { circuit: None, pinning_hash: None, k, srs_digest: None }
+    }
+
+    /// `pinning_path` should be read after the proving key is created, because creating it writes the pinning.
+    pub fn pkey(
+        circuit: String,
+        pinning_path: impl AsRef<Path>,
+        k: u32,
+        srs_digest: String,
+    ) -> io::Result<Self> {
+        let pinning_hash = file_digest(pinning_path)?;
+        Ok(Self {
+            circuit: Some(circuit),
+            pinning_hash: Some(pinning_hash),
+            k,
+            srs_digest: Some(srs_digest),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactEntry {
+    #[serde(flatten)]
+    pub artifact: Artifact,
+    /// The digest of the artifact file.
+    pub digest: String,
+    /// The metadata of the artifact file when `digest` was computed, `None` in manifests written before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stamp: Option<FileStamp>,
+}
+
+/// The size and modification time of a file.
+///
+/// Hashing a large SRS or proving key takes seconds, a file whose stamp didn't change since its digest was recorded
+/// isn't hashed again.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub len: u64,
+    pub modified: SystemTime,
+}
+
+impl FileStamp {
+    pub fn of(path: impl AsRef<Path>) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(Self { len: metadata.len(), modified: metadata.modified()? })
+    }
+}
+
+/// Why an artifact can't be used.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ArtifactMismatch {
+    #[error("it is not recorded in the manifest")]
+    Untracked,
+    #[error("it was generated for circuit {recorded:?}, expected {expected:?}")]
+    Circuit { recorded: Option<String>, expected: Option<String> },
+    #[error("its pinning changed since it was generated")]
+    Pinning,
+    #[error("it was generated with k = {recorded}, expected {expected}")]
+    Degree { recorded: u32, expected: u32 },
+    #[error("it was generated with another SRS")]
+    Srs,
+    #[error("its content changed since it was recorded")]
+    Digest,
+}
+
+#[derive(Error, Debug)]
+pub enum ManifestError {
+    #[error("{path} has manifest version {version}, expected {}", MANIFEST_VERSION)]
+    UnsupportedVersion { path: String, version: u32 },
+    #[error("refusing to load {path}: {mismatch}, regenerate it without readonly")]
+    Refused { path: String, mismatch: ArtifactMismatch },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub version: u32,
+    /// Keyed by the path of the artifact.
+    pub artifacts: BTreeMap<String, ArtifactEntry>,
+}
+
+/// The SHA-256 digest of a file, as a hex string.
+pub fn file_digest(path: impl AsRef<Path>) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// The manifest of the artifacts of a scheduler, `{data_dir}/manifest.json`.
+#[derive(Debug)]
+pub struct ArtifactManifest {
+    path: PathBuf,
+    artifacts: Mutex<BTreeMap<String, ArtifactEntry>>,
+}
+
+impl ArtifactManifest {
+    /// A missing file is an empty manifest, it is created by the first recorded artifact.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        let path = path.as_ref().to_path_buf();
+        let artifacts = if path.exists() {
+            let file: ManifestFile = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            if file.version != MANIFEST_VERSION {
+                return Err(ManifestError::UnsupportedVersion {
+                    path: path.display().to_string(),
+                    version: file.version,
+                });
+            }
+            file.artifacts
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, artifacts: Mutex::new(artifacts) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, artifact_path: impl AsRef<Path>) -> Option<ArtifactEntry> {
+        self.artifacts.lock().unwrap().get(&key(artifact_path)).cloned()
+    }
+
+    /// Records the artifact at `artifact_path` with the digest of its content and writes the whole manifest again.
+    pub fn record(
+        &self,
+        artifact_path: impl AsRef<Path>,
+        artifact: Artifact,
+    ) -> Result<ArtifactEntry, ManifestError> {
+        let entry = ArtifactEntry {
+            artifact,
+            digest: file_digest(&artifact_path)?,
+            stamp: FileStamp::of(&artifact_path).ok(),
+        };
+        let mut artifacts = self.artifacts.lock().unwrap();
+        artifacts.insert(key(artifact_path), entry.clone());
+        let file = ManifestFile { version: MANIFEST_VERSION, artifacts: artifacts.clone() };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&file)?)?;
+        Ok(entry)
+    }
+
+    /// Why the artifact at `artifact_path` wasn't generated from `expected` or was modified since, if it is so.
+    pub fn check(
+        &self,
+        artifact_path: impl AsRef<Path>,
+        expected: &Artifact,
+    ) -> Result<Option<ArtifactMismatch>, ManifestError> {
+        let recorded = match self.get(&artifact_path) {
+            Some(entry) => entry,
+            None => return Ok(Some(ArtifactMismatch::Untracked)),
+        };
+        let mismatch = if recorded.artifact.circuit != expected.circuit {
+            ArtifactMismatch::Circuit {
+                recorded: recorded.artifact.circuit,
+                expected: expected.circuit.clone(),
+            }
+        } else if recorded.artifact.pinning_hash != expected.pinning_hash {
+            ArtifactMismatch::Pinning
+        } else if recorded.artifact.k != expected.k {
+            ArtifactMismatch::Degree { recorded: recorded.artifact.k, expected: expected.k }
+        } else if recorded.artifact.srs_digest != expected.srs_digest {
+            ArtifactMismatch::Srs
+        } else if !digest_matches(&recorded, &artifact_path)? {
+            ArtifactMismatch::Digest
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(mismatch))
+    }
+
+    /// Makes sure the artifact at `artifact_path`, if any, can be loaded: a mismatched artifact is removed so
+    /// that it is generated again, or refused if `read_only`.
+    pub fn validate(
+        &self,
+        artifact_path: impl AsRef<Path>,
+        expected: &Artifact,
+        read_only: bool,
+    ) -> Result<(), ManifestError> {
+        let artifact_path = artifact_path.as_ref();
+        if !artifact_path.exists() {
+            return Ok(());
+        }
+        if let Some(mismatch) = self.check(artifact_path, expected)? {
+            let path = artifact_path.display().to_string();
+            if read_only {
+                return Err(ManifestError::Refused { path, mismatch });
+            }
+            warn!("regenerating {path}: {mismatch}");
+            fs::remove_file(artifact_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether the file at `artifact_path` still has the recorded digest, only hashed if its stamp changed.
+fn digest_matches(recorded: &ArtifactEntry, artifact_path: impl AsRef<Path>) -> io::Result<bool> {
+    if recorded.stamp.is_some() && recorded.stamp == FileStamp::of(&artifact_path).ok() {
+        return Ok(true);
+    }
+    Ok(recorded.digest == file_digest(&artifact_path)?)
+}
+
+fn key(artifact_path: impl AsRef<Path>) -> String {
+    artifact_path.as_ref().display().to_string()
+}
@@ -1,8 +1,12 @@
 pub mod arbitration_scheduler;
 pub mod evm_wrapper;
+pub mod manifest;
+#[cfg(test)]
+mod tests;
+pub mod worker_pool;
 
 use ethers_core::types::U256;
-use ethers_providers::{Http, Middleware, Provider, RetryClient};
+use ethers_providers::{Middleware, Provider};
 use halo2_base::{
     halo2_proofs::{
         halo2curves::bn256::{Bn256, G1Affine},
@@ -28,10 +32,17 @@ use std::{
 };
 
 use crate::util::helpers::get_provider;
+use crate::util::rpc_client::RpcClient;
 use crate::Network;
 
 use super::circuit::AnyCircuit;
-use rayon::prelude::*;
+#[cfg(feature = "evm")]
+use crate::gen::solidity::SolidityVerifier;
+use manifest::{srs_path, Artifact, ArtifactManifest, MANIFEST_FILE};
+use worker_pool::{SchedulerBudget, WorkerPool};
+
+/// A rough estimate of the memory needed to keygen or prove a circuit, per row.
+pub const ESTIMATED_BYTES_PER_ROW: u64 = 8 << 10;
 
 /// This is a tag for the type of a circuit, independent of the circuit's inputs.
 /// For example, it can be used to fetch the proving key for the circuit.
@@ -50,6 +61,18 @@ pub trait Task: Clone + Debug + Sync + Send {
 
     /// The previous tasks this task depends on (i.e., the edges of the DAG that point to this node).
     fn dependencies(&self) -> Vec<Self>;
+
+    /// The names of the tasks of the DAG ending at this task, dependencies first and without duplicates.
+    fn dag_names(&self) -> Vec<String> {
+        let mut names = vec![];
+        for name in self.dependencies().iter().flat_map(Task::dag_names) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names.push(self.name());
+        names
+    }
 }
 
 pub trait SchedulerCommon {
@@ -72,10 +95,20 @@ pub trait SchedulerCommon {
     ///
     /// Recommended: Cache the params in a hashmap if they are not already cached.
     fn get_params(&self, k: u32) -> Arc<ParamsKZG<Bn256>>;
+    /// The digest of the SRS of degree `k`, that the proving keys generated with it are recorded with.
+    fn srs_digest(&self, k: u32) -> String;
+    /// What the SRS and proving keys were generated from.
+    fn manifest(&self) -> &ArtifactManifest;
     /// Fetch pkey from cache (intended to be from HashMap).
     fn get_pkey(&self, circuit_type: &Self::CircuitType) -> Option<Arc<ProvingKey<G1Affine>>>;
     /// Assumes this uses the same HashMap as `get_pkey`.
     fn insert_pkey(&self, circuit_type: Self::CircuitType, pkey: ProvingKey<G1Affine>);
+    /// Bounds the keygens and provings that run at the same time.
+    fn worker_pool(&self) -> &WorkerPool;
+    /// The memory, in bytes, that the budget of the worker pool reserves to keygen or prove the circuit.
+    fn memory_estimate(&self, circuit_type: &Self::CircuitType) -> u64 {
+        ESTIMATED_BYTES_PER_ROW << self.get_degree(circuit_type)
+    }
 }
 /// A basic implementation of `SchedulerCommon` with support for ETH JSON-RPC requests.
 pub struct EthScheduler<T: Task> {
@@ -90,24 +123,51 @@ pub struct EthScheduler<T: Task> {
     pub pkeys: RwLock<HashMap<T::CircuitType, Arc<ProvingKey<G1Affine>>>>,
     pub degree: RwLock<HashMap<T::CircuitType, u32>>,
     pub params: RwLock<HashMap<u32, Arc<ParamsKZG<Bn256>>>>,
-    pub provider: Arc<Provider<RetryClient<Http>>>,
+    pub srs_digests: RwLock<HashMap<u32, String>>,
+    pub provider: Arc<Provider<RpcClient>>,
     pub network: Network,
+    worker_pool: WorkerPool,
+    manifest: ArtifactManifest,
 
     _marker: PhantomData<T>,
 }
 
 impl<T: Task> EthScheduler<T> {
+    /// The budget of the worker pool is read from the environment, see [`SchedulerBudget::from_env`].
     pub fn new(
         network: Network,
-        mut srs_read_only: bool,
+        srs_read_only: bool,
         read_only: bool,
         config_dir: PathBuf,
         data_dir: PathBuf,
         cache_data_dir: PathBuf,
     ) -> Self {
         let provider = get_provider(&network);
+        Self::with_provider(
+            network,
+            provider,
+            srs_read_only,
+            read_only,
+            config_dir,
+            data_dir,
+            cache_data_dir,
+        )
+    }
+
+    /// Like [`EthScheduler::new`] with another provider of `network`, e.g. `get_offline_provider`.
+    pub fn with_provider(
+        network: Network,
+        provider: Provider<RpcClient>,
+        mut srs_read_only: bool,
+        read_only: bool,
+        config_dir: PathBuf,
+        data_dir: PathBuf,
+        cache_data_dir: PathBuf,
+    ) -> Self {
         fs::create_dir_all(&config_dir).expect("could not create config directory");
         fs::create_dir_all(&data_dir).expect("could not create data directory");
+        let manifest = ArtifactManifest::open(data_dir.join(MANIFEST_FILE))
+            .expect("could not open artifact manifest");
         srs_read_only = srs_read_only || read_only;
         #[cfg(feature = "production")]
         {
@@ -122,11 +182,19 @@ impl<T: Task> EthScheduler<T> {
             pkeys: Default::default(),
             degree: Default::default(),
             params: Default::default(),
+            srs_digests: Default::default(),
             provider: Arc::new(provider),
             network,
+            worker_pool: WorkerPool::new(SchedulerBudget::from_env()),
+            manifest,
             _marker: PhantomData,
         }
     }
+
+    pub fn with_budget(mut self, budget: SchedulerBudget) -> Self {
+        self.worker_pool = WorkerPool::new(budget);
+        self
+    }
 }
 
 impl<T: Task> SchedulerCommon for EthScheduler<T> {
@@ -161,16 +229,37 @@ impl<T: Task> SchedulerCommon for EthScheduler<T> {
             return Arc::clone(params);
         }
         let params = if self.srs_readonly() { read_params(k) } else { gen_srs(k) };
+        // a changed SRS is recorded again, the proving keys generated with the previous one get regenerated
+        let path = srs_path(k);
+        let srs = Artifact::srs(k);
+        let entry = match self.manifest.check(&path, &srs).expect("could not check SRS") {
+            None => self.manifest.get(&path).unwrap(),
+            Some(_) => self.manifest.record(&path, srs).expect("could not record SRS"),
+        };
+        self.srs_digests.write().unwrap().insert(k, entry.digest);
         let params = Arc::new(params);
         self.params.write().unwrap().insert(k, Arc::clone(&params));
         params
     }
+    fn srs_digest(&self, k: u32) -> String {
+        if let Some(digest) = self.srs_digests.read().unwrap().get(&k) {
+            return digest.clone();
+        }
+        self.get_params(k);
+        self.srs_digests.read().unwrap()[&k].clone()
+    }
+    fn manifest(&self) -> &ArtifactManifest {
+        &self.manifest
+    }
     fn get_pkey(&self, circuit_type: &Self::CircuitType) -> Option<Arc<ProvingKey<G1Affine>>> {
         self.pkeys.read().unwrap().get(circuit_type).map(Arc::clone)
     }
     fn insert_pkey(&self, circuit_type: Self::CircuitType, pkey: ProvingKey<G1Affine>) {
         self.pkeys.write().unwrap().insert(circuit_type, Arc::new(pkey));
     }
+    fn worker_pool(&self) -> &WorkerPool {
+        &self.worker_pool
+    }
 }
 
 /// A scheduler that can recursively generate SNARKs for a DAG of tasks. The directed acyclic graph (DAG) is implicitly
@@ -211,91 +300,128 @@ pub trait Scheduler:
     fn calldata_path(&self, task: &Self::Task) -> PathBuf {
         self.data_dir().join(format!("{}.calldata", task.name()))
     }
-    fn cache_srs_pk_files(&self, task: Self::Task) -> Box<Snark> {
-        let snark_path = self.snark_cache_path(&task);
 
-        let read_only = self.pkey_readonly();
-
-        // Recursively generate the SNARKs for the dependencies of this task.
-        #[cfg(feature = "parallel-sub-circuit")]
-        let dep_snarks: Vec<Box<Snark>> = task
-            .dependencies()
-            .par_iter()
-            .map(|dep| self.cache_srs_pk_files(dep.clone()))
-            .collect();
-        #[cfg(not(feature = "parallel-sub-circuit"))]
-        let dep_snarks: Vec<Box<Snark>> =
-            task.dependencies().into_iter().map(|dep| self.cache_srs_pk_files(dep)).collect();
-
-        let circuit_type = task.circuit_type();
-        let k = self.get_degree(&circuit_type);
-        let params = &self.get_params(k);
-        // Construct the pre-circuit for this task from the dependency SNARKs.
-        let pre_circuit = self.get_circuit(task, dep_snarks.into_iter().map(|a| *a).collect());
-        let pk_path = self.pkey_cache_path(&circuit_type);
-        let pinning_path = self.pinning_path(&circuit_type);
-
-        let pk = if let Some(pk) = self.get_pkey(&circuit_type) {
-            pk
-        } else {
+    fn solidity_calldata_path(&self, task: &Self::Task) -> PathBuf {
+        self.data_dir().join(format!("{}.sol.calldata", task.name()))
+    }
+    /// Reads the proving key of the circuit from cache, or from `pk_path`, creating it if it doesn't exist.
+    ///
+    /// Only one thread creates the proving key of a circuit type, the others wait for it. A proving key that doesn't
+    /// match its entry in the manifest is regenerated, or refused in readonly mode.
+    fn get_or_create_pkey(
+        &self,
+        pre_circuit: &Self::CircuitRouter,
+        circuit_type: &<Self::Task as Task>::CircuitType,
+        params: &ParamsKZG<Bn256>,
+        pk_path: PathBuf,
+    ) -> Arc<ProvingKey<G1Affine>> {
+        if let Some(pk) = self.get_pkey(circuit_type) {
+            return pk;
+        }
+        self.worker_pool().run_once(&pk_path.display().to_string(), || {
+            // it may have been inserted by a thread that finished in the meantime
+            if let Some(pk) = self.get_pkey(circuit_type) {
+                return pk;
+            }
+            let _permit = self.worker_pool().acquire(self.memory_estimate(circuit_type));
+            let pinning_path = self.pinning_path(circuit_type);
+            let read_only = self.pkey_readonly();
+            let k = self.get_degree(circuit_type);
+            let artifact = || {
+                Artifact::pkey(circuit_type.name(), &pinning_path, k, self.srs_digest(k))
+                    .expect("could not read pinning")
+            };
+            self.manifest()
+                .validate(&pk_path, &artifact(), read_only)
+                .unwrap_or_else(|e| panic!("{e}"));
+            let created = !pk_path.exists();
             let pk =
-                pre_circuit.clone().read_or_create_pk(params, pk_path, &pinning_path, read_only);
+                pre_circuit.clone().read_or_create_pk(params, &pk_path, &pinning_path, read_only);
+            if created {
+                // read the pinning again, creating the proving key writes it
+                self.manifest().record(&pk_path, artifact()).expect("could not record proving key");
+            }
             self.insert_pkey(circuit_type.clone(), pk);
-            self.get_pkey(&circuit_type).unwrap()
-        };
-        #[cfg(feature = "halo2-axiom")]
-        if let Ok(snark) = read_snark(snark_path.clone()) {
-            return Box::new(snark);
-        } else {
-            let snark_path = Some(snark_path);
-            let snark_result =
-                pre_circuit.gen_snark_shplonk(params, &pk, &pinning_path, snark_path);
-            return Box::new(snark_result);
-        }
+            self.get_pkey(circuit_type).unwrap()
+        })
+    }
+
+    fn cache_srs_pk_files(&self, task: Self::Task) -> Box<Snark> {
+        let snark_path = self.snark_cache_path(&task);
+        let snark = self.worker_pool().run_once(&snark_path.display().to_string(), || {
+            // Recursively generate the SNARKs for the dependencies of this task, independent ones at the same time.
+            let dep_snarks = self
+                .worker_pool()
+                .join_all(task.dependencies(), |dep| *self.cache_srs_pk_files(dep));
+
+            let circuit_type = task.circuit_type();
+            let k = self.get_degree(&circuit_type);
+            let params = &self.get_params(k);
+            // Construct the pre-circuit for this task from the dependency SNARKs.
+            let pre_circuit = self.get_circuit(task, dep_snarks);
+            let pk_path = self.pkey_cache_path(&circuit_type);
+            let pinning_path = self.pinning_path(&circuit_type);
+
+            let pk = self.get_or_create_pkey(&pre_circuit, &circuit_type, params, pk_path);
+            #[cfg(feature = "halo2-axiom")]
+            if let Ok(snark) = read_snark(snark_path.clone()) {
+                return snark;
+            }
+            let _permit = self.worker_pool().acquire(self.memory_estimate(&circuit_type));
+            pre_circuit.gen_snark_shplonk(params, &pk, &pinning_path, Some(snark_path.clone()))
+        });
+        Box::new(snark)
     }
 
     // recursively generates necessary circuits and snarks to create snark
     fn get_snark(&self, task: Self::Task) -> Box<Snark> {
         let snark_path = self.snark_path(&task);
-        #[cfg(feature = "halo2-axiom")]
-        if let Ok(snark) = read_snark(snark_path.clone()) {
-            return Box::new(snark);
-        }
-        let read_only = self.pkey_readonly();
+        let snark = self.worker_pool().run_once(&snark_path.display().to_string(), || {
+            #[cfg(feature = "halo2-axiom")]
+            if let Ok(snark) = read_snark(snark_path.clone()) {
+                return snark;
+            }
+
+            // Recursively generate the SNARKs for the dependencies of this task, independent ones at the same time.
+            let dep_snarks =
+                self.worker_pool().join_all(task.dependencies(), |dep| *self.get_snark(dep));
+
+            let circuit_type = task.circuit_type();
+            let k = self.get_degree(&circuit_type);
+            let params = &self.get_params(k);
+            // Construct the pre-circuit for this task from the dependency SNARKs.
+            let pre_circuit = self.get_circuit(task, dep_snarks);
+            let pk_path = self.pkey_path(&circuit_type);
+            let pinning_path = self.pinning_path(&circuit_type);
+
+            let pk = self.get_or_create_pkey(&pre_circuit, &circuit_type, params, pk_path);
+            let _permit = self.worker_pool().acquire(self.memory_estimate(&circuit_type));
+            pre_circuit.gen_snark_shplonk(params, &pk, &pinning_path, Some(snark_path.clone()))
+        });
+        Box::new(snark)
+    }
 
-        // Recursively generate the SNARKs for the dependencies of this task.
-        #[cfg(feature = "parallel-sub-circuit")]
-        let dep_snarks: Vec<Box<Snark>> =
-            task.dependencies().par_iter().map(|dep| self.get_snark(dep.clone())).collect();
-        #[cfg(not(feature = "parallel-sub-circuit"))]
-        let dep_snarks: Vec<Box<Snark>> =
-            task.dependencies().into_iter().map(|dep| self.get_snark(dep)).collect();
+    /// The pre-circuit of `task` from the snarks of its dependencies, with the params and proving key of its circuit type.
+    fn get_circuit_with_pkey(
+        &self,
+        task: Self::Task,
+    ) -> (Self::CircuitRouter, Arc<ParamsKZG<Bn256>>, Arc<ProvingKey<G1Affine>>) {
+        let dep_snarks =
+            self.worker_pool().join_all(task.dependencies(), |dep| *self.get_snark(dep));
 
         let circuit_type = task.circuit_type();
         let k = self.get_degree(&circuit_type);
-        let params = &self.get_params(k);
+        let params = self.get_params(k);
         // Construct the pre-circuit for this task from the dependency SNARKs.
-        let pre_circuit = self.get_circuit(task, dep_snarks.into_iter().map(|a| *a).collect());
+        let pre_circuit = self.get_circuit(task, dep_snarks);
         let pk_path = self.pkey_path(&circuit_type);
-        let pinning_path = self.pinning_path(&circuit_type);
 
-        let pk = if let Some(pk) = self.get_pkey(&circuit_type) {
-            pk
-        } else {
-            let pk =
-                pre_circuit.clone().read_or_create_pk(params, pk_path, &pinning_path, read_only);
-            self.insert_pkey(circuit_type.clone(), pk);
-            self.get_pkey(&circuit_type).unwrap()
-        };
-        let snark_path = Some(snark_path);
-        let snark_result = pre_circuit.gen_snark_shplonk(params, &pk, &pinning_path, snark_path);
-        Box::new(snark_result)
+        let pk = self.get_or_create_pkey(&pre_circuit, &circuit_type, &params, pk_path);
+        (pre_circuit, params, pk)
     }
 
     #[cfg(feature = "evm")]
     fn get_calldata(&self, task: Self::Task, generate_smart_contract: bool) -> String {
-        // TODO: some shared code with `get_snark`; clean up somehow
-
         let calldata_path = self.calldata_path(&task);
 
         if let Ok(calldata) = fs::read_to_string(&calldata_path) {
@@ -303,34 +429,55 @@ pub trait Scheduler:
             return calldata;
         }
 
-        #[cfg(feature = "parallel-sub-circuit")]
-        let dep_snarks: Vec<Box<Snark>> =
-            task.dependencies().par_iter().map(|dep| self.get_snark(dep.clone())).collect();
-        #[cfg(not(feature = "parallel-sub-circuit"))]
-        let dep_snarks: Vec<Box<Snark>> =
-            task.dependencies().into_iter().map(|dep| self.get_snark(dep)).collect();
-
         let circuit_type = task.circuit_type();
-        let k = self.get_degree(&circuit_type);
-        let params = &self.get_params(k);
-        // Construct the pre-circuit for this task from the dependency SNARKs.
-        let pre_circuit = self.get_circuit(task, dep_snarks.into_iter().map(|a| *a).collect());
-        let pk_path = self.pkey_path(&circuit_type);
         let pinning_path = self.pinning_path(&circuit_type);
+        let (pre_circuit, params, pk) = self.get_circuit_with_pkey(task);
 
-        let pk = if let Some(pk) = self.get_pkey(&circuit_type) {
-            pk
-        } else {
-            let read_only = self.pkey_readonly();
-            let pk =
-                pre_circuit.clone().read_or_create_pk(params, pk_path, &pinning_path, read_only);
-            self.insert_pkey(circuit_type.clone(), pk);
-            self.get_pkey(&circuit_type).unwrap()
-        };
-
+        let _permit = self.worker_pool().acquire(self.memory_estimate(&circuit_type));
         let deployment_code = generate_smart_contract.then(|| {
-            pre_circuit.clone().gen_evm_verifier_shplonk(params, &pk, self.yul_path(&circuit_type))
+            pre_circuit.clone().gen_evm_verifier_shplonk(
+                &params,
+                &pk,
+                &pinning_path,
+                self.yul_path(&circuit_type),
+            )
         });
-        pre_circuit.gen_calldata(params, &pk, pinning_path, calldata_path, deployment_code)
+        pre_circuit.gen_calldata(&params, &pk, pinning_path, calldata_path, deployment_code)
+    }
+
+    /// Writes the Solidity verifier and verifying key contracts of `circuit_type` to `dir`, see [`SolidityVerifier::new`].
+    ///
+    /// The contracts only depend on the proving key, which must already be loaded, e.g. by proving a task of
+    /// the circuit type. Returns `None` otherwise.
+    #[cfg(feature = "evm")]
+    fn gen_solidity_verifier(
+        &self,
+        circuit_type: &<Self::Task as Task>::CircuitType,
+        num_instance: usize,
+        accumulator: bool,
+        dir: &Path,
+    ) -> Option<SolidityVerifier> {
+        let pk = self.get_pkey(circuit_type)?;
+        let params = self.get_params(self.get_degree(circuit_type));
+        let verifier = SolidityVerifier::new(&params, pk.get_vk(), num_instance, accumulator);
+        verifier.write(dir);
+        Some(verifier)
+    }
+
+    /// Like `get_calldata`, for the verifier of [`Scheduler::gen_solidity_verifier`] whose verifying key contract is deployed at `vk_address`.
+    #[cfg(feature = "evm")]
+    fn get_solidity_calldata(&self, task: Self::Task, vk_address: [u8; 20]) -> String {
+        let calldata_path = self.solidity_calldata_path(&task);
+
+        if let Ok(calldata) = fs::read_to_string(&calldata_path) {
+            return calldata;
+        }
+
+        let circuit_type = task.circuit_type();
+        let pinning_path = self.pinning_path(&circuit_type);
+        let (pre_circuit, params, pk) = self.get_circuit_with_pkey(task);
+
+        let _permit = self.worker_pool().acquire(self.memory_estimate(&circuit_type));
+        pre_circuit.gen_solidity_calldata(&params, &pk, pinning_path, vk_address, calldata_path)
     }
 }
@@ -0,0 +1,539 @@
+use super::manifest::{Artifact, ArtifactManifest, ArtifactMismatch, FileStamp, ManifestError};
+use super::worker_pool::{SchedulerBudget, WorkerPool};
+use super::{CircuitType, Scheduler, SchedulerCommon, Task};
+use crate::util::circuit::AnyCircuit;
+use crate::util::circuit_config::CircuitConfigScope;
+use crate::util::verify::tests::{add_circuit, K};
+use halo2_base::gates::builder::{CircuitBuilderStage, MultiPhaseThreadBreakPoints};
+use halo2_base::halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::ProvingKey,
+    poly::kzg::commitment::ParamsKZG,
+};
+use halo2_base::utils::fs::gen_srs;
+use lazy_static::lazy_static;
+use snark_verifier_sdk::{gen_pk, halo2::gen_snark_shplonk, Snark};
+use std::collections::HashMap;
+use std::env::temp_dir;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+const GB: u64 = 1 << 30;
+
+/// The circuit of the mock tasks that take `.0` GB to keygen or prove.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct MockCircuitType(u64);
+
+impl CircuitType for MockCircuitType {
+    fn name(&self) -> String {
+        format!("mock_{}gb", self.0)
+    }
+    fn get_degree_from_pinning(&self, _: impl AsRef<Path>) -> u32 {
+        K
+    }
+}
+
+/// A mock circuit that takes `memory` GB and 100ms to prove, in the config scope of a real circuit that
+/// it also proves if `proves`.
+#[derive(Clone, Debug)]
+struct MockTask {
+    name: &'static str,
+    memory: u64,
+    fails: bool,
+    proves: bool,
+    dependencies: Vec<MockTask>,
+}
+
+impl MockTask {
+    fn new(name: &'static str, dependencies: Vec<MockTask>) -> Self {
+        Self { name, memory: 1, fails: false, proves: false, dependencies }
+    }
+}
+
+impl Task for MockTask {
+    type CircuitType = MockCircuitType;
+
+    fn circuit_type(&self) -> Self::CircuitType {
+        MockCircuitType(self.memory)
+    }
+    fn name(&self) -> String {
+        self.name.to_string()
+    }
+    fn dependencies(&self) -> Vec<Self> {
+        self.dependencies.clone()
+    }
+}
+
+/// The instance of a task name, names are at most 8 bytes.
+fn name_instance(name: &str) -> Fr {
+    let mut bytes = [0; 8];
+    bytes[..name.len()].copy_from_slice(name.as_bytes());
+    Fr::from(u64::from_le_bytes(bytes))
+}
+
+fn dag_instances(names: &[&str]) -> Vec<Vec<Fr>> {
+    vec![names.iter().map(|name| name_instance(name)).collect()]
+}
+
+/// The params, proving key and a snark of a real circuit, that the mock circuits return.
+struct Template {
+    params: Arc<ParamsKZG<Bn256>>,
+    pk: ProvingKey<G1Affine>,
+    break_points: MultiPhaseThreadBreakPoints,
+    snark: Snark,
+}
+
+lazy_static! {
+    static ref TEMPLATE: Template = {
+        let params = gen_srs(K);
+        let (circuit, config) = add_circuit(CircuitBuilderStage::Keygen, None);
+        let _scope = CircuitConfigScope::enter(config);
+        let pk = gen_pk(&params, &circuit, None);
+        let break_points = circuit.break_points();
+        let (circuit, _) = add_circuit(CircuitBuilderStage::Prover, Some(break_points.clone()));
+        let snark = gen_snark_shplonk(&params, &pk, circuit, None::<&str>);
+        Template { params: Arc::new(params), pk, break_points, snark }
+    };
+}
+
+#[derive(Default)]
+struct Usage {
+    running: usize,
+    max_running: usize,
+    keygens: usize,
+    proven: Vec<String>,
+}
+
+/// The snark of a mock circuit exposes the names of the tasks of its DAG.
+#[derive(Clone)]
+struct MockCircuit {
+    task: MockTask,
+    instances: Vec<Fr>,
+    usage: Arc<Mutex<Usage>>,
+}
+
+impl AnyCircuit for MockCircuit {
+    fn read_or_create_pk(
+        self,
+        _: &ParamsKZG<Bn256>,
+        pk_path: impl AsRef<Path>,
+        _: impl AsRef<Path>,
+        _: bool,
+    ) -> ProvingKey<G1Affine> {
+        if !pk_path.as_ref().exists() {
+            self.usage.lock().unwrap().keygens += 1;
+            fs::write(pk_path, self.task.circuit_type().name()).unwrap();
+        }
+        TEMPLATE.pk.clone()
+    }
+
+    fn gen_snark_shplonk(
+        self,
+        _: &ParamsKZG<Bn256>,
+        _: &ProvingKey<G1Affine>,
+        _: impl AsRef<Path>,
+        _: Option<impl AsRef<Path>>,
+    ) -> Snark {
+        let (circuit, config) =
+            add_circuit(CircuitBuilderStage::Prover, Some(TEMPLATE.break_points.clone()));
+        let scope = CircuitConfigScope::enter(config);
+        {
+            let mut usage = self.usage.lock().unwrap();
+            usage.running += 1;
+            usage.max_running = usage.max_running.max(usage.running);
+        }
+        thread::sleep(Duration::from_millis(100));
+        if self.task.proves {
+            gen_snark_shplonk(&TEMPLATE.params, &TEMPLATE.pk, circuit, None::<&str>);
+        }
+        self.usage.lock().unwrap().running -= 1;
+        drop(scope);
+        assert!(!self.task.fails, "{} failed", self.task.name);
+        self.usage.lock().unwrap().proven.push(self.task.name());
+        Snark::new(TEMPLATE.snark.protocol.clone(), vec![self.instances], vec![])
+    }
+
+    #[cfg(feature = "evm")]
+    fn gen_evm_verifier_shplonk(
+        self,
+        _: &ParamsKZG<Bn256>,
+        _: &ProvingKey<G1Affine>,
+        _: impl AsRef<Path>,
+        _: impl AsRef<Path>,
+    ) -> Vec<u8> {
+        unimplemented!("mock circuits have no verifier")
+    }
+
+    #[cfg(feature = "evm")]
+    fn gen_calldata(
+        self,
+        _: &ParamsKZG<Bn256>,
+        _: &ProvingKey<G1Affine>,
+        _: impl AsRef<Path>,
+        _: impl AsRef<Path>,
+        _: Option<Vec<u8>>,
+    ) -> String {
+        unimplemented!("mock circuits have no verifier")
+    }
+
+    #[cfg(feature = "evm")]
+    fn gen_solidity_calldata(
+        self,
+        _: &ParamsKZG<Bn256>,
+        _: &ProvingKey<G1Affine>,
+        _: impl AsRef<Path>,
+        _: [u8; 20],
+        _: impl AsRef<Path>,
+    ) -> String {
+        unimplemented!("mock circuits have no verifier")
+    }
+}
+
+/// Proves DAGs of mock tasks with `Scheduler::get_snark`, in a temporary directory removed once dropped.
+struct MockScheduler {
+    dir: PathBuf,
+    pool: WorkerPool,
+    manifest: ArtifactManifest,
+    pkeys: RwLock<HashMap<MockCircuitType, Arc<ProvingKey<G1Affine>>>>,
+    read_only: bool,
+    usage: Arc<Mutex<Usage>>,
+}
+
+impl MockScheduler {
+    fn new(name: &str, max_workers: usize, max_memory: u64) -> Self {
+        let dir = temp_dir().join(format!("zkspv_scheduler_{name}_{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let manifest = ArtifactManifest::open(dir.join("manifest.json")).unwrap();
+        let scheduler = Self {
+            dir,
+            pool: WorkerPool::new(SchedulerBudget { max_workers, max_memory }),
+            manifest,
+            pkeys: Default::default(),
+            read_only: false,
+            usage: Default::default(),
+        };
+        for memory in [1, 6, 20] {
+            fs::write(scheduler.pinning_path(&MockCircuitType(memory)), "{}").unwrap();
+        }
+        scheduler
+    }
+
+    fn max_running(&self) -> usize {
+        self.usage.lock().unwrap().max_running
+    }
+
+    fn keygens(&self) -> usize {
+        self.usage.lock().unwrap().keygens
+    }
+
+    fn proven(&self) -> Vec<String> {
+        self.usage.lock().unwrap().proven.clone()
+    }
+}
+
+impl Drop for MockScheduler {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+impl SchedulerCommon for MockScheduler {
+    type CircuitType = MockCircuitType;
+
+    fn config_dir(&self) -> &Path {
+        &self.dir
+    }
+    fn data_dir(&self) -> &Path {
+        &self.dir
+    }
+    fn cache_data_dir(&self) -> &Path {
+        &self.dir
+    }
+    fn pkey_readonly(&self) -> bool {
+        self.read_only
+    }
+    fn srs_readonly(&self) -> bool {
+        false
+    }
+    fn get_degree(&self, _: &Self::CircuitType) -> u32 {
+        K
+    }
+    fn get_params(&self, _: u32) -> Arc<ParamsKZG<Bn256>> {
+        Arc::clone(&TEMPLATE.params)
+    }
+    fn srs_digest(&self, _: u32) -> String {
+        "srs".to_string()
+    }
+    fn manifest(&self) -> &ArtifactManifest {
+        &self.manifest
+    }
+    fn get_pkey(&self, circuit_type: &Self::CircuitType) -> Option<Arc<ProvingKey<G1Affine>>> {
+        self.pkeys.read().unwrap().get(circuit_type).map(Arc::clone)
+    }
+    fn insert_pkey(&self, circuit_type: Self::CircuitType, pkey: ProvingKey<G1Affine>) {
+        self.pkeys.write().unwrap().insert(circuit_type, Arc::new(pkey));
+    }
+    fn worker_pool(&self) -> &WorkerPool {
+        &self.pool
+    }
+    fn memory_estimate(&self, circuit_type: &Self::CircuitType) -> u64 {
+        circuit_type.0 * GB
+    }
+}
+
+impl Scheduler for MockScheduler {
+    type Task = MockTask;
+    type CircuitRouter = MockCircuit;
+
+    fn get_circuit(&self, task: Self::Task, prev_snarks: Vec<Snark>) -> Self::CircuitRouter {
+        assert_eq!(prev_snarks.len(), task.dependencies.len());
+        let instances = prev_snarks
+            .into_iter()
+            .flat_map(|snark| snark.instances.into_iter().flatten())
+            .chain([name_instance(task.name)])
+            .collect();
+        MockCircuit { task, instances, usage: Arc::clone(&self.usage) }
+    }
+}
+
+#[test]
+fn test_independent_dependencies_run_concurrently() {
+    // e.g. the transaction and block merkle snarks of an arbitration task
+    let scheduler = MockScheduler::new("concurrent", 2, u64::MAX);
+    let task =
+        MockTask::new("root", vec![MockTask::new("tx", vec![]), MockTask::new("block", vec![])]);
+    assert_eq!(scheduler.get_snark(task).instances, dag_instances(&["tx", "block", "root"]));
+    assert_eq!(scheduler.max_running(), 2);
+    assert_eq!(scheduler.proven().last().unwrap(), "root");
+    // the circuit type is keygen'd once
+    assert_eq!(scheduler.keygens(), 1);
+}
+
+#[test]
+fn test_independent_dependencies_prove_concurrently() {
+    // the scopes of circuits with the same config don't exclude each other
+    let scheduler = MockScheduler::new("concurrent_proofs", 2, u64::MAX);
+    let [tx, block] =
+        ["tx", "block"].map(|name| MockTask { proves: true, ..MockTask::new(name, vec![]) });
+    let task = MockTask::new("root", vec![tx, block]);
+    assert_eq!(scheduler.get_snark(task).instances, dag_instances(&["tx", "block", "root"]));
+    assert_eq!(scheduler.max_running(), 2);
+}
+
+#[test]
+fn test_dependencies_run_before_their_task() {
+    let scheduler = MockScheduler::new("order", 1, u64::MAX);
+    let task =
+        MockTask::new("root", vec![MockTask::new("tx", vec![]), MockTask::new("block", vec![])]);
+    assert_eq!(scheduler.get_snark(task).instances, dag_instances(&["tx", "block", "root"]));
+    assert_eq!(scheduler.max_running(), 1);
+    // the dependencies are walked at the same time, but proven one at a time
+    let mut proven = scheduler.proven();
+    assert_eq!(proven.pop().unwrap(), "root");
+    proven.sort();
+    assert_eq!(proven, ["block", "tx"]);
+}
+
+#[test]
+fn test_max_workers() {
+    let scheduler = MockScheduler::new("max_workers", 2, u64::MAX);
+    let leaves = ["a", "b", "c", "d"].map(|name| MockTask::new(name, vec![]));
+    scheduler.get_snark(MockTask::new("root", leaves.to_vec()));
+    assert_eq!(scheduler.max_running(), 2);
+    assert_eq!(scheduler.proven().len(), 5);
+}
+
+#[test]
+fn test_walker_threads_are_bounded() {
+    let scheduler = MockScheduler::new("walkers", 1, u64::MAX);
+    let caller = thread::current().id();
+    // the tasks running off the calling thread, and their maximum
+    let walkers = Mutex::new((0, 0));
+    scheduler.pool.join_all(vec![(); 4], |_| {
+        let is_walker = thread::current().id() != caller;
+        if is_walker {
+            let mut walkers = walkers.lock().unwrap();
+            walkers.0 += 1;
+            walkers.1 = walkers.1.max(walkers.0);
+        }
+        thread::sleep(Duration::from_millis(50));
+        if is_walker {
+            walkers.lock().unwrap().0 -= 1;
+        }
+    });
+    assert_eq!(walkers.lock().unwrap().1, 1);
+
+    // a deep DAG is walked without waiting for threads
+    let mut task = MockTask::new("leaf", vec![]);
+    for name in ["a", "b", "c", "d"] {
+        task = MockTask::new(name, vec![task, MockTask::new("leaf", vec![])]);
+    }
+    scheduler.get_snark(task);
+    assert_eq!(scheduler.max_running(), 1);
+}
+
+#[test]
+fn test_max_memory() {
+    let scheduler = MockScheduler::new("max_memory", 4, 10 * GB);
+    let leaves = ["a", "b", "c"].map(|name| MockTask { memory: 6, ..MockTask::new(name, vec![]) });
+    // a task that exceeds the whole budget still runs, alone
+    let root = MockTask { memory: 20, ..MockTask::new("root", leaves.to_vec()) };
+    scheduler.get_snark(root);
+    assert_eq!(scheduler.max_running(), 1);
+    assert_eq!(scheduler.proven().len(), 4);
+}
+
+#[test]
+fn test_identical_tasks_in_flight_run_once() {
+    let shared = MockTask::new("shared", vec![]);
+    let a = MockTask::new("a", vec![shared.clone()]);
+    let b = MockTask::new("b", vec![shared]);
+    let scheduler = MockScheduler::new("identical", 4, u64::MAX);
+    let snark = scheduler.get_snark(MockTask::new("root", vec![a, b]));
+    assert_eq!(snark.instances, dag_instances(&["shared", "a", "shared", "b", "root"]));
+    let mut proven = scheduler.proven();
+    proven.sort();
+    assert_eq!(proven, ["a", "b", "root", "shared"]);
+}
+
+#[test]
+fn test_failed_dependency() {
+    let failing = MockTask { fails: true, ..MockTask::new("failing", vec![]) };
+    let scheduler = MockScheduler::new("failed", 1, u64::MAX);
+    let task = MockTask::new("root", vec![MockTask::new("ok", vec![]), failing]);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| scheduler.get_snark(task.clone())));
+    assert!(result.is_err());
+    assert_eq!(scheduler.proven(), ["ok"]);
+    // the permit of the failed task is released and the task can be run again
+    drop(scheduler.pool.acquire(u64::MAX));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| scheduler.get_snark(task)));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_scheduler_regenerates_pkey_of_changed_pinning() {
+    let mut scheduler = MockScheduler::new("pinning", 1, u64::MAX);
+    let task = MockTask::new("leaf", vec![]);
+    let circuit_type = task.circuit_type();
+    let pre_circuit = scheduler.get_circuit(task, vec![]);
+    let pk_path = scheduler.pkey_path(&circuit_type);
+    let pinning_path = scheduler.pinning_path(&circuit_type);
+    // a restarted scheduler, which has no proving key in memory
+    let get_or_create_pkey = |scheduler: &MockScheduler| {
+        scheduler.pkeys.write().unwrap().clear();
+        scheduler.get_or_create_pkey(&pre_circuit, &circuit_type, &TEMPLATE.params, pk_path.clone())
+    };
+
+    get_or_create_pkey(&scheduler);
+    assert_eq!(scheduler.keygens(), 1);
+    get_or_create_pkey(&scheduler);
+    assert_eq!(scheduler.keygens(), 1);
+
+    fs::write(&pinning_path, r#"{"changed":true}"#).unwrap();
+    get_or_create_pkey(&scheduler);
+    assert_eq!(scheduler.keygens(), 2);
+    let artifact =
+        Artifact::pkey(circuit_type.name(), &pinning_path, K, scheduler.srs_digest(K)).unwrap();
+    assert_eq!(scheduler.manifest.check(&pk_path, &artifact).unwrap(), None);
+
+    // a readonly scheduler refuses the proving key instead
+    scheduler.read_only = true;
+    fs::write(&pinning_path, r#"{"changed":2}"#).unwrap();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| get_or_create_pkey(&scheduler)));
+    assert!(result.is_err());
+    assert_eq!(scheduler.keygens(), 2);
+    assert!(pk_path.exists());
+}
+
+#[test]
+fn test_unchanged_file_is_not_hashed_again() {
+    let dir = temp_dir().join(format!("zkspv_manifest_stamp_{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (srs_path, manifest_path) = (dir.join("kzg_bn254_10.srs"), dir.join("manifest.json"));
+    fs::write(&srs_path, "srs").unwrap();
+    let manifest = ArtifactManifest::open(&manifest_path).unwrap();
+    let entry = manifest.record(&srs_path, Artifact::srs(10)).unwrap();
+    assert_eq!(entry.stamp, Some(FileStamp::of(&srs_path).unwrap()));
+
+    // a stale digest goes unnoticed while the stamp matches, so the file wasn't hashed
+    let stale = fs::read_to_string(&manifest_path).unwrap().replace(&entry.digest, "stale");
+    fs::write(&manifest_path, stale).unwrap();
+    let manifest = ArtifactManifest::open(&manifest_path).unwrap();
+    assert_eq!(manifest.check(&srs_path, &Artifact::srs(10)).unwrap(), None);
+    fs::write(&srs_path, "other srs").unwrap();
+    assert_eq!(
+        manifest.check(&srs_path, &Artifact::srs(10)).unwrap(),
+        Some(ArtifactMismatch::Digest)
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_changed_pinning_invalidates_pkey() {
+    let dir = temp_dir().join(format!("zkspv_manifest_{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (pk_path, pinning_path) = (dir.join("mock.pk"), dir.join("mock.json"));
+    fs::write(&pk_path, "pk").unwrap();
+    fs::write(&pinning_path, r#"{"params":{"degree":10}}"#).unwrap();
+    let artifact = || Artifact::pkey("mock".to_string(), &pinning_path, 10, "srs".to_string());
+
+    let manifest = ArtifactManifest::open(dir.join("manifest.json")).unwrap();
+    // a proving key of an older version of the scheduler
+    assert!(matches!(
+        manifest.validate(&pk_path, &artifact().unwrap(), true),
+        Err(ManifestError::Refused { mismatch: ArtifactMismatch::Untracked, .. })
+    ));
+    manifest.record(&pk_path, artifact().unwrap()).unwrap();
+    manifest.validate(&pk_path, &artifact().unwrap(), true).unwrap();
+
+    // the manifest is read back from file
+    let manifest = ArtifactManifest::open(dir.join("manifest.json")).unwrap();
+    let other_srs = Artifact { srs_digest: Some("other".to_string()), ..artifact().unwrap() };
+    assert_eq!(manifest.check(&pk_path, &other_srs).unwrap(), Some(ArtifactMismatch::Srs));
+
+    fs::write(&pinning_path, r#"{"params":{"degree":11}}"#).unwrap();
+    assert_eq!(
+        manifest.check(&pk_path, &artifact().unwrap()).unwrap(),
+        Some(ArtifactMismatch::Pinning)
+    );
+    assert!(matches!(
+        manifest.validate(&pk_path, &artifact().unwrap(), true),
+        Err(ManifestError::Refused { mismatch: ArtifactMismatch::Pinning, .. })
+    ));
+    assert!(pk_path.exists());
+    // removed so that the scheduler generates it again
+    manifest.validate(&pk_path, &artifact().unwrap(), false).unwrap();
+    assert!(!pk_path.exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_modified_pkey_is_detected() {
+    let dir = temp_dir().join(format!("zkspv_manifest_digest_{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (pk_path, pinning_path) = (dir.join("mock.pk"), dir.join("mock.json"));
+    fs::write(&pk_path, "pk").unwrap();
+    fs::write(&pinning_path, "{}").unwrap();
+    let artifact =
+        Artifact::pkey("mock".to_string(), &pinning_path, 10, "srs".to_string()).unwrap();
+
+    let manifest = ArtifactManifest::open(dir.join("manifest.json")).unwrap();
+    manifest.record(&pk_path, artifact.clone()).unwrap();
+    fs::write(&pk_path, "truncated").unwrap();
+    assert_eq!(manifest.check(&pk_path, &artifact).unwrap(), Some(ArtifactMismatch::Digest));
+    let other_circuit = Artifact { circuit: Some("other".to_string()), ..artifact };
+    assert_eq!(
+        manifest.check(&pk_path, &other_circuit).unwrap(),
+        Some(ArtifactMismatch::Circuit {
+            recorded: Some("mock".to_string()),
+            expected: Some("other".to_string())
+        })
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,254 @@
+//! Runs the independent tasks of a DAG at the same time, within a memory and thread budget.
+//!
+//! The dependencies of a task are walked on their own threads, see [`WorkerPool::join_all`], but a thread
+//! only keygens or proves while it holds a [`WorkerPermit`].
+//! Permits are never held while waiting for other tasks, so a task can't wait for a dependency that
+//! waits for its permit.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::env::var;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+pub const SCHEDULER_MAX_WORKERS_ENV: &str = "SCHEDULER_MAX_WORKERS";
+pub const SCHEDULER_MAX_MEMORY_ENV: &str = "SCHEDULER_MAX_MEMORY_GB";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchedulerBudget {
+    /// The maximum number of tasks that are keygen'd or proven at the same time.
+    pub max_workers: usize,
+    /// The maximum sum of the memory estimates of these tasks, in bytes.
+    pub max_memory: u64,
+}
+
+impl Default for SchedulerBudget {
+    /// Two workers, e.g. for the transaction and block merkle snarks of an arbitration task, and no memory
+    /// limit. A prover already runs on every core, with rayon, so more workers mostly need more memory.
+    fn default() -> Self {
+        Self { max_workers: 2, max_memory: u64::MAX }
+    }
+}
+
+impl SchedulerBudget {
+    /// Reads `SCHEDULER_MAX_WORKERS` and `SCHEDULER_MAX_MEMORY_GB`, the default for the ones that are not set.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let max_workers = var(SCHEDULER_MAX_WORKERS_ENV).map_or(default.max_workers, |v| {
+            v.parse().unwrap_or_else(|_| panic!("invalid {SCHEDULER_MAX_WORKERS_ENV} `{v}`"))
+        });
+        let max_memory = var(SCHEDULER_MAX_MEMORY_ENV).map_or(default.max_memory, |v| {
+            let gb: u64 =
+                v.parse().unwrap_or_else(|_| panic!("invalid {SCHEDULER_MAX_MEMORY_ENV} `{v}`"));
+            gb << 30
+        });
+        assert!(max_workers > 0, "{SCHEDULER_MAX_WORKERS_ENV} must be positive");
+        Self { max_workers, max_memory }
+    }
+}
+
+#[derive(Default)]
+struct Usage {
+    workers: usize,
+    memory: u64,
+}
+
+#[derive(Default)]
+enum Slot {
+    #[default]
+    Running,
+    Done(Arc<dyn Any + Send + Sync>),
+    Failed,
+}
+
+/// A task that is being run, the other callers of [`WorkerPool::run_once`] with its key wait for it.
+#[derive(Default)]
+struct InFlight {
+    slot: Mutex<Slot>,
+    finished: Condvar,
+}
+
+pub struct WorkerPool {
+    budget: SchedulerBudget,
+    usage: Mutex<Usage>,
+    /// The threads spawned by [`WorkerPool::join_all`] that are running.
+    walkers: Mutex<usize>,
+    released: Condvar,
+    in_flight: Mutex<HashMap<String, Arc<InFlight>>>,
+}
+
+// a panicking task must not block the other tasks forever
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl WorkerPool {
+    pub fn new(budget: SchedulerBudget) -> Self {
+        Self {
+            budget,
+            usage: Default::default(),
+            walkers: Default::default(),
+            released: Default::default(),
+            in_flight: Default::default(),
+        }
+    }
+
+    pub fn budget(&self) -> SchedulerBudget {
+        self.budget
+    }
+
+    /// Blocks until a worker and `memory` bytes of the budget are free.
+    ///
+    /// A task whose estimate exceeds the whole memory budget waits until no other task runs.
+    pub fn acquire(&self, memory: u64) -> WorkerPermit<'_> {
+        let memory = memory.min(self.budget.max_memory);
+        let mut usage = lock(&self.usage);
+        while usage.workers >= self.budget.max_workers
+            || usage.memory.saturating_add(memory) > self.budget.max_memory
+        {
+            usage = self.released.wait(usage).unwrap_or_else(PoisonError::into_inner);
+        }
+        usage.workers += 1;
+        usage.memory += memory;
+        WorkerPermit { pool: self, memory }
+    }
+
+    /// Runs `f`, unless a task with the same `key` is already running: then its result is returned instead.
+    ///
+    /// The scheduler uses the path of the file that a task writes as its key. Panics if that task panics.
+    pub fn run_once<T: Clone + Send + Sync + 'static>(
+        &self,
+        key: &str,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        let (in_flight, is_runner) = {
+            let mut tasks = lock(&self.in_flight);
+            match tasks.get(key) {
+                Some(in_flight) => (Arc::clone(in_flight), false),
+                None => {
+                    let in_flight = Arc::new(InFlight::default());
+                    tasks.insert(key.to_string(), Arc::clone(&in_flight));
+                    (in_flight, true)
+                }
+            }
+        };
+        if !is_runner {
+            let mut slot = lock(&in_flight.slot);
+            loop {
+                match &*slot {
+                    Slot::Done(result) => {
+                        return result
+                            .downcast_ref::<T>()
+                            .expect("tasks of a key have one type")
+                            .clone()
+                    }
+                    Slot::Failed => panic!("task {key} failed in another thread"),
+                    Slot::Running => {
+                        slot = in_flight.finished.wait(slot).unwrap_or_else(PoisonError::into_inner)
+                    }
+                }
+            }
+        }
+        let mut guard = RunGuard { pool: self, key, in_flight, result: None };
+        let result = f();
+        guard.result = Some(Arc::new(result.clone()));
+        result
+    }
+}
+
+/// Publishes the result of [`WorkerPool::run_once`], or its failure if `f` panicked.
+struct RunGuard<'a> {
+    pool: &'a WorkerPool,
+    key: &'a str,
+    in_flight: Arc<InFlight>,
+    result: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        // later callers run the task again, e.g. to read the file it wrote
+        lock(&self.pool.in_flight).remove(self.key);
+        *lock(&self.in_flight.slot) = self.result.take().map_or(Slot::Failed, Slot::Done);
+        self.in_flight.finished.notify_all();
+    }
+}
+
+/// A worker and memory of the budget of a [`WorkerPool`], released when dropped.
+#[must_use]
+pub struct WorkerPermit<'a> {
+    pool: &'a WorkerPool,
+    memory: u64,
+}
+
+impl Drop for WorkerPermit<'_> {
+    fn drop(&mut self) {
+        let mut usage = lock(&self.pool.usage);
+        usage.workers -= 1;
+        usage.memory -= self.memory;
+        self.pool.released.notify_all();
+    }
+}
+
+/// A thread spawned by [`WorkerPool::join_all`], released when dropped.
+struct Walker<'a> {
+    pool: &'a WorkerPool,
+}
+
+impl Drop for Walker<'_> {
+    fn drop(&mut self) {
+        *lock(&self.pool.walkers) -= 1;
+    }
+}
+
+impl WorkerPool {
+    /// A new thread, if fewer than `max_workers` threads of [`WorkerPool::join_all`] are running.
+    fn walker(&self) -> Option<Walker<'_>> {
+        let mut walkers = lock(&self.walkers);
+        if *walkers >= self.budget.max_workers {
+            return None;
+        }
+        *walkers += 1;
+        Some(Walker { pool: self })
+    }
+
+    /// Runs `f` on every task and returns the results in the order of `tasks`.
+    ///
+    /// A task runs on its own thread while fewer than `max_workers` threads of the pool are running, and
+    /// on the calling thread otherwise, so the threads of a DAG are bounded however deep it is. A panic of any of them is resumed on the calling thread once all of them
+    /// are finished.
+    pub fn join_all<T: Send, S: Send>(&self, tasks: Vec<T>, f: impl Fn(T) -> S + Sync) -> Vec<S> {
+        if tasks.len() <= 1 {
+            return tasks.into_iter().map(f).collect();
+        }
+        let f = &f;
+        thread::scope(|scope| {
+            let pending = tasks
+                .into_iter()
+                .map(|task| match self.walker() {
+                    Some(walker) => Ok(scope.spawn(move || {
+                        let _walker = walker;
+                        f(task)
+                    })),
+                    None => Err(task),
+                })
+                .collect::<Vec<_>>();
+            // the tasks without a thread run while the others do
+            let pending = pending
+                .into_iter()
+                .map(|task| task.map_err(|task| panic::catch_unwind(AssertUnwindSafe(|| f(task)))))
+                .collect::<Vec<_>>();
+            let results = pending
+                .into_iter()
+                .map(|task| match task {
+                    Ok(handle) => handle.join(),
+                    Err(result) => result,
+                })
+                .collect::<Vec<_>>();
+            results
+                .into_iter()
+                .map(|result| result.unwrap_or_else(|err| panic::resume_unwind(err)))
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,179 @@
+//! Verifies the snarks and calldata written by the scheduler, without proving anything.
+
+use super::{
+    circuit_config::{CircuitConfig, CircuitConfigScope},
+    AggregationConfigPinning, EthConfigPinning, Halo2ConfigPinning,
+};
+#[cfg(feature = "evm")]
+use crate::gen::solidity::{call_contract, deploy_contract};
+use crate::rlp::{builder::RlcThreadBuilder, rlc::RlcChip};
+use crate::EthCircuitBuilder;
+use ethers_core::utils::hex::FromHex;
+use ff::PrimeField;
+use halo2_base::gates::builder::RangeWithInstanceCircuitBuilder;
+use halo2_base::halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{verify_proof, VerifyingKey},
+    poly::{
+        commitment::{Params, ParamsProver},
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::VerifierSHPLONK,
+            strategy::SingleStrategy,
+        },
+    },
+};
+use itertools::Itertools;
+#[cfg(feature = "evm")]
+use revm::{primitives::ExecutionResult, InMemoryDB, EVM};
+#[cfg(feature = "evm")]
+use snark_verifier::loader::evm::compile_yul;
+use snark_verifier::{
+    pcs::{
+        kzg::{KzgDecidingKey, KzgSuccinctVerifyingKey},
+        AccumulationDecider,
+    },
+    verifier::SnarkVerifier,
+};
+use snark_verifier_sdk::{
+    halo2::{PoseidonTranscript, POSEIDON_SPEC},
+    read_pk, NativeLoader, PlonkSuccinctVerifier, Snark, SHPLONK,
+};
+use std::{
+    fs::{self, File},
+    path::Path,
+};
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+/// Verifies the proof of `snark` against the protocol it carries, and decides the accumulators of an aggregation snark.
+///
+/// This is the check an aggregation circuit does. It doesn't tell which circuit the protocol is of, anyone can
+/// write a snark file with the protocol of their own circuit, see [`verify_snark_with_vk`] for that.
+pub fn verify_snark(params: &ParamsKZG<Bn256>, snark: &Snark) -> bool {
+    let svk: KzgSuccinctVerifyingKey<G1Affine> = params.get_g()[0].into();
+    let dk: KzgDecidingKey<Bn256> = (params.get_g()[0], params.g2(), params.s_g2()).into();
+    let mut transcript = PoseidonTranscript::<NativeLoader, &[u8]>::from_spec(
+        snark.proof.as_slice(),
+        POSEIDON_SPEC.clone(),
+    );
+    let accumulators = PlonkSuccinctVerifier::<SHPLONK>::read_proof(
+        &svk,
+        &snark.protocol,
+        &snark.instances,
+        &mut transcript,
+    )
+    .and_then(|proof| {
+        PlonkSuccinctVerifier::<SHPLONK>::verify(&svk, &snark.protocol, &snark.instances, &proof)
+    });
+    match accumulators {
+        Ok(accumulators) => SHPLONK::decide_all(&dk, accumulators).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Verifies the proof of `snark` against the verifying key of its circuit.
+pub fn verify_snark_with_vk(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    snark: &Snark,
+) -> bool {
+    let instances = snark.instances.iter().map(Vec::as_slice).collect_vec();
+    let verifier_params = params.verifier_params();
+    let strategy = SingleStrategy::new(params);
+    let mut transcript = PoseidonTranscript::<NativeLoader, &[u8]>::from_spec(
+        snark.proof.as_slice(),
+        POSEIDON_SPEC.clone(),
+    );
+    verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<'_, Bn256>, _, _, _>(
+        verifier_params,
+        vk,
+        strategy,
+        &[&instances],
+        &mut transcript,
+    )
+    .is_ok()
+}
+
+/// The synthesize function of the [`EthCircuitBuilder`] a verifying key is read for, keygen doesn't call it.
+type ReadVkSynthesize = fn(&mut RlcThreadBuilder<Fr>, &RlcChip<Fr>);
+
+/// Reads the verifying key of a circuit from its proving key and the pinning it was generated with.
+///
+/// The pinning tells which builder the circuit is configured with: an [`EthConfigPinning`] is the pinning of an
+/// [`EthCircuitBuilder`], an [`AggregationConfigPinning`] the pinning of a gate circuit such as a final assembly.
+pub fn read_vk(
+    pk_path: impl AsRef<Path>,
+    pinning_path: impl AsRef<Path>,
+) -> VerifyingKey<G1Affine> {
+    let pinning_path = pinning_path.as_ref();
+    let pinning: serde_json::Value = serde_json::from_reader(
+        File::open(pinning_path)
+            .unwrap_or_else(|e| panic!("{pinning_path:?} does not exist: {e:?}")),
+    )
+    .expect("pinning should be json");
+    let config = match serde_json::from_value::<EthConfigPinning>(pinning.clone()) {
+        Ok(pinning) => pinning.config(),
+        Err(_) => serde_json::from_value::<AggregationConfigPinning>(pinning)
+            .unwrap_or_else(|e| panic!("{pinning_path:?} is not a circuit pinning: {e:?}"))
+            .config(),
+    };
+    let _scope = CircuitConfigScope::enter(config.clone());
+    let pk = match config {
+        CircuitConfig::Eth(_) => {
+            read_pk::<EthCircuitBuilder<Fr, ReadVkSynthesize>>(pk_path.as_ref())
+        }
+        // the aggregation circuits are configured like the gate circuit they are built on
+        CircuitConfig::Gate { .. } => {
+            read_pk::<RangeWithInstanceCircuitBuilder<Fr>>(pk_path.as_ref())
+        }
+    };
+    pk.expect("proving key should exist").get_vk().clone()
+}
+
+/// Reads a calldata file, which is a hex string.
+pub fn read_calldata(path: impl AsRef<Path>) -> Vec<u8> {
+    let calldata = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{:?} does not exist: {e:?}", path.as_ref()));
+    Vec::from_hex(calldata.trim().trim_start_matches("0x")).expect("calldata should be hex")
+}
+
+/// Splits the calldata of the EVM verifier into its `num_instance` instances and the proof.
+///
+/// `None` if the calldata is too short or an instance is not a field element.
+pub fn decode_calldata(calldata: &[u8], num_instance: usize) -> Option<(Vec<Fr>, Vec<u8>)> {
+    if calldata.len() < num_instance * 32 {
+        return None;
+    }
+    let (instances, proof) = calldata.split_at(num_instance * 32);
+    let instances = instances
+        .chunks(32)
+        .map(|word| {
+            // instances are big-endian words
+            let mut repr = <Fr as PrimeField>::Repr::default();
+            repr.as_mut().copy_from_slice(word);
+            repr.as_mut().reverse();
+            Option::from(Fr::from_repr(repr))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some((instances, proof.to_vec()))
+}
+
+/// The deployment code of the EVM verifier written to `yul_path` by `gen_evm_verifier_shplonk`.
+#[cfg(feature = "evm")]
+pub fn read_evm_verifier(yul_path: impl AsRef<Path>) -> Vec<u8> {
+    let yul = fs::read_to_string(&yul_path)
+        .unwrap_or_else(|e| panic!("{:?} does not exist: {e:?}", yul_path.as_ref()));
+    compile_yul(&yul)
+}
+
+/// Deploys the EVM verifier in a local revm and calls it with `calldata`, true if the proof is valid.
+#[cfg(feature = "evm")]
+pub fn verify_calldata(deployment_code: Vec<u8>, calldata: Vec<u8>) -> bool {
+    let mut evm = EVM::new();
+    evm.database(InMemoryDB::default());
+    let verifier = deploy_contract(&mut evm, deployment_code);
+    // the verifier reverts on an invalid proof
+    matches!(call_contract(&mut evm, verifier, calldata), ExecutionResult::Success { .. })
+}
@@ -0,0 +1,152 @@
+use super::{decode_calldata, read_vk, verify_snark, verify_snark_with_vk};
+use crate::util::circuit::PinnableCircuit;
+use crate::util::circuit_config::{isolate_env, CircuitConfig, CircuitConfigScope};
+use ff::PrimeField;
+use halo2_base::gates::builder::{
+    CircuitBuilderStage, GateThreadBuilder, MultiPhaseThreadBreakPoints, RangeCircuitBuilder,
+    RangeWithInstanceCircuitBuilder,
+};
+use halo2_base::gates::{GateChip, GateInstructions};
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::utils::fs::gen_srs;
+use halo2_base::{AssignedValue, Context};
+use snark_verifier_sdk::{gen_pk, halo2::gen_snark_shplonk, CircuitExt};
+use std::env::temp_dir;
+use std::fs;
+use std::process;
+
+pub const K: u32 = 8;
+const LOOKUP_BITS: usize = 7;
+
+/// Exposes `a` and `op(a, b)`, for `a = 3` and `b = 4`.
+pub fn gate_circuit(
+    stage: CircuitBuilderStage,
+    break_points: Option<MultiPhaseThreadBreakPoints>,
+    op: impl FnOnce(
+        &GateChip<Fr>,
+        &mut Context<Fr>,
+        AssignedValue<Fr>,
+        AssignedValue<Fr>,
+    ) -> AssignedValue<Fr>,
+) -> (RangeWithInstanceCircuitBuilder<Fr>, CircuitConfig) {
+    let mut builder = GateThreadBuilder::new(stage == CircuitBuilderStage::Prover);
+    let gate = GateChip::default();
+    let ctx = builder.main(0);
+    let [a, b] = [3, 4].map(|x| ctx.load_witness(Fr::from(x)));
+    let c = op(&gate, ctx, a, b);
+    let params = isolate_env(|| builder.config(K as usize, Some(20)));
+    let circuit = match break_points {
+        Some(break_points) => RangeCircuitBuilder::prover(builder, break_points),
+        None => RangeCircuitBuilder::keygen(builder),
+    };
+    let config = CircuitConfig::Gate { params, lookup_bits: LOOKUP_BITS };
+    (RangeWithInstanceCircuitBuilder::new(circuit, vec![a, c]), config)
+}
+
+/// Exposes `a` and `a + b`.
+pub fn add_circuit(
+    stage: CircuitBuilderStage,
+    break_points: Option<MultiPhaseThreadBreakPoints>,
+) -> (RangeWithInstanceCircuitBuilder<Fr>, CircuitConfig) {
+    gate_circuit(stage, break_points, |gate, ctx, a, b| gate.add(ctx, a, b))
+}
+
+/// Exposes `a` and `a * b`.
+pub fn mul_circuit(
+    stage: CircuitBuilderStage,
+    break_points: Option<MultiPhaseThreadBreakPoints>,
+) -> (RangeWithInstanceCircuitBuilder<Fr>, CircuitConfig) {
+    gate_circuit(stage, break_points, |gate, ctx, a, b| gate.mul(ctx, a, b))
+}
+
+#[test]
+fn test_verify_snark() {
+    let params = gen_srs(K);
+    let (circuit, config) = add_circuit(CircuitBuilderStage::Keygen, None);
+    let _scope = CircuitConfigScope::enter(config);
+    let pk = gen_pk(&params, &circuit, None);
+    let (circuit, _) = add_circuit(CircuitBuilderStage::Prover, Some(circuit.break_points()));
+    let snark = gen_snark_shplonk(&params, &pk, circuit, None::<&str>);
+
+    assert_eq!(snark.instances, [vec![Fr::from(3), Fr::from(7)]]);
+    assert!(verify_snark(&params, &snark));
+    assert!(verify_snark_with_vk(&params, pk.get_vk(), &snark));
+
+    let mut forged = snark;
+    forged.instances[0][1] = Fr::from(8);
+    assert!(!verify_snark(&params, &forged));
+    assert!(!verify_snark_with_vk(&params, pk.get_vk(), &forged));
+}
+
+#[test]
+fn test_verify_snark_with_read_vk() {
+    let params = gen_srs(K);
+    let dir = temp_dir().join(format!("zkspv_verify_vk_{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (pk_path, pinning_path) = (dir.join("add.pk"), dir.join("add.json"));
+    let (circuit, config) = add_circuit(CircuitBuilderStage::Keygen, None);
+    let add_snark = {
+        let _scope = CircuitConfigScope::enter(config.clone());
+        let pk = gen_pk(&params, &circuit, Some(&pk_path));
+        circuit.write_pinning(config, &pinning_path);
+        let (circuit, _) = add_circuit(CircuitBuilderStage::Prover, Some(circuit.break_points()));
+        gen_snark_shplonk(&params, &pk, circuit, None::<&str>)
+    };
+    let vk = read_vk(&pk_path, &pinning_path);
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(verify_snark_with_vk(&params, &vk, &add_snark));
+
+    // a valid snark of another circuit carries its own protocol
+    let (circuit, config) = mul_circuit(CircuitBuilderStage::Keygen, None);
+    let _scope = CircuitConfigScope::enter(config);
+    let pk = gen_pk(&params, &circuit, None);
+    let (circuit, _) = mul_circuit(CircuitBuilderStage::Prover, Some(circuit.break_points()));
+    let mul_snark = gen_snark_shplonk(&params, &pk, circuit, None::<&str>);
+    assert!(verify_snark(&params, &mul_snark));
+    assert!(!verify_snark_with_vk(&params, &vk, &mul_snark));
+}
+
+#[test]
+fn test_decode_calldata() {
+    let word = |x: u64| {
+        let mut word = Fr::from(x).to_repr();
+        word.reverse();
+        word.to_vec()
+    };
+    let calldata = [word(3), word(7), vec![1, 2, 3]].concat();
+    assert_eq!(
+        decode_calldata(&calldata, 2),
+        Some((vec![Fr::from(3), Fr::from(7)], vec![1, 2, 3]))
+    );
+    assert_eq!(decode_calldata(&calldata, 3), None);
+    // not a field element
+    assert_eq!(decode_calldata(&[vec![0xff; 32], word(7)].concat(), 2), None);
+}
+
+#[cfg(feature = "evm")]
+#[test]
+fn test_verify_calldata() {
+    use super::verify_calldata;
+    use snark_verifier::loader::evm::encode_calldata;
+    use snark_verifier_sdk::evm::{gen_evm_proof_shplonk, gen_evm_verifier_shplonk};
+
+    let params = gen_srs(K);
+    let (circuit, config) = add_circuit(CircuitBuilderStage::Keygen, None);
+    let _scope = CircuitConfigScope::enter(config);
+    let pk = gen_pk(&params, &circuit, None);
+    let deployment_code = gen_evm_verifier_shplonk::<RangeWithInstanceCircuitBuilder<Fr>>(
+        &params,
+        pk.get_vk(),
+        circuit.num_instance(),
+        None,
+    );
+    let (circuit, _) = add_circuit(CircuitBuilderStage::Prover, Some(circuit.break_points()));
+    let instances = circuit.instances();
+    let proof = gen_evm_proof_shplonk(&params, &pk, circuit, instances.clone());
+
+    let calldata = encode_calldata(&instances, &proof);
+    assert_eq!(decode_calldata(&calldata, 2), Some((instances[0].clone(), proof.clone())));
+    assert!(verify_calldata(deployment_code.clone(), calldata));
+    let calldata = encode_calldata(&[vec![Fr::from(3), Fr::from(8)]], &proof);
+    assert!(!verify_calldata(deployment_code, calldata));
+}
@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+/// The contract of `storage_layout.json`, whose locations follow its storage layout:
+///
+/// | Variable     | Slot | Offset |
+/// |--------------|------|--------|
+/// | `owner`      | 0    | 0      |
+/// | `version`    | 0    | 20     |
+/// | `paused`     | 0    | 24     |
+/// | `balances`   | 1    | 0      |
+/// | `rules`      | 2    | 0      |
+/// | `roots`      | 3    | 0      |
+/// | `history`    | 4    | 0      |
+/// | `timestamps` | 5    | 0      |
+/// | `names`      | 6    | 0      |
+///
+/// `A` is 0x70997970C51812dc3A010C7d01b50e0d17dc79C8 and `B` is 0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC.
+///
+/// The table and the slots of `storage_layout.json` were derived by hand from the layout rules, not from
+/// `solc --storage-layout` or `eth_getProof` of a deployed contract. Check them against both before relying on them.
+contract StorageLayout {
+    /// 3 slots: `chainId` and `minPrice` share the first one, `token` and `status` the second one.
+    struct Rule {
+        uint64 chainId;
+        uint64 minPrice;
+        address token;
+        uint8 status;
+        uint256 maxPrice;
+    }
+
+    address public owner;
+    uint32 public version;
+    bool public paused;
+    mapping(address => uint256) public balances;
+    mapping(address => mapping(uint64 => Rule)) public rules;
+    uint256[] public roots;
+    Rule[] public history;
+    /// 4 timestamps per slot.
+    uint64[] public timestamps;
+    mapping(string => bytes32) public names;
+}